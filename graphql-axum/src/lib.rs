@@ -0,0 +1,206 @@
+//! Mounts a GraphQL schema on an axum `Router`, on top of the
+//! framework-agnostic `GraphQLHttpHandler`.
+//!
+//! Schema types are reference counted with `Rc` and cannot be shared between
+//! threads, so the handler is built by a factory, once per worker thread.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use axum::body::{to_bytes, Body, Bytes};
+use axum::extract::{Request, State};
+use axum::http::header::{ACCEPT, CONTENT_LENGTH, CONTENT_TYPE};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::any;
+use axum::Router;
+use http_body_util::LengthLimitError;
+use serde_json::json;
+
+use graphql_rs::handler::{parse_query, GraphQLHttpHandler};
+use graphql_rs::request::ExecutionConfig;
+use graphql_rs::schema::GraphQLSchema;
+
+pub static DEFAULT_PATH: &str = "/graphql";
+pub static DEFAULT_BODY_LIMIT: usize = 1024 * 1024;
+
+type MakeHandlerFn = dyn Fn() -> GraphQLHttpHandler + Send + Sync;
+type ContextFn = dyn Fn(&HeaderMap) -> Box<dyn Any> + Send + Sync;
+
+/// Router builder
+pub struct GraphQLRouter {
+  path: String,
+  make_handler: Arc<MakeHandlerFn>,
+  context: Arc<ContextFn>,
+  body_limit: usize,
+  graphiql: bool,
+}
+
+impl GraphQLRouter {
+  /// `make_handler` is called once on each thread serving requests.
+  pub fn new<F>(make_handler: F) -> GraphQLRouter
+    where F: Fn() -> GraphQLHttpHandler + Send + Sync + 'static
+  {
+    GraphQLRouter {
+      path: DEFAULT_PATH.to_owned(),
+      make_handler: Arc::new(make_handler),
+      context: Arc::new(|_| Box::new(())),
+      body_limit: DEFAULT_BODY_LIMIT,
+      graphiql: true,
+    }
+  }
+
+  /// Serves the schema `make_schema` builds, parsing queries with
+  /// `parse_query`. `make_schema` is called once on each thread serving
+  /// requests.
+  pub fn from_schema<F>(make_schema: F) -> GraphQLRouter
+    where F: Fn() -> Rc<GraphQLSchema> + Send + Sync + 'static
+  {
+    GraphQLRouter::new(move || {
+      GraphQLHttpHandler::new(&Rc::new(ExecutionConfig::new(&make_schema(), parse_query)))
+    })
+  }
+
+  pub fn path(mut self, path: &str) -> GraphQLRouter {
+    self.path = path.to_owned();
+    self
+  }
+
+  /// Builds the context handed to resolvers from the headers of each request.
+  pub fn context<F, C>(mut self, context: F) -> GraphQLRouter
+    where F: Fn(&HeaderMap) -> C + Send + Sync + 'static,
+          C: Any
+  {
+    self.context = Arc::new(move |headers| Box::new(context(headers)));
+    self
+  }
+
+  /// Maximum size in bytes of a request body, larger requests get a
+  /// `413 Payload Too Large`.
+  pub fn body_limit(mut self, body_limit: usize) -> GraphQLRouter {
+    self.body_limit = body_limit;
+    self
+  }
+
+  /// Whether GET requests accepting `text/html` are served the GraphiQL IDE.
+  pub fn graphiql(mut self, graphiql: bool) -> GraphQLRouter {
+    self.graphiql = graphiql;
+    self
+  }
+
+  pub fn build(self) -> Router {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+    let path = self.path.clone();
+    let endpoint = Arc::new(Endpoint {
+      id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+      router: self,
+    });
+    Router::new().route(&path, any(serve)).with_state(endpoint)
+  }
+}
+
+struct Endpoint {
+  id: usize,
+  router: GraphQLRouter,
+}
+
+thread_local! {
+  // Handlers built on this thread, keyed by endpoint id.
+  static HANDLERS: RefCell<HashMap<usize, Rc<GraphQLHttpHandler>>> = RefCell::new(HashMap::new());
+}
+
+impl Endpoint {
+  fn handler(&self) -> Rc<GraphQLHttpHandler> {
+    HANDLERS.with(|handlers| {
+      handlers.borrow_mut()
+        .entry(self.id)
+        .or_insert_with(|| Rc::new((self.router.make_handler)()))
+        .clone()
+    })
+  }
+}
+
+async fn serve(State(endpoint): State<Arc<Endpoint>>, request: Request) -> Response {
+  let (parts, body) = request.into_parts();
+  if endpoint.router.graphiql && parts.method == Method::GET && accepts_html(&parts.headers) {
+    return Html(graphiql_page(&endpoint.router.path)).into_response();
+  }
+
+  let body_limit = endpoint.router.body_limit;
+  let content_length = parts.headers
+    .get(CONTENT_LENGTH)
+    .and_then(|l| l.to_str().ok())
+    .and_then(|l| l.parse::<usize>().ok());
+  if content_length.is_some_and(|l| l > body_limit) {
+    return payload_too_large(body_limit);
+  }
+  let body = match to_bytes(body, body_limit).await {
+    Ok(body) => body,
+    Err(e) => {
+      if e.into_inner().downcast_ref::<LengthLimitError>().is_some() {
+        return payload_too_large(body_limit);
+      }
+      return error_response(StatusCode::BAD_REQUEST, "Request body could not be read.");
+    }
+  };
+
+  // Nothing reference counted may be held across an await.
+  execute(&endpoint, axum::http::Request::from_parts(parts, body))
+}
+
+fn execute(endpoint: &Endpoint, request: axum::http::Request<Bytes>) -> Response {
+  let context = (endpoint.router.context)(request.headers());
+  endpoint.handler().handle(&request, &(), &*context).map(Body::from)
+}
+
+fn accepts_html(headers: &HeaderMap) -> bool {
+  headers.get(ACCEPT)
+    .and_then(|a| a.to_str().ok())
+    .is_some_and(|a| {
+      a.split(',').any(|m| m.split(';').next().unwrap_or("").trim().eq_ignore_ascii_case("text/html"))
+    })
+}
+
+fn payload_too_large(body_limit: usize) -> Response {
+  error_response(StatusCode::PAYLOAD_TOO_LARGE,
+                 &format!("Request body exceeds the limit of {} bytes.", body_limit))
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+  let body = json!({ "errors": [{ "message": message }] }).to_string();
+  let mut response = (status, body).into_response();
+  response.headers_mut()
+    .insert(CONTENT_TYPE, HeaderValue::from_static("application/json; charset=utf-8"));
+  response
+}
+
+fn graphiql_page(path: &str) -> String {
+  GRAPHIQL_HTML.replace("{endpoint}", &json!(path).to_string())
+}
+
+static GRAPHIQL_HTML: &str = r#"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8">
+    <title>GraphiQL</title>
+    <style>body { margin: 0; height: 100vh; } #graphiql { height: 100vh; }</style>
+    <link rel="stylesheet" href="https://unpkg.com/graphiql@3/graphiql.min.css">
+  </head>
+  <body>
+    <div id="graphiql">Loading...</div>
+    <script crossorigin src="https://unpkg.com/react@18/umd/react.production.min.js"></script>
+    <script crossorigin src="https://unpkg.com/react-dom@18/umd/react-dom.production.min.js"></script>
+    <script crossorigin src="https://unpkg.com/graphiql@3/graphiql.min.js"></script>
+    <script>
+      const fetcher = GraphiQL.createFetcher({ url: {endpoint} });
+      ReactDOM.createRoot(document.getElementById('graphiql'))
+        .render(React.createElement(GraphiQL, { fetcher }));
+    </script>
+  </body>
+</html>
+"#;
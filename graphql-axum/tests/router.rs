@@ -0,0 +1,107 @@
+use std::any::Any;
+use std::rc::Rc;
+
+use graphql_axum::GraphQLRouter;
+use graphql_rs::handler::{parse_query, GraphQLHttpHandler};
+use graphql_rs::request::ExecutionConfig;
+use graphql_rs::schema::{GraphQLSchema, GraphQLSchemaType};
+use graphql_rs::types::*;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+struct Viewer(Option<String>);
+
+fn make_schema() -> Rc<GraphQLSchema> {
+  let string = &GraphQLScalarType::string();
+  let query = &GraphQLObjectType::new("Query")
+    .field("hello", |f| f.type_of(string).resolve(|_, _, _| Ok(Box::new("world".to_owned()))))
+    .field("viewer", |f| {
+      f.type_of(&GraphQLOptionalType::output(string)).resolve(|_, context, _| {
+        let viewer = context.downcast_ref::<Viewer>().unwrap();
+        let viewer: Option<Box<dyn Any>> = viewer.0.clone().map(|v| Box::new(v) as Box<dyn Any>);
+        Ok(Box::new(viewer))
+      })
+    })
+    .build();
+  GraphQLSchemaType::new(query).build().unwrap()
+}
+
+fn make_handler() -> GraphQLHttpHandler {
+  GraphQLHttpHandler::new(&Rc::new(ExecutionConfig::new(&make_schema(), parse_query)))
+}
+
+async fn serve(router: GraphQLRouter) -> String {
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let address = listener.local_addr().unwrap().to_string();
+  tokio::spawn(async move { axum::serve(listener, router.build()).await.unwrap() });
+  address
+}
+
+// Sends a raw HTTP/1.1 request, returning the status and the body.
+async fn send(address: &str, method: &str, target: &str, headers: &[&str], body: &str)
+              -> (u16, String) {
+  let mut stream = TcpStream::connect(address).await.unwrap();
+  let mut request = format!("{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n", method, target, address);
+  for header in headers {
+    request.push_str(header);
+    request.push_str("\r\n");
+  }
+  request.push_str(&format!("Content-Length: {}\r\n\r\n{}", body.len(), body));
+  stream.write_all(request.as_bytes()).await.unwrap();
+
+  let mut response = String::new();
+  stream.read_to_string(&mut response).await.unwrap();
+  let status = response[9..12].parse().unwrap();
+  let body = response.split("\r\n\r\n").nth(1).unwrap_or("").to_owned();
+  (status, body)
+}
+
+#[tokio::test]
+async fn test_post() {
+  let router = GraphQLRouter::from_schema(make_schema)
+    .context(|headers| Viewer(headers.get("x-user").map(|u| u.to_str().unwrap().to_owned())));
+  let address = serve(router).await;
+  let json = ["Content-Type: application/json", "Accept: application/graphql-response+json"];
+
+  assert_eq!(send(&address, "POST", "/graphql", &json, r#"{"query": "{ hello }"}"#).await,
+             (200, r#"{"data":{"hello":"world"}}"#.to_owned()));
+
+  let headers = [json[0], json[1], "X-User: leia"];
+  assert_eq!(send(&address, "POST", "/graphql", &headers, r#"{"query": "{ viewer }"}"#).await,
+             (200, r#"{"data":{"viewer":"leia"}}"#.to_owned()));
+  assert_eq!(send(&address, "POST", "/graphql", &json, r#"{"query": "{ viewer }"}"#).await,
+             (200, r#"{"data":{"viewer":null}}"#.to_owned()));
+
+  let syntax_error = r#"{"errors":[{"locations":[{"column":2,"line":1}],"#.to_owned() +
+                     r#""message":"Syntax Error: Expected a selection, found <EOF>."}]}"#;
+  assert_eq!(send(&address, "POST", "/graphql", &json, r#"{"query": "{"}"#).await,
+             (400, syntax_error));
+  assert_eq!(send(&address, "POST", "/other", &json, r#"{"query": "{ hello }"}"#).await.0, 404);
+}
+
+#[tokio::test]
+async fn test_get() {
+  let address = serve(GraphQLRouter::from_schema(make_schema).path("/api")).await;
+
+  assert_eq!(send(&address, "GET", "/api?query=%7B%20hello%20%7D", &["Accept: application/json"], "")
+               .await,
+             (200, r#"{"data":{"hello":"world"}}"#.to_owned()));
+
+  let (status, body) = send(&address, "GET", "/api", &["Accept: text/html,*/*;q=0.8"], "").await;
+  assert_eq!(status, 200);
+  assert!(body.contains("GraphiQL.createFetcher({ url: \"/api\" })"));
+
+  let address = serve(GraphQLRouter::new(make_handler).graphiql(false)).await;
+  assert_eq!(send(&address, "GET", "/graphql", &["Accept: text/html"], "").await.0, 406);
+}
+
+#[tokio::test]
+async fn test_body_limit() {
+  let address = serve(GraphQLRouter::new(make_handler).body_limit(32)).await;
+  let json = ["Content-Type: application/json"];
+
+  assert_eq!(send(&address, "POST", "/graphql", &json, r#"{"query": "{ hello }"}"#).await.0, 200);
+  assert_eq!(send(&address, "POST", "/graphql", &json, r#"{"query": "{ hello }", "variables": {}}"#)
+               .await,
+             (413, r#"{"errors":[{"message":"Request body exceeds the limit of 32 bytes."}]}"#.to_owned()));
+}
@@ -0,0 +1,135 @@
+//! Command line of the tool: a command, its options and its paths.
+
+use std::path::PathBuf;
+
+pub static USAGE: &str = "\
+Usage: graphql <command> [options]
+
+Commands:
+  fmt [--check] [--json] <path>...
+      Format documents in place, or only check that they are formatted.
+      Directories are searched for .graphql and .gql files.
+  validate --schema <file> [--json] <path>...
+      Validate operations against a schema, in the schema definition
+      language or an introspection result in JSON.
+  schema print [--to sdl|json] <file>
+      Print a schema in the other format, or in the one of `--to`.
+  lex [--json] <file>
+      Print the tokens of a document.
+  parse [--json] <file>
+      Print the syntax tree of a document, as graphql-js JSON with `--json`.
+
+Exit codes: 0 when no problem is found, 1 when some is, 2 for usage and IO
+errors.
+";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+  Fmt {
+    check: bool,
+    json: bool,
+    paths: Vec<PathBuf>,
+  },
+  Validate {
+    schema: PathBuf,
+    json: bool,
+    paths: Vec<PathBuf>,
+  },
+  SchemaPrint {
+    to: Option<SchemaFormat>,
+    path: PathBuf,
+  },
+  Lex {
+    json: bool,
+    path: PathBuf,
+  },
+  Parse {
+    json: bool,
+    path: PathBuf,
+  },
+  Help,
+}
+
+/// How schemas are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaFormat {
+  /// The schema definition language, as in `type Query { hero: Hero }`.
+  Sdl,
+  /// An introspection result, as in `{ "__schema": { "types": [...] } }`.
+  Json,
+}
+
+/// Parses the arguments following the name of the binary.
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+  let mut args = args.into_iter();
+  let mut name = match args.next() {
+    Some(name) => name,
+    None => return Err("missing command".to_owned()),
+  };
+  if name == "schema" {
+    match args.next() {
+      Some(ref subcommand) if subcommand == "print" => name = "schema print".to_owned(),
+      Some(subcommand) => return Err(format!("unknown command `schema {}`", subcommand)),
+      None => return Err("missing command after `schema`".to_owned()),
+    }
+  }
+  let allowed: &[&str] = match name.as_ref() {
+    "fmt" => &["--check", "--json"],
+    "validate" => &["--schema", "--json"],
+    "schema print" => &["--to"],
+    "lex" | "parse" => &["--json"],
+    "help" | "-h" | "--help" => return Ok(Command::Help),
+    _ => return Err(format!("unknown command `{}`", name)),
+  };
+
+  let mut check = false;
+  let mut json = false;
+  let mut schema = None;
+  let mut to = None;
+  let mut paths = Vec::new();
+  while let Some(arg) = args.next() {
+    if arg == "-h" || arg == "--help" {
+      return Ok(Command::Help);
+    }
+    if !arg.starts_with("--") {
+      paths.push(PathBuf::from(arg));
+      continue;
+    }
+    if !allowed.contains(&arg.as_str()) {
+      return Err(format!("unknown option `{}` of `{}`", arg, name));
+    }
+    match arg.as_ref() {
+      "--check" => check = true,
+      "--json" => json = true,
+      "--schema" => {
+        schema = Some(PathBuf::from(args.next().ok_or("missing file after `--schema`")?));
+      }
+      _ => {
+        to = match args.next().as_deref() {
+          Some("sdl") => Some(SchemaFormat::Sdl),
+          Some("json") => Some(SchemaFormat::Json),
+          _ => return Err("expected `sdl` or `json` after `--to`".to_owned()),
+        };
+      }
+    }
+  }
+
+  let single = |paths: Vec<PathBuf>| match paths.len() {
+    1 => Ok(paths.into_iter().next().unwrap()),
+    0 => Err(format!("missing file of `{}`", name)),
+    _ => Err(format!("`{}` takes a single file", name)),
+  };
+  match name.as_ref() {
+    _ if paths.is_empty() && (name == "fmt" || name == "validate") => {
+      Err(format!("missing paths of `{}`", name))
+    }
+    "fmt" => Ok(Command::Fmt { check, json, paths }),
+    "validate" => {
+      let schema = schema.ok_or("missing `--schema <file>` of `validate`")?;
+      Ok(Command::Validate { schema, json, paths })
+    }
+    "schema print" => Ok(Command::SchemaPrint { to, path: single(paths)? }),
+    "lex" => Ok(Command::Lex { json, path: single(paths)? }),
+    _ => Ok(Command::Parse { json, path: single(paths)? }),
+  }
+}
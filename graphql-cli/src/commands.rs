@@ -0,0 +1,418 @@
+//! What the commands do, writing their output to `out` and the problems they
+//! find to `err`, or everything to `out` as JSON with `--json`.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use graphql_language::ast::owned;
+use graphql_language::diagnostic::{Diagnostic, Span};
+use graphql_language::format::format;
+use graphql_language::lexer::{Lexer, Token};
+use graphql_language::parser;
+use graphql_language::sdl::{parse_schema, print_schema, SchemaDocument};
+use graphql_language::source::Source;
+use graphql_rs::introspection::{introspect, schema_document};
+use graphql_rs::schema::GraphQLSchema;
+use graphql_rs::sdl::build_schema;
+use graphql_rs::validation;
+use serde_json::Value;
+
+use args::{Command, SchemaFormat};
+use report::Report;
+use {ERROR, FAILURE, SUCCESS};
+
+// Schema read by `load_schema`, with the format it was written in.
+type Loaded = (SchemaFormat, SchemaDocument, Rc<GraphQLSchema>);
+
+/// Runs `command`, returning its exit code. Errors are the ones of writing
+/// the output.
+pub fn run<W: Write, E: Write>(command: &Command, out: &mut W, err: &mut E) -> io::Result<i32> {
+  match *command {
+    Command::Fmt { check, json, ref paths } => fmt(check, json, paths, out, err),
+    Command::Validate { ref schema, json, ref paths } => validate(schema, json, paths, out, err),
+    Command::SchemaPrint { to, ref path } => schema_print(to, path, out, err),
+    Command::Lex { json, ref path } => lex(json, path, out, err),
+    Command::Parse { json, ref path } => parse(json, path, out, err),
+    Command::Help => Ok(SUCCESS),
+  }
+}
+
+// Outcome of a command for one file.
+struct FileResult {
+  file: String,
+  // Whether `fmt` changed the file, or would have under `--check`.
+  changed: Option<bool>,
+  reports: Vec<Report>,
+  // Whether the file could not be read or written.
+  io_error: bool,
+}
+
+// Prints the problems of `results` to `err`, or all of them to `out` as
+// `{ "ok": ..., "files": [{ "file": ..., "diagnostics": [...] }] }`.
+fn print_results<W: Write, E: Write>(results: &[FileResult],
+                                     code: i32,
+                                     json: bool,
+                                     out: &mut W,
+                                     err: &mut E)
+                                     -> io::Result<()> {
+  if json {
+    let files: Vec<_> = results.iter()
+      .map(|result| {
+        let mut file = json!({
+          "file": result.file,
+          "diagnostics": result.reports.iter().map(Report::to_json).collect::<Vec<_>>(),
+        });
+        if let Some(changed) = result.changed {
+          file["changed"] = json!(changed);
+        }
+        file
+      })
+      .collect();
+    let output = json!({ "ok": code == SUCCESS, "files": files });
+    writeln!(out, "{}", serde_json::to_string_pretty(&output)?)
+  } else {
+    for report in results.iter().flat_map(|result| &result.reports) {
+      write!(err, "{}", report.render())?;
+    }
+    Ok(())
+  }
+}
+
+fn fmt<W: Write, E: Write>(check: bool,
+                           json: bool,
+                           paths: &[PathBuf],
+                           out: &mut W,
+                           err: &mut E)
+                           -> io::Result<i32> {
+  let mut code = SUCCESS;
+  let mut results = Vec::new();
+  for path in documents(paths, &mut results) {
+    let file = path.display().to_string();
+    let text = match fs::read_to_string(&path) {
+      Ok(text) => text,
+      Err(e) => {
+        results.push(io_error(&file, "read", &e));
+        continue;
+      }
+    };
+    let mut result = FileResult {
+      file,
+      changed: None,
+      reports: Vec::new(),
+      io_error: false,
+    };
+    match format(&text) {
+      Ok(formatted) => {
+        let changed = formatted != text;
+        result.changed = Some(changed);
+        if changed && check {
+          code = code.max(FAILURE);
+          if !json {
+            writeln!(out, "{} is not formatted", result.file)?;
+          }
+        } else if changed {
+          if let Err(e) = fs::write(&path, formatted) {
+            results.push(io_error(&result.file, "write", &e));
+            continue;
+          }
+          if !json {
+            writeln!(out, "Formatted {}", result.file)?;
+          }
+        }
+      }
+      Err(diagnostics) => {
+        code = code.max(FAILURE);
+        let file = &result.file;
+        result.reports =
+          diagnostics.iter().map(|d| Report::of_diagnostic(file, &text, d)).collect();
+      }
+    }
+    results.push(result);
+  }
+  code = code.max(io_code(&results));
+  print_results(&results, code, json, out, err)?;
+  Ok(code)
+}
+
+fn validate<W: Write, E: Write>(schema: &Path,
+                                json: bool,
+                                paths: &[PathBuf],
+                                out: &mut W,
+                                err: &mut E)
+                                -> io::Result<i32> {
+  let mut results = Vec::new();
+  let schema = match load_schema(schema) {
+    Ok((_, _, schema)) => schema,
+    Err(result) => {
+      results.push(result);
+      let code = io_code(&results).max(FAILURE);
+      print_results(&results, code, json, out, err)?;
+      return Ok(code);
+    }
+  };
+
+  let mut code = SUCCESS;
+  for path in documents(paths, &mut results) {
+    let file = path.display().to_string();
+    let text = match fs::read_to_string(&path) {
+      Ok(text) => text,
+      Err(e) => {
+        results.push(io_error(&file, "read", &e));
+        continue;
+      }
+    };
+    let source = Source::new(&text).name(&file);
+    let reports: Vec<_> = match parser::parse(&source) {
+      Ok(document) => {
+        let mut errors = validation::validate_names(&schema, &document);
+        errors.extend(validation::validate(&schema, &document));
+        errors.into_iter()
+          .map(|e| {
+            let (line, column) = e.locations().first().map_or((1, 1), |l| (l.line, l.column));
+            Report::at_location(&file, &text, line, column, e.message().to_owned())
+          })
+          .collect()
+      }
+      Err(diagnostics) => {
+        diagnostics.iter().map(|d| Report::of_diagnostic(&file, &text, d)).collect()
+      }
+    };
+    if !reports.is_empty() {
+      code = code.max(FAILURE);
+    }
+    results.push(FileResult {
+      file,
+      changed: None,
+      reports,
+      io_error: false,
+    });
+  }
+  code = code.max(io_code(&results));
+  print_results(&results, code, json, out, err)?;
+  Ok(code)
+}
+
+fn schema_print<W: Write, E: Write>(to: Option<SchemaFormat>,
+                                    path: &Path,
+                                    out: &mut W,
+                                    err: &mut E)
+                                    -> io::Result<i32> {
+  let (from, document, schema) = match load_schema(path) {
+    Ok(loaded) => loaded,
+    Err(result) => {
+      let results = [result];
+      let code = io_code(&results).max(FAILURE);
+      print_results(&results, code, false, out, err)?;
+      return Ok(code);
+    }
+  };
+  let to = to.unwrap_or(match from {
+    SchemaFormat::Sdl => SchemaFormat::Json,
+    SchemaFormat::Json => SchemaFormat::Sdl,
+  });
+  match to {
+    SchemaFormat::Sdl => write!(out, "{}", print_schema(&document))?,
+    SchemaFormat::Json => {
+      writeln!(out, "{}", serde_json::to_string_pretty(&introspect(&schema))?)?
+    }
+  }
+  Ok(SUCCESS)
+}
+
+fn lex<W: Write, E: Write>(json: bool, path: &Path, out: &mut W, err: &mut E) -> io::Result<i32> {
+  let file = path.display().to_string();
+  let text = match fs::read_to_string(path) {
+    Ok(text) => text,
+    Err(e) => return print_io_error(io_error(&file, "read", &e), json, out, err),
+  };
+  let source = Source::new(&text);
+  let mut tokens = Vec::new();
+  let mut reports = Vec::new();
+  let mut lexer = Lexer::new(&text);
+  while let Some(token) = lexer.next() {
+    let mut span = Span::new(lexer.position(), lexer.offset());
+    let (kind, value) = match token {
+      Ok(Token::Eof) => ("EOF", String::new()),
+      Ok(Token::Punctuator(..)) => ("Punctuator", text[span.start..span.end].to_owned()),
+      Ok(Token::Name(value, _, _)) => ("Name", value.to_owned()),
+      Ok(Token::IntValue(value, _, _)) => ("Int", value.to_owned()),
+      Ok(Token::FloatValue(value, _, _)) => ("Float", value.to_owned()),
+      Ok(Token::StringValue(value, _, _)) => {
+        // Strings are scanned from after their opening quotes.
+        span.start -= if text[..span.start].ends_with("\"\"\"") { 3 } else { 1 };
+        ("String", value)
+      }
+      // Trivia is not emitted without `with_trivia`.
+      Ok(_) => continue,
+      Err(e) => {
+        let diagnostic = Diagnostic::new(format!("{}.", e), span, e.hint());
+        reports.push(Report::of_diagnostic(&file, &text, &diagnostic));
+        continue;
+      }
+    };
+    let location = source.location(span.start);
+    tokens.push(json!({
+      "kind": kind,
+      "value": value,
+      "start": span.start,
+      "end": span.end,
+      "line": location.line,
+      "column": location.column,
+    }));
+  }
+
+  let code = if reports.is_empty() { SUCCESS } else { FAILURE };
+  if json {
+    let output = json!({
+      "tokens": tokens,
+      "diagnostics": reports.iter().map(Report::to_json).collect::<Vec<_>>(),
+    });
+    writeln!(out, "{}", serde_json::to_string_pretty(&output)?)?;
+  } else {
+    for token in &tokens {
+      let value = match token["kind"].as_str() {
+        Some("EOF") => String::new(),
+        Some("String") => format!(" {}", token["value"]),
+        _ => format!(" {}", token["value"].as_str().unwrap_or("")),
+      };
+      let kind = token["kind"].as_str().unwrap_or("");
+      writeln!(out, "{}:{} {}{}", token["line"], token["column"], kind, value)?;
+    }
+    for report in &reports {
+      write!(err, "{}", report.render())?;
+    }
+  }
+  Ok(code)
+}
+
+fn parse<W: Write, E: Write>(json: bool,
+                             path: &Path,
+                             out: &mut W,
+                             err: &mut E)
+                             -> io::Result<i32> {
+  let file = path.display().to_string();
+  let text = match fs::read_to_string(path) {
+    Ok(text) => text,
+    Err(e) => return print_io_error(io_error(&file, "read", &e), json, out, err),
+  };
+  let source = Source::new(&text).name(&file);
+  let (document, reports) = match parser::parse(&source) {
+    Ok(document) => (Some(owned::Document::from(&document)), Vec::new()),
+    Err(diagnostics) => {
+      (None, diagnostics.iter().map(|d| Report::of_diagnostic(&file, &text, d)).collect())
+    }
+  };
+
+  let code = if reports.is_empty() { SUCCESS } else { FAILURE };
+  if json {
+    let output = json!({
+      "document": document,
+      "diagnostics": reports.iter().map(Report::to_json).collect::<Vec<_>>(),
+    });
+    writeln!(out, "{}", serde_json::to_string_pretty(&output)?)?;
+  } else {
+    if let Some(ref document) = document {
+      writeln!(out, "{:#?}", document)?;
+    }
+    for report in &reports {
+      write!(err, "{}", report.render())?;
+    }
+  }
+  Ok(code)
+}
+
+// Reads and builds the schema of `path`, an introspection result if it has
+// the `.json` extension or starts with `{`.
+fn load_schema(path: &Path) -> Result<Loaded, FileResult> {
+  let file = path.display().to_string();
+  let text = fs::read_to_string(path).map_err(|e| io_error(&file, "read", &e))?;
+  let invalid = |reports| {
+    FileResult {
+      file: file.clone(),
+      changed: None,
+      reports,
+      io_error: false,
+    }
+  };
+  let format = schema_format(path, &text);
+  let document = match format {
+    SchemaFormat::Json => {
+      let result: Value = serde_json::from_str(&text)
+        .map_err(|e| invalid(vec![Report::of_file(&file, format!("Invalid JSON: {}.", e))]))?;
+      schema_document(&result).map_err(|e| invalid(vec![Report::of_file(&file, e)]))?
+    }
+    SchemaFormat::Sdl => {
+      parse_schema(&text).map_err(|d| invalid(vec![Report::of_diagnostic(&file, &text, &d)]))?
+    }
+  };
+  match build_schema(&document) {
+    Ok(schema) => Ok((format, document, schema)),
+    Err(errors) => {
+      Err(invalid(errors.iter().map(|e| Report::of_file(&file, e.message().to_owned())).collect()))
+    }
+  }
+}
+
+fn schema_format(path: &Path, text: &str) -> SchemaFormat {
+  if path.extension().is_some_and(|e| e == "json") || text.trim_start().starts_with('{') {
+    SchemaFormat::Json
+  } else {
+    SchemaFormat::Sdl
+  }
+}
+
+// Files of `paths`, the documents under the directories among them
+// included. Paths which cannot be read are added to `results`.
+fn documents(paths: &[PathBuf], results: &mut Vec<FileResult>) -> Vec<PathBuf> {
+  let mut files = Vec::new();
+  for path in paths {
+    if !path.is_dir() {
+      files.push(path.clone());
+      continue;
+    }
+    let mut found = Vec::new();
+    if let Err(e) = find_documents(path, &mut found) {
+      results.push(io_error(&path.display().to_string(), "read", &e));
+    }
+    found.sort();
+    files.extend(found);
+  }
+  files
+}
+
+fn find_documents(dir: &Path, found: &mut Vec<PathBuf>) -> io::Result<()> {
+  for entry in fs::read_dir(dir)? {
+    let path = entry?.path();
+    if path.is_dir() {
+      find_documents(&path, found)?;
+    } else if path.extension().is_some_and(|e| e == "graphql" || e == "gql") {
+      found.push(path);
+    }
+  }
+  Ok(())
+}
+
+fn io_error(file: &str, action: &str, e: &io::Error) -> FileResult {
+  FileResult {
+    file: file.to_owned(),
+    changed: None,
+    reports: vec![Report::of_file(file, format!("Cannot {} the file: {}.", action, e))],
+    io_error: true,
+  }
+}
+
+fn print_io_error<W: Write, E: Write>(result: FileResult,
+                                      json: bool,
+                                      out: &mut W,
+                                      err: &mut E)
+                                      -> io::Result<i32> {
+  print_results(&[result], ERROR, json, out, err)?;
+  Ok(ERROR)
+}
+
+// `ERROR` if files of `results` could not be read or written.
+fn io_code(results: &[FileResult]) -> i32 {
+  if results.iter().any(|result| result.io_error) { ERROR } else { SUCCESS }
+}
@@ -0,0 +1,26 @@
+//! Command-line tool for GraphQL documents and schemas, the `graphql`
+//! binary.
+//!
+//! `graphql fmt` formats documents, `graphql validate` checks operations
+//! against a schema, `graphql schema print` converts schemas between the
+//! schema definition language and introspection results, and `graphql lex`
+//! and `graphql parse` show the tokens and the syntax tree of a document.
+//! Problems are reported along with the lines they are about, or as JSON
+//! with `--json`.
+//!
+//! The exit code is `SUCCESS` when no problem is found, `FAILURE` when some
+//! is, such as an unformatted document under `--check`, and `ERROR` when the
+//! command line is wrong or files cannot be read or written.
+
+extern crate graphql_language;
+extern crate graphql_rs;
+#[macro_use]
+extern crate serde_json;
+
+pub mod args;
+pub mod commands;
+pub mod report;
+
+pub static SUCCESS: i32 = 0;
+pub static FAILURE: i32 = 1;
+pub static ERROR: i32 = 2;
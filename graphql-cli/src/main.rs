@@ -0,0 +1,33 @@
+extern crate graphql_cli;
+
+use std::env;
+use std::io;
+use std::process;
+
+use graphql_cli::args::{self, Command};
+use graphql_cli::{commands, ERROR, SUCCESS};
+
+fn main() {
+  let code = match args::parse_args(env::args().skip(1)) {
+    Ok(Command::Help) => {
+      print!("{}", args::USAGE);
+      SUCCESS
+    }
+    Ok(command) => {
+      let stdout = io::stdout();
+      let stderr = io::stderr();
+      match commands::run(&command, &mut stdout.lock(), &mut stderr.lock()) {
+        Ok(code) => code,
+        Err(e) => {
+          eprintln!("graphql: {}", e);
+          ERROR
+        }
+      }
+    }
+    Err(e) => {
+      eprint!("graphql: {}\n\n{}", e, args::USAGE);
+      ERROR
+    }
+  };
+  process::exit(code);
+}
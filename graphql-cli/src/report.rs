@@ -0,0 +1,120 @@
+//! Problems found in files, printed as `error:` followed by where they are
+//! and the line they are about, or as JSON.
+
+use graphql_language::diagnostic::{Diagnostic, Span};
+use graphql_language::source::Source;
+use serde_json::Value;
+
+/// Problem of a file, at a line and column of it or about all of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+  pub file: String,
+  /// 1-indexed line and column, none for problems of the whole file.
+  pub location: Option<(usize, usize)>,
+  /// Line of the location, shown under the message.
+  pub source_line: Option<String>,
+  /// Number of characters the problem is about from the column, at least 1.
+  pub length: usize,
+  pub message: String,
+  pub hint: Option<String>,
+}
+
+impl Report {
+  /// Problem of the whole file.
+  pub fn of_file(file: &str, message: String) -> Report {
+    Report {
+      file: file.to_owned(),
+      location: None,
+      source_line: None,
+      length: 1,
+      message,
+      hint: None,
+    }
+  }
+
+  /// Problem found parsing `text`, about the span of the diagnostic.
+  pub fn of_diagnostic(file: &str, text: &str, diagnostic: &Diagnostic) -> Report {
+    let mut report = Report::at(file, text, diagnostic.span, diagnostic.message.clone());
+    if !diagnostic.hint.is_empty() {
+      report.hint = Some(diagnostic.hint.clone());
+    }
+    report
+  }
+
+  /// Problem found at a line and column of `text`, as validation errors
+  /// give them, about the name starting there.
+  pub fn at_location(file: &str,
+                     text: &str,
+                     line: usize,
+                     column: usize,
+                     message: String)
+                     -> Report {
+    let offset = line_offset(text, line, column);
+    let rest = &text[offset..];
+    let sigil = if rest.starts_with(['@', '$']) { 1 } else { 0 };
+    let name = rest[sigil..].find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'));
+    let end = offset + sigil + name.unwrap_or(rest.len() - sigil);
+    Report::at(file, text, Span::new(offset, end), message)
+  }
+
+  fn at(file: &str, text: &str, span: Span, message: String) -> Report {
+    let location = Source::new(text).location(span.start);
+    let line_start = text[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = text[span.start..].find('\n').map_or(text.len(), |i| span.start + i);
+    let end = span.end.min(line_end).max(span.start);
+    Report {
+      file: file.to_owned(),
+      location: Some((location.line, location.column)),
+      source_line: Some(text[line_start..line_end].trim_end_matches('\r').to_owned()),
+      length: text[span.start..end].chars().count().max(1),
+      message,
+      hint: None,
+    }
+  }
+
+  /// Report as printed to the terminal, ending with a new line.
+  pub fn render(&self) -> String {
+    let (line, column) = match self.location {
+      Some(location) => location,
+      None => return format!("error: {}\n --> {}\n", self.message, self.file),
+    };
+    let gutter = " ".repeat(line.to_string().len());
+    let mut rendered = format!("error: {}\n{}--> {}:{}:{}\n", self.message, gutter, self.file,
+                               line, column);
+    if let Some(ref source_line) = self.source_line {
+      // Tabs are kept under tabs so that the carets line up.
+      let indent: String = source_line.chars()
+        .take(column - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+      rendered.push_str(&format!("{} |\n{} | {}\n{} | {}{}\n", gutter, line, source_line,
+                                 gutter, indent, "^".repeat(self.length)));
+    }
+    if let Some(ref hint) = self.hint {
+      rendered.push_str(&format!("{} = hint: {}\n", gutter, hint));
+    }
+    rendered
+  }
+
+  pub fn to_json(&self) -> Value {
+    json!({
+      "file": self.file,
+      "line": self.location.map(|l| l.0),
+      "column": self.location.map(|l| l.1),
+      "message": self.message,
+      "hint": self.hint,
+    })
+  }
+}
+
+// Byte offset of a 1-indexed line and column of `text`, its end past them.
+fn line_offset(text: &str, line: usize, column: usize) -> usize {
+  let mut offset = 0;
+  for _ in 1..line {
+    match text[offset..].find('\n') {
+      Some(i) => offset += i + 1,
+      None => return text.len(),
+    }
+  }
+  text[offset..].char_indices().nth(column - 1).map_or(text.len(), |(i, _)| offset + i)
+}
@@ -0,0 +1,222 @@
+extern crate serde_json;
+extern crate tempfile;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::Value;
+
+static SCHEMA: &str = r#"
+type Query {
+  hero(id: ID!): Hero
+}
+
+"A hero of the saga"
+type Hero {
+  name: String!
+  age: Int @deprecated(reason: "Use birthYear")
+}
+"#;
+
+// Runs the binary in `dir`, returning its exit code, output and errors.
+fn graphql(dir: &Path, args: &[&str]) -> (i32, String, String) {
+  let output = Command::new(env!("CARGO_BIN_EXE_graphql"))
+    .args(args)
+    .current_dir(dir)
+    .output()
+    .unwrap();
+  (output.status.code().unwrap(),
+   String::from_utf8(output.stdout).unwrap(),
+   String::from_utf8(output.stderr).unwrap())
+}
+
+#[test]
+fn formats_documents() {
+  let dir = tempfile::tempdir().unwrap();
+  let dir = dir.path();
+  fs::create_dir(dir.join("src")).unwrap();
+  fs::write(dir.join("src/a.graphql"), "{hero(id:1){name}}").unwrap();
+  fs::write(dir.join("src/b.gql"), "{\n  hero(id: 1) {\n    name\n  }\n}\n").unwrap();
+  fs::write(dir.join("schema.graphql"), SCHEMA).unwrap();
+
+  let (code, out, _) = graphql(dir, &["fmt", "--check", "src"]);
+  assert_eq!((1, "src/a.graphql is not formatted\n"), (code, out.as_ref()));
+  assert_eq!("{hero(id:1){name}}", fs::read_to_string(dir.join("src/a.graphql")).unwrap());
+
+  let (code, out, _) = graphql(dir, &["fmt", "src"]);
+  assert_eq!((0, "Formatted src/a.graphql\n"), (code, out.as_ref()));
+  assert_eq!("{\n  hero(id: 1) {\n    name\n  }\n}\n",
+             fs::read_to_string(dir.join("src/a.graphql")).unwrap());
+  let (code, out, _) = graphql(dir, &["fmt", "--check", "--json", "src"]);
+  assert_eq!(0, code);
+  let output: Value = serde_json::from_str(&out).unwrap();
+  assert_eq!(serde_json::json!([false, false]),
+             serde_json::json!([output["files"][0]["changed"], output["files"][1]["changed"]]));
+
+  let (code, out, _) = graphql(dir, &["fmt", "schema.graphql"]);
+  assert_eq!((0, "Formatted schema.graphql\n"), (code, out.as_ref()));
+  assert_eq!(SCHEMA.trim_start(), fs::read_to_string(dir.join("schema.graphql")).unwrap());
+
+  fs::write(dir.join("c.graphql"), "{\n  hero(id: 1) {\n  }\n}\n").unwrap();
+  let (code, _, err) = graphql(dir, &["fmt", "c.graphql"]);
+  assert_eq!(1, code);
+  assert_eq!("error: Expected a selection, found \"}\".\n \
+              --> c.graphql:3:3\n  \
+              |\n\
+              3 |   }\n  \
+              |   ^\n  \
+              = hint: Select a field, as in `name`, or a fragment, as in `...Name`.\n",
+             err);
+
+  let (code, _, err) = graphql(dir, &["fmt", "missing.graphql"]);
+  assert_eq!(2, code);
+  assert!(err.starts_with("error: Cannot read the file: "));
+}
+
+#[test]
+fn validates_operations() {
+  let dir = tempfile::tempdir().unwrap();
+  let dir = dir.path();
+  fs::write(dir.join("schema.graphql"), SCHEMA).unwrap();
+  fs::write(dir.join("query.graphql"), "query {\n  hero(id: 1) {\n    nme\n  }\n}\n").unwrap();
+  fs::write(dir.join("valid.graphql"), "{ hero(id: 1) { name } }\n").unwrap();
+
+  let (code, _, err) = graphql(dir, &["validate", "--schema", "schema.graphql", "query.graphql"]);
+  assert_eq!(1, code);
+  assert_eq!("error: Cannot query field \"nme\" on type \"Hero\".\n \
+              --> query.graphql:3:5\n  \
+              |\n\
+              3 |     nme\n  \
+              |     ^^^\n",
+             err);
+
+  let args = ["validate", "--json", "--schema", "schema.graphql", "valid.graphql", "query.graphql"];
+  let (code, out, _) = graphql(dir, &args);
+  assert_eq!(1, code);
+  let output: Value = serde_json::from_str(&out).unwrap();
+  assert_eq!(serde_json::json!({
+               "ok": false,
+               "files": [
+                 { "file": "valid.graphql", "diagnostics": [] },
+                 {
+                   "file": "query.graphql",
+                   "diagnostics": [{ "file": "query.graphql", "line": 3, "column": 5,
+                                     "message": "Cannot query field \"nme\" on type \"Hero\".",
+                                     "hint": null }]
+                 }
+               ]
+             }),
+             output);
+
+  // Schemas may be introspection results too.
+  let (code, out, _) = graphql(dir, &["schema", "print", "schema.graphql"]);
+  assert_eq!(0, code);
+  fs::write(dir.join("schema.json"), out).unwrap();
+  let args = ["validate", "--schema", "schema.json", "valid.graphql"];
+  assert_eq!((0, String::new(), String::new()), graphql(dir, &args));
+
+  fs::write(dir.join("invalid.graphql"), "type Query { hero: Hero }").unwrap();
+  let (code, _, err) = graphql(dir, &["validate", "--schema", "invalid.graphql", "valid.graphql"]);
+  assert_eq!(1, code);
+  assert_eq!("error: Field Query.hero references unknown type Hero.\n --> invalid.graphql\n", err);
+}
+
+#[test]
+fn prints_schemas() {
+  let dir = tempfile::tempdir().unwrap();
+  let dir = dir.path();
+  fs::write(dir.join("schema.graphql"), SCHEMA).unwrap();
+
+  let (code, out, _) = graphql(dir, &["schema", "print", "schema.graphql"]);
+  assert_eq!(0, code);
+  let result: Value = serde_json::from_str(&out).unwrap();
+  assert_eq!(serde_json::json!({ "name": "Query" }), result["__schema"]["queryType"]);
+  fs::write(dir.join("schema.json"), out).unwrap();
+
+  let printed = "type Query {\n  hero(id: ID!): Hero\n}\n\n\"\"\"A hero of the saga\"\"\"\n\
+                 type Hero {\n  name: String!\n  \
+                 age: Int @deprecated(reason: \"Use birthYear\")\n}\n";
+  assert_eq!((0, printed.to_owned(), String::new()),
+             graphql(dir, &["schema", "print", "schema.json"]));
+  assert_eq!((0, printed.to_owned(), String::new()),
+             graphql(dir, &["schema", "print", "--to", "sdl", "schema.graphql"]));
+
+  fs::write(dir.join("broken.json"), r#"{ "data": { "types": [] } }"#).unwrap();
+  let (code, _, err) = graphql(dir, &["schema", "print", "broken.json"]);
+  assert_eq!(1, code);
+  assert_eq!("error: Expected an introspection result with a \"__schema\" object.\n \
+              --> broken.json\n",
+             err);
+}
+
+#[test]
+fn round_trips_schemas_through_introspection() {
+  let dir = tempfile::tempdir().unwrap();
+  let dir = dir.path();
+  let schema = "\"\"\"The saga\"\"\"\nschema {\n  query: Root\n}\n\n\
+                \"\"\"Only users in the role may see it.\"\"\"\n\
+                directive @auth(role: String! = \"admin\", scope: String @deprecated) \
+                repeatable on FIELD_DEFINITION | OBJECT\n\n\
+                type Root {\n  hero: String\n}\n\n\
+                scalar Date\n\n\
+                union U = Root\n";
+  fs::write(dir.join("schema.graphql"), schema).unwrap();
+
+  let (code, out, _) = graphql(dir, &["schema", "print", "schema.graphql"]);
+  assert_eq!(0, code);
+  fs::write(dir.join("schema.json"), out).unwrap();
+  assert_eq!((0, schema.to_owned(), String::new()),
+             graphql(dir, &["schema", "print", "schema.json"]));
+}
+
+#[test]
+fn dumps_tokens_and_syntax_trees() {
+  let dir = tempfile::tempdir().unwrap();
+  let dir = dir.path();
+  fs::write(dir.join("query.graphql"), "{ hero(id: \"1\") }\n").unwrap();
+
+  let (code, out, _) = graphql(dir, &["lex", "query.graphql"]);
+  assert_eq!(0, code);
+  assert_eq!("1:1 Punctuator {\n1:3 Name hero\n1:7 Punctuator (\n1:8 Name id\n\
+              1:10 Punctuator :\n1:12 String \"1\"\n1:15 Punctuator )\n1:17 Punctuator }\n\
+              2:1 EOF\n",
+             out);
+  let (code, out, _) = graphql(dir, &["lex", "--json", "query.graphql"]);
+  assert_eq!(0, code);
+  let output: Value = serde_json::from_str(&out).unwrap();
+  assert_eq!(serde_json::json!({ "kind": "Name", "value": "hero", "start": 2, "end": 6,
+                                 "line": 1, "column": 3 }),
+             output["tokens"][1]);
+
+  let (code, out, _) = graphql(dir, &["parse", "--json", "query.graphql"]);
+  assert_eq!(0, code);
+  let output: Value = serde_json::from_str(&out).unwrap();
+  let definition = &output["document"]["definitions"][0];
+  assert_eq!("OperationDefinition", definition["kind"]);
+  assert_eq!("hero", definition["selectionSet"]["selections"][0]["name"]["value"]);
+  assert_eq!(serde_json::json!([]), output["diagnostics"]);
+
+  fs::write(dir.join("broken.graphql"), "{ hero(id: \"1) }\n").unwrap();
+  let (code, out, _) = graphql(dir, &["parse", "--json", "broken.graphql"]);
+  assert_eq!(1, code);
+  let output: Value = serde_json::from_str(&out).unwrap();
+  assert_eq!(Value::Null, output["document"]);
+  assert_eq!("Unterminated string.", output["diagnostics"][0]["message"]);
+}
+
+#[test]
+fn rejects_wrong_command_lines() {
+  let dir = tempfile::tempdir().unwrap();
+  let dir = dir.path();
+  for args in [&["frobnicate"][..], &["fmt"], &["fmt", "--to", "sdl", "a.graphql"],
+               &["validate", "a.graphql"], &["lex", "a.graphql", "b.graphql"],
+               &["schema", "print", "--to", "xml", "a.graphql"]] {
+    let (code, out, err) = graphql(dir, args);
+    assert_eq!((2, ""), (code, out.as_ref()), "{:?}", args);
+    assert!(err.contains("Usage: graphql <command>"), "{:?}", args);
+  }
+  let (code, out, _) = graphql(dir, &["--help"]);
+  assert_eq!(0, code);
+  assert!(out.starts_with("Usage: graphql <command>"));
+}
@@ -0,0 +1,120 @@
+use proc_macro2::TokenStream;
+use syn::{Attribute, Expr, ExprLit, ExprPath, Lit, LitStr, Meta, Result};
+use syn::meta::ParseNestedMeta;
+
+/// Options collected from doc comments and `#[graphql(...)]` attributes.
+#[derive(Default)]
+pub struct Attrs {
+  pub name: Option<String>,
+  pub description: Option<String>,
+  pub deprecated: Option<String>,
+  pub placeholder: Option<String>,
+  /// Expression building the default `Value`.
+  pub default: Option<TokenStream>,
+  pub skip: bool,
+  /// Marks the resolver parameter which receives the execution context.
+  pub context: bool,
+}
+
+impl Attrs {
+  pub fn parse(attrs: &[Attribute]) -> Result<Attrs> {
+    let mut result = Attrs::default();
+    let mut doc = Vec::new();
+
+    for attr in attrs {
+      if attr.path().is_ident("doc") {
+        if let Meta::NameValue(ref nv) = attr.meta {
+          if let Expr::Lit(ExprLit { lit: Lit::Str(ref s), .. }) = nv.value {
+            doc.push(s.value().trim().to_owned());
+          }
+        }
+      } else if attr.path().is_ident("graphql") {
+        attr.parse_nested_meta(|meta| result.parse_meta(meta))?;
+      }
+    }
+
+    if result.description.is_none() {
+      let doc = doc.join("\n").trim().to_owned();
+      if !doc.is_empty() {
+        result.description = Some(doc);
+      }
+    }
+    Ok(result)
+  }
+
+  /// Parses a single `key = value` or `key` item of a `graphql` attribute.
+  pub fn parse_meta(&mut self, meta: ParseNestedMeta) -> Result<()> {
+    if meta.path.is_ident("name") {
+      self.name = Some(meta.value()?.parse::<LitStr>()?.value());
+    } else if meta.path.is_ident("description") {
+      self.description = Some(meta.value()?.parse::<LitStr>()?.value());
+    } else if meta.path.is_ident("deprecated") {
+      self.deprecated = Some(meta.value()?.parse::<LitStr>()?.value());
+    } else if meta.path.is_ident("placeholder") {
+      self.placeholder = Some(meta.value()?.parse::<LitStr>()?.value());
+    } else if meta.path.is_ident("default") {
+      let value = meta.value()?;
+      self.default = Some(if value.peek(Lit) {
+        match value.parse::<Lit>()? {
+          Lit::Str(s) => quote! { ::graphql_rs::value::Value::String(#s.to_owned()) },
+          Lit::Int(i) => quote! { ::graphql_rs::value::Value::Int(#i) },
+          Lit::Float(f) => quote! { ::graphql_rs::value::Value::Float(#f) },
+          Lit::Bool(b) => quote! { ::graphql_rs::value::Value::Boolean(#b) },
+          _ => return Err(meta.error("unsupported default value")),
+        }
+      } else {
+        // `null`, or the name of an enum value such as `RED`.
+        let path = value.parse::<ExprPath>()?;
+        match path.path.get_ident() {
+          Some(ident) if ident == "null" => quote! { ::graphql_rs::value::Value::Null },
+          Some(ident) => {
+            let name = ident.to_string();
+            quote! { ::graphql_rs::value::Value::Enum(#name.to_owned()) }
+          }
+          None => return Err(meta.error("unsupported default value")),
+        }
+      });
+    } else if meta.path.is_ident("skip") {
+      self.skip = true;
+    } else if meta.path.is_ident("context") {
+      self.context = true;
+    } else {
+      return Err(meta.error("unsupported graphql attribute"));
+    }
+    Ok(())
+  }
+}
+
+/// Removes `#[graphql(...)]` attributes which have already been consumed.
+pub fn strip(attrs: &mut Vec<Attribute>) {
+  attrs.retain(|attr| !attr.path().is_ident("graphql"));
+}
+
+/// `snake_case` field names are exposed as `camelCase`.
+pub fn to_camel_case(s: &str) -> String {
+  let mut result = String::with_capacity(s.len());
+  let mut upper = false;
+  for (i, c) in s.trim_start_matches("r#").chars().enumerate() {
+    if c == '_' && i > 0 {
+      upper = true;
+    } else if upper {
+      result.extend(c.to_uppercase());
+      upper = false;
+    } else {
+      result.push(c);
+    }
+  }
+  result
+}
+
+/// `CamelCase` variant names are exposed as `SCREAMING_SNAKE_CASE`.
+pub fn to_screaming_snake_case(s: &str) -> String {
+  let mut result = String::with_capacity(s.len() + 4);
+  for (i, c) in s.chars().enumerate() {
+    if c.is_uppercase() && i > 0 {
+      result.push('_');
+    }
+    result.extend(c.to_uppercase());
+  }
+  result
+}
@@ -0,0 +1,73 @@
+use proc_macro2::TokenStream;
+use syn::{Data, DeriveInput, Error, Fields, Result};
+
+use attrs::{to_screaming_snake_case, Attrs};
+
+pub fn expand(ast: &DeriveInput) -> Result<TokenStream> {
+  let variants = match ast.data {
+    Data::Enum(ref data) => &data.variants,
+    _ => return Err(Error::new_spanned(ast, "GraphQLEnum can only be derived for enums")),
+  };
+
+  let attrs = Attrs::parse(&ast.attrs)?;
+  let ident = &ast.ident;
+  let name = attrs.name.unwrap_or_else(|| ident.to_string());
+  let description = attrs.description.map(|d| quote! { .description(#d) });
+
+  let mut value_defs = Vec::new();
+  let mut from_arms = Vec::new();
+  for variant in variants {
+    if !matches!(variant.fields, Fields::Unit) {
+      return Err(Error::new_spanned(variant, "GraphQLEnum variants cannot have fields"));
+    }
+    let variant_attrs = Attrs::parse(&variant.attrs)?;
+    let variant_ident = &variant.ident;
+    let value_name = variant_attrs.name
+      .unwrap_or_else(|| to_screaming_snake_case(&variant_ident.to_string()));
+    let description = variant_attrs.description.map(|d| quote! { .description(#d) });
+    let deprecated = variant_attrs.deprecated.map(|r| quote! { .mark_deprecated(#r) });
+    value_defs.push(quote! {
+      .value(#value_name, |v| v.internal_value(#ident::#variant_ident) #description #deprecated)
+    });
+    from_arms.push(quote! {
+      #value_name => Some(#ident::#variant_ident),
+    });
+  }
+
+  Ok(quote! {
+    impl ::graphql_rs::types::AsOutputType for #ident {
+      type Type = ::graphql_rs::types::GraphQLEnum;
+
+      fn output_type() -> ::std::rc::Rc<::graphql_rs::types::GraphQLEnum> {
+        ::graphql_rs::types::shared_type::<Self, _, _>(|| {
+          ::graphql_rs::types::GraphQLEnumType::new(#name)
+            #description
+            #(#value_defs)*
+            .build()
+        })
+      }
+    }
+
+    impl ::graphql_rs::types::AsInputType for #ident {
+      type Type = ::graphql_rs::types::GraphQLEnum;
+
+      fn input_type() -> ::std::rc::Rc<::graphql_rs::types::GraphQLEnum> {
+        <#ident as ::graphql_rs::types::AsOutputType>::output_type()
+      }
+    }
+
+    impl ::graphql_rs::value::FromInputValue for #ident {
+      fn from_input_value(value: &::graphql_rs::value::Value) -> Option<Self> {
+        match *value {
+          ::graphql_rs::value::Value::Enum(ref name) => {
+            match name.as_ref() {
+              #(#from_arms)*
+              _ => None,
+            }
+          }
+          _ => None,
+        }
+      }
+    }
+  })
+}
@@ -0,0 +1,79 @@
+use proc_macro2::TokenStream;
+use syn::{Data, DeriveInput, Error, Fields, Result};
+
+use attrs::{to_camel_case, Attrs};
+
+pub fn expand(ast: &DeriveInput) -> Result<TokenStream> {
+  let fields = match ast.data {
+    Data::Struct(ref data) => {
+      match data.fields {
+        Fields::Named(ref fields) => &fields.named,
+        _ => return Err(Error::new_spanned(ast, "GraphQLInputObject requires named fields")),
+      }
+    }
+    _ => {
+      return Err(Error::new_spanned(ast, "GraphQLInputObject can only be derived for structs"))
+    }
+  };
+
+  let attrs = Attrs::parse(&ast.attrs)?;
+  let ident = &ast.ident;
+  let name = attrs.name.unwrap_or_else(|| ident.to_string());
+  let description = attrs.description.map(|d| quote! { .description(#d) });
+
+  let mut field_defs = Vec::new();
+  let mut field_values = Vec::new();
+  for field in fields {
+    let field_attrs = Attrs::parse(&field.attrs)?;
+    if field_attrs.skip || field_attrs.placeholder.is_some() {
+      return Err(Error::new_spanned(field, "unsupported attribute on input object field"));
+    }
+    let field_ident = field.ident.as_ref().unwrap();
+    let field_name = field_attrs.name.unwrap_or_else(|| to_camel_case(&field_ident.to_string()));
+    let ty = &field.ty;
+    let field_description = field_attrs.description.map(|d| quote! { .description(#d) });
+    let deprecated = field_attrs.deprecated.map(|r| quote! { .mark_deprecated(#r) });
+    let default_value = field_attrs.default.as_ref().map(|d| quote! { .default_value(#d) });
+    let default = field_attrs.default.unwrap_or_else(|| quote! { ::graphql_rs::value::Value::Null });
+    field_defs.push(quote! {
+      .field(#field_name, |f| {
+        f.type_of(&<#ty as ::graphql_rs::types::AsInputType>::input_type())
+          #field_description
+          #deprecated
+          #default_value
+      })
+    });
+    field_values.push(quote! {
+      #field_ident: ::graphql_rs::value::FromInputValue::from_input_value(
+        fields.get(#field_name).unwrap_or(&#default))?,
+    });
+  }
+
+  Ok(quote! {
+    impl ::graphql_rs::types::AsInputType for #ident {
+      type Type = ::graphql_rs::types::GraphQLInputObject;
+
+      fn input_type() -> ::std::rc::Rc<::graphql_rs::types::GraphQLInputObject> {
+        ::graphql_rs::types::shared_type::<Self, _, _>(|| {
+          ::graphql_rs::types::GraphQLInputObjectType::new(#name)
+            #description
+            #(#field_defs)*
+            .build()
+        })
+      }
+    }
+
+    impl ::graphql_rs::value::FromInputValue for #ident {
+      fn from_input_value(value: &::graphql_rs::value::Value) -> Option<Self> {
+        match *value {
+          ::graphql_rs::value::Value::Object(ref fields) => {
+            Some(#ident {
+              #(#field_values)*
+            })
+          }
+          _ => None,
+        }
+      }
+    }
+  })
+}
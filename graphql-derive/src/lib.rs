@@ -0,0 +1,52 @@
+//! Custom derives which declare `graphql_rs::types` definitions from Rust
+//! structs and enums.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+mod attrs;
+mod enums;
+mod input_object;
+mod object;
+mod object_impl;
+
+use proc_macro::TokenStream;
+use syn::{DeriveInput, ItemImpl};
+
+#[proc_macro_derive(GraphQLObject, attributes(graphql))]
+pub fn derive_object(input: TokenStream) -> TokenStream {
+  let ast = syn::parse_macro_input!(input as DeriveInput);
+  object::expand(&ast).unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+/// Each variant is bound as the internal value of its enum value, so the enum
+/// must also implement `PartialEq`.
+#[proc_macro_derive(GraphQLEnum, attributes(graphql))]
+pub fn derive_enum(input: TokenStream) -> TokenStream {
+  let ast = syn::parse_macro_input!(input as DeriveInput);
+  enums::expand(&ast).unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+#[proc_macro_derive(GraphQLInputObject, attributes(graphql))]
+pub fn derive_input_object(input: TokenStream) -> TokenStream {
+  let ast = syn::parse_macro_input!(input as DeriveInput);
+  input_object::expand(&ast).unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+/// Declares a `GraphQLObject` from an `impl` block. Every `&self` method
+/// becomes a field whose arguments are taken from the method parameters, and
+/// whose resolver coerces those arguments before calling the method. A
+/// parameter marked `#[graphql(context)]`, such as `#[graphql(context)] ctx:
+/// &Context`, receives the execution context instead. Methods returning a
+/// `Result` may use any error type which converts into `GraphQLError`.
+#[proc_macro_attribute]
+pub fn graphql_object(args: TokenStream, input: TokenStream) -> TokenStream {
+  let mut attrs = attrs::Attrs::default();
+  let parser = syn::meta::parser(|meta| attrs.parse_meta(meta));
+  syn::parse_macro_input!(args with parser);
+  let item = syn::parse_macro_input!(input as ItemImpl);
+  object_impl::expand(attrs, item).unwrap_or_else(|e| e.to_compile_error()).into()
+}
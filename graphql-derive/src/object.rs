@@ -0,0 +1,73 @@
+use proc_macro2::TokenStream;
+use syn::{parse_quote, Data, DeriveInput, Error, Fields, Result};
+
+use attrs::{to_camel_case, Attrs};
+
+pub fn expand(ast: &DeriveInput) -> Result<TokenStream> {
+  let fields = match ast.data {
+    Data::Struct(ref data) => {
+      match data.fields {
+        Fields::Named(ref fields) => &fields.named,
+        _ => return Err(Error::new_spanned(ast, "GraphQLObject requires named fields")),
+      }
+    }
+    _ => return Err(Error::new_spanned(ast, "GraphQLObject can only be derived for structs")),
+  };
+
+  let attrs = Attrs::parse(&ast.attrs)?;
+  let ident = &ast.ident;
+  let name = attrs.name.unwrap_or_else(|| ident.to_string());
+  let description = attrs.description.map(|d| quote! { .description(#d) });
+
+  let mut field_defs = Vec::new();
+  for field in fields {
+    let field_attrs = Attrs::parse(&field.attrs)?;
+    if field_attrs.skip {
+      continue;
+    }
+    let field_name = field_attrs.name
+      .unwrap_or_else(|| to_camel_case(&field.ident.as_ref().unwrap().to_string()));
+    let ty = &field.ty;
+    let type_of = match field_attrs.placeholder {
+      Some(target) => quote! { f.placeholder_type_of(#target) },
+      None => {
+        quote! { f.type_of(&<#ty as ::graphql_rs::types::AsOutputType>::output_type()) }
+      }
+    };
+    let description = field_attrs.description.map(|d| quote! { .description(#d) });
+    let deprecated = field_attrs.deprecated.map(|r| quote! { .mark_deprecated(#r) });
+    field_defs.push(quote! {
+      .field(#field_name, |f| #type_of #description #deprecated)
+    });
+  }
+
+  // Values of a generic struct cannot be recognised without knowing its
+  // type parameters.
+  let is_type_of = if ast.generics.params.is_empty() {
+    Some(quote! { .is_type_of(|value| value.is::<#ident>()) })
+  } else {
+    None
+  };
+
+  // Types are shared by the `TypeId` of the struct, which needs it `'static`.
+  let mut generics = ast.generics.clone();
+  if !generics.params.is_empty() {
+    generics.make_where_clause().predicates.push(parse_quote! { Self: 'static });
+  }
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  Ok(quote! {
+    impl #impl_generics ::graphql_rs::types::AsOutputType for #ident #ty_generics #where_clause {
+      type Type = ::graphql_rs::types::GraphQLObject;
+
+      fn output_type() -> ::std::rc::Rc<::graphql_rs::types::GraphQLObject> {
+        ::graphql_rs::types::shared_type::<Self, _, _>(|| {
+          ::graphql_rs::types::GraphQLObjectType::new(#name)
+            #description
+            #(#field_defs)*
+            #is_type_of
+            .build()
+        })
+      }
+    }
+  })
+}
@@ -0,0 +1,201 @@
+use proc_macro2::TokenStream;
+use syn::{Error, FnArg, GenericArgument, ImplItem, ItemImpl, Pat, PathArguments, Result,
+          ReturnType, Type};
+
+use attrs::{self, to_camel_case, Attrs};
+
+pub fn expand(mut attrs: Attrs, mut item: ItemImpl) -> Result<TokenStream> {
+  if item.trait_.is_some() || !item.generics.params.is_empty() {
+    return Err(Error::new_spanned(&item.self_ty,
+                                  "graphql_object requires an inherent impl without generics"));
+  }
+
+  let self_ty = item.self_ty.clone();
+  let impl_attrs = Attrs::parse(&item.attrs)?;
+  let name = match attrs.name.take() {
+    Some(name) => name,
+    None => {
+      match *self_ty {
+        Type::Path(ref p) => p.path.segments.last().unwrap().ident.to_string(),
+        _ => return Err(Error::new_spanned(&self_ty, "cannot infer GraphQL object name")),
+      }
+    }
+  };
+  let description = attrs.description.or(impl_attrs.description).map(|d| quote! { .description(#d) });
+
+  let mut field_defs = Vec::new();
+  for impl_item in &mut item.items {
+    let method = match *impl_item {
+      ImplItem::Fn(ref mut method) => method,
+      _ => continue,
+    };
+    let method_attrs = Attrs::parse(&method.attrs)?;
+    attrs::strip(&mut method.attrs);
+    if method_attrs.skip {
+      continue;
+    }
+
+    let method_ident = method.sig.ident.clone();
+    let field_name = method_attrs.name
+      .unwrap_or_else(|| to_camel_case(&method_ident.to_string()));
+
+    let mut has_receiver = false;
+    let mut context_ty = None;
+    let mut arg_defs = Vec::new();
+    let mut arg_values = Vec::new();
+    let mut call_args = Vec::new();
+    for input in &mut method.sig.inputs {
+      let pat_ty = match *input {
+        FnArg::Receiver(ref receiver) => {
+          if receiver.reference.is_none() || receiver.mutability.is_some() {
+            return Err(Error::new_spanned(receiver, "resolvers must take `&self`"));
+          }
+          has_receiver = true;
+          continue;
+        }
+        FnArg::Typed(ref mut pat_ty) => pat_ty,
+      };
+      let arg_attrs = Attrs::parse(&pat_ty.attrs)?;
+      attrs::strip(&mut pat_ty.attrs);
+
+      // The parameter marked `#[graphql(context)]` receives the execution
+      // context, by reference. Other parameters are arguments, which are
+      // owned values.
+      if arg_attrs.context {
+        let r = match *pat_ty.ty {
+          Type::Reference(ref r) if r.mutability.is_none() => r,
+          _ => {
+            return Err(Error::new_spanned(&pat_ty.ty,
+                                          "the context parameter must be a shared reference"))
+          }
+        };
+        if context_ty.is_some() {
+          return Err(Error::new_spanned(pat_ty, "only one context parameter is allowed"));
+        }
+        context_ty = Some(r.elem.clone());
+        call_args.push(quote! { context });
+        continue;
+      }
+      if let Type::Reference(_) = *pat_ty.ty {
+        return Err(Error::new_spanned(&pat_ty.ty,
+                                      "arguments cannot be references; mark the context \
+                                       parameter with `#[graphql(context)]`"));
+      }
+
+      let arg_ident = match *pat_ty.pat {
+        Pat::Ident(ref p) => p.ident.clone(),
+        _ => return Err(Error::new_spanned(&pat_ty.pat, "argument must be a plain identifier")),
+      };
+      let arg_name = arg_attrs.name.unwrap_or_else(|| to_camel_case(&arg_ident.to_string()));
+      let ty = &pat_ty.ty;
+      let arg_description = arg_attrs.description.map(|d| quote! { .description(#d) });
+      let arg_deprecated = arg_attrs.deprecated.map(|r| quote! { .mark_deprecated(#r) });
+      let default_value = arg_attrs.default.as_ref().map(|d| quote! { .default_value(#d) });
+      let default = arg_attrs.default.unwrap_or_else(|| quote! { ::graphql_rs::value::Value::Null });
+      arg_defs.push(quote! {
+        .arg(#arg_name, |a| {
+          a.type_of(&<#ty as ::graphql_rs::types::AsInputType>::input_type())
+            #arg_description
+            #arg_deprecated
+            #default_value
+        })
+      });
+      arg_values.push(quote! {
+        let #arg_ident = <#ty as ::graphql_rs::value::FromInputValue>::from_input_value(
+            args.get(#arg_name).unwrap_or(&#default))
+          .ok_or_else(|| format!("Invalid value for argument {:} of field {:}.",
+                                 #arg_name, #field_name))?;
+      });
+      call_args.push(quote! { #arg_ident });
+    }
+    if !has_receiver {
+      return Err(Error::new_spanned(&method.sig, "resolvers must take `&self`"));
+    }
+
+    let context = context_ty.map(|ty| {
+      quote! {
+        let context = context.downcast_ref::<#ty>()
+          .ok_or_else(|| format!("Unexpected context type for field {:}.", #field_name))?;
+      }
+    });
+
+    // Parameters of the resolver closure, left unnamed when unused.
+    let context_param = if context.is_some() { quote! { context } } else { quote! { _ } };
+    let args_param = if arg_values.is_empty() { quote! { _ } } else { quote! { args } };
+
+    let (field_ty, returns_result) = match method.sig.output {
+      ReturnType::Type(_, ref ty) => {
+        match result_ok_type(ty) {
+          Some(ok_ty) => (ok_ty.clone(), true),
+          None => ((**ty).clone(), false),
+        }
+      }
+      ReturnType::Default => {
+        return Err(Error::new_spanned(&method.sig, "resolvers must return a value"));
+      }
+    };
+    let call = if returns_result {
+      quote! { source.#method_ident(#(#call_args),*)? }
+    } else {
+      quote! { source.#method_ident(#(#call_args),*) }
+    };
+
+    let field_description = method_attrs.description.map(|d| quote! { .description(#d) });
+    let deprecated = method_attrs.deprecated.map(|r| quote! { .mark_deprecated(#r) });
+    field_defs.push(quote! {
+      .field(#field_name, |f| {
+        f.type_of(&<#field_ty as ::graphql_rs::types::AsOutputType>::output_type())
+          #field_description
+          #deprecated
+          #(#arg_defs)*
+          .resolve(|source, #context_param, #args_param| {
+            let source = source.downcast_ref::<#self_ty>()
+              .ok_or_else(|| format!("Unexpected source type for field {:}.", #field_name))?;
+            #context
+            #(#arg_values)*
+            let value: Box<dyn std::any::Any> = Box::new(#call);
+            Ok(value)
+          })
+      })
+    });
+  }
+  attrs::strip(&mut item.attrs);
+
+  Ok(quote! {
+    #item
+
+    impl ::graphql_rs::types::AsOutputType for #self_ty {
+      type Type = ::graphql_rs::types::GraphQLObject;
+
+      fn output_type() -> ::std::rc::Rc<::graphql_rs::types::GraphQLObject> {
+        ::graphql_rs::types::shared_type::<Self, _, _>(|| {
+          ::graphql_rs::types::GraphQLObjectType::new(#name)
+            #description
+            #(#field_defs)*
+            .is_type_of(|value| value.is::<#self_ty>())
+            .build()
+        })
+      }
+    }
+  })
+}
+
+/// Returns `T` if `ty` is spelled `Result<T, E>`.
+fn result_ok_type(ty: &Type) -> Option<&Type> {
+  let segment = match *ty {
+    Type::Path(ref p) => p.path.segments.last().unwrap(),
+    _ => return None,
+  };
+  if segment.ident != "Result" {
+    return None;
+  }
+  match segment.arguments {
+    PathArguments::AngleBracketed(ref args) => {
+      match args.args.first() {
+        Some(GenericArgument::Type(ok_ty)) => Some(ok_ty),
+        _ => None,
+      }
+    }
+    _ => None,
+  }
+}
@@ -0,0 +1,151 @@
+#[macro_use]
+extern crate graphql_derive;
+extern crate graphql_rs;
+extern crate indexmap;
+
+use indexmap::IndexMap;
+
+use graphql_rs::types::*;
+use graphql_rs::value::{FromInputValue, Value};
+
+/// Image Type
+#[derive(GraphQLObject)]
+#[allow(dead_code)]
+struct Image {
+  url: String,
+  /// Width in pixels
+  width: i32,
+  #[graphql(deprecated = "Use `width` instead")]
+  pixel_width: Option<i32>,
+  #[graphql(name = "thumbnails")]
+  thumbnail_urls: Vec<String>,
+  #[graphql(skip)]
+  cache_key: u64,
+}
+
+#[derive(GraphQLObject)]
+#[graphql(name = "Writer")]
+#[allow(dead_code)]
+struct Author {
+  name: String,
+  #[graphql(placeholder = "Article")]
+  recent_article: (),
+}
+
+#[derive(GraphQLEnum, Debug, PartialEq)]
+#[allow(dead_code)]
+enum Color {
+  /// Pure red
+  Red,
+  DarkGreen,
+  #[graphql(name = "AZURE", deprecated = "Use `DARK_GREEN` instead")]
+  Blue,
+}
+
+/// A point on earth
+#[derive(GraphQLInputObject, Debug, PartialEq)]
+struct GeoPoint {
+  /// Latitude in degrees
+  lat: f64,
+  lon: f64,
+  #[graphql(name = "altitude")]
+  alt: Option<f64>,
+  #[graphql(default = RED, deprecated = "Colors are ignored")]
+  tint: Option<Color>,
+}
+
+#[test]
+fn test_derive_object() {
+  let image = Image::output_type();
+  assert_eq!("Image", image.name());
+  assert_eq!(Some("Image Type"), image.description());
+
+  let fields = image.fields();
+  assert_eq!(4, fields.len());
+  assert_eq!("String", fields["url"].typ().name());
+  assert_eq!(Some("Width in pixels"), fields["width"].description());
+  assert_eq!(Some("Optional Int"), fields["pixelWidth"].typ().description());
+  assert_eq!(Some("Use `width` instead"),
+             fields["pixelWidth"].deprecation_reason());
+  assert_eq!(Some("List of String"), fields["thumbnails"].typ().description());
+  assert!(!fields.contains_key("cacheKey"));
+  drop(fields);
+
+  assert_eq!(Some(true), image.is_type_of(&Image {
+    url: String::new(),
+    width: 0,
+    pixel_width: None,
+    thumbnail_urls: vec![],
+    cache_key: 0,
+  }));
+  assert_eq!(Some(false), image.is_type_of(&0));
+}
+
+#[test]
+fn test_derive_object_placeholder() {
+  let author = Author::output_type();
+  assert_eq!("Writer", author.name());
+  assert_eq!("Article___TypePlaceholder___",
+             author.fields()["recentArticle"].typ().name());
+}
+
+#[test]
+fn test_derive_enum() {
+  let color = Color::output_type();
+  assert_eq!("Color", color.name());
+
+  let values = color.values();
+  assert_eq!(3, values.len());
+  assert_eq!(Some("Pure red"), values["RED"].description());
+  assert!(values.contains_key("DARK_GREEN"));
+  assert_eq!(Some("Use `DARK_GREEN` instead"),
+             values["AZURE"].deprecation_reason());
+
+  assert_eq!(Some(Color::DarkGreen),
+             Color::from_input_value(&Value::Enum("DARK_GREEN".to_owned())));
+  assert_eq!(Some(Color::Blue),
+             Color::from_input_value(&Value::Enum("AZURE".to_owned())));
+  assert_eq!(None, Color::from_input_value(&Value::Enum("BLUE".to_owned())));
+  assert_eq!(None, Color::from_input_value(&Value::String("RED".to_owned())));
+
+  assert_eq!(Some("DARK_GREEN"), color.serialize(&Color::DarkGreen));
+  assert_eq!(Some("AZURE"), color.serialize(&Color::Blue));
+  assert_eq!(Some(&Color::Red),
+             color.coerce_literal("RED").and_then(|v| v.downcast_ref::<Color>()));
+}
+
+#[test]
+fn test_derive_input_object() {
+  let geo_point = GeoPoint::input_type();
+  assert_eq!("GeoPoint", geo_point.name());
+  assert_eq!(Some("A point on earth"), geo_point.description());
+
+  let fields = geo_point.fields();
+  assert_eq!(4, fields.len());
+  assert_eq!("Float", fields["lat"].typ().name());
+  assert_eq!(Some("Optional Float"), fields["altitude"].typ().description());
+  assert_eq!(Some("Optional Color"), fields["tint"].typ().description());
+  assert_eq!(Some("Latitude in degrees"), fields["lat"].description());
+  assert_eq!(Some(&Value::Enum("RED".to_owned())), fields["tint"].default_value());
+  assert_eq!(Some("Colors are ignored"), fields["tint"].deprecation_reason());
+
+  let mut value = IndexMap::new();
+  value.insert("lat".to_owned(), Value::Float(22.3));
+  value.insert("lon".to_owned(), Value::Int(114));
+  value.insert("tint".to_owned(), Value::Enum("DARK_GREEN".to_owned()));
+  assert_eq!(Some(GeoPoint {
+               lat: 22.3,
+               lon: 114.0,
+               alt: None,
+               tint: Some(Color::DarkGreen),
+             }),
+             GeoPoint::from_input_value(&Value::Object(value.clone())));
+
+  value.shift_remove("tint");
+  assert_eq!(Some(Some(Color::Red)),
+             GeoPoint::from_input_value(&Value::Object(value.clone())).map(|p| p.tint));
+
+  value.shift_remove("lon");
+  assert_eq!(None, GeoPoint::from_input_value(&Value::Object(value)));
+  assert_eq!(None, GeoPoint::from_input_value(&Value::Null));
+}
@@ -0,0 +1,143 @@
+#[macro_use]
+extern crate graphql_derive;
+extern crate graphql_rs;
+
+use graphql_derive::graphql_object;
+use graphql_rs::error::GraphQLError;
+use graphql_rs::types::*;
+use graphql_rs::value::{Arguments, Value};
+
+struct Context {
+  names: Vec<String>,
+}
+
+#[derive(GraphQLObject, Debug, PartialEq)]
+struct User {
+  id: i32,
+  name: String,
+}
+
+struct Query;
+
+/// The root query type
+#[graphql_object]
+impl Query {
+  /// Looks up a user by id
+  fn user(&self, #[graphql(context)] ctx: &Context, id: i32) -> Option<User> {
+    ctx.names.get(id as usize).map(|name| {
+      User {
+        id,
+        name: name.clone(),
+      }
+    })
+  }
+
+  fn user_count(&self,
+                #[graphql(context)] ctx: &Context,
+                #[graphql(default = false)] include_removed: bool)
+                -> i32 {
+    let removed = if include_removed { 1 } else { 0 };
+    ctx.names.len() as i32 + removed
+  }
+
+  #[graphql(name = "greeting", deprecated = "Use `hello` instead")]
+  fn greet(&self, #[graphql(name = "to", description = "Who to greet")] name: String) -> Result<String, String> {
+    if name.is_empty() {
+      Err("Name cannot be empty".to_owned())
+    } else {
+      Ok(format!("Hello, {}!", name))
+    }
+  }
+
+  fn secret(&self) -> Result<String, GraphQLError> {
+    Err(GraphQLError::new("Not authorized").extension("code", Value::String("FORBIDDEN".to_owned())))
+  }
+
+  #[graphql(skip)]
+  #[allow(dead_code)]
+  fn helper(&self) -> i32 {
+    0
+  }
+}
+
+fn args(values: Vec<(&str, Value)>) -> Arguments {
+  values.into_iter().map(|(k, v)| (k.to_owned(), v)).collect()
+}
+
+#[test]
+fn test_graphql_object_definition() {
+  let query = Query::output_type();
+  assert_eq!("Query", query.name());
+  assert_eq!(Some("The root query type"), query.description());
+
+  let fields = query.fields();
+  assert_eq!(4, fields.len());
+  assert!(!fields.contains_key("helper"));
+
+  let user = &fields["user"];
+  assert_eq!(Some("Looks up a user by id"), user.description());
+  assert_eq!(Some("Optional User"), user.typ().description());
+  let user_args = user.args().unwrap();
+  assert_eq!(1, user_args.len());
+  assert_eq!("Int", user_args["id"].typ().name());
+
+  let include_removed = &fields["userCount"].args().unwrap()["includeRemoved"];
+  assert_eq!(Some(&Value::Boolean(false)), include_removed.default_value());
+
+  let greeting = &fields["greeting"];
+  assert_eq!("String", greeting.typ().name());
+  assert_eq!(Some("Use `hello` instead"), greeting.deprecation_reason());
+  assert_eq!(Some("Who to greet"),
+             greeting.args().unwrap()["to"].description());
+  assert_eq!(Some(true), query.is_type_of(&Query));
+}
+
+#[test]
+fn test_graphql_object_resolvers() {
+  let query = Query::output_type();
+  let fields = query.fields();
+  let context = Context { names: vec!["Alice".to_owned(), "Bob".to_owned()] };
+
+  let user = fields["user"].resolve(&Query, &context, &args(vec![("id", Value::Int(1))])).unwrap();
+  assert_eq!(Some(&Some(User {
+               id: 1,
+               name: "Bob".to_owned(),
+             })),
+             user.downcast_ref::<Option<User>>());
+
+  let count = fields["userCount"]
+    .resolve(&Query,
+             &context,
+             &args(vec![("includeRemoved", Value::Boolean(true))]))
+    .unwrap();
+  assert_eq!(Some(&3), count.downcast_ref::<i32>());
+  let count = fields["userCount"].resolve(&Query, &context, &args(vec![])).unwrap();
+  assert_eq!(Some(&2), count.downcast_ref::<i32>());
+
+  let greeting = fields["greeting"]
+    .resolve(&Query, &(), &args(vec![("to", Value::String("Bob".to_owned()))]))
+    .unwrap();
+  assert_eq!(Some(&"Hello, Bob!".to_owned()), greeting.downcast_ref::<String>());
+}
+
+#[test]
+fn test_graphql_object_resolver_errors() {
+  let query = Query::output_type();
+  let fields = query.fields();
+  let context = Context { names: vec![] };
+
+  assert_eq!(Some("Invalid value for argument id of field user.".to_owned()),
+             fields["user"].resolve(&Query, &context, &args(vec![])).err().map(|e| e.message().to_owned()));
+  assert_eq!(Some("Invalid value for argument id of field user.".to_owned()),
+             fields["user"]
+               .resolve(&Query, &context, &args(vec![("id", Value::Float(1.5))]))
+               .err().map(|e| e.message().to_owned()));
+  assert_eq!(Some("Unexpected context type for field user.".to_owned()),
+             fields["user"].resolve(&Query, &(), &args(vec![("id", Value::Int(1))])).err().map(|e| e.message().to_owned()));
+  assert_eq!(Some("Unexpected source type for field user.".to_owned()),
+             fields["user"].resolve(&(), &context, &args(vec![("id", Value::Int(1))])).err().map(|e| e.message().to_owned()));
+  assert_eq!(Some("Name cannot be empty".to_owned()),
+             fields["greeting"]
+               .resolve(&Query, &(), &args(vec![("to", Value::String(String::new()))]))
+               .err().map(|e| e.message().to_owned()));
+}
@@ -5,6 +5,11 @@
 
 use source::Source;
 
+pub mod owned;
+
+#[cfg(test)]
+mod tests;
+
 /// Contains some character offsets that identify where the source of the AST
 /// is from.
 pub struct Location<'a> {
@@ -95,10 +100,13 @@ pub struct OperationDefinition<'a> {
 
 impl_node_for! { OperationDefinition }
 
-/// OperationType : one of query mutation
+/// OperationType : one of query mutation subscription
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
 pub enum OperationType {
   Query,
-  Mutation
+  Mutation,
+  Subscription
 }
 
 /// SelectionSet : { Selection+ }
@@ -0,0 +1,935 @@
+//! AST nodes owning their contents, unlike the nodes of `ast` which borrow
+//! from the source they are parsed from. Owned documents can be cached, sent
+//! to another thread or kept after the source is dropped.
+//!
+//! Every node is built from its borrowed counterpart with `From`, and mirrors
+//! it field for field.
+//!
+//! With the `serde` feature, nodes, borrowed ones included, serialize to the
+//! JSON of `graphql-js` nodes, with their `kind` and a `loc` of offsets, and
+//! owned nodes deserialize from it.
+
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+
+use ast;
+
+/// Character offsets of a node in its source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Location {
+  pub start: usize,
+  pub end: usize
+}
+
+impl<'a, 'b> From<&'b ast::Location<'a>> for Location {
+  fn from(loc: &'b ast::Location<'a>) -> Location {
+    Location {
+      start: loc.start,
+      end: loc.end
+    }
+  }
+}
+
+fn location(loc: &Option<ast::Location>) -> Option<Location> {
+  loc.as_ref().map(Location::from)
+}
+
+fn nodes<'b, T: 'b, U: From<&'b T>>(nodes: &'b [T]) -> Vec<U> {
+  nodes.iter().map(U::from).collect()
+}
+
+/// Name :: /[_A-Za-z][_0-9A-Za-z]*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct Name {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub value: String
+}
+
+impl<'a, 'b> From<&'b ast::Name<'a>> for Name {
+  fn from(node: &'b ast::Name<'a>) -> Name {
+    Name {
+      loc: location(&node.loc),
+      value: node.value.to_owned()
+    }
+  }
+}
+
+/// IntValue :: IntegerPart
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct IntValue {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub value: String
+}
+
+impl<'a, 'b> From<&'b ast::IntValue<'a>> for IntValue {
+  fn from(node: &'b ast::IntValue<'a>) -> IntValue {
+    IntValue {
+      loc: location(&node.loc),
+      value: node.value.to_owned()
+    }
+  }
+}
+
+/// FloatValue ::
+///   - IntegerPart FractionalPart
+///   - IntegerPart ExponentPart
+///   - IntegerPart FractionalPart ExponentPart
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct FloatValue {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub value: String
+}
+
+impl<'a, 'b> From<&'b ast::FloatValue<'a>> for FloatValue {
+  fn from(node: &'b ast::FloatValue<'a>) -> FloatValue {
+    FloatValue {
+      loc: location(&node.loc),
+      value: node.value.to_owned()
+    }
+  }
+}
+
+/// StringValue ::
+///   - `""`
+///   - `"` StringCharacter+ `"`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct StringValue {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub value: String
+}
+
+impl<'a, 'b> From<&'b ast::StringValue<'a>> for StringValue {
+  fn from(node: &'b ast::StringValue<'a>) -> StringValue {
+    StringValue {
+      loc: location(&node.loc),
+      value: node.value.clone()
+    }
+  }
+}
+
+/// Document : Definition+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct Document {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub definitions: Vec<Definition>
+}
+
+impl<'a, 'b> From<&'b ast::Document<'a>> for Document {
+  fn from(node: &'b ast::Document<'a>) -> Document {
+    Document {
+      loc: location(&node.loc),
+      definitions: nodes(&node.definitions)
+    }
+  }
+}
+
+/// Definition :
+///   - OperationDefinition
+///   - FragmentDefinition
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(untagged, from = "JsonDefinition"))]
+pub enum Definition {
+  Operation(OperationDefinition),
+  Fragment(FragmentDefinition)
+}
+
+impl<'a, 'b> From<&'b ast::Definition<'a>> for Definition {
+  fn from(node: &'b ast::Definition<'a>) -> Definition {
+    match *node {
+      ast::Definition::Operation(ref operation) => Definition::Operation(operation.into()),
+      ast::Definition::Fragment(ref fragment) => Definition::Fragment(fragment.into())
+    }
+  }
+}
+
+/// OperationDefinition :
+///   - SelectionSet
+///   - OperationType Name? VariableDefinitions? Directives? SelectionSet
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct OperationDefinition {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub operation: ast::OperationType,
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub name: Option<Name>,
+  #[cfg_attr(feature = "serde", serde(default, with = "nodes_or_empty"))]
+  pub variable_definitions: Option<VariableDefinitions>,
+  #[cfg_attr(feature = "serde", serde(default, with = "nodes_or_empty"))]
+  pub directives: Option<Directives>,
+  pub selection_set: SelectionSet
+}
+
+impl<'a, 'b> From<&'b ast::OperationDefinition<'a>> for OperationDefinition {
+  fn from(node: &'b ast::OperationDefinition<'a>) -> OperationDefinition {
+    OperationDefinition {
+      loc: location(&node.loc),
+      operation: node.operation,
+      name: node.name.as_ref().map(Name::from),
+      variable_definitions: node.variable_definitions.as_ref().map(|v| nodes(v)),
+      directives: node.directives.as_ref().map(|d| nodes(d)),
+      selection_set: (&node.selection_set).into()
+    }
+  }
+}
+
+/// SelectionSet : { Selection+ }
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct SelectionSet {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub selections: Vec<Selection>
+}
+
+impl<'a, 'b> From<&'b ast::SelectionSet<'a>> for SelectionSet {
+  fn from(node: &'b ast::SelectionSet<'a>) -> SelectionSet {
+    SelectionSet {
+      loc: location(&node.loc),
+      selections: nodes(&node.selections)
+    }
+  }
+}
+
+/// Selection :
+///   - Field
+///   - FragmentSpread
+///   - InlineFragment
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(untagged, from = "JsonSelection"))]
+pub enum Selection {
+  Field(Field),
+  FragmentSpread(FragmentSpread),
+  InlineFragment(InlineFragment)
+}
+
+impl<'a, 'b> From<&'b ast::Selection<'a>> for Selection {
+  fn from(node: &'b ast::Selection<'a>) -> Selection {
+    match *node {
+      ast::Selection::Field(ref field) => Selection::Field(field.into()),
+      ast::Selection::FragmentSpread(ref spread) => Selection::FragmentSpread(spread.into()),
+      ast::Selection::InlineFragment(ref fragment) => Selection::InlineFragment(fragment.into())
+    }
+  }
+}
+
+/// Field : Alias? Name Arguments? Directives? SelectionSet?
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct Field {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub alias: Option<Alias>,
+  pub name: Name,
+  #[cfg_attr(feature = "serde", serde(default, with = "nodes_or_empty"))]
+  pub arguments: Option<Arguments>,
+  #[cfg_attr(feature = "serde", serde(default, with = "nodes_or_empty"))]
+  pub directives: Option<Directives>,
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub selection_set: Option<SelectionSet>
+}
+
+impl<'a, 'b> From<&'b ast::Field<'a>> for Field {
+  fn from(node: &'b ast::Field<'a>) -> Field {
+    Field {
+      loc: location(&node.loc),
+      alias: node.alias.as_ref().map(Name::from),
+      name: (&node.name).into(),
+      arguments: node.arguments.as_ref().map(|a| nodes(a)),
+      directives: node.directives.as_ref().map(|d| nodes(d)),
+      selection_set: node.selection_set.as_ref().map(SelectionSet::from)
+    }
+  }
+}
+
+/// Alias : Name :
+pub type Alias = Name;
+
+/// Arguments : ( Argument+ )
+pub type Arguments = Vec<Argument>;
+
+/// Argument : Name : Value
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct Argument {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub name: Name,
+  pub value: Value
+}
+
+impl<'a, 'b> From<&'b ast::Argument<'a>> for Argument {
+  fn from(node: &'b ast::Argument<'a>) -> Argument {
+    Argument {
+      loc: location(&node.loc),
+      name: (&node.name).into(),
+      value: (&node.value).into()
+    }
+  }
+}
+
+/// FragmentSpread : ... FragmentName Directives?
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct FragmentSpread {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub name: Name,
+  #[cfg_attr(feature = "serde", serde(default, with = "nodes_or_empty"))]
+  pub directives: Option<Directives>
+}
+
+impl<'a, 'b> From<&'b ast::FragmentSpread<'a>> for FragmentSpread {
+  fn from(node: &'b ast::FragmentSpread<'a>) -> FragmentSpread {
+    FragmentSpread {
+      loc: location(&node.loc),
+      name: (&node.name).into(),
+      directives: node.directives.as_ref().map(|d| nodes(d))
+    }
+  }
+}
+
+/// InlineFragment : ... TypeCondition? Directives? SelectionSet
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct InlineFragment {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub type_condition: Option<TypeCondition>,
+  #[cfg_attr(feature = "serde", serde(default, with = "nodes_or_empty"))]
+  pub directives: Option<Directives>,
+  pub selection_set: SelectionSet
+}
+
+impl<'a, 'b> From<&'b ast::InlineFragment<'a>> for InlineFragment {
+  fn from(node: &'b ast::InlineFragment<'a>) -> InlineFragment {
+    InlineFragment {
+      loc: location(&node.loc),
+      type_condition: node.type_condition.as_ref().map(NamedType::from),
+      directives: node.directives.as_ref().map(|d| nodes(d)),
+      selection_set: (&node.selection_set).into()
+    }
+  }
+}
+
+/// FragmentDefinition : fragment FragmentName TypeCondition Directives? SelectionSet
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct FragmentDefinition {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub name: Name,
+  pub type_condition: TypeCondition,
+  #[cfg_attr(feature = "serde", serde(default, with = "nodes_or_empty"))]
+  pub directives: Option<Directives>,
+  pub selection_set: SelectionSet
+}
+
+impl<'a, 'b> From<&'b ast::FragmentDefinition<'a>> for FragmentDefinition {
+  fn from(node: &'b ast::FragmentDefinition<'a>) -> FragmentDefinition {
+    FragmentDefinition {
+      loc: location(&node.loc),
+      name: (&node.name).into(),
+      type_condition: (&node.type_condition).into(),
+      directives: node.directives.as_ref().map(|d| nodes(d)),
+      selection_set: (&node.selection_set).into()
+    }
+  }
+}
+
+/// FragmentName : Name but not `on`
+pub type FragmentName = Name;
+
+/// TypeCondition : on NamedType
+pub type TypeCondition = NamedType;
+
+/// Value[Const] :
+///   - [~Const] Variable
+///   - IntValue
+///   - FloatValue
+///   - StringValue
+///   - BooleanValue
+///   - EnumValue
+///   - ListValue[?Const]
+///   - ObjectValue[?Const]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(untagged, from = "JsonValue"))]
+pub enum Value {
+  Variable(Variable),
+  Int(IntValue),
+  Float(FloatValue),
+  String(StringValue),
+  Boolean(BooleanValue),
+  Enum(EnumValue),
+  List(ListValue),
+  Object(ObjectValue)
+}
+
+impl<'a, 'b> From<&'b ast::Value<'a>> for Value {
+  fn from(node: &'b ast::Value<'a>) -> Value {
+    match *node {
+      ast::Value::Variable(ref v) => Value::Variable(v.into()),
+      ast::Value::Int(ref v) => Value::Int(v.into()),
+      ast::Value::Float(ref v) => Value::Float(v.into()),
+      ast::Value::String(ref v) => Value::String(v.into()),
+      ast::Value::Boolean(ref v) => Value::Boolean(v.into()),
+      ast::Value::Enum(ref v) => Value::Enum(v.into()),
+      ast::Value::List(ref v) => Value::List(v.into()),
+      ast::Value::Object(ref v) => Value::Object(v.into())
+    }
+  }
+}
+
+/// BooleanValue : one of `true` `false`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct BooleanValue {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub value: bool
+}
+
+impl<'a, 'b> From<&'b ast::BooleanValue<'a>> for BooleanValue {
+  fn from(node: &'b ast::BooleanValue<'a>) -> BooleanValue {
+    BooleanValue {
+      loc: location(&node.loc),
+      value: node.value
+    }
+  }
+}
+
+/// EnumValue : Name but not `true`, `false` or `null`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(into = "JsonEnumOrNull", from = "JsonEnumOrNull"))]
+pub struct EnumValue {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub name: Name
+}
+
+impl<'a, 'b> From<&'b ast::EnumValue<'a>> for EnumValue {
+  fn from(node: &'b ast::EnumValue<'a>) -> EnumValue {
+    EnumValue {
+      loc: location(&node.loc),
+      name: (&node.name).into()
+    }
+  }
+}
+
+/// ListValue[Const] :
+///   - [ ]
+///   - [ Value[?Const]+ ]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct ListValue {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub values: Vec<Value>
+}
+
+impl<'a, 'b> From<&'b ast::ListValue<'a>> for ListValue {
+  fn from(node: &'b ast::ListValue<'a>) -> ListValue {
+    ListValue {
+      loc: location(&node.loc),
+      values: nodes(&node.values)
+    }
+  }
+}
+
+/// ObjectValue[Const] :
+///   - { }
+///   - { ObjectField[?Const]+ }
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct ObjectValue {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub fields: Vec<ObjectField>
+}
+
+impl<'a, 'b> From<&'b ast::ObjectValue<'a>> for ObjectValue {
+  fn from(node: &'b ast::ObjectValue<'a>) -> ObjectValue {
+    ObjectValue {
+      loc: location(&node.loc),
+      fields: nodes(&node.fields)
+    }
+  }
+}
+
+/// ObjectField[Const] : Name : Value[?Const]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct ObjectField {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub name: Name,
+  pub value: Value
+}
+
+impl<'a, 'b> From<&'b ast::ObjectField<'a>> for ObjectField {
+  fn from(node: &'b ast::ObjectField<'a>) -> ObjectField {
+    ObjectField {
+      loc: location(&node.loc),
+      name: (&node.name).into(),
+      value: (&node.value).into()
+    }
+  }
+}
+
+/// VariableDefinitions : ( VariableDefinition+ )
+pub type VariableDefinitions = Vec<VariableDefinition>;
+
+/// VariableDefinition : Variable : Type DefaultValue?
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct VariableDefinition {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub variable: Variable,
+  #[cfg_attr(feature = "serde", serde(rename = "type"))]
+  pub type_: Type,
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub default_value: Option<DefaultValue>
+}
+
+impl<'a, 'b> From<&'b ast::VariableDefinition<'a>> for VariableDefinition {
+  fn from(node: &'b ast::VariableDefinition<'a>) -> VariableDefinition {
+    VariableDefinition {
+      loc: location(&node.loc),
+      variable: (&node.variable).into(),
+      type_: (&node.type_).into(),
+      default_value: node.default_value.as_ref().map(Value::from)
+    }
+  }
+}
+
+/// Variable : $ Name
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct Variable {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub name: Name
+}
+
+impl<'a, 'b> From<&'b ast::Variable<'a>> for Variable {
+  fn from(node: &'b ast::Variable<'a>) -> Variable {
+    Variable {
+      loc: location(&node.loc),
+      name: (&node.name).into()
+    }
+  }
+}
+
+/// DefaultValue : = Value[Const]
+pub type DefaultValue = Value;
+
+/// Type :
+///   - NamedType
+///   - ListType
+///   - NonNullType
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(untagged, try_from = "JsonType"))]
+pub enum Type {
+  Named(NamedType),
+  List(Box<ListType>),
+  NonNullNamed(Box<NonNullNamedType>),
+  NonNullList(Box<NonNullListType>)
+}
+
+impl<'a, 'b> From<&'b ast::Type<'a>> for Type {
+  fn from(node: &'b ast::Type<'a>) -> Type {
+    match *node {
+      ast::Type::Named(ref t) => Type::Named(t.into()),
+      ast::Type::List(ref t) => Type::List(Box::new((&**t).into())),
+      ast::Type::NonNullNamed(ref t) => Type::NonNullNamed(Box::new((&**t).into())),
+      ast::Type::NonNullList(ref t) => Type::NonNullList(Box::new((&**t).into()))
+    }
+  }
+}
+
+/// NamedType : Name
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct NamedType {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub name: Name
+}
+
+impl<'a, 'b> From<&'b ast::NamedType<'a>> for NamedType {
+  fn from(node: &'b ast::NamedType<'a>) -> NamedType {
+    NamedType {
+      loc: location(&node.loc),
+      name: (&node.name).into()
+    }
+  }
+}
+
+/// ListType : [ Type ]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct ListType {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  #[cfg_attr(feature = "serde", serde(rename = "type"))]
+  pub type_: Type
+}
+
+impl<'a, 'b> From<&'b ast::ListType<'a>> for ListType {
+  fn from(node: &'b ast::ListType<'a>) -> ListType {
+    ListType {
+      loc: location(&node.loc),
+      type_: (&node.type_).into()
+    }
+  }
+}
+
+/// NonNullType :
+///   - NamedType !
+///   - ListType !
+///
+/// Split in two types as in `ast`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename = "NonNullType", rename_all = "camelCase"))]
+pub struct NonNullNamedType {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  #[cfg_attr(feature = "serde", serde(rename = "type"))]
+  pub type_: NamedType
+}
+
+impl<'a, 'b> From<&'b ast::NonNullNamedType<'a>> for NonNullNamedType {
+  fn from(node: &'b ast::NonNullNamedType<'a>) -> NonNullNamedType {
+    NonNullNamedType {
+      loc: location(&node.loc),
+      type_: (&node.type_).into()
+    }
+  }
+}
+
+/// See documentation for the `NonNullNamedType` struct.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename = "NonNullType", rename_all = "camelCase"))]
+pub struct NonNullListType {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  #[cfg_attr(feature = "serde", serde(rename = "type"))]
+  pub type_: ListType
+}
+
+impl<'a, 'b> From<&'b ast::NonNullListType<'a>> for NonNullListType {
+  fn from(node: &'b ast::NonNullListType<'a>) -> NonNullListType {
+    NonNullListType {
+      loc: location(&node.loc),
+      type_: (&node.type_).into()
+    }
+  }
+}
+
+/// Directives : Directive+
+pub type Directives = Vec<Directive>;
+
+/// Directive : @ Name Arguments?
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde",
+           derive(Serialize, Deserialize),
+           serde(tag = "kind", rename_all = "camelCase"))]
+pub struct Directive {
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  pub loc: Option<Location>,
+  pub name: Name,
+  #[cfg_attr(feature = "serde", serde(default, with = "nodes_or_empty"))]
+  pub arguments: Option<Arguments>
+}
+
+impl<'a, 'b> From<&'b ast::Directive<'a>> for Directive {
+  fn from(node: &'b ast::Directive<'a>) -> Directive {
+    Directive {
+      loc: location(&node.loc),
+      name: (&node.name).into(),
+      arguments: node.arguments.as_ref().map(|a| nodes(a))
+    }
+  }
+}
+
+// `graphql-js` writes the value of enum values as a string, their name having
+// no node of its own, and `null`, which is parsed as an enum value, as a
+// `NullValue` node.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum JsonEnumOrNull {
+  Enum(JsonEnumValue),
+  Null(JsonNullValue)
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename = "EnumValue")]
+struct JsonEnumValue {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  loc: Option<Location>,
+  value: String
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename = "NullValue")]
+struct JsonNullValue {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  loc: Option<Location>
+}
+
+#[cfg(feature = "serde")]
+impl From<EnumValue> for JsonEnumOrNull {
+  fn from(node: EnumValue) -> JsonEnumOrNull {
+    if node.name.value == "null" {
+      JsonEnumOrNull::Null(JsonNullValue { loc: node.loc })
+    } else {
+      JsonEnumOrNull::Enum(JsonEnumValue {
+        loc: node.loc,
+        value: node.name.value
+      })
+    }
+  }
+}
+
+#[cfg(feature = "serde")]
+impl From<JsonEnumOrNull> for EnumValue {
+  fn from(node: JsonEnumOrNull) -> EnumValue {
+    let (loc, value) = match node {
+      JsonEnumOrNull::Enum(node) => (node.loc, node.value),
+      JsonEnumOrNull::Null(node) => (node.loc, "null".to_owned())
+    };
+    EnumValue {
+      loc,
+      name: Name { loc, value }
+    }
+  }
+}
+
+// Enums are read by the kind of their node, and written as the node alone.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(tag = "kind")]
+enum JsonDefinition {
+  OperationDefinition(OperationDefinition),
+  FragmentDefinition(FragmentDefinition)
+}
+
+#[cfg(feature = "serde")]
+impl From<JsonDefinition> for Definition {
+  fn from(node: JsonDefinition) -> Definition {
+    match node {
+      JsonDefinition::OperationDefinition(operation) => Definition::Operation(operation),
+      JsonDefinition::FragmentDefinition(fragment) => Definition::Fragment(fragment)
+    }
+  }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(tag = "kind")]
+enum JsonSelection {
+  Field(Field),
+  FragmentSpread(FragmentSpread),
+  InlineFragment(InlineFragment)
+}
+
+#[cfg(feature = "serde")]
+impl From<JsonSelection> for Selection {
+  fn from(node: JsonSelection) -> Selection {
+    match node {
+      JsonSelection::Field(field) => Selection::Field(field),
+      JsonSelection::FragmentSpread(spread) => Selection::FragmentSpread(spread),
+      JsonSelection::InlineFragment(fragment) => Selection::InlineFragment(fragment)
+    }
+  }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(tag = "kind")]
+enum JsonValue {
+  Variable(Variable),
+  IntValue(IntValue),
+  FloatValue(FloatValue),
+  StringValue(StringValue),
+  BooleanValue(BooleanValue),
+  EnumValue(JsonEnumValue),
+  NullValue(JsonNullValue),
+  ListValue(ListValue),
+  ObjectValue(ObjectValue)
+}
+
+#[cfg(feature = "serde")]
+impl From<JsonValue> for Value {
+  fn from(node: JsonValue) -> Value {
+    match node {
+      JsonValue::Variable(v) => Value::Variable(v),
+      JsonValue::IntValue(v) => Value::Int(v),
+      JsonValue::FloatValue(v) => Value::Float(v),
+      JsonValue::StringValue(v) => Value::String(v),
+      JsonValue::BooleanValue(v) => Value::Boolean(v),
+      JsonValue::EnumValue(v) => Value::Enum(JsonEnumOrNull::Enum(v).into()),
+      JsonValue::NullValue(v) => Value::Enum(JsonEnumOrNull::Null(v).into()),
+      JsonValue::ListValue(v) => Value::List(v),
+      JsonValue::ObjectValue(v) => Value::Object(v)
+    }
+  }
+}
+
+// `NonNullType` wraps a named or a list type, which are split in two types
+// here.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(tag = "kind")]
+enum JsonType {
+  #[serde(rename = "NamedType")]
+  Named(NamedType),
+  #[serde(rename = "ListType")]
+  List(ListType),
+  #[serde(rename = "NonNullType")]
+  NonNull {
+    #[serde(default)]
+    loc: Option<Location>,
+    #[serde(rename = "type")]
+    type_: Box<JsonType>
+  }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<JsonType> for Type {
+  type Error = String;
+
+  fn try_from(node: JsonType) -> Result<Type, String> {
+    match node {
+      JsonType::Named(t) => Ok(Type::Named(t)),
+      JsonType::List(t) => Ok(Type::List(Box::new(t))),
+      JsonType::NonNull { loc, type_ } => {
+        match *type_ {
+          JsonType::Named(type_) => {
+            Ok(Type::NonNullNamed(Box::new(NonNullNamedType { loc, type_ })))
+          }
+          JsonType::List(type_) => {
+            Ok(Type::NonNullList(Box::new(NonNullListType { loc, type_ })))
+          }
+          JsonType::NonNull { .. } => Err("NonNullType cannot wrap a NonNullType.".to_owned())
+        }
+      }
+    }
+  }
+}
+
+// Lists of nodes which the grammar makes optional are empty arrays in
+// `graphql-js`, and empty arrays are read back as missing lists.
+#[cfg(feature = "serde")]
+mod nodes_or_empty {
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+  pub fn serialize<S, T>(nodes: &Option<Vec<T>>, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer,
+          T: Serialize
+  {
+    nodes.as_ref().map_or(&[][..], |nodes| &nodes[..]).serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+    where D: Deserializer<'de>,
+          T: Deserialize<'de>
+  {
+    let nodes: Option<Vec<T>> = Deserialize::deserialize(deserializer)?;
+    Ok(nodes.filter(|nodes| !nodes.is_empty()))
+  }
+}
+
+// Borrowed nodes serialize like their owned counterpart, and are deserialized
+// as owned nodes since they cannot borrow from the JSON.
+#[cfg(feature = "serde")]
+macro_rules! impl_serialize_for {
+  ($($node:ident),*) => {
+    $(
+      impl<'a> ::serde::Serialize for ast::$node<'a> {
+        fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+          $node::from(self).serialize(serializer)
+        }
+      }
+    )*
+  }
+}
+
+#[cfg(feature = "serde")]
+impl_serialize_for! {
+  Location, Name, IntValue, FloatValue, StringValue, Document, Definition, OperationDefinition,
+  SelectionSet, Selection, Field, Argument, FragmentSpread, InlineFragment, FragmentDefinition,
+  Value, BooleanValue, EnumValue, ListValue, ObjectValue, ObjectField, VariableDefinition,
+  Variable, Type, NamedType, ListType, NonNullNamedType, NonNullListType, Directive
+}
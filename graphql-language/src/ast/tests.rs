@@ -0,0 +1,212 @@
+use std::collections::HashSet;
+
+use super::owned;
+use super::*;
+use source::Source;
+
+fn name<'a>(value: &'a str, start: usize, source: &'a Source<'a>) -> Name<'a> {
+  Name {
+    loc: Some(Location {
+      start,
+      end: start + value.len(),
+      source: Some(source)
+    }),
+    value
+  }
+}
+
+fn field<'a>(name: Name<'a>,
+             arguments: Option<Arguments<'a>>,
+             selections: Option<Vec<Selection<'a>>>)
+             -> Selection<'a> {
+  Selection::Field(Field {
+    loc: None,
+    alias: None,
+    name,
+    arguments,
+    directives: None,
+    selection_set: selections.map(|selections| SelectionSet { loc: None, selections })
+  })
+}
+
+// `query Hero($id: ID!) { hero(id: $id) { name } }`
+fn document<'a>(source: &'a Source<'a>) -> Document<'a> {
+  let id = || {
+    Variable {
+      loc: None,
+      name: name("id", 12, source)
+    }
+  };
+  let id_type = NonNullNamedType {
+    loc: None,
+    type_: NamedType {
+      loc: None,
+      name: name("ID", 17, source)
+    }
+  };
+  let variable_definition = VariableDefinition {
+    loc: None,
+    variable: id(),
+    type_: Type::NonNullNamed(Box::new(id_type)),
+    default_value: None
+  };
+  let argument = Argument {
+    loc: None,
+    name: name("id", 28, source),
+    value: Value::Variable(id())
+  };
+  let hero = field(name("hero", 23, source),
+                   Some(vec![argument]),
+                   Some(vec![field(name("name", 39, source), None, None)]));
+  let operation = OperationDefinition {
+    loc: None,
+    operation: OperationType::Query,
+    name: Some(name("Hero", 6, source)),
+    variable_definitions: Some(vec![variable_definition]),
+    directives: None,
+    selection_set: SelectionSet {
+      loc: None,
+      selections: vec![hero]
+    }
+  };
+  Document {
+    loc: None,
+    definitions: vec![Definition::Operation(operation)]
+  }
+}
+
+#[test]
+fn owned_document_outlives_source() {
+  let owned = {
+    let body = String::from("query Hero($id: ID!) { hero(id: $id) { name } }");
+    let source = Source::new(&body);
+    owned::Document::from(&document(&source))
+  };
+
+  let operation = match owned.definitions[0] {
+    owned::Definition::Operation(ref operation) => operation,
+    _ => panic!("Expected an operation."),
+  };
+  assert_eq!(operation.operation, OperationType::Query);
+  let name = operation.name.as_ref().unwrap();
+  assert_eq!(name.value, "Hero");
+  assert_eq!(name.loc, Some(owned::Location { start: 6, end: 10 }));
+  match operation.selection_set.selections[0] {
+    owned::Selection::Field(ref field) => assert_eq!(field.name.value, "hero"),
+    _ => panic!("Expected a field."),
+  }
+
+  let body = "query Hero($id: ID!) { hero(id: $id) { name } }";
+  let source = Source::new(body);
+  let other = owned::Document::from(&document(&source));
+  assert_eq!(other, owned.clone());
+  let mut documents = HashSet::new();
+  documents.insert(owned);
+  assert!(documents.contains(&other));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn graphql_js_json() {
+  extern crate serde_json;
+
+  let body = "query Hero($id: ID!) { hero(id: $id) { name } }";
+  let source = Source::new(body);
+  let document = document(&source);
+  let name = |value: &str, start: usize| {
+    serde_json::json!({
+      "kind": "Name",
+      "loc": { "start": start, "end": start + value.len() },
+      "value": value,
+    })
+  };
+  let variable = serde_json::json!({ "kind": "Variable", "name": name("id", 12) });
+  let json = serde_json::json!({
+    "kind": "Document",
+    "definitions": [{
+      "kind": "OperationDefinition",
+      "operation": "query",
+      "name": name("Hero", 6),
+      "variableDefinitions": [{
+        "kind": "VariableDefinition",
+        "variable": variable,
+        "type": {
+          "kind": "NonNullType",
+          "type": { "kind": "NamedType", "name": name("ID", 17) },
+        },
+      }],
+      "directives": [],
+      "selectionSet": {
+        "kind": "SelectionSet",
+        "selections": [{
+          "kind": "Field",
+          "name": name("hero", 23),
+          "arguments": [{ "kind": "Argument", "name": name("id", 28), "value": variable }],
+          "directives": [],
+          "selectionSet": {
+            "kind": "SelectionSet",
+            "selections": [{
+              "kind": "Field",
+              "name": name("name", 39),
+              "arguments": [],
+              "directives": [],
+            }],
+          },
+        }],
+      },
+    }],
+  });
+  assert_eq!(serde_json::to_value(&document).unwrap(), json);
+
+  let owned: owned::Document = serde_json::from_value(json).unwrap();
+  assert_eq!(owned, owned::Document::from(&document));
+
+  let value = serde_json::json!({
+    "kind": "ListValue",
+    "values": [
+      { "kind": "EnumValue", "value": "RED" },
+      { "kind": "NullValue" },
+      { "kind": "ObjectValue", "fields": [{
+        "kind": "ObjectField",
+        "name": { "kind": "Name", "value": "size" },
+        "value": { "kind": "FloatValue", "value": "1.5" },
+      }] },
+    ],
+  });
+  let list: owned::Value = serde_json::from_value(value.clone()).unwrap();
+  match list {
+    owned::Value::List(ref list) => {
+      assert_eq!(list.values[0],
+                 owned::Value::Enum(owned::EnumValue {
+                   loc: None,
+                   name: owned::Name {
+                     loc: None,
+                     value: "RED".to_owned()
+                   }
+                 }))
+    }
+    _ => panic!("Expected a list value."),
+  }
+  assert_eq!(serde_json::to_value(&list).unwrap(), value);
+
+  // `null` is parsed as an enum value, but written as a `NullValue` node.
+  let loc = Some(owned::Location { start: 11, end: 15 });
+  let null = owned::Value::Enum(owned::EnumValue {
+    loc,
+    name: owned::Name {
+      loc,
+      value: "null".to_owned()
+    }
+  });
+  let json = serde_json::json!({ "kind": "NullValue", "loc": { "start": 11, "end": 15 } });
+  assert_eq!(serde_json::to_value(&null).unwrap(), json);
+  assert_eq!(serde_json::from_value::<owned::Value>(json).unwrap(), null);
+
+  let unknown = serde_json::json!({ "kind": "UndefinedValue" });
+  assert!(serde_json::from_value::<owned::Value>(unknown).is_err());
+  let named = serde_json::json!({ "kind": "NamedType", "name": { "kind": "Name", "value": "ID" } });
+  let non_null = serde_json::json!({ "kind": "NonNullType", "type": named });
+  assert!(serde_json::from_value::<owned::Type>(non_null.clone()).is_ok());
+  let non_null = serde_json::json!({ "kind": "NonNullType", "type": non_null });
+  assert!(serde_json::from_value::<owned::Type>(non_null).is_err());
+}
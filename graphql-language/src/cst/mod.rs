@@ -0,0 +1,444 @@
+//! Lossless concrete syntax tree of documents. Unlike the `ast`, it keeps
+//! every byte of the source, white space, comments and commas included, so
+//! that a document can be edited and printed back without losing them.
+//!
+//! Executable documents are built with `parse`, and schema documents, of
+//! type system definitions and extensions, with `parse_schema`.
+//!
+//! The tree is made of untyped `SyntaxNode`s, with typed nodes on top giving
+//! access to the parts of each construct.
+
+use std::fmt;
+
+use lexer::Punctuator;
+
+mod parser;
+#[cfg(test)]
+mod tests;
+
+pub use self::parser::{parse, parse_schema, Parse};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+  Whitespace,
+  Comment,
+  Comma,
+  Punctuator(Punctuator),
+  Name,
+  IntValue,
+  FloatValue,
+  StringValue,
+  /// Input the lexer failed to scan.
+  Error,
+}
+
+impl TokenKind {
+  /// Whether tokens of the kind are ignored by the grammar.
+  pub fn is_trivia(self) -> bool {
+    matches!(self, TokenKind::Whitespace | TokenKind::Comment | TokenKind::Comma)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+  Document,
+  OperationDefinition,
+  FragmentDefinition,
+  VariableDefinitions,
+  VariableDefinition,
+  Variable,
+  DefaultValue,
+  SelectionSet,
+  Field,
+  Alias,
+  Arguments,
+  Argument,
+  FragmentSpread,
+  InlineFragment,
+  TypeCondition,
+  NamedType,
+  ListType,
+  NonNullType,
+  Directives,
+  Directive,
+  IntValue,
+  FloatValue,
+  StringValue,
+  BooleanValue,
+  NullValue,
+  EnumValue,
+  ListValue,
+  ObjectValue,
+  ObjectField,
+  /// String describing the definition it starts.
+  Description,
+  SchemaDefinition,
+  RootOperationTypeDefinition,
+  /// Scalar type definition, or extension when it starts with `extend`, as
+  /// are the other type definitions.
+  ScalarTypeDefinition,
+  ObjectTypeDefinition,
+  InterfaceTypeDefinition,
+  UnionTypeDefinition,
+  EnumTypeDefinition,
+  InputObjectTypeDefinition,
+  DirectiveDefinition,
+  ImplementsInterfaces,
+  FieldsDefinition,
+  FieldDefinition,
+  ArgumentsDefinition,
+  /// Argument definition or input object field.
+  InputValueDefinition,
+  UnionMemberTypes,
+  EnumValuesDefinition,
+  EnumValueDefinition,
+  InputFieldsDefinition,
+  DirectiveLocations,
+  /// Tokens which do not fit the grammar where they are found.
+  Error,
+}
+
+/// Token with its text, as found in the source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxToken<'a> {
+  pub kind: TokenKind,
+  pub text: &'a str,
+  /// Byte offset in the source.
+  pub start: usize,
+}
+
+impl<'a> SyntaxToken<'a> {
+  pub fn end(&self) -> usize {
+    self.start + self.text.len()
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement<'a> {
+  Node(SyntaxNode<'a>),
+  Token(SyntaxToken<'a>),
+}
+
+/// Node of the tree, whose tokens are the source it spans. The trivia before
+/// a node belongs to its parent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode<'a> {
+  pub kind: NodeKind,
+  /// Byte offset in the source, for nodes without tokens as well.
+  pub start: usize,
+  pub children: Vec<SyntaxElement<'a>>,
+}
+
+impl<'a> SyntaxNode<'a> {
+  pub fn end(&self) -> usize {
+    match self.children.last() {
+      Some(SyntaxElement::Node(node)) => node.end(),
+      Some(SyntaxElement::Token(token)) => token.end(),
+      None => self.start,
+    }
+  }
+
+  /// Tokens of the node and of its descendants, in source order.
+  pub fn tokens(&self) -> Vec<&SyntaxToken<'a>> {
+    let mut tokens = Vec::new();
+    self.collect_tokens(&mut tokens);
+    tokens
+  }
+
+  fn collect_tokens<'t>(&'t self, tokens: &mut Vec<&'t SyntaxToken<'a>>) {
+    for child in &self.children {
+      match *child {
+        SyntaxElement::Node(ref node) => node.collect_tokens(tokens),
+        SyntaxElement::Token(ref token) => tokens.push(token),
+      }
+    }
+  }
+
+  pub fn child_nodes(&self) -> impl Iterator<Item = &SyntaxNode<'a>> {
+    self.children.iter().filter_map(|child| {
+      match *child {
+        SyntaxElement::Node(ref node) => Some(node),
+        SyntaxElement::Token(_) => None,
+      }
+    })
+  }
+
+  pub fn child_tokens(&self) -> impl Iterator<Item = &SyntaxToken<'a>> {
+    self.children.iter().filter_map(|child| {
+      match *child {
+        SyntaxElement::Token(ref token) => Some(token),
+        SyntaxElement::Node(_) => None,
+      }
+    })
+  }
+
+  pub fn child_node(&self, kind: NodeKind) -> Option<&SyntaxNode<'a>> {
+    self.child_nodes().find(|node| node.kind == kind)
+  }
+
+  /// Name tokens directly under the node.
+  fn names(&self) -> impl Iterator<Item = &SyntaxToken<'a>> {
+    self.child_tokens().filter(|token| token.kind == TokenKind::Name)
+  }
+}
+
+/// Prints the source the node spans, unchanged.
+impl<'a> fmt::Display for SyntaxNode<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for token in self.tokens() {
+      f.write_str(token.text)?;
+    }
+    Ok(())
+  }
+}
+
+macro_rules! typed_nodes {
+  ($($node:ident),*) => {
+    $(
+      #[derive(Debug, Clone, Copy)]
+      pub struct $node<'a> {
+        syntax: &'a SyntaxNode<'a>,
+      }
+
+      impl<'a> $node<'a> {
+        pub fn cast(syntax: &'a SyntaxNode<'a>) -> Option<$node<'a>> {
+          if syntax.kind == NodeKind::$node {
+            Some($node { syntax })
+          } else {
+            None
+          }
+        }
+
+        pub fn syntax(&self) -> &'a SyntaxNode<'a> {
+          self.syntax
+        }
+      }
+    )*
+  }
+}
+
+typed_nodes! {
+  Document, OperationDefinition, FragmentDefinition, VariableDefinition, Variable, SelectionSet,
+  Field, Argument, FragmentSpread, InlineFragment, NamedType, Directive, ObjectField
+}
+
+fn children<'a, T>(syntax: &'a SyntaxNode<'a>,
+                   cast: fn(&'a SyntaxNode<'a>) -> Option<T>)
+                   -> Vec<T> {
+  syntax.child_nodes().filter_map(cast).collect()
+}
+
+fn directives<'a>(syntax: &'a SyntaxNode<'a>) -> Vec<Directive<'a>> {
+  syntax.child_node(NodeKind::Directives).map_or(Vec::new(), |d| children(d, Directive::cast))
+}
+
+fn arguments<'a>(syntax: &'a SyntaxNode<'a>) -> Vec<Argument<'a>> {
+  syntax.child_node(NodeKind::Arguments).map_or(Vec::new(), |a| children(a, Argument::cast))
+}
+
+fn selection_set<'a>(syntax: &'a SyntaxNode<'a>) -> Option<SelectionSet<'a>> {
+  syntax.child_nodes().find_map(SelectionSet::cast)
+}
+
+fn type_condition<'a>(syntax: &'a SyntaxNode<'a>) -> Option<NamedType<'a>> {
+  syntax.child_node(NodeKind::TypeCondition).and_then(|t| t.child_nodes().find_map(NamedType::cast))
+}
+
+// Nodes of values and types, telling which by their kind.
+fn value_node<'a>(syntax: &'a SyntaxNode<'a>) -> Option<&'a SyntaxNode<'a>> {
+  syntax.child_nodes().find(|node| node.kind != NodeKind::Error)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Definition<'a> {
+  Operation(OperationDefinition<'a>),
+  Fragment(FragmentDefinition<'a>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Selection<'a> {
+  Field(Field<'a>),
+  FragmentSpread(FragmentSpread<'a>),
+  InlineFragment(InlineFragment<'a>),
+}
+
+impl<'a> Document<'a> {
+  pub fn definitions(&self) -> Vec<Definition<'a>> {
+    self.syntax
+      .child_nodes()
+      .filter_map(|node| {
+        OperationDefinition::cast(node)
+          .map(Definition::Operation)
+          .or_else(|| FragmentDefinition::cast(node).map(Definition::Fragment))
+      })
+      .collect()
+  }
+}
+
+impl<'a> OperationDefinition<'a> {
+  /// `query`, `mutation` or `subscription`, none for the query shorthand.
+  pub fn operation_type(&self) -> Option<&'a SyntaxToken<'a>> {
+    self.syntax.names().next()
+  }
+
+  pub fn name(&self) -> Option<&'a SyntaxToken<'a>> {
+    self.syntax.names().nth(1)
+  }
+
+  pub fn variable_definitions(&self) -> Vec<VariableDefinition<'a>> {
+    self.syntax
+      .child_node(NodeKind::VariableDefinitions)
+      .map_or(Vec::new(), |v| children(v, VariableDefinition::cast))
+  }
+
+  pub fn directives(&self) -> Vec<Directive<'a>> {
+    directives(self.syntax)
+  }
+
+  pub fn selection_set(&self) -> Option<SelectionSet<'a>> {
+    selection_set(self.syntax)
+  }
+}
+
+impl<'a> FragmentDefinition<'a> {
+  pub fn name(&self) -> Option<&'a SyntaxToken<'a>> {
+    self.syntax.names().nth(1)
+  }
+
+  pub fn type_condition(&self) -> Option<NamedType<'a>> {
+    type_condition(self.syntax)
+  }
+
+  pub fn directives(&self) -> Vec<Directive<'a>> {
+    directives(self.syntax)
+  }
+
+  pub fn selection_set(&self) -> Option<SelectionSet<'a>> {
+    selection_set(self.syntax)
+  }
+}
+
+impl<'a> VariableDefinition<'a> {
+  pub fn variable(&self) -> Option<Variable<'a>> {
+    self.syntax.child_nodes().find_map(Variable::cast)
+  }
+
+  /// A `NamedType`, `ListType` or `NonNullType` node.
+  pub fn type_(&self) -> Option<&'a SyntaxNode<'a>> {
+    self.syntax.child_nodes().find(|node| {
+      matches!(node.kind, NodeKind::NamedType | NodeKind::ListType | NodeKind::NonNullType)
+    })
+  }
+
+  pub fn default_value(&self) -> Option<&'a SyntaxNode<'a>> {
+    self.syntax.child_node(NodeKind::DefaultValue).and_then(value_node)
+  }
+}
+
+impl<'a> Variable<'a> {
+  pub fn name(&self) -> Option<&'a SyntaxToken<'a>> {
+    self.syntax.names().next()
+  }
+}
+
+impl<'a> SelectionSet<'a> {
+  pub fn selections(&self) -> Vec<Selection<'a>> {
+    self.syntax
+      .child_nodes()
+      .filter_map(|node| {
+        match node.kind {
+          NodeKind::Field => Some(Selection::Field(Field { syntax: node })),
+          NodeKind::FragmentSpread => {
+            Some(Selection::FragmentSpread(FragmentSpread { syntax: node }))
+          }
+          NodeKind::InlineFragment => {
+            Some(Selection::InlineFragment(InlineFragment { syntax: node }))
+          }
+          _ => None,
+        }
+      })
+      .collect()
+  }
+}
+
+impl<'a> Field<'a> {
+  pub fn alias(&self) -> Option<&'a SyntaxToken<'a>> {
+    self.syntax.child_node(NodeKind::Alias).and_then(|alias| alias.names().next())
+  }
+
+  pub fn name(&self) -> Option<&'a SyntaxToken<'a>> {
+    self.syntax.names().next()
+  }
+
+  pub fn arguments(&self) -> Vec<Argument<'a>> {
+    arguments(self.syntax)
+  }
+
+  pub fn directives(&self) -> Vec<Directive<'a>> {
+    directives(self.syntax)
+  }
+
+  pub fn selection_set(&self) -> Option<SelectionSet<'a>> {
+    selection_set(self.syntax)
+  }
+}
+
+impl<'a> Argument<'a> {
+  pub fn name(&self) -> Option<&'a SyntaxToken<'a>> {
+    self.syntax.names().next()
+  }
+
+  pub fn value(&self) -> Option<&'a SyntaxNode<'a>> {
+    value_node(self.syntax)
+  }
+}
+
+impl<'a> FragmentSpread<'a> {
+  pub fn name(&self) -> Option<&'a SyntaxToken<'a>> {
+    self.syntax.names().next()
+  }
+
+  pub fn directives(&self) -> Vec<Directive<'a>> {
+    directives(self.syntax)
+  }
+}
+
+impl<'a> InlineFragment<'a> {
+  pub fn type_condition(&self) -> Option<NamedType<'a>> {
+    type_condition(self.syntax)
+  }
+
+  pub fn directives(&self) -> Vec<Directive<'a>> {
+    directives(self.syntax)
+  }
+
+  pub fn selection_set(&self) -> Option<SelectionSet<'a>> {
+    selection_set(self.syntax)
+  }
+}
+
+impl<'a> NamedType<'a> {
+  pub fn name(&self) -> Option<&'a SyntaxToken<'a>> {
+    self.syntax.names().next()
+  }
+}
+
+impl<'a> Directive<'a> {
+  pub fn name(&self) -> Option<&'a SyntaxToken<'a>> {
+    self.syntax.names().next()
+  }
+
+  pub fn arguments(&self) -> Vec<Argument<'a>> {
+    arguments(self.syntax)
+  }
+}
+
+impl<'a> ObjectField<'a> {
+  pub fn name(&self) -> Option<&'a SyntaxToken<'a>> {
+    self.syntax.names().next()
+  }
+
+  pub fn value(&self) -> Option<&'a SyntaxNode<'a>> {
+    value_node(self.syntax)
+  }
+}
@@ -0,0 +1,972 @@
+use diagnostic::{Diagnostic, Span};
+use lexer::{Lexer, Punctuator, Token};
+
+use super::{NodeKind, SyntaxElement, SyntaxNode, SyntaxToken, TokenKind};
+
+/// Tree of a document, along with the errors found while building it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parse<'a> {
+  /// `Document` node spanning the whole input.
+  pub root: SyntaxNode<'a>,
+  /// Errors in the syntax of the document, in source order.
+  pub errors: Vec<Diagnostic>,
+}
+
+/// Builds the tree of `input`. Input which does not fit the grammar ends up in
+/// `Error` nodes, and tokens the grammar requires are left out, so that the
+/// tree always spans the whole input.
+///
+/// After an error, parsing resumes at the next token which can continue the
+/// construct being parsed or close it, such as a `}`. A definition keyword
+/// starting a line, as in `query Name {`, closes the selection sets left
+/// open before it. Selection sets, list types and values nested too deeply
+/// end up whole in `Error` nodes.
+pub fn parse(input: &str) -> Parse<'_> {
+  build(input,
+        Parser::definition,
+        "Add an operation, as in `{ field }`, or a fragment, as in \
+         `fragment Name on Type { field }`.")
+}
+
+/// Builds the tree of the schema document `input`, made of type system
+/// definitions and extensions, recovering from errors as `parse` does. A
+/// definition keyword starting a line, as in `type Name`, closes the blocks
+/// of fields and values left open before it.
+pub fn parse_schema(input: &str) -> Parse<'_> {
+  build(input, Parser::type_system_definition, TYPE_SYSTEM_HINT)
+}
+
+// Builds the tree of `input` from the definitions `definition` parses,
+// `hint` telling how to write one when there is none.
+fn build<'a>(input: &'a str, definition: fn(&mut Parser<'a>), hint: &str) -> Parse<'a> {
+  let mut errors = Vec::new();
+  let mut tokens = Vec::new();
+  let mut lexer = Lexer::new(input).with_trivia();
+  let mut start = 0;
+  while let Some(token) = lexer.next() {
+    let end = lexer.offset();
+    let kind = match token {
+      Ok(Token::Eof) => break,
+      Ok(Token::Punctuator(p, _, _)) => TokenKind::Punctuator(p),
+      Ok(Token::Name(..)) => TokenKind::Name,
+      Ok(Token::IntValue(..)) => TokenKind::IntValue,
+      Ok(Token::FloatValue(..)) => TokenKind::FloatValue,
+      Ok(Token::StringValue(..)) => TokenKind::StringValue,
+      Ok(Token::Whitespace(..)) => TokenKind::Whitespace,
+      Ok(Token::Comment(..)) => TokenKind::Comment,
+      Ok(Token::Comma(..)) => TokenKind::Comma,
+      Err(e) => {
+        errors.push(Diagnostic::new(format!("{}.", e), Span::new(lexer.position(), end), e.hint()));
+        TokenKind::Error
+      }
+    };
+    tokens.push(SyntaxToken {
+      kind,
+      text: &input[start..end],
+      start,
+    });
+    start = end;
+  }
+
+  let mut parser = Parser {
+    tokens,
+    pos: 0,
+    end: input.len(),
+    stack: vec![SyntaxNode {
+                  kind: NodeKind::Document,
+                  start: 0,
+                  children: Vec::new(),
+                }],
+    errors,
+  };
+  while parser.peek().is_some() {
+    definition(&mut parser);
+  }
+  if parser.stack[0].child_nodes().next().is_none() {
+    parser.expected("a definition", hint);
+  }
+  let root = parser.stack.pop().unwrap();
+  let mut errors = parser.errors;
+  errors.sort_by_key(|e| e.span.start);
+  Parse { root, errors }
+}
+
+static SELECTION_HINT: &str = "Select a field, as in `name`, or a fragment, as in `...Name`.";
+static ARGUMENT_HINT: &str = "Write arguments as in `name: value`.";
+static VARIABLE_HINT: &str = "Define variables as in `$name: Type`.";
+static OBJECT_FIELD_HINT: &str = "Write object fields as in `name: value`.";
+static TYPE_SYSTEM_HINT: &str =
+  "Define types, as in `type Name { field: Type }`, or directives, as in `directive @name on FIELD`.";
+static FIELD_DEFINITION_HINT: &str = "Define fields as in `name: Type`.";
+static INPUT_VALUE_HINT: &str = "Define arguments and input fields as in `name: Type`.";
+static ENUM_VALUE_HINT: &str = "Define enum values as in `NAME`.";
+static ROOT_OPERATION_HINT: &str = "Give the root operation types as in `query: Query`.";
+
+// Nodes nested deeper than this are not built, so that neither building the
+// tree nor walking it runs out of stack.
+const MAX_DEPTH: usize = 512;
+
+// Punctuators ending arguments and variable definitions, closed or not.
+static PARENS_END: &[Punctuator] =
+  &[Punctuator::RightParen, Punctuator::LeftBrace, Punctuator::RightBrace];
+
+// Punctuators closing the lists of values and the constructs around them.
+static VALUE_CLOSERS: &[Punctuator] =
+  &[Punctuator::RightBracket, Punctuator::RightBrace, Punctuator::RightParen];
+
+struct Parser<'a> {
+  tokens: Vec<SyntaxToken<'a>>,
+  pos: usize,
+  end: usize,
+  // Nodes being built, the document first.
+  stack: Vec<SyntaxNode<'a>>,
+  errors: Vec<Diagnostic>,
+}
+
+impl<'a> Parser<'a> {
+  fn push(&mut self, element: SyntaxElement<'a>) {
+    self.stack.last_mut().unwrap().children.push(element);
+  }
+
+  fn skip_trivia(&mut self) {
+    while self.pos < self.tokens.len() && self.tokens[self.pos].kind.is_trivia() {
+      let token = self.tokens[self.pos].clone();
+      self.push(SyntaxElement::Token(token));
+      self.pos += 1;
+    }
+  }
+
+  // Next significant token, the trivia before it going to the current node.
+  fn peek(&mut self) -> Option<&SyntaxToken<'a>> {
+    self.skip_trivia();
+    self.tokens.get(self.pos)
+  }
+
+  // Significant token after the next one.
+  fn peek_second(&mut self) -> Option<&SyntaxToken<'a>> {
+    self.skip_trivia();
+    self.tokens[self.pos..].iter().skip(1).find(|token| !token.kind.is_trivia())
+  }
+
+  fn offset(&mut self) -> usize {
+    let end = self.end;
+    self.peek().map_or(end, |token| token.start)
+  }
+
+  fn at(&mut self, punctuator: Punctuator) -> bool {
+    self.peek().is_some_and(|token| token.kind == TokenKind::Punctuator(punctuator))
+  }
+
+  fn at_any(&mut self, punctuators: &[Punctuator]) -> bool {
+    punctuators.iter().any(|&p| self.at(p))
+  }
+
+  fn at_name(&mut self) -> bool {
+    self.peek().is_some_and(|token| token.kind == TokenKind::Name)
+  }
+
+  fn at_keyword(&mut self, keyword: &str) -> bool {
+    self.peek().is_some_and(|token| token.kind == TokenKind::Name && token.text == keyword)
+  }
+
+  fn at_operation_keyword(&mut self) -> bool {
+    self.at_keyword("query") || self.at_keyword("mutation") || self.at_keyword("subscription")
+  }
+
+  fn at_value(&mut self) -> bool {
+    self.peek().is_some_and(|token| {
+      match token.kind {
+        TokenKind::Punctuator(p) => {
+          matches!(p, Punctuator::Dollar | Punctuator::LeftBracket | Punctuator::LeftBrace)
+        }
+        kind => kind != TokenKind::Error,
+      }
+    })
+  }
+
+  // Whether the next tokens start a definition at the beginning of a line, as
+  // in `query Name` or `fragment Name`, which is taken as the end of the
+  // definition before it even where they could be fields.
+  fn at_line_definition(&mut self) -> bool {
+    if !self.at_line_start() {
+      return false;
+    }
+    let second = self.peek_second().map(|token| token.kind);
+    if self.at_operation_keyword() {
+      matches!(second,
+               Some(TokenKind::Name) |
+               Some(TokenKind::Punctuator(Punctuator::LeftBrace)) |
+               Some(TokenKind::Punctuator(Punctuator::LeftParen)))
+    } else {
+      self.at_keyword("fragment") && second == Some(TokenKind::Name)
+    }
+  }
+
+  // Whether the next token starts a line.
+  fn at_line_start(&mut self) -> bool {
+    self.skip_trivia();
+    self.pos == 0 ||
+    self.tokens[self.pos - 1].kind == TokenKind::Whitespace &&
+    self.tokens[self.pos - 1].text.ends_with(['\n', '\r'])
+  }
+
+  // Kind of the type system definition or extension the next tokens start,
+  // looking past its description and `extend` for its keyword and the token
+  // after it, as in `type Name` or `schema {`.
+  fn at_type_system_definition(&mut self) -> Option<NodeKind> {
+    self.skip_trivia();
+    let mut tokens =
+      self.tokens[self.pos..].iter().filter(|token| !token.kind.is_trivia()).peekable();
+    let described = tokens.next_if(|token| token.kind == TokenKind::StringValue).is_some();
+    let extend = tokens.next_if(|token| token.kind == TokenKind::Name && token.text == "extend")
+      .is_some();
+    let keyword = tokens.next().filter(|token| token.kind == TokenKind::Name)?;
+    let next = tokens.next().map(|token| token.kind);
+    if described && extend {
+      return None;
+    }
+    let kind = match keyword.text {
+      "schema" => {
+        let at_block = next == Some(TokenKind::Punctuator(Punctuator::LeftBrace)) ||
+                       next == Some(TokenKind::Punctuator(Punctuator::At));
+        return if at_block { Some(NodeKind::SchemaDefinition) } else { None };
+      }
+      "directive" if !extend => {
+        let at_name = next == Some(TokenKind::Punctuator(Punctuator::At));
+        return if at_name { Some(NodeKind::DirectiveDefinition) } else { None };
+      }
+      "scalar" => NodeKind::ScalarTypeDefinition,
+      "type" => NodeKind::ObjectTypeDefinition,
+      "interface" => NodeKind::InterfaceTypeDefinition,
+      "union" => NodeKind::UnionTypeDefinition,
+      "enum" => NodeKind::EnumTypeDefinition,
+      "input" => NodeKind::InputObjectTypeDefinition,
+      _ => return None,
+    };
+    if next == Some(TokenKind::Name) { Some(kind) } else { None }
+  }
+
+  // Whether the next tokens start a type system definition at the beginning
+  // of a line, which is taken as the end of the definition before it.
+  fn at_line_type_system_definition(&mut self) -> bool {
+    self.at_line_start() && self.at_type_system_definition().is_some()
+  }
+
+  fn bump(&mut self) {
+    self.skip_trivia();
+    let token = self.tokens[self.pos].clone();
+    self.push(SyntaxElement::Token(token));
+    self.pos += 1;
+  }
+
+  fn start(&mut self, kind: NodeKind) {
+    let start = self.offset();
+    self.stack.push(SyntaxNode {
+      kind,
+      start,
+      children: Vec::new(),
+    });
+  }
+
+  // Ends the current node, leaving the trivia after its last token to the
+  // parent.
+  fn finish(&mut self) {
+    let mut node = self.stack.pop().unwrap();
+    let trivia = node.children
+      .iter()
+      .rev()
+      .take_while(|child| matches!(child, SyntaxElement::Token(token) if token.kind.is_trivia()))
+      .count();
+    let trivia = node.children.split_off(node.children.len() - trivia);
+    self.push(SyntaxElement::Node(node));
+    for token in trivia {
+      self.push(token);
+    }
+  }
+
+  // Position in the current node, to start a node wrapping what follows it.
+  fn checkpoint(&mut self) -> usize {
+    self.skip_trivia();
+    self.stack.last().unwrap().children.len()
+  }
+
+  fn start_at(&mut self, checkpoint: usize, kind: NodeKind) {
+    let children: Vec<_> = self.stack.last_mut().unwrap().children.drain(checkpoint..).collect();
+    let start = match children.first() {
+      Some(SyntaxElement::Node(node)) => node.start,
+      Some(SyntaxElement::Token(token)) => token.start,
+      None => self.offset(),
+    };
+    self.stack.push(SyntaxNode {
+      kind,
+      start,
+      children,
+    });
+  }
+
+  // Number of nodes of the current node, besides `Error` ones.
+  fn child_count(&self) -> usize {
+    self.stack.last().unwrap().child_nodes().filter(|node| node.kind != NodeKind::Error).count()
+  }
+
+  fn error(&mut self, message: String, hint: &str) {
+    let end = self.end;
+    let span = self.peek().map_or(Span::new(end, end), |token| Span::new(token.start, token.end()));
+    self.errors.push(Diagnostic::new(message, span, hint));
+  }
+
+  // Reports the next token in place of `expected`, unless the lexer already
+  // reported it.
+  fn expected(&mut self, expected: &str, hint: &str) {
+    if self.peek().is_some_and(|token| token.kind == TokenKind::Error) {
+      return;
+    }
+    let found = self.describe();
+    self.error(format!("Expected {}, found {}.", expected, found), hint);
+  }
+
+  fn expect(&mut self, punctuator: Punctuator, expected: &str, hint: &str) -> bool {
+    if self.at(punctuator) {
+      self.bump();
+      true
+    } else {
+      self.expected(expected, hint);
+      false
+    }
+  }
+
+  fn expect_name(&mut self, hint: &str) -> bool {
+    if self.at_name() {
+      self.bump();
+      true
+    } else {
+      self.expected("Name", hint);
+      false
+    }
+  }
+
+  // Whether the current node is nested too deeply to start another within it.
+  // The bracketed construct starting at the next token is then put in an
+  // `Error` node, up to its closing bracket or the end of the input.
+  fn too_deep(&mut self) -> bool {
+    if self.stack.len() < MAX_DEPTH {
+      return false;
+    }
+    self.error("Document is nested too deeply.".to_owned(),
+               "Move nested selections into fragments, and nest lists and objects less.");
+    self.start(NodeKind::Error);
+    let mut open = 0;
+    while let Some(kind) = self.peek().map(|token| token.kind) {
+      match kind {
+        TokenKind::Punctuator(Punctuator::LeftBrace) |
+        TokenKind::Punctuator(Punctuator::LeftBracket) |
+        TokenKind::Punctuator(Punctuator::LeftParen) => open += 1,
+        TokenKind::Punctuator(Punctuator::RightBrace) |
+        TokenKind::Punctuator(Punctuator::RightBracket) |
+        TokenKind::Punctuator(Punctuator::RightParen) => open -= 1,
+        _ => (),
+      }
+      self.bump();
+      if open == 0 {
+        break;
+      }
+    }
+    self.finish();
+    true
+  }
+
+  // Puts the next tokens in an `Error` node, up to one `resume` accepts,
+  // reporting the first one unless the lexer already did.
+  fn recover(&mut self, hint: &str, resume: impl Fn(&mut Parser<'a>) -> bool) {
+    if self.peek().is_some_and(|token| token.kind != TokenKind::Error) {
+      let found = self.describe();
+      self.error(format!("Unexpected {}.", found), hint);
+    }
+    self.start(NodeKind::Error);
+    self.bump();
+    while self.peek().is_some() && !resume(self) {
+      self.bump();
+    }
+    self.finish();
+  }
+
+  fn describe(&mut self) -> String {
+    match self.peek() {
+      None => "<EOF>".to_owned(),
+      Some(token) => {
+        match token.kind {
+          TokenKind::Name => format!("Name \"{}\"", token.text),
+          TokenKind::IntValue => format!("Int \"{}\"", token.text),
+          TokenKind::FloatValue => format!("Float \"{}\"", token.text),
+          TokenKind::StringValue => format!("String {}", token.text),
+          _ => format!("\"{}\"", token.text),
+        }
+      }
+    }
+  }
+
+  fn definition(&mut self) {
+    if self.at(Punctuator::LeftBrace) || self.at_operation_keyword() {
+      self.operation_definition();
+    } else if self.at_keyword("fragment") {
+      self.fragment_definition();
+    } else {
+      self.recover("Start operations with `{`, `query`, `mutation` or `subscription`, and \
+                    fragments with `fragment`.",
+                   |p| {
+                     p.at(Punctuator::LeftBrace) || p.at_operation_keyword() ||
+                     p.at_keyword("fragment")
+                   });
+    }
+  }
+
+  // OperationDefinition :
+  //   - SelectionSet
+  //   - OperationType Name? VariableDefinitions? Directives? SelectionSet
+  fn operation_definition(&mut self) {
+    self.start(NodeKind::OperationDefinition);
+    if self.at_name() {
+      self.bump();
+      if self.at_name() {
+        self.bump();
+      }
+      if self.at(Punctuator::LeftParen) {
+        self.variable_definitions();
+      }
+      self.directives();
+    }
+    self.selection_set();
+    self.finish();
+  }
+
+  // FragmentDefinition : fragment FragmentName TypeCondition Directives? SelectionSet
+  fn fragment_definition(&mut self) {
+    self.start(NodeKind::FragmentDefinition);
+    self.bump();
+    let hint = "Name the fragment, as in `fragment Name on Type`.";
+    if self.at_keyword("on") {
+      self.expected("fragment name", hint);
+    } else {
+      self.expect_name(hint);
+    }
+    self.type_condition();
+    self.directives();
+    self.selection_set();
+    self.finish();
+  }
+
+  // TypeCondition : on NamedType
+  fn type_condition(&mut self) {
+    self.start(NodeKind::TypeCondition);
+    if self.at_keyword("on") {
+      self.bump();
+    } else {
+      self.expected("\"on\"", "Give the type the fragment applies to, as in `on Type`.");
+    }
+    self.named_type();
+    self.finish();
+  }
+
+  // VariableDefinitions : ( VariableDefinition+ )
+  fn variable_definitions(&mut self) {
+    self.start(NodeKind::VariableDefinitions);
+    self.bump();
+    while self.peek().is_some() && !self.at_any(PARENS_END) {
+      if self.at(Punctuator::Dollar) {
+        self.variable_definition();
+      } else {
+        self.recover(VARIABLE_HINT, |p| p.at(Punctuator::Dollar) || p.at_any(PARENS_END));
+      }
+    }
+    if self.child_count() == 0 {
+      self.expected("a variable definition", VARIABLE_HINT);
+    }
+    self.expect(Punctuator::RightParen, "\")\"", "Close the variable definitions with `)`.");
+    self.finish();
+  }
+
+  // VariableDefinition : Variable : Type DefaultValue?
+  fn variable_definition(&mut self) {
+    self.start(NodeKind::VariableDefinition);
+    self.variable();
+    self.expect(Punctuator::Colon, "\":\"", "Give the variable a type, as in `$name: Type`.");
+    self.type_();
+    if self.at(Punctuator::Equals) {
+      self.start(NodeKind::DefaultValue);
+      self.bump();
+      self.value();
+      self.finish();
+    }
+    self.finish();
+  }
+
+  // Variable : $ Name
+  fn variable(&mut self) {
+    self.start(NodeKind::Variable);
+    self.bump();
+    self.expect_name("Name the variable after `$`, as in `$name`.");
+    self.finish();
+  }
+
+  // Type : NamedType | ListType | NonNullType
+  fn type_(&mut self) {
+    let checkpoint = self.checkpoint();
+    if self.at(Punctuator::LeftBracket) {
+      if self.too_deep() {
+        return;
+      }
+      self.start(NodeKind::ListType);
+      self.bump();
+      self.type_();
+      self.expect(Punctuator::RightBracket, "\"]\"", "Close the list type with `]`.");
+      self.finish();
+    } else {
+      self.named_type();
+    }
+    if self.at(Punctuator::Bang) {
+      self.start_at(checkpoint, NodeKind::NonNullType);
+      self.bump();
+      self.finish();
+    }
+  }
+
+  // NamedType : Name
+  fn named_type(&mut self) {
+    self.start(NodeKind::NamedType);
+    self.expect_name("Name a type, as in `String`.");
+    self.finish();
+  }
+
+  // Directives : Directive+
+  fn directives(&mut self) {
+    if !self.at(Punctuator::At) {
+      return;
+    }
+    self.start(NodeKind::Directives);
+    while self.at(Punctuator::At) {
+      self.start(NodeKind::Directive);
+      self.bump();
+      self.expect_name("Name the directive after `@`, as in `@include`.");
+      if self.at(Punctuator::LeftParen) {
+        self.arguments();
+      }
+      self.finish();
+    }
+    self.finish();
+  }
+
+  // Arguments : ( Argument+ )
+  fn arguments(&mut self) {
+    self.start(NodeKind::Arguments);
+    self.bump();
+    while self.peek().is_some() && !self.at_any(PARENS_END) {
+      if self.at_name() {
+        // Argument : Name : Value
+        self.start(NodeKind::Argument);
+        self.bump();
+        self.expect(Punctuator::Colon, "\":\"", ARGUMENT_HINT);
+        self.value();
+        self.finish();
+      } else {
+        self.recover(ARGUMENT_HINT, |p| p.at_name() || p.at_any(PARENS_END));
+      }
+    }
+    if self.child_count() == 0 {
+      self.expected("an argument", ARGUMENT_HINT);
+    }
+    self.expect(Punctuator::RightParen, "\")\"", "Close the arguments with `)`.");
+    self.finish();
+  }
+
+  // SelectionSet : { Selection+ }
+  fn selection_set(&mut self) {
+    if !self.at(Punctuator::LeftBrace) {
+      self.expected("\"{\"", "Select fields of the type, as in `{ name }`.");
+      return;
+    }
+    if self.too_deep() {
+      return;
+    }
+    self.start(NodeKind::SelectionSet);
+    self.bump();
+    while self.peek().is_some() && !self.at(Punctuator::RightBrace) && !self.at_line_definition() {
+      if self.at_name() {
+        self.field();
+      } else if self.at(Punctuator::Spread) {
+        self.fragment();
+      } else {
+        self.recover(SELECTION_HINT, |p| {
+          p.at_name() || p.at(Punctuator::Spread) || p.at(Punctuator::RightBrace)
+        });
+      }
+    }
+    if self.child_count() == 0 {
+      self.expected("a selection", SELECTION_HINT);
+    }
+    self.expect(Punctuator::RightBrace, "\"}\"", "Close the selection set with `}`.");
+    self.finish();
+  }
+
+  // Field : Alias? Name Arguments? Directives? SelectionSet?
+  fn field(&mut self) {
+    self.start(NodeKind::Field);
+    let checkpoint = self.checkpoint();
+    self.bump();
+    if self.at(Punctuator::Colon) {
+      self.start_at(checkpoint, NodeKind::Alias);
+      self.bump();
+      self.finish();
+      self.expect_name("Follow the alias with the field name, as in `alias: name`.");
+    }
+    if self.at(Punctuator::LeftParen) {
+      self.arguments();
+    }
+    self.directives();
+    if self.at(Punctuator::LeftBrace) {
+      self.selection_set();
+    }
+    self.finish();
+  }
+
+  // FragmentSpread : ... FragmentName Directives?
+  // InlineFragment : ... TypeCondition? Directives? SelectionSet
+  fn fragment(&mut self) {
+    let checkpoint = self.checkpoint();
+    self.bump();
+    if self.at_name() && !self.at_keyword("on") {
+      self.start_at(checkpoint, NodeKind::FragmentSpread);
+      self.bump();
+      self.directives();
+    } else {
+      self.start_at(checkpoint, NodeKind::InlineFragment);
+      if self.at_keyword("on") {
+        self.type_condition();
+      }
+      self.directives();
+      self.selection_set();
+    }
+    self.finish();
+  }
+
+  // Value : Variable | IntValue | FloatValue | StringValue | BooleanValue |
+  //   NullValue | EnumValue | ListValue | ObjectValue
+  fn value(&mut self) {
+    let kind = match self.peek().map(|token| (token.kind, token.text)) {
+      Some((TokenKind::Punctuator(Punctuator::Dollar), _)) => return self.variable(),
+      Some((TokenKind::Punctuator(Punctuator::LeftBracket), _)) => return self.list_value(),
+      Some((TokenKind::Punctuator(Punctuator::LeftBrace), _)) => return self.object_value(),
+      Some((TokenKind::IntValue, _)) => NodeKind::IntValue,
+      Some((TokenKind::FloatValue, _)) => NodeKind::FloatValue,
+      Some((TokenKind::StringValue, _)) => NodeKind::StringValue,
+      Some((TokenKind::Name, "true")) | Some((TokenKind::Name, "false")) => NodeKind::BooleanValue,
+      Some((TokenKind::Name, "null")) => NodeKind::NullValue,
+      Some((TokenKind::Name, _)) => NodeKind::EnumValue,
+      _ => {
+        return self.expected("a value",
+                             "Use a variable, a number, a string, `true`, `false`, `null`, an \
+                              enum value, a list or an object.")
+      }
+    };
+    self.start(kind);
+    self.bump();
+    self.finish();
+  }
+
+  // ListValue : [ Value* ]
+  fn list_value(&mut self) {
+    if self.too_deep() {
+      return;
+    }
+    self.start(NodeKind::ListValue);
+    self.bump();
+    while self.peek().is_some() && !self.at_any(VALUE_CLOSERS) {
+      if self.at_value() {
+        self.value();
+      } else {
+        self.recover("Separate the values of lists with spaces or commas.",
+                     |p| p.at_value() || p.at_any(VALUE_CLOSERS));
+      }
+    }
+    self.expect(Punctuator::RightBracket, "\"]\"", "Close the list with `]`.");
+    self.finish();
+  }
+
+  // ObjectValue : { ObjectField* }
+  fn object_value(&mut self) {
+    if self.too_deep() {
+      return;
+    }
+    self.start(NodeKind::ObjectValue);
+    self.bump();
+    while self.peek().is_some() && !self.at_any(VALUE_CLOSERS) {
+      if self.at_name() {
+        // ObjectField : Name : Value
+        self.start(NodeKind::ObjectField);
+        self.bump();
+        self.expect(Punctuator::Colon, "\":\"", OBJECT_FIELD_HINT);
+        self.value();
+        self.finish();
+      } else {
+        self.recover(OBJECT_FIELD_HINT, |p| p.at_name() || p.at_any(VALUE_CLOSERS));
+      }
+    }
+    self.expect(Punctuator::RightBrace, "\"}\"", "Close the object with `}`.");
+    self.finish();
+  }
+
+  // TypeSystemDefinition : SchemaDefinition | TypeDefinition | DirectiveDefinition
+  // TypeSystemExtension : SchemaExtension | TypeExtension
+  fn type_system_definition(&mut self) {
+    let kind = match self.at_type_system_definition() {
+      Some(kind) => kind,
+      None => {
+        return self.recover(TYPE_SYSTEM_HINT, |p| p.at_type_system_definition().is_some());
+      }
+    };
+    self.start(kind);
+    self.description();
+    let extend = self.at_keyword("extend");
+    if extend {
+      self.bump();
+    }
+    self.bump();
+    match kind {
+      // SchemaDefinition : Description? schema Directives? { RootOperationTypeDefinition+ }
+      NodeKind::SchemaDefinition => {
+        self.directives();
+        if !extend || self.at(Punctuator::LeftBrace) {
+          self.root_operation_types();
+        }
+      }
+      // DirectiveDefinition :
+      //   Description? directive @ Name ArgumentsDefinition? repeatable? on DirectiveLocations
+      NodeKind::DirectiveDefinition => {
+        self.bump();
+        self.expect_name("Name the directive after `@`, as in `@name`.");
+        if self.at(Punctuator::LeftParen) {
+          self.arguments_definition();
+        }
+        if self.at_keyword("repeatable") {
+          self.bump();
+        }
+        if self.at_keyword("on") {
+          self.bump();
+        } else {
+          self.expected("\"on\"", "Give the locations of the directive, as in `on FIELD`.");
+        }
+        self.directive_locations();
+      }
+      _ => {
+        self.bump();
+        if self.at_keyword("implements") &&
+           matches!(kind, NodeKind::ObjectTypeDefinition | NodeKind::InterfaceTypeDefinition) {
+          self.implements_interfaces();
+        }
+        self.directives();
+        match kind {
+          NodeKind::UnionTypeDefinition if self.at(Punctuator::Equals) => {
+            self.union_member_types()
+          }
+          NodeKind::ObjectTypeDefinition | NodeKind::InterfaceTypeDefinition
+            if self.at(Punctuator::LeftBrace) => {
+            self.block(NodeKind::FieldsDefinition,
+                       "a field definition",
+                       FIELD_DEFINITION_HINT,
+                       "Close the field definitions with `}`.",
+                       Parser::field_definition)
+          }
+          NodeKind::EnumTypeDefinition if self.at(Punctuator::LeftBrace) => {
+            self.block(NodeKind::EnumValuesDefinition,
+                       "an enum value",
+                       ENUM_VALUE_HINT,
+                       "Close the enum values with `}`.",
+                       Parser::enum_value_definition)
+          }
+          NodeKind::InputObjectTypeDefinition if self.at(Punctuator::LeftBrace) => {
+            self.block(NodeKind::InputFieldsDefinition,
+                       "an input field",
+                       INPUT_VALUE_HINT,
+                       "Close the input fields with `}`.",
+                       Parser::input_value_definition)
+          }
+          _ => {}
+        }
+      }
+    }
+    self.finish();
+  }
+
+  // Description : StringValue
+  fn description(&mut self) {
+    if self.peek().is_some_and(|token| token.kind == TokenKind::StringValue) {
+      self.start(NodeKind::Description);
+      self.bump();
+      self.finish();
+    }
+  }
+
+  // `{ RootOperationTypeDefinition+ }` of schema definitions.
+  fn root_operation_types(&mut self) {
+    if !self.at(Punctuator::LeftBrace) {
+      self.expected("\"{\"", "Give the root operation types, as in `{ query: Query }`.");
+      return;
+    }
+    let count = self.child_count();
+    self.bump();
+    while self.peek().is_some() && !self.at(Punctuator::RightBrace) &&
+          !self.at_line_type_system_definition() {
+      if self.at_name() {
+        // RootOperationTypeDefinition : OperationType : NamedType
+        self.start(NodeKind::RootOperationTypeDefinition);
+        self.bump();
+        self.expect(Punctuator::Colon, "\":\"", ROOT_OPERATION_HINT);
+        self.named_type();
+        self.finish();
+      } else {
+        self.recover(ROOT_OPERATION_HINT, |p| {
+          p.at_name() || p.at(Punctuator::RightBrace) || p.at_line_type_system_definition()
+        });
+      }
+    }
+    if self.child_count() == count {
+      self.expected("a root operation type", ROOT_OPERATION_HINT);
+    }
+    self.expect(Punctuator::RightBrace, "\"}\"", "Close the root operation types with `}`.");
+  }
+
+  // Whether the next token starts a field, argument or enum value definition,
+  // with its name or its description.
+  fn at_member(&mut self) -> bool {
+    self.peek().is_some_and(|token| {
+      token.kind == TokenKind::Name || token.kind == TokenKind::StringValue
+    })
+  }
+
+  // `{ item+ }` of fields, enum values and input fields, in a `kind` node,
+  // `item` parsing each of the members it holds.
+  fn block(&mut self,
+           kind: NodeKind,
+           expected: &str,
+           hint: &str,
+           closing: &str,
+           item: fn(&mut Parser<'a>)) {
+    self.start(kind);
+    self.bump();
+    while self.peek().is_some() && !self.at(Punctuator::RightBrace) &&
+          !self.at_line_type_system_definition() {
+      if self.at_member() {
+        item(self);
+      } else {
+        self.recover(hint, |p| {
+          p.at_member() || p.at(Punctuator::RightBrace) || p.at_line_type_system_definition()
+        });
+      }
+    }
+    if self.child_count() == 0 {
+      self.expected(expected, hint);
+    }
+    self.expect(Punctuator::RightBrace, "\"}\"", closing);
+    self.finish();
+  }
+
+  // FieldDefinition : Description? Name ArgumentsDefinition? : Type Directives?
+  fn field_definition(&mut self) {
+    self.start(NodeKind::FieldDefinition);
+    self.description();
+    self.expect_name(FIELD_DEFINITION_HINT);
+    if self.at(Punctuator::LeftParen) {
+      self.arguments_definition();
+    }
+    self.expect(Punctuator::Colon, "\":\"", "Give the field a type, as in `name: Type`.");
+    self.type_();
+    self.directives();
+    self.finish();
+  }
+
+  // ArgumentsDefinition : ( InputValueDefinition+ )
+  fn arguments_definition(&mut self) {
+    self.start(NodeKind::ArgumentsDefinition);
+    self.bump();
+    while self.peek().is_some() && !self.at_any(PARENS_END) {
+      if self.at_member() {
+        self.input_value_definition();
+      } else {
+        self.recover(INPUT_VALUE_HINT, |p| p.at_member() || p.at_any(PARENS_END));
+      }
+    }
+    if self.child_count() == 0 {
+      self.expected("an argument definition", INPUT_VALUE_HINT);
+    }
+    self.expect(Punctuator::RightParen, "\")\"", "Close the argument definitions with `)`.");
+    self.finish();
+  }
+
+  // InputValueDefinition : Description? Name : Type DefaultValue? Directives?
+  fn input_value_definition(&mut self) {
+    self.start(NodeKind::InputValueDefinition);
+    self.description();
+    self.expect_name(INPUT_VALUE_HINT);
+    self.expect(Punctuator::Colon, "\":\"", INPUT_VALUE_HINT);
+    self.type_();
+    if self.at(Punctuator::Equals) {
+      self.start(NodeKind::DefaultValue);
+      self.bump();
+      self.value();
+      self.finish();
+    }
+    self.directives();
+    self.finish();
+  }
+
+  // EnumValueDefinition : Description? EnumValue Directives?
+  fn enum_value_definition(&mut self) {
+    self.start(NodeKind::EnumValueDefinition);
+    self.description();
+    self.expect_name(ENUM_VALUE_HINT);
+    self.directives();
+    self.finish();
+  }
+
+  // ImplementsInterfaces : implements &? NamedType ( & NamedType )*
+  fn implements_interfaces(&mut self) {
+    self.start(NodeKind::ImplementsInterfaces);
+    self.bump();
+    self.separated_types(Punctuator::Ampersand);
+    self.finish();
+  }
+
+  // UnionMemberTypes : = |? NamedType ( | NamedType )*
+  fn union_member_types(&mut self) {
+    self.start(NodeKind::UnionMemberTypes);
+    self.bump();
+    self.separated_types(Punctuator::Pipe);
+    self.finish();
+  }
+
+  // Named types separated by `separator`, which may also lead them.
+  fn separated_types(&mut self, separator: Punctuator) {
+    if self.at(separator) {
+      self.bump();
+    }
+    self.named_type();
+    while self.at(separator) {
+      self.bump();
+      self.named_type();
+    }
+  }
+
+  // DirectiveLocations : |? DirectiveLocation ( | DirectiveLocation )*
+  fn directive_locations(&mut self) {
+    self.start(NodeKind::DirectiveLocations);
+    if self.at(Punctuator::Pipe) {
+      self.bump();
+    }
+    let hint = "Name the locations of the directive, as in `on FIELD | OBJECT`.";
+    self.expect_name(hint);
+    while self.at(Punctuator::Pipe) {
+      self.bump();
+      self.expect_name(hint);
+    }
+    self.finish();
+  }
+}
@@ -0,0 +1,181 @@
+use lexer::Punctuator;
+
+use super::*;
+
+static QUERY: &str = "# Heroes and their friends.
+query Hero($episode: Episode = JEDI, $ids: [ID!]!) @cached {
+  hero(episode: $episode, first: 10) {
+    alias: name, # trailing comment
+    ...Friends @include(if: true)
+    ... on Droid { primaryFunction }
+  }
+}
+
+fragment Friends on Character {
+  friends(filter: { name: \"Luke\", rank: [1.5, null] }) { name }
+}
+";
+
+static SCHEMA: &str = "\"\"\"The saga\"\"\"
+schema { query: Root }
+
+directive @auth(role: String = \"admin\") repeatable on | FIELD | OBJECT
+
+type Root implements Node & Entity @key(fields: \"id\") {
+  \"The hero\"
+  hero(id: ID!, first: Int = 10): [Hero!]! # trailing comment
+}
+
+extend union Result = | Root | Hero
+enum Episode { NEWHOPE JEDI @deprecated }
+input Filter { name: String = \"Luke\" }
+scalar Date
+";
+
+#[test]
+fn prints_the_source_back() {
+  let parse = parse(QUERY);
+  assert_eq!(parse.errors, vec![]);
+  assert_eq!(parse.root.to_string(), QUERY);
+  assert_eq!((parse.root.start, parse.root.end()), (0, QUERY.len()));
+
+  let comments: Vec<_> = parse.root
+    .tokens()
+    .into_iter()
+    .filter(|token| token.kind == TokenKind::Comment)
+    .map(|token| token.text)
+    .collect();
+  assert_eq!(comments, vec!["# Heroes and their friends.", "# trailing comment"]);
+}
+
+#[test]
+fn typed_nodes() {
+  let parse = parse(QUERY);
+  let document = Document::cast(&parse.root).unwrap();
+  let definitions = document.definitions();
+  assert_eq!(definitions.len(), 2);
+
+  let operation = match definitions[0] {
+    Definition::Operation(operation) => operation,
+    _ => panic!("Expected an operation."),
+  };
+  assert_eq!(operation.operation_type().unwrap().text, "query");
+  assert_eq!(operation.name().unwrap().text, "Hero");
+  let variables = operation.variable_definitions();
+  assert_eq!(variables[0].variable().unwrap().name().unwrap().text, "episode");
+  assert_eq!(variables[0].default_value().unwrap().kind, NodeKind::EnumValue);
+  let ids_type = variables[1].type_().unwrap();
+  assert_eq!((ids_type.kind, ids_type.to_string()), (NodeKind::NonNullType, "[ID!]!".to_owned()));
+  assert_eq!(operation.directives()[0].name().unwrap().text, "cached");
+
+  let selections = operation.selection_set().unwrap().selections();
+  let hero = match selections[0] {
+    Selection::Field(field) => field,
+    _ => panic!("Expected a field."),
+  };
+  assert_eq!(hero.alias().map(|a| a.text), None);
+  let arguments: Vec<_> = hero.arguments()
+    .iter()
+    .map(|a| (a.name().unwrap().text, a.value().unwrap().to_string()))
+    .collect();
+  assert_eq!(arguments, vec![("episode", "$episode".to_owned()), ("first", "10".to_owned())]);
+
+  let selections = hero.selection_set().unwrap().selections();
+  assert_eq!(selections.len(), 3);
+  match selections[0] {
+    Selection::Field(field) => {
+      assert_eq!((field.alias().unwrap().text, field.name().unwrap().text), ("alias", "name"))
+    }
+    _ => panic!("Expected a field."),
+  }
+  match selections[1] {
+    Selection::FragmentSpread(spread) => {
+      assert_eq!(spread.name().unwrap().text, "Friends");
+      let include = spread.directives()[0];
+      assert_eq!(include.arguments()[0].value().unwrap().kind, NodeKind::BooleanValue);
+    }
+    _ => panic!("Expected a fragment spread."),
+  }
+  match selections[2] {
+    Selection::InlineFragment(fragment) => {
+      assert_eq!(fragment.type_condition().unwrap().name().unwrap().text, "Droid");
+      assert_eq!(fragment.syntax().to_string(), "... on Droid { primaryFunction }");
+    }
+    _ => panic!("Expected an inline fragment."),
+  }
+
+  let fragment = match definitions[1] {
+    Definition::Fragment(fragment) => fragment,
+    _ => panic!("Expected a fragment."),
+  };
+  assert_eq!(fragment.name().unwrap().text, "Friends");
+  assert_eq!(fragment.type_condition().unwrap().name().unwrap().text, "Character");
+  let friends = match fragment.selection_set().unwrap().selections()[0] {
+    Selection::Field(field) => field,
+    _ => panic!("Expected a field."),
+  };
+  let filter = friends.arguments()[0].value().unwrap();
+  let fields: Vec<_> = filter.child_nodes()
+    .filter_map(ObjectField::cast)
+    .map(|f| (f.name().unwrap().text, f.value().unwrap().kind))
+    .collect();
+  assert_eq!(fields, vec![("name", NodeKind::StringValue), ("rank", NodeKind::ListValue)]);
+}
+
+#[test]
+fn keeps_invalid_input() {
+  let input = "{ hero(first: ) { ? name } ";
+  let parse = parse(input);
+  assert_eq!(parse.root.to_string(), input);
+  let errors: Vec<_> = parse.errors.iter().map(|e| (e.message.as_ref(), e.span.start)).collect();
+  assert_eq!(errors,
+             vec![("Expected a value, found \")\".", 14),
+                  ("Unexpected character.", 18),
+                  ("Expected \"}\", found <EOF>.", 27)]);
+
+  let parse = super::parse("} query");
+  assert_eq!(parse.root.to_string(), "} query");
+  assert_eq!(parse.root.child_nodes().next().unwrap().kind, NodeKind::Error);
+  let tokens: Vec<_> = parse.root.tokens().iter().map(|t| t.kind).collect();
+  assert_eq!(tokens,
+             vec![TokenKind::Punctuator(Punctuator::RightBrace),
+                  TokenKind::Whitespace,
+                  TokenKind::Name]);
+  assert_eq!(parse.errors[0].message, "Unexpected \"}\".");
+  assert_eq!(parse.errors[1].message, "Expected \"{\", found <EOF>.");
+}
+
+#[test]
+fn parses_schemas() {
+  let parse = parse_schema(SCHEMA);
+  assert_eq!(parse.errors, vec![]);
+  assert_eq!(parse.root.to_string(), SCHEMA);
+  let kinds: Vec<_> = parse.root.child_nodes().map(|node| node.kind).collect();
+  assert_eq!(kinds,
+             vec![NodeKind::SchemaDefinition,
+                  NodeKind::DirectiveDefinition,
+                  NodeKind::ObjectTypeDefinition,
+                  NodeKind::UnionTypeDefinition,
+                  NodeKind::EnumTypeDefinition,
+                  NodeKind::InputObjectTypeDefinition,
+                  NodeKind::ScalarTypeDefinition]);
+
+  let root = parse.root.child_nodes().nth(2).unwrap();
+  let fields = root.child_nodes().find(|node| node.kind == NodeKind::FieldsDefinition).unwrap();
+  let hero = fields.child_nodes().next().unwrap();
+  let parts: Vec<_> = hero.child_nodes().map(|node| node.kind).collect();
+  assert_eq!(parts,
+             vec![NodeKind::Description, NodeKind::ArgumentsDefinition, NodeKind::NonNullType]);
+  assert_eq!(hero.to_string(), "\"The hero\"\n  hero(id: ID!, first: Int = 10): [Hero!]!");
+
+  // An unclosed block ends at the next definition starting a line.
+  let input = "type Query { hero: Hero\ntype Hero { name: String }";
+  let parse = parse_schema(input);
+  assert_eq!(parse.root.to_string(), input);
+  assert_eq!(parse.root.child_nodes().count(), 2);
+  assert_eq!(parse.errors[0].message, "Expected \"}\", found Name \"type\".");
+
+  let parse = parse_schema("{ hero }");
+  assert_eq!(parse.root.child_nodes().next().unwrap().kind, NodeKind::Error);
+  assert_eq!(parse.errors[0].message, "Unexpected \"{\".");
+}
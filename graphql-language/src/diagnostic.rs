@@ -0,0 +1,48 @@
+use std::fmt;
+
+/// Byte offsets of a part of a source, from `start` up to `end` excluded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+  pub start: usize,
+  pub end: usize,
+}
+
+impl Span {
+  pub fn new(start: usize, end: usize) -> Span {
+    Span { start, end }
+  }
+
+  pub fn len(&self) -> usize {
+    self.end - self.start
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.start == self.end
+  }
+}
+
+/// Problem found in a source, with the part of the source it is about and a
+/// hint at how to fix it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+  pub message: String,
+  /// Where the problem is, empty where something is missing.
+  pub span: Span,
+  pub hint: String,
+}
+
+impl Diagnostic {
+  pub fn new(message: String, span: Span, hint: &str) -> Diagnostic {
+    Diagnostic {
+      message,
+      span,
+      hint: hint.to_owned(),
+    }
+  }
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} {}", self.message, self.hint)
+  }
+}
@@ -0,0 +1,274 @@
+//! Prints documents in a canonical layout, the one of `print` in graphql-js:
+//! one selection per line indented by two spaces, arguments and values on
+//! one line with their items separated by `, `, and a blank line between
+//! definitions.
+//!
+//! Schema documents, the ones starting with a description or a type system
+//! keyword such as `type`, are laid out as by `printSchema`: one field, enum
+//! value or input field per line, and the arguments of a field on one line
+//! unless one of them has a description, which puts each on its own line.
+//!
+//! Comments are kept. A comment which follows a token on its line stays
+//! after it, others are put on their own line at the indentation of what
+//! follows them.
+
+use cst::{self, NodeKind, SyntaxElement, SyntaxNode, SyntaxToken, TokenKind};
+use diagnostic::Diagnostic;
+use lexer::{Lexer, Punctuator, Token};
+
+#[cfg(test)]
+mod tests;
+
+/// Formats `input`, failing with the errors of its syntax if there is any.
+pub fn format(input: &str) -> Result<String, Vec<Diagnostic>> {
+  let parse = if is_schema(input) { cst::parse_schema(input) } else { cst::parse(input) };
+  if !parse.errors.is_empty() {
+    return Err(parse.errors);
+  }
+  let mut printer = Printer {
+    out: String::with_capacity(input.len()),
+    indent: 0,
+    pending: Break::None,
+    last: None,
+    operation_names: 0,
+    newline_before: true,
+  };
+  printer.document(&parse.root);
+  printer.out.push('\n');
+  Ok(printer.out)
+}
+
+// Whether `input` is a schema document, starting with a description or the
+// keyword of a type system definition or extension.
+fn is_schema(input: &str) -> bool {
+  match Lexer::new(input).next() {
+    Some(Ok(Token::StringValue(..))) => true,
+    Some(Ok(Token::Name(name, _, _))) => {
+      matches!(name,
+               "schema" | "scalar" | "type" | "interface" | "union" | "enum" | "input" |
+               "directive" | "extend")
+    }
+    _ => false,
+  }
+}
+
+// Whether `{` and `}` in nodes of the kind open and close an indented block.
+fn is_block(kind: NodeKind) -> bool {
+  matches!(kind,
+           NodeKind::SelectionSet | NodeKind::SchemaDefinition | NodeKind::FieldsDefinition |
+           NodeKind::EnumValuesDefinition | NodeKind::InputFieldsDefinition)
+}
+
+// Whether the argument definitions are put one per line, as they are when
+// one of them has a description.
+fn is_multiline(node: &SyntaxNode) -> bool {
+  node.kind == NodeKind::ArgumentsDefinition &&
+  node.child_nodes().any(|argument| {
+    argument.child_nodes().any(|child| child.kind == NodeKind::Description)
+  })
+}
+
+// Whether the child at `i` is a `&` or `|` before the first item of the
+// list, which is left out.
+fn is_leading_separator(node: &SyntaxNode, i: usize) -> bool {
+  let separator = match node.children[i] {
+    SyntaxElement::Token(ref token) => {
+      token.kind == TokenKind::Punctuator(Punctuator::Ampersand) ||
+      token.kind == TokenKind::Punctuator(Punctuator::Pipe)
+    }
+    SyntaxElement::Node(_) => false,
+  };
+  separator &&
+  !node.children[..i].iter().any(|child| {
+    match *child {
+      SyntaxElement::Node(_) => true,
+      SyntaxElement::Token(ref token) => {
+        token.kind == TokenKind::Name && node.kind == NodeKind::DirectiveLocations
+      }
+    }
+  })
+}
+
+// Line break before the next token, the larger one winning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Break {
+  None,
+  Line,
+  BlankLine,
+}
+
+struct Printer {
+  out: String,
+  indent: usize,
+  pending: Break,
+  // Last token written, with the kind of its node.
+  last: Option<(TokenKind, NodeKind)>,
+  // Names of the operation written so far, its type included.
+  operation_names: usize,
+  // Whether there is a line break in the source since the last token
+  // written, which puts the comments found next on their own line.
+  newline_before: bool,
+}
+
+impl Printer {
+  fn brk(&mut self, brk: Break) {
+    self.pending = self.pending.max(brk);
+  }
+
+  fn write(&mut self, text: &str, space: bool) {
+    if !self.out.is_empty() {
+      if self.pending > Break::None {
+        if self.pending == Break::BlankLine {
+          self.out.push('\n');
+        }
+        self.out.push('\n');
+        for _ in 0..self.indent {
+          self.out.push_str("  ");
+        }
+      } else if space {
+        self.out.push(' ');
+      }
+    }
+    self.pending = Break::None;
+    self.out.push_str(text);
+    self.newline_before = false;
+  }
+
+  fn trivia(&mut self, token: &SyntaxToken) {
+    match token.kind {
+      TokenKind::Whitespace if token.text.contains(['\n', '\r']) => self.newline_before = true,
+      TokenKind::Comment if self.newline_before => {
+        self.brk(Break::Line);
+        self.write(token.text.trim_end(), false);
+        self.brk(Break::Line);
+        self.newline_before = true;
+      }
+      TokenKind::Comment => {
+        self.out.push(' ');
+        self.out.push_str(token.text.trim_end());
+        self.brk(Break::Line);
+        self.newline_before = true;
+      }
+      _ => {}
+    }
+  }
+
+  fn document(&mut self, node: &SyntaxNode) {
+    for child in &node.children {
+      match *child {
+        SyntaxElement::Token(ref token) => self.trivia(token),
+        SyntaxElement::Node(ref definition) => {
+          self.node(definition);
+          self.brk(Break::BlankLine);
+        }
+      }
+    }
+  }
+
+  fn node(&mut self, node: &SyntaxNode) {
+    if node.kind == NodeKind::OperationDefinition {
+      self.operation_names = 0;
+    }
+    let multiline = is_multiline(node);
+    let is_item = |child: &SyntaxNode| {
+      match node.kind {
+        NodeKind::Arguments => child.kind == NodeKind::Argument,
+        NodeKind::ArgumentsDefinition => !multiline && child.kind == NodeKind::InputValueDefinition,
+        NodeKind::VariableDefinitions => child.kind == NodeKind::VariableDefinition,
+        NodeKind::ObjectValue => child.kind == NodeKind::ObjectField,
+        NodeKind::ListValue => true,
+        _ => false,
+      }
+    };
+    for (i, child) in node.children.iter().enumerate() {
+      match *child {
+        SyntaxElement::Token(ref token) if token.kind.is_trivia() => self.trivia(token),
+        SyntaxElement::Token(_) if is_leading_separator(node, i) => {}
+        SyntaxElement::Token(ref token) => self.token(node, token),
+        SyntaxElement::Node(ref child) => {
+          let own_line = match child.kind {
+            NodeKind::Field | NodeKind::FragmentSpread | NodeKind::InlineFragment |
+            NodeKind::FieldDefinition | NodeKind::EnumValueDefinition |
+            NodeKind::RootOperationTypeDefinition => true,
+            NodeKind::InputValueDefinition => {
+              multiline || node.kind != NodeKind::ArgumentsDefinition
+            }
+            _ => false,
+          };
+          if own_line {
+            self.brk(Break::Line);
+          }
+          self.node(child);
+          if child.kind == NodeKind::Description {
+            self.brk(Break::Line);
+          }
+          let more = node.children[i + 1..]
+            .iter()
+            .any(|next| matches!(next, SyntaxElement::Node(next) if is_item(next)));
+          if is_item(child) && more {
+            self.out.push(',');
+          }
+        }
+      }
+    }
+  }
+
+  fn token(&mut self, node: &SyntaxNode, token: &SyntaxToken) {
+    let parent = node.kind;
+    let space = self.space_before(parent, token);
+    // Brackets around what is indented on the lines between them.
+    let brackets = if is_block(parent) {
+      Some((Punctuator::LeftBrace, Punctuator::RightBrace))
+    } else if is_multiline(node) {
+      Some((Punctuator::LeftParen, Punctuator::RightParen))
+    } else {
+      None
+    };
+    let punctuator = match token.kind {
+      TokenKind::Punctuator(p) => Some(p),
+      _ => None,
+    };
+    if brackets.is_some_and(|(_, close)| punctuator == Some(close)) {
+      self.indent -= 1;
+      self.brk(Break::Line);
+    }
+    self.write(token.text, space);
+    if brackets.is_some_and(|(open, _)| punctuator == Some(open)) {
+      self.indent += 1;
+      self.brk(Break::Line);
+    }
+    if parent == NodeKind::OperationDefinition && token.kind == TokenKind::Name {
+      self.operation_names += 1;
+    }
+    self.last = Some((token.kind, parent));
+  }
+
+  fn space_before(&self, parent: NodeKind, token: &SyntaxToken) -> bool {
+    let (last, last_parent) = match self.last {
+      Some(last) => last,
+      None => return false,
+    };
+    match token.kind {
+      TokenKind::Punctuator(Punctuator::RightParen) |
+      TokenKind::Punctuator(Punctuator::RightBracket) |
+      TokenKind::Punctuator(Punctuator::Colon) |
+      TokenKind::Punctuator(Punctuator::Bang) => return false,
+      TokenKind::Punctuator(Punctuator::RightBrace) => return parent != NodeKind::ObjectValue,
+      // Only the variables of anonymous operations are apart, as in
+      // `query ($id: ID)`.
+      TokenKind::Punctuator(Punctuator::LeftParen) => {
+        return parent == NodeKind::VariableDefinitions && self.operation_names == 1
+      }
+      _ => {}
+    }
+    match last {
+      TokenKind::Punctuator(Punctuator::LeftParen) |
+      TokenKind::Punctuator(Punctuator::LeftBracket) |
+      TokenKind::Punctuator(Punctuator::Dollar) |
+      TokenKind::Punctuator(Punctuator::At) => false,
+      TokenKind::Punctuator(Punctuator::LeftBrace) => last_parent != NodeKind::ObjectValue,
+      TokenKind::Punctuator(Punctuator::Spread) => parent != NodeKind::FragmentSpread,
+      _ => true,
+    }
+  }
+}
@@ -0,0 +1,130 @@
+use super::*;
+
+#[test]
+fn prints_the_canonical_layout() {
+  let input = "query Hero($episode:Episode=JEDI,$ids:[ID!]!)@cached{hero(episode:$episode first:10)\
+               {alias:name ...Friends@include(if:true) ...on Droid{primaryFunction}}}
+fragment Friends on Character{friends(filter:{name:\"Luke\" rank:[1.5,null]}){name}}";
+  assert_eq!(format(input).unwrap(),
+             "query Hero($episode: Episode = JEDI, $ids: [ID!]!) @cached {
+  hero(episode: $episode, first: 10) {
+    alias: name
+    ...Friends @include(if: true)
+    ... on Droid {
+      primaryFunction
+    }
+  }
+}
+
+fragment Friends on Character {
+  friends(filter: {name: \"Luke\", rank: [1.5, null]}) {
+    name
+  }
+}
+");
+
+  assert_eq!(format("{ a }").unwrap(), "{\n  a\n}\n");
+  assert_eq!(format("query($id: ID) { a }").unwrap(), "query ($id: ID) {\n  a\n}\n");
+  assert_eq!(format("{ ... @skip(if: false) { a } }").unwrap(),
+             "{\n  ... @skip(if: false) {\n    a\n  }\n}\n");
+}
+
+#[test]
+fn keeps_comments() {
+  let input = "# Heroes.
+{
+
+
+  hero { # the hero
+    # Names only.
+    name,   id # trailing
+  }
+}
+# Between.
+
+{ b }   # last
+";
+  assert_eq!(format(input).unwrap(),
+             "# Heroes.
+{
+  hero { # the hero
+    # Names only.
+    name
+    id # trailing
+  }
+}
+
+# Between.
+{
+  b
+} # last
+");
+}
+
+#[test]
+fn prints_schemas() {
+  let input = "\"\"\"The saga\"\"\" schema{query:Root}
+# Auth.
+directive @auth(role:String!=\"admin\")repeatable on|FIELD_DEFINITION|OBJECT
+type Root implements&Node&Entity @key(fields:\"id\"){
+  \"The hero\" hero(\"Which one\" id:ID! first:Int=10):[Hero!]! # old
+  name(a:Int,b:[String]=[\"x\"]):String @deprecated
+}
+enum Episode{NEWHOPE,\"Last\" JEDI}
+union Result=|Root|Hero
+input Filter{name:String=\"Luke\" rank:Int}
+extend scalar Date@specifiedBy(url:\"https://tools.ietf.org/html/rfc3339\")";
+  assert_eq!(format(input).unwrap(),
+             "\"\"\"The saga\"\"\"
+schema {
+  query: Root
+}
+
+# Auth.
+directive @auth(role: String! = \"admin\") repeatable on FIELD_DEFINITION | OBJECT
+
+type Root implements Node & Entity @key(fields: \"id\") {
+  \"The hero\"
+  hero(
+    \"Which one\"
+    id: ID!
+    first: Int = 10
+  ): [Hero!]! # old
+  name(a: Int, b: [String] = [\"x\"]): String @deprecated
+}
+
+enum Episode {
+  NEWHOPE
+  \"Last\"
+  JEDI
+}
+
+union Result = Root | Hero
+
+input Filter {
+  name: String = \"Luke\"
+  rank: Int
+}
+
+extend scalar Date @specifiedBy(url: \"https://tools.ietf.org/html/rfc3339\")
+");
+}
+
+#[test]
+fn is_idempotent() {
+  let input = "query Q($a: [Int!] = [1, 2]) { a(b: {c: [{d: 1}]}) # c\n  ...F }";
+  let formatted = format(input).unwrap();
+  assert_eq!(format(&formatted).unwrap(), formatted);
+
+  let input = "\"A\" type A{\"f\" f(\"x\" x:Int y:Int):Int} union U=A|B";
+  let formatted = format(input).unwrap();
+  assert_eq!(format(&formatted).unwrap(), formatted);
+}
+
+#[test]
+fn fails_on_syntax_errors() {
+  let errors = format("{ hero(first: ) }").unwrap_err();
+  assert_eq!(errors[0].message, "Expected a value, found \")\".");
+  let errors = format("type Hero { name }").unwrap_err();
+  assert_eq!(errors[0].message, "Expected \":\", found \"}\".");
+}
@@ -12,6 +12,29 @@ pub enum Error {
   InvalidUtfChar,
 }
 
+impl Error {
+  /// How to fix the input the error is found in.
+  pub fn hint(&self) -> &'static str {
+    match *self {
+      Error::UnxepectedChar => {
+        "Remove the character, documents are made of names, numbers, strings and punctuators."
+      }
+      Error::InvalidInt => "Write integers without leading zeros, as in `10`.",
+      Error::InvalidFloat => "Write floats with digits after the dot or exponent, as in `1.5e3`.",
+      Error::UnterminatedString => {
+        "Close the string with `\"` before the end of the line, or the block string with \
+         `\"\"\"`."
+      }
+      Error::BadEscape => {
+        "Escape characters with one of `\\\"`, `\\\\`, `\\/`, `\\b`, `\\f`, `\\n`, `\\r`, \
+         `\\t` or `\\u`."
+      }
+      Error::BadUnicodeEscape => "Write unicode escapes with four hex digits, as in `\\u00e9`.",
+      Error::InvalidUtfChar => "Remove the control character, or escape it in a string.",
+    }
+  }
+}
+
 impl fmt::Display for Error {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     match *self {
@@ -39,7 +62,7 @@ impl error::Error for Error {
     }
   }
 
-  fn cause(&self) -> Option<&error::Error> {
+  fn cause(&self) -> Option<&dyn error::Error> {
     None
   }
 }
@@ -76,18 +76,3 @@ macro_rules! peek {
         _ => false
     });
 }
-
-// Advance iter if pattern match, keep hi position unchange
-#[macro_export]
-macro_rules! follow_by {
-  ($lexer: ident, $($p: pat)|*) => (
-    match $lexer.iter.peek() {
-        $(
-          Some(&(_, $p)) => {
-            $lexer.iter.next();
-            true
-          }
-        )*
-        _ => false
-    });
-}
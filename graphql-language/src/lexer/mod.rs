@@ -20,9 +20,15 @@ pub enum Token<'a> {
   IntValue(&'a str, usize, usize),
   FloatValue(&'a str, usize, usize),
   StringValue(String, usize, usize),
+  /// Run of white space and byte order marks, only emitted with trivia.
+  Whitespace(&'a str, usize, usize),
+  /// Comment up to the end of its line, only emitted with trivia.
+  Comment(&'a str, usize, usize),
+  /// Comma, only emitted with trivia.
+  Comma(usize, usize),
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub enum Punctuator {
   Bang,
   Dollar,
@@ -37,12 +43,14 @@ pub enum Punctuator {
   LeftBrace,
   RightBrace,
   Pipe,
+  Ampersand,
 }
 
 pub struct Lexer<'a> {
   input: &'a str,
   iter: Peekable<CharIndices<'a>>,
   eof_emmited: bool,
+  trivia: bool,
   lo: usize,
   hi: usize,
 }
@@ -50,50 +58,107 @@ pub struct Lexer<'a> {
 impl<'a> Lexer<'a> {
   pub fn new(input: &'a str) -> Lexer<'a> {
     Lexer {
-      input: input,
+      input,
       iter: input.char_indices().peekable(),
       eof_emmited: false,
+      trivia: false,
       lo: 0,
       hi: 0,
     }
   }
+
+  /// Emits the white space, comments and commas which are otherwise skipped,
+  /// so that the tokens cover every byte of the input.
+  pub fn with_trivia(mut self) -> Lexer<'a> {
+    self.trivia = true;
+    self
+  }
+
+  /// Start offset of the token scanned last, which is where a returned error
+  /// was found.
+  pub fn position(&self) -> usize {
+    self.lo
+  }
+
+  /// Offset up to which the input is consumed, which is the end of the token
+  /// scanned last or of the input skipped for an error.
+  pub fn offset(&self) -> usize {
+    self.iter.clone().peek().map_or(self.input.len(), |&(p, _)| p)
+  }
 }
 
 impl<'a> Iterator for Lexer<'a> {
   type Item = Result<Token<'a>>;
   fn next(&mut self) -> Option<Result<Token<'a>>> {
     if self.eof_emmited {
-      None
-    } else if self.iter.peek().is_none() {
-      self.eof_emmited = true;
-      Some(Ok(Token::Eof))
+      return None;
+    }
+    if self.trivia {
+      if let Some(token) = scan_trivia(self) {
+        return Some(Ok(token));
+      }
     } else {
       skip_ignored_token(self);
-      let &(p, c) = self.iter.peek().unwrap();
-      self.lo = p;
-      Some(match c {
-        '!' => punctuator!(self, Bang),
-        '$' => punctuator!(self, Dollar),
-        '(' => punctuator!(self, LeftParen),
-        ')' => punctuator!(self, RightParen),
-        ':' => punctuator!(self, Colon),
-        '=' => punctuator!(self, Equals),
-        '@' => punctuator!(self, At),
-        '[' => punctuator!(self, LeftBracket),
-        ']' => punctuator!(self, RightBracket),
-        '{' => punctuator!(self, LeftBrace),
-        '}' => punctuator!(self, RightBrace),
-        '|' => punctuator!(self, Pipe),
-        '.' => scan_spread(self),
-        '_' | 'a'...'z' | 'A'...'Z' => scan_name(self),
-        '-' | '0'...'9' => scan_number(self),
-        '"' => scan_string(self),
-        _ => Err(Error::UnxepectedChar),
-      })
+    }
+    match self.iter.peek() {
+      None => {
+        self.lo = self.input.len();
+        self.eof_emmited = true;
+        Some(Ok(Token::Eof))
+      }
+      Some(&(p, c)) => {
+        self.lo = p;
+        Some(match c {
+          '!' => punctuator!(self, Bang),
+          '$' => punctuator!(self, Dollar),
+          '(' => punctuator!(self, LeftParen),
+          ')' => punctuator!(self, RightParen),
+          ':' => punctuator!(self, Colon),
+          '=' => punctuator!(self, Equals),
+          '@' => punctuator!(self, At),
+          '[' => punctuator!(self, LeftBracket),
+          ']' => punctuator!(self, RightBracket),
+          '{' => punctuator!(self, LeftBrace),
+          '}' => punctuator!(self, RightBrace),
+          '|' => punctuator!(self, Pipe),
+          '&' => punctuator!(self, Ampersand),
+          '.' => scan_spread(self),
+          '_' | 'a'..='z' | 'A'..='Z' => scan_name(self),
+          '-' | '0'..='9' => scan_number(self),
+          '"' => scan_string(self),
+          _ => {
+            self.iter.next();
+            Err(Error::UnxepectedChar)
+          }
+        })
+      }
     }
   }
 }
 
+fn scan_trivia<'a>(lexer: &mut Lexer<'a>) -> Option<Token<'a>> {
+  let &(p, c) = lexer.iter.peek()?;
+  lexer.lo = p;
+  Some(match c {
+    ',' => {
+      lexer.iter.next();
+      Token::Comma(p, p + 1)
+    }
+    '#' => {
+      lexer.iter.next();
+      take_while_not!(lexer, '\r' | '\n');
+      let hi = lexer.offset();
+      Token::Comment(&lexer.input[p..hi], p, hi)
+    }
+    '\u{feff}' | ' ' | '\t' | '\r' | '\n' => {
+      take_while!(lexer, '\u{feff}' | ' ' | '\t' | '\r' | '\n');
+      let hi = lexer.offset();
+      Token::Whitespace(&lexer.input[p..hi], p, hi)
+    }
+    _ => return None,
+  })
+}
+
 fn skip_ignored_token(lexer: &mut Lexer) {
   loop {
     if take!(lexer, '#') {
@@ -115,43 +180,41 @@ fn scan_spread<'a>(lexer: &mut Lexer<'a>) -> Result<Token<'a>> {
 }
 
 fn scan_name<'a>(lexer: &mut Lexer<'a>) -> Result<Token<'a>> {
-  take_while!(lexer, '_' | 'a'...'z' | 'A'...'Z' | '0'...'9');
+  take_while!(lexer, '_' | 'a'..='z' | 'A'..='Z' | '0'..='9');
   Ok(Token::Name(&lexer.input[lexer.lo..lexer.hi], lexer.lo, lexer.hi))
 }
 
 fn scan_number<'a>(lexer: &mut Lexer<'a>) -> Result<Token<'a>> {
   take!(lexer, '-');
   // Integer part
-  if take!(lexer, '1'...'9') {
-    take_while!(lexer, '0'...'9');
-    if follow_by!(lexer, ' ' | '\t' | '\r' | '\n' | ',') || lexer.iter.peek().is_none() {
-      return Ok(Token::IntValue(&lexer.input[lexer.lo..lexer.hi], lexer.lo, lexer.hi));
-    }
-  } else if take!(lexer, '0') {
-    if follow_by!(lexer, ' ' | '\t' | '\r' | '\n' | ',') || lexer.iter.peek().is_none() {
+  if take!(lexer, '1'..='9') {
+    take_while!(lexer, '0'..='9');
+    if !peek!(lexer, '.' | 'E' | 'e' | '_' | 'a'..='z' | 'A'..='Z') {
       return Ok(Token::IntValue(&lexer.input[lexer.lo..lexer.hi], lexer.lo, lexer.hi));
     }
+  } else if take!(lexer, '0')
+    && !peek!(lexer, '.' | 'E' | 'e' | '_' | 'a'..='z' | 'A'..='Z' | '0'..='9')
+  {
+    return Ok(Token::IntValue(&lexer.input[lexer.lo..lexer.hi], lexer.lo, lexer.hi));
   }
   if !peek!(lexer, '.' | 'E' | 'e') {
     lexer.iter.next();
     return Err(Error::InvalidInt);
   }
   // Fractional part
-  if take!(lexer, '.') {
-    if !take_while!(lexer, '0'...'9') {
-      lexer.iter.next();
-      return Err(Error::InvalidFloat);
-    }
+  if take!(lexer, '.') && !take_while!(lexer, '0'..='9') {
+    lexer.iter.next();
+    return Err(Error::InvalidFloat);
   }
   // Exponent part
   if take!(lexer, 'E' | 'e') {
     take!(lexer, '+' | '-');
-    if !take_while!(lexer, '0'...'9') {
+    if !take_while!(lexer, '0'..='9') {
       lexer.iter.next();
       return Err(Error::InvalidFloat);
     }
   }
-  if follow_by!(lexer, ' ' | '\t' | '\r' | '\n' | ',') || lexer.iter.peek().is_none() {
+  if !peek!(lexer, '.' | '_' | 'a'..='z' | 'A'..='Z' | '0'..='9') {
     return Ok(Token::FloatValue(&lexer.input[lexer.lo..lexer.hi], lexer.lo, lexer.hi));
   }
   lexer.iter.next();
@@ -159,18 +222,19 @@ fn scan_number<'a>(lexer: &mut Lexer<'a>) -> Result<Token<'a>> {
 }
 
 fn scan_string<'a>(lexer: &mut Lexer<'a>) -> Result<Token<'a>> {
+  if lexer.input[lexer.lo..].starts_with("\"\"\"") {
+    return scan_block_string(lexer);
+  }
   if take!(lexer, '"') {
     lexer.lo += 1;
   }
   loop {
-    if take!(lexer, '\\') {
-      if !take!(lexer, '\\' | '"' | 'b' | 'f' | 'n' | 'r' | 't' | '/' | 'u') {
-        lexer.iter.next();
-        return Err(Error::BadEscape);
-      }
+    if take!(lexer, '\\') && !take!(lexer, '\\' | '"' | 'b' | 'f' | 'n' | 'r' | 't' | '/' | 'u') {
+      lexer.iter.next();
+      return Err(Error::BadEscape);
     }
-    if !take_while_not!(lexer, '"' | '\\' | '\r' | '\n' | '\x00'...'\x1f') {
-      if peek!(lexer, '\x00'...'\x1f') {
+    if !take_while_not!(lexer, '"' | '\\' | '\r' | '\n' | '\x00'..='\x1f') {
+      if peek!(lexer, '\x00'..='\x1f') {
         lexer.iter.next();
         return Err(Error::UnxepectedChar)
       } else if !peek!(lexer, '"' | '\\') {
@@ -184,12 +248,66 @@ fn scan_string<'a>(lexer: &mut Lexer<'a>) -> Result<Token<'a>> {
     }
     if peek!(lexer, '"') {
       lexer.iter.next();
-      let s = try!(unexcape_str(&lexer.input[lexer.lo..lexer.hi]));
+      let s = unexcape_str(&lexer.input[lexer.lo..lexer.hi])?;
       return Ok(Token::StringValue(s, lexer.lo, lexer.hi));
     }
   }
 }
 
+// Block strings are taken as they are, but for `\"""` escaping `"""`, and
+// their indentation and leading and trailing blank lines are removed.
+fn scan_block_string<'a>(lexer: &mut Lexer<'a>) -> Result<Token<'a>> {
+  lexer.iter.nth(2);
+  lexer.lo += 3;
+  loop {
+    match lexer.iter.peek() {
+      None => return Err(Error::UnterminatedString),
+      Some(&(p, '"')) if lexer.input[p..].starts_with("\"\"\"") => {
+        lexer.iter.nth(2);
+        lexer.hi = p;
+        let raw = lexer.input[lexer.lo..p].replace("\\\"\"\"", "\"\"\"");
+        return Ok(Token::StringValue(block_string_value(&raw), lexer.lo, p));
+      }
+      Some(&(p, '\\')) if lexer.input[p..].starts_with("\\\"\"\"") => {
+        lexer.iter.nth(3);
+      }
+      Some(&(_, '\t')) | Some(&(_, '\r')) | Some(&(_, '\n')) => {
+        lexer.iter.next();
+      }
+      Some(&(_, '\x00'..='\x1f')) => {
+        lexer.iter.next();
+        return Err(Error::UnxepectedChar);
+      }
+      Some(_) => {
+        lexer.iter.next();
+      }
+    }
+  }
+}
+
+fn block_string_value(raw: &str) -> String {
+  let raw = raw.replace("\r\n", "\n");
+  let lines: Vec<_> = raw.split(['\n', '\r']).collect();
+  let is_blank = |line: &str| line.trim_start_matches([' ', '\t']).is_empty();
+  let indent = lines.iter()
+    .skip(1)
+    .filter(|line| !is_blank(line))
+    .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+    .min();
+  let lines: Vec<_> = lines.iter()
+    .enumerate()
+    .map(|(i, line)| {
+      match indent {
+        Some(indent) if i > 0 => line.get(indent..).unwrap_or(""),
+        _ => line,
+      }
+    })
+    .collect();
+  let first = lines.iter().position(|line| !is_blank(line)).unwrap_or(lines.len());
+  let last = lines.iter().rposition(|line| !is_blank(line)).map_or(first, |last| last + 1);
+  lines[first..last].join("\n")
+}
+
 fn unexcape_str(s: &str) -> Result<String> {
   let mut buf = String::with_capacity(s.len());
   let mut p = s.chars().peekable();
@@ -206,13 +324,13 @@ fn unexcape_str(s: &str) -> Result<String> {
           Some('b') => buf.push('\x08'),
           Some('f') => buf.push('\x0c'),
           Some('u') => {
-            let u1 = try!(p.next().ok_or(Error::BadUnicodeEscape));
-            let u2 = try!(p.next().ok_or(Error::BadUnicodeEscape));
-            let u3 = try!(p.next().ok_or(Error::BadUnicodeEscape));
-            let u4 = try!(p.next().ok_or(Error::BadUnicodeEscape));
-            let s = try!(u32::from_str_radix(format!("{}{}{}{}", u1, u2, u3, u4).as_ref(), 16)
-              .or(Err(Error::BadUnicodeEscape)));
-            let ch = try!(::std::char::from_u32(s).ok_or(Error::InvalidUtfChar));
+            let u1 = p.next().ok_or(Error::BadUnicodeEscape)?;
+            let u2 = p.next().ok_or(Error::BadUnicodeEscape)?;
+            let u3 = p.next().ok_or(Error::BadUnicodeEscape)?;
+            let u4 = p.next().ok_or(Error::BadUnicodeEscape)?;
+            let s = u32::from_str_radix(format!("{}{}{}{}", u1, u2, u3, u4).as_ref(), 16)
+              .or(Err(Error::BadUnicodeEscape))?;
+            let ch = ::std::char::from_u32(s).ok_or(Error::InvalidUtfChar)?;
             buf.push(ch);
           }
           _ => return Err(Error::UnxepectedChar),
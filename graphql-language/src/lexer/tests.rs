@@ -57,6 +57,33 @@ fn skips_ignored_chars() {
   assert_token("", Eof);
 }
 
+#[test]
+fn lexes_trivia() {
+  let mut lexer = Lexer::new("\u{feff} foo, # comment\n  bar").with_trivia();
+  lexer.next_is_token(Whitespace("\u{feff} ", 0, 4));
+  lexer.next_is_token(Name("foo", 4, 7));
+  lexer.next_is_token(Comma(7, 8));
+  lexer.next_is_token(Whitespace(" ", 8, 9));
+  lexer.next_is_token(Comment("# comment", 9, 18));
+  lexer.next_is_token(Whitespace("\n  ", 18, 21));
+  lexer.next_is_token(Name("bar", 21, 24));
+  lexer.next_is_token(Eof);
+  assert_eq!(lexer.next(), None);
+
+  let mut lexer = Lexer::new("\u{feff}foo").with_trivia();
+  lexer.next_is_token(Whitespace("\u{feff}", 0, 3));
+  lexer.next_is_token(Name("foo", 3, 6));
+  lexer.next_is_token(Eof);
+}
+
+#[test]
+fn lexes_eof_after_ignored_chars() {
+  let mut lexer = Lexer::new("foo ,\n");
+  lexer.next_is_token(Name("foo", 0, 3));
+  lexer.next_is_token(Eof);
+  assert_eq!(lexer.position(), 6);
+}
+
 #[test]
 fn lexes_names() {
   assert_token("simple", Name("simple", 0, 6));
@@ -112,6 +139,20 @@ fn lexes_bad_string() {
   assert_error("\"bфы世ыы𠱸d \\uXXXF esc\"", Error::BadUnicodeEscape);
 }
 
+#[test]
+fn lexes_block_string() {
+  assert_token("\"\"\"simple\"\"\"", StringValue("simple".into(), 3, 9));
+  assert_token("\"\"\"quote \" and \\n\"\"\"", StringValue("quote \" and \\n".into(), 3, 17));
+  assert_token("\"\"\"escaped \\\"\"\"\"\"\"", StringValue("escaped \"\"\"".into(), 3, 15));
+  assert_token("\"\"\"\n\n    spans\r\n      lines\n\n    \"\"\"",
+               StringValue("spans\n  lines".into(), 3, 33));
+  let mut lexer = Lexer::new("\"\"\"a\"\"\" \"\"");
+  lexer.next_is_token(StringValue("a".into(), 3, 4));
+  lexer.next_is_token(StringValue("".into(), 9, 9));
+  assert_error("\"\"\"no end \"\"", Error::UnterminatedString);
+  assert_error("\"\"\"contains \u{0007}\"\"\"", Error::UnxepectedChar);
+}
+
 #[test]
 fn lexes_number() {
   assert_token("0", IntValue("0", 0, 1));
@@ -142,6 +183,20 @@ fn lexes_number() {
   assert_token("1e+00", FloatValue("1e+00", 0, 5));
 }
 
+#[test]
+fn lexes_number_before_punctuator() {
+  let mut lexer = Lexer::new("(first: 10)");
+  lexer.next_is_token(Punctuator(LeftParen, 0, 1));
+  lexer.next_is_token(Name("first", 1, 6));
+  lexer.next_is_token(Punctuator(Colon, 6, 7));
+  lexer.next_is_token(IntValue("10", 8, 10));
+  lexer.next_is_token(Punctuator(RightParen, 10, 11));
+  assert_token("[1.5]", Punctuator(LeftBracket, 0, 1));
+  let mut lexer = Lexer::new("1.5]");
+  lexer.next_is_token(FloatValue("1.5", 0, 3));
+  lexer.next_is_token(Punctuator(RightBracket, 3, 4));
+}
+
 #[test]
 fn lexes_bad_number() {
   assert_error("00", Error::InvalidInt);
@@ -154,6 +209,8 @@ fn lexes_bad_number() {
   assert_error("1.0e-", Error::InvalidFloat);
   assert_error("1.0e+", Error::InvalidFloat);
   assert_error("1.0eA", Error::InvalidFloat);
+  assert_error("1a", Error::InvalidInt);
+  assert_error("1.0.1", Error::InvalidFloat);
 }
 
 #[test]
@@ -170,9 +227,21 @@ fn lexes_punctuation() {
   assert_token("{", Punctuator(LeftBrace, 0, 1));
   assert_token("}", Punctuator(RightBrace, 0, 1));
   assert_token("|", Punctuator(Pipe, 0, 1));
+  assert_token("&", Punctuator(Ampersand, 0, 1));
   assert_token("...", Punctuator(Spread, 0, 3));
 }
 
+#[test]
+fn lexes_after_unexpected_chars() {
+  let mut lexer = Lexer::new("?foo 1");
+  lexer.next_is_error(Error::UnxepectedChar);
+  assert_eq!(lexer.offset(), 1);
+  lexer.next_is_token(Name("foo", 1, 4));
+  lexer.next_is_token(IntValue("1", 5, 6));
+  assert_eq!(lexer.offset(), 6);
+  lexer.next_is_token(Eof);
+}
+
 #[test]
 fn lexes_unexpected_chars() {
   assert_error(".", Error::UnxepectedChar);
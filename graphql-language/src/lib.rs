@@ -1,3 +1,12 @@
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+
 pub mod ast;
+pub mod cst;
+pub mod diagnostic;
+pub mod format;
 pub mod lexer;
-pub mod source;
\ No newline at end of file
+pub mod parser;
+pub mod sdl;
+pub mod source;
@@ -0,0 +1,392 @@
+//! Parses documents into the `ast`. Parsing goes through the `cst`, so that
+//! it carries on past errors: every error of the document is reported, and
+//! the parts missing from the AST are filled in with empty nodes.
+
+use ast::*;
+use cst::{self, NodeKind, SyntaxNode, SyntaxToken, TokenKind};
+use diagnostic::Diagnostic;
+use lexer::{Lexer, Token};
+use source::Source;
+
+#[cfg(test)]
+mod tests;
+
+/// Parses `source`, failing with every error of the document if there is any.
+pub fn parse<'a>(source: &'a Source<'a>) -> Result<Document<'a>, Vec<Diagnostic>> {
+  let (document, diagnostics) = parse_tolerant(source);
+  if diagnostics.is_empty() {
+    Ok(document)
+  } else {
+    Err(diagnostics)
+  }
+}
+
+/// Parses `source` whatever errors it has, returning the document along with
+/// the errors in source order.
+///
+/// Parts the grammar requires but which are missing from the source are
+/// inserted with an empty location where they are expected: names, types and
+/// enum values with an empty name, and selection sets without selections. A
+/// missing value is an enum value, and a missing operation type `query`.
+/// Input which does not fit the grammar is left out of the document.
+pub fn parse_tolerant<'a>(source: &'a Source<'a>) -> (Document<'a>, Vec<Diagnostic>) {
+  let parse = cst::parse(source.body);
+  let lower = Lower { source };
+  (lower.document(&parse.root), parse.errors)
+}
+
+// Builds the AST from the tree of a document.
+struct Lower<'a> {
+  source: &'a Source<'a>,
+}
+
+impl<'a> Lower<'a> {
+  fn loc(&self, node: &SyntaxNode<'a>) -> Option<Location<'a>> {
+    Some(Location {
+      start: node.start,
+      end: node.end(),
+      source: Some(self.source)
+    })
+  }
+
+  fn token_loc(&self, token: &SyntaxToken<'a>) -> Option<Location<'a>> {
+    Some(Location {
+      start: token.start,
+      end: token.end(),
+      source: Some(self.source)
+    })
+  }
+
+  // Location of a node missing from the end of `node`.
+  fn missing_loc(&self, node: &SyntaxNode<'a>) -> Option<Location<'a>> {
+    Some(Location {
+      start: node.end(),
+      end: node.end(),
+      source: Some(self.source)
+    })
+  }
+
+  // The `index`th name directly under `node`, or an empty one.
+  fn name(&self, node: &SyntaxNode<'a>, index: usize) -> Name<'a> {
+    match node.child_tokens().filter(|token| token.kind == TokenKind::Name).nth(index) {
+      Some(token) => {
+        Name {
+          loc: self.token_loc(token),
+          value: token.text
+        }
+      }
+      None => self.missing_name(node),
+    }
+  }
+
+  fn missing_name(&self, node: &SyntaxNode<'a>) -> Name<'a> {
+    Name {
+      loc: self.missing_loc(node),
+      value: ""
+    }
+  }
+
+  fn document(&self, node: &SyntaxNode<'a>) -> Document<'a> {
+    let definitions = node.child_nodes()
+      .filter_map(|node| {
+        match node.kind {
+          NodeKind::OperationDefinition => {
+            Some(Definition::Operation(self.operation_definition(node)))
+          }
+          NodeKind::FragmentDefinition => {
+            Some(Definition::Fragment(self.fragment_definition(node)))
+          }
+          _ => None,
+        }
+      })
+      .collect();
+    Document {
+      loc: self.loc(node),
+      definitions
+    }
+  }
+
+  fn operation_definition(&self, node: &SyntaxNode<'a>) -> OperationDefinition<'a> {
+    let mut names = node.child_tokens().filter(|token| token.kind == TokenKind::Name);
+    let operation = match names.next().map(|token| token.text) {
+      Some("mutation") => OperationType::Mutation,
+      Some("subscription") => OperationType::Subscription,
+      _ => OperationType::Query,
+    };
+    let name = names.next().map(|token| {
+      Name {
+        loc: self.token_loc(token),
+        value: token.text
+      }
+    });
+    OperationDefinition {
+      loc: self.loc(node),
+      operation,
+      name,
+      variable_definitions: node.child_node(NodeKind::VariableDefinitions)
+        .map(|node| self.variable_definitions(node)),
+      directives: self.directives(node),
+      selection_set: self.selection_set(node)
+    }
+  }
+
+  fn fragment_definition(&self, node: &SyntaxNode<'a>) -> FragmentDefinition<'a> {
+    FragmentDefinition {
+      loc: self.loc(node),
+      // The first name is `fragment`.
+      name: self.name(node, 1),
+      type_condition: self.type_condition(node),
+      directives: self.directives(node),
+      selection_set: self.selection_set(node)
+    }
+  }
+
+  fn type_condition(&self, node: &SyntaxNode<'a>) -> TypeCondition<'a> {
+    match node.child_node(NodeKind::TypeCondition) {
+      Some(condition) => {
+        condition.child_node(NodeKind::NamedType)
+          .map_or_else(|| self.missing_named_type(condition), |node| self.named_type(node))
+      }
+      None => self.missing_named_type(node),
+    }
+  }
+
+  fn variable_definitions(&self, node: &SyntaxNode<'a>) -> VariableDefinitions<'a> {
+    node.child_nodes()
+      .filter(|node| node.kind == NodeKind::VariableDefinition)
+      .map(|node| {
+        VariableDefinition {
+          loc: self.loc(node),
+          variable: self.variable(node.child_node(NodeKind::Variable).unwrap()),
+          type_: self.type_(node),
+          default_value: node.child_node(NodeKind::DefaultValue).map(|node| self.value(node))
+        }
+      })
+      .collect()
+  }
+
+  fn variable(&self, node: &SyntaxNode<'a>) -> Variable<'a> {
+    Variable {
+      loc: self.loc(node),
+      name: self.name(node, 0)
+    }
+  }
+
+  // The type under `node`, or a named type with an empty name.
+  fn type_(&self, node: &SyntaxNode<'a>) -> Type<'a> {
+    let type_node = node.child_nodes().find(|node| {
+      matches!(node.kind, NodeKind::NamedType | NodeKind::ListType | NodeKind::NonNullType)
+    });
+    let type_node = match type_node {
+      Some(type_node) => type_node,
+      None => return Type::Named(self.missing_named_type(node)),
+    };
+    match type_node.kind {
+      NodeKind::NamedType => Type::Named(self.named_type(type_node)),
+      NodeKind::ListType => Type::List(Box::new(self.list_type(type_node))),
+      _ => {
+        match self.type_(type_node) {
+          Type::List(list) => {
+            Type::NonNullList(Box::new(NonNullListType {
+              loc: self.loc(type_node),
+              type_: *list
+            }))
+          }
+          Type::Named(named) => {
+            Type::NonNullNamed(Box::new(NonNullNamedType {
+              loc: self.loc(type_node),
+              type_: named
+            }))
+          }
+          // The parser wraps a type in one `NonNullType` at most.
+          type_ => type_,
+        }
+      }
+    }
+  }
+
+  fn named_type(&self, node: &SyntaxNode<'a>) -> NamedType<'a> {
+    NamedType {
+      loc: self.loc(node),
+      name: self.name(node, 0)
+    }
+  }
+
+  fn missing_named_type(&self, node: &SyntaxNode<'a>) -> NamedType<'a> {
+    NamedType {
+      loc: self.missing_loc(node),
+      name: self.missing_name(node)
+    }
+  }
+
+  fn list_type(&self, node: &SyntaxNode<'a>) -> ListType<'a> {
+    ListType {
+      loc: self.loc(node),
+      type_: self.type_(node)
+    }
+  }
+
+  fn directives(&self, node: &SyntaxNode<'a>) -> Option<Directives<'a>> {
+    node.child_node(NodeKind::Directives).map(|node| {
+      node.child_nodes()
+        .filter(|node| node.kind == NodeKind::Directive)
+        .map(|node| {
+          Directive {
+            loc: self.loc(node),
+            name: self.name(node, 0),
+            arguments: self.arguments(node)
+          }
+        })
+        .collect()
+    })
+  }
+
+  fn arguments(&self, node: &SyntaxNode<'a>) -> Option<Arguments<'a>> {
+    node.child_node(NodeKind::Arguments).map(|node| {
+      node.child_nodes()
+        .filter(|node| node.kind == NodeKind::Argument)
+        .map(|node| {
+          Argument {
+            loc: self.loc(node),
+            name: self.name(node, 0),
+            value: self.value(node)
+          }
+        })
+        .collect()
+    })
+  }
+
+  // The selection set under `node`, or an empty one.
+  fn selection_set(&self, node: &SyntaxNode<'a>) -> SelectionSet<'a> {
+    match node.child_node(NodeKind::SelectionSet) {
+      Some(node) => self.selections(node),
+      None => {
+        SelectionSet {
+          loc: self.missing_loc(node),
+          selections: Vec::new()
+        }
+      }
+    }
+  }
+
+  fn selections(&self, node: &SyntaxNode<'a>) -> SelectionSet<'a> {
+    let selections = node.child_nodes()
+      .filter_map(|node| {
+        match node.kind {
+          NodeKind::Field => Some(Selection::Field(self.field(node))),
+          NodeKind::FragmentSpread => {
+            Some(Selection::FragmentSpread(FragmentSpread {
+              loc: self.loc(node),
+              name: self.name(node, 0),
+              directives: self.directives(node)
+            }))
+          }
+          NodeKind::InlineFragment => {
+            Some(Selection::InlineFragment(InlineFragment {
+              loc: self.loc(node),
+              type_condition: node.child_node(NodeKind::TypeCondition)
+                .map(|_| self.type_condition(node)),
+              directives: self.directives(node),
+              selection_set: self.selection_set(node)
+            }))
+          }
+          _ => None,
+        }
+      })
+      .collect();
+    SelectionSet {
+      loc: self.loc(node),
+      selections
+    }
+  }
+
+  fn field(&self, node: &SyntaxNode<'a>) -> Field<'a> {
+    Field {
+      loc: self.loc(node),
+      alias: node.child_node(NodeKind::Alias).map(|alias| self.name(alias, 0)),
+      name: self.name(node, 0),
+      arguments: self.arguments(node),
+      directives: self.directives(node),
+      selection_set: node.child_node(NodeKind::SelectionSet).map(|node| self.selections(node))
+    }
+  }
+
+  // The value under `node`, or an enum value with an empty name.
+  fn value(&self, node: &SyntaxNode<'a>) -> Value<'a> {
+    match node.child_nodes().find(|node| node.kind != NodeKind::Error) {
+      Some(value_node) => self.value_node(value_node),
+      None => {
+        Value::Enum(EnumValue {
+          loc: self.missing_loc(node),
+          name: self.missing_name(node)
+        })
+      }
+    }
+  }
+
+  fn value_node(&self, node: &SyntaxNode<'a>) -> Value<'a> {
+    let text = node.child_tokens().find(|token| !token.kind.is_trivia()).map_or("", |t| t.text);
+    match node.kind {
+      NodeKind::Variable => Value::Variable(self.variable(node)),
+      NodeKind::IntValue => {
+        Value::Int(IntValue {
+          loc: self.loc(node),
+          value: text
+        })
+      }
+      NodeKind::FloatValue => {
+        Value::Float(FloatValue {
+          loc: self.loc(node),
+          value: text
+        })
+      }
+      NodeKind::StringValue => {
+        let value = match Lexer::new(text).next() {
+          Some(Ok(Token::StringValue(value, _, _))) => value,
+          _ => String::new(),
+        };
+        Value::String(StringValue {
+          loc: self.loc(node),
+          value
+        })
+      }
+      NodeKind::BooleanValue => {
+        Value::Boolean(BooleanValue {
+          loc: self.loc(node),
+          value: text == "true"
+        })
+      }
+      NodeKind::ListValue => {
+        Value::List(ListValue {
+          loc: self.loc(node),
+          values: node.child_nodes()
+            .filter(|node| node.kind != NodeKind::Error)
+            .map(|node| self.value_node(node))
+            .collect()
+        })
+      }
+      NodeKind::ObjectValue => {
+        Value::Object(ObjectValue {
+          loc: self.loc(node),
+          fields: node.child_nodes()
+            .filter(|node| node.kind == NodeKind::ObjectField)
+            .map(|node| {
+              ObjectField {
+                loc: self.loc(node),
+                name: self.name(node, 0),
+                value: self.value(node)
+              }
+            })
+            .collect()
+        })
+      }
+      // The AST has no null value, `null` being an enum value there.
+      _ => {
+        Value::Enum(EnumValue {
+          loc: self.loc(node),
+          name: self.name(node, 0)
+        })
+      }
+    }
+  }
+}
@@ -0,0 +1,217 @@
+use super::*;
+use diagnostic::Span;
+
+fn name<'a>(node: &Name<'a>) -> (&'a str, usize, usize) {
+  let loc = node.loc.as_ref().unwrap();
+  (node.value, loc.start, loc.end)
+}
+
+fn operation<'d, 'a>(document: &'d Document<'a>, index: usize) -> &'d OperationDefinition<'a> {
+  match document.definitions[index] {
+    Definition::Operation(ref operation) => operation,
+    _ => panic!("Expected an operation."),
+  }
+}
+
+fn field<'s, 'a>(selection_set: &'s SelectionSet<'a>, index: usize) -> &'s Field<'a> {
+  match selection_set.selections[index] {
+    Selection::Field(ref field) => field,
+    _ => panic!("Expected a field."),
+  }
+}
+
+#[test]
+fn parses_document() {
+  let source = Source::new("query Hero($ids: [ID!]! = [\"1\"], $admin: Boolean) {
+  hero: character(ids: $ids, filter: { name: \"Luke\\n\", ranks: [1, 2.5] }) {
+    ...Names @include(if: $admin)
+    ... on Droid { primaryFunction }
+    nickname(default: null)
+  }
+}
+
+fragment Names on Character { name }
+");
+  let document = parse(&source).ok().unwrap();
+  assert_eq!(document.definitions.len(), 2);
+  let hero = operation(&document, 0);
+  assert_eq!(hero.operation, OperationType::Query);
+  assert_eq!(name(hero.name.as_ref().unwrap()), ("Hero", 6, 10));
+  let loc = hero.loc.as_ref().unwrap();
+  assert_eq!((loc.start, loc.end), (0, 232));
+
+  let variables = hero.variable_definitions.as_ref().unwrap();
+  match variables[0].type_ {
+    Type::NonNullList(ref list) => {
+      match list.type_.type_ {
+        Type::NonNullNamed(ref id) => assert_eq!(id.type_.name.value, "ID"),
+        _ => panic!("Expected a non-null named type."),
+      }
+    }
+    _ => panic!("Expected a non-null list type."),
+  }
+  match variables[0].default_value {
+    Some(Value::List(ref list)) => {
+      match list.values[..] {
+        [Value::String(ref id)] => assert_eq!(id.value, "1"),
+        _ => panic!("Expected a string."),
+      }
+    }
+    _ => panic!("Expected a list."),
+  }
+  match variables[1].type_ {
+    Type::Named(ref boolean) => assert_eq!(name(&boolean.name), ("Boolean", 41, 48)),
+    _ => panic!("Expected a named type."),
+  }
+
+  let character = field(&hero.selection_set, 0);
+  assert_eq!(name(character.alias.as_ref().unwrap()), ("hero", 54, 58));
+  assert_eq!(name(&character.name), ("character", 60, 69));
+  let arguments = character.arguments.as_ref().unwrap();
+  assert!(matches!(arguments[0].value, Value::Variable(ref ids) if ids.name.value == "ids"));
+  match arguments[1].value {
+    Value::Object(ref filter) => {
+      match filter.fields[0].value {
+        Value::String(ref luke) => assert_eq!(luke.value, "Luke\n"),
+        _ => panic!("Expected a string."),
+      }
+      match filter.fields[1].value {
+        Value::List(ref ranks) => {
+          assert!(matches!(ranks.values[..],
+                           [Value::Int(ref one), Value::Float(ref half)]
+                           if one.value == "1" && half.value == "2.5"));
+        }
+        _ => panic!("Expected a list."),
+      }
+    }
+    _ => panic!("Expected an object."),
+  }
+
+  let selections = &character.selection_set.as_ref().unwrap().selections;
+  match selections[0] {
+    Selection::FragmentSpread(ref names) => {
+      assert_eq!(names.name.value, "Names");
+      let include = &names.directives.as_ref().unwrap()[0];
+      assert_eq!(include.name.value, "include");
+    }
+    _ => panic!("Expected a fragment spread."),
+  }
+  match selections[1] {
+    Selection::InlineFragment(ref droid) => {
+      assert_eq!(droid.type_condition.as_ref().unwrap().name.value, "Droid");
+      assert_eq!(field(&droid.selection_set, 0).name.value, "primaryFunction");
+    }
+    _ => panic!("Expected an inline fragment."),
+  }
+  match selections[2] {
+    Selection::Field(ref nickname) => {
+      match nickname.arguments.as_ref().unwrap()[0].value {
+        Value::Enum(ref null) => assert_eq!(null.name.value, "null"),
+        _ => panic!("Expected null."),
+      }
+    }
+    _ => panic!("Expected a field."),
+  }
+
+  match document.definitions[1] {
+    Definition::Fragment(ref names) => {
+      assert_eq!(names.name.value, "Names");
+      assert_eq!(names.type_condition.name.value, "Character");
+      assert_eq!(field(&names.selection_set, 0).name.value, "name");
+    }
+    _ => panic!("Expected a fragment."),
+  }
+}
+
+#[test]
+fn reports_every_error() {
+  let source = Source::new("query Hero {
+  hero(episode: ) {
+    name
+    ... on Droid
+  }
+
+query Villain($id: ) {
+  villain { }
+}
+
+fragment on Character { name }
+");
+  let (document, diagnostics) = parse_tolerant(&source);
+  let errors: Vec<_> = diagnostics.iter().map(|d| (d.message.as_ref(), d.span)).collect();
+  assert_eq!(errors,
+             vec![("Expected a value, found \")\".", Span::new(29, 30)),
+                  ("Expected \"{\", found \"}\".", Span::new(61, 62)),
+                  ("Expected \"}\", found Name \"query\".", Span::new(64, 69)),
+                  ("Expected Name, found \")\".", Span::new(83, 84)),
+                  ("Expected a selection, found \"}\".", Span::new(99, 100)),
+                  ("Expected fragment name, found Name \"on\".", Span::new(113, 115))]);
+  assert_eq!(diagnostics[0].hint,
+             "Use a variable, a number, a string, `true`, `false`, `null`, an enum value, a list \
+              or an object.");
+  assert!(parse(&source).is_err());
+
+  // Missing parts are inserted empty, where they are expected.
+  assert_eq!(document.definitions.len(), 3);
+  let hero = field(&operation(&document, 0).selection_set, 0);
+  match hero.arguments.as_ref().unwrap()[0].value {
+    Value::Enum(ref missing) => assert_eq!(name(&missing.name), ("", 28, 28)),
+    _ => panic!("Expected an empty enum value."),
+  }
+  match hero.selection_set.as_ref().unwrap().selections[1] {
+    Selection::InlineFragment(ref droid) => assert!(droid.selection_set.selections.is_empty()),
+    _ => panic!("Expected an inline fragment."),
+  }
+  let villain = operation(&document, 1);
+  let id = &villain.variable_definitions.as_ref().unwrap()[0];
+  assert!(matches!(id.type_, Type::Named(ref missing) if missing.name.value.is_empty()));
+  assert!(field(&villain.selection_set, 0).selection_set.as_ref().unwrap().selections.is_empty());
+  match document.definitions[2] {
+    Definition::Fragment(ref fragment) => {
+      assert_eq!(fragment.name.value, "");
+      assert_eq!(fragment.type_condition.name.value, "Character");
+    }
+    _ => panic!("Expected a fragment."),
+  }
+}
+
+#[test]
+fn skips_input_out_of_the_grammar() {
+  let source = Source::new("garbage 1 \"2\" { a(x: 1 y: %) { b } @ } }");
+  let (document, diagnostics) = parse_tolerant(&source);
+  let errors: Vec<_> = diagnostics.iter().map(|d| (d.message.as_ref(), d.span.start)).collect();
+  assert_eq!(errors,
+             vec![("Unexpected Name \"garbage\".", 0),
+                  ("Unexpected character.", 26),
+                  ("Unexpected \"@\".", 35),
+                  ("Unexpected \"}\".", 39)]);
+  assert_eq!(diagnostics[1].hint, ::lexer::Error::UnxepectedChar.hint());
+
+  assert_eq!(document.definitions.len(), 1);
+  let a = field(&operation(&document, 0).selection_set, 0);
+  let arguments: Vec<_> = a.arguments.as_ref().unwrap().iter().map(|a| a.name.value).collect();
+  assert_eq!(arguments, vec!["x", "y"]);
+  assert_eq!(field(a.selection_set.as_ref().unwrap(), 0).name.value, "b");
+
+  let source = Source::new("# Nothing to see.\n");
+  let (document, diagnostics) = parse_tolerant(&source);
+  assert!(document.definitions.is_empty());
+  assert_eq!(diagnostics[0].message, "Expected a definition, found <EOF>.");
+  assert_eq!(diagnostics[0].span, Span::new(18, 18));
+}
+
+#[test]
+fn stops_descending_into_deep_nesting() {
+  let mut query = "{ a ".repeat(3000) + &"}".repeat(3000);
+  query.push_str("\n{ b(list: ");
+  query.push_str(&("[".repeat(3000) + &"]".repeat(3000)));
+  query.push_str(") }");
+  let source = Source::new(&query);
+  let (document, diagnostics) = parse_tolerant(&source);
+  let errors: Vec<&str> = diagnostics.iter().map(|d| d.message.as_ref()).collect();
+  assert_eq!(errors, vec!["Document is nested too deeply.", "Document is nested too deeply."]);
+
+  // The input past the nesting is parsed as usual.
+  assert_eq!(document.definitions.len(), 2);
+  assert_eq!(field(&operation(&document, 1).selection_set, 0).name.value, "b");
+}
@@ -0,0 +1,966 @@
+//! Parses documents of the schema definition language, which define the types
+//! of a schema, as in `type Query { hero(episode: Episode): Character }`.
+//!
+//! Definitions are parsed into owned nodes keeping what building a schema
+//! needs: descriptions, types, default values and the reasons given by
+//! `@deprecated`. Other directives applied to definitions are checked for
+//! syntax only. Unlike `parser`, parsing stops at the first error.
+
+use std::fmt;
+use std::result;
+
+use ast::owned::{BooleanValue, EnumValue, FloatValue, IntValue, ListValue, Location, Name,
+                 ObjectField, ObjectValue, StringValue, Value};
+use diagnostic::{Diagnostic, Span};
+use lexer::{Lexer, Punctuator, Token};
+
+#[cfg(test)]
+mod tests;
+
+/// Reason of `@deprecated` when none is given.
+pub static DEFAULT_DEPRECATION_REASON: &str = "No longer supported";
+
+/// Definitions of a schema document, in source order by kind.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SchemaDocument {
+  pub schema: Option<SchemaDefinition>,
+  pub types: Vec<TypeDefinition>,
+  pub directives: Vec<DirectiveDefinition>,
+}
+
+impl SchemaDocument {
+  pub fn get_type(&self, name: &str) -> Option<&TypeDefinition> {
+    self.types.iter().find(|t| t.name == name)
+  }
+}
+
+/// `schema { query: Query }`, naming the root operation types.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaDefinition {
+  pub description: Option<String>,
+  pub query: Option<String>,
+  pub mutation: Option<String>,
+  pub subscription: Option<String>,
+  /// Span of the `schema` keyword.
+  pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeDefinition {
+  pub description: Option<String>,
+  pub name: String,
+  /// Span of the name.
+  pub span: Span,
+  pub kind: TypeDefinitionKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeDefinitionKind {
+  Scalar,
+  Object {
+    interfaces: Vec<String>,
+    fields: Vec<FieldDefinition>,
+  },
+  Interface {
+    interfaces: Vec<String>,
+    fields: Vec<FieldDefinition>,
+  },
+  Union {
+    types: Vec<String>,
+  },
+  Enum {
+    values: Vec<EnumValueDefinition>,
+  },
+  InputObject {
+    fields: Vec<InputValueDefinition>,
+  },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDefinition {
+  pub description: Option<String>,
+  pub name: String,
+  pub span: Span,
+  pub arguments: Vec<InputValueDefinition>,
+  pub type_: TypeRef,
+  pub deprecation_reason: Option<String>,
+}
+
+/// Argument or input object field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputValueDefinition {
+  pub description: Option<String>,
+  pub name: String,
+  pub span: Span,
+  pub type_: TypeRef,
+  pub default_value: Option<Value>,
+  pub deprecation_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumValueDefinition {
+  pub description: Option<String>,
+  pub name: String,
+  pub span: Span,
+  pub deprecation_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectiveDefinition {
+  pub description: Option<String>,
+  pub name: String,
+  pub span: Span,
+  pub arguments: Vec<InputValueDefinition>,
+  pub repeatable: bool,
+  pub locations: Vec<String>,
+}
+
+/// Type of a field or an argument, as in `[String!]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeRef {
+  Named(String),
+  List(Box<TypeRef>),
+  NonNull(Box<TypeRef>),
+}
+
+impl TypeRef {
+  /// Name of the type inside the lists and non-null types.
+  pub fn name(&self) -> &str {
+    match *self {
+      TypeRef::Named(ref name) => name,
+      TypeRef::List(ref of_type) |
+      TypeRef::NonNull(ref of_type) => of_type.name(),
+    }
+  }
+}
+
+impl fmt::Display for TypeRef {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      TypeRef::Named(ref name) => write!(f, "{}", name),
+      TypeRef::List(ref of_type) => write!(f, "[{}]", of_type),
+      TypeRef::NonNull(ref of_type) => write!(f, "{}!", of_type),
+    }
+  }
+}
+
+/// Parses a schema document, failing with the first error found.
+pub fn parse_schema(input: &str) -> result::Result<SchemaDocument, Diagnostic> {
+  let mut parser = Parser {
+    tokens: tokenize(input)?,
+    pos: 0,
+  };
+  let mut document = SchemaDocument::default();
+  while *parser.peek() != Token::Eof {
+    parser.definition(&mut document)?;
+  }
+  Ok(document)
+}
+
+/// Parses a single constant value, as the default values of introspection
+/// results are given.
+pub fn parse_value(input: &str) -> result::Result<Value, Diagnostic> {
+  let mut parser = Parser {
+    tokens: tokenize(input)?,
+    pos: 0,
+  };
+  let value = parser.value()?;
+  if *parser.peek() != Token::Eof {
+    return parser.error("the end of the value", "Write a single value.");
+  }
+  Ok(value)
+}
+
+/// Prints a schema document as graphql-js `printSchema` does: the `schema`
+/// definition only when it does not name the conventional root types, then
+/// directives and types, one definition per block.
+pub fn print_schema(document: &SchemaDocument) -> String {
+  let mut blocks = Vec::new();
+  if let Some(ref schema) = document.schema {
+    let roots = [("query", &schema.query, "Query"),
+                 ("mutation", &schema.mutation, "Mutation"),
+                 ("subscription", &schema.subscription, "Subscription")];
+    // Root types named as their operation may be left to be found by name.
+    let conventional = roots.iter().all(|&(_, name, conventional)| match *name {
+      Some(ref name) => name == conventional,
+      None => document.get_type(conventional).is_none(),
+    });
+    if schema.description.is_some() || !conventional {
+      let mut block = String::new();
+      print_description(&mut block, &schema.description, "", true);
+      block.push_str("schema {\n");
+      for &(operation, name, _) in &roots {
+        if let Some(ref name) = *name {
+          block.push_str(&format!("  {}: {}\n", operation, name));
+        }
+      }
+      block.push('}');
+      blocks.push(block);
+    }
+  }
+  for directive in &document.directives {
+    let mut block = String::new();
+    print_description(&mut block, &directive.description, "", true);
+    block.push_str(&format!("directive @{}", directive.name));
+    print_arguments(&mut block, &directive.arguments, "");
+    if directive.repeatable {
+      block.push_str(" repeatable");
+    }
+    block.push_str(&format!(" on {}", directive.locations.join(" | ")));
+    blocks.push(block);
+  }
+  for type_ in &document.types {
+    blocks.push(print_type(type_));
+  }
+
+  let mut printed = blocks.join("\n\n");
+  if !printed.is_empty() {
+    printed.push('\n');
+  }
+  printed
+}
+
+fn print_type(type_: &TypeDefinition) -> String {
+  let mut out = String::new();
+  print_description(&mut out, &type_.description, "", true);
+  match type_.kind {
+    TypeDefinitionKind::Scalar => out.push_str(&format!("scalar {}", type_.name)),
+    TypeDefinitionKind::Object { ref interfaces, ref fields } |
+    TypeDefinitionKind::Interface { ref interfaces, ref fields } => {
+      let keyword = match type_.kind {
+        TypeDefinitionKind::Object { .. } => "type",
+        _ => "interface",
+      };
+      out.push_str(&format!("{} {}", keyword, type_.name));
+      if !interfaces.is_empty() {
+        out.push_str(&format!(" implements {}", interfaces.join(" & ")));
+      }
+      out.push_str(" {\n");
+      for (i, field) in fields.iter().enumerate() {
+        print_description(&mut out, &field.description, "  ", i == 0);
+        out.push_str(&format!("  {}", field.name));
+        print_arguments(&mut out, &field.arguments, "  ");
+        out.push_str(&format!(": {}", field.type_));
+        print_deprecated(&mut out, &field.deprecation_reason);
+        out.push('\n');
+      }
+      out.push('}');
+    }
+    TypeDefinitionKind::Union { ref types } => {
+      out.push_str(&format!("union {}", type_.name));
+      if !types.is_empty() {
+        out.push_str(&format!(" = {}", types.join(" | ")));
+      }
+    }
+    TypeDefinitionKind::Enum { ref values } => {
+      out.push_str(&format!("enum {} {{\n", type_.name));
+      for (i, value) in values.iter().enumerate() {
+        print_description(&mut out, &value.description, "  ", i == 0);
+        out.push_str(&format!("  {}", value.name));
+        print_deprecated(&mut out, &value.deprecation_reason);
+        out.push('\n');
+      }
+      out.push('}');
+    }
+    TypeDefinitionKind::InputObject { ref fields } => {
+      out.push_str(&format!("input {} {{\n", type_.name));
+      for (i, field) in fields.iter().enumerate() {
+        print_description(&mut out, &field.description, "  ", i == 0);
+        out.push_str("  ");
+        print_input_value(&mut out, field);
+        out.push('\n');
+      }
+      out.push('}');
+    }
+  }
+  out
+}
+
+// Arguments go on a line of their own each when any has a description.
+fn print_arguments(out: &mut String, arguments: &[InputValueDefinition], indent: &str) {
+  if arguments.is_empty() {
+    return;
+  }
+  out.push('(');
+  if arguments.iter().all(|argument| argument.description.is_none()) {
+    for (i, argument) in arguments.iter().enumerate() {
+      if i > 0 {
+        out.push_str(", ");
+      }
+      print_input_value(out, argument);
+    }
+  } else {
+    out.push('\n');
+    let inner = format!("{}  ", indent);
+    for (i, argument) in arguments.iter().enumerate() {
+      print_description(out, &argument.description, &inner, i == 0);
+      out.push_str(&inner);
+      print_input_value(out, argument);
+      out.push('\n');
+    }
+    out.push_str(indent);
+  }
+  out.push(')');
+}
+
+fn print_input_value(out: &mut String, input_value: &InputValueDefinition) {
+  out.push_str(&format!("{}: {}", input_value.name, input_value.type_));
+  if let Some(ref value) = input_value.default_value {
+    out.push_str(" = ");
+    write_value(out, value);
+  }
+  print_deprecated(out, &input_value.deprecation_reason);
+}
+
+fn print_deprecated(out: &mut String, reason: &Option<String>) {
+  match *reason {
+    Some(ref reason) if reason == DEFAULT_DEPRECATION_REASON => out.push_str(" @deprecated"),
+    Some(ref reason) => out.push_str(&format!(" @deprecated(reason: {})", string_literal(reason))),
+    None => {}
+  }
+}
+
+// Prints a description as a block string, on lines of its own when it has
+// several or could not end the string on one line, after a blank line unless
+// it is the first of an indented block.
+fn print_description(out: &mut String,
+                     description: &Option<String>,
+                     indent: &str,
+                     first_in_block: bool) {
+  let description = match *description {
+    Some(ref description) => description.replace("\"\"\"", "\\\"\"\""),
+    None => return,
+  };
+  if !indent.is_empty() && !first_in_block {
+    out.push('\n');
+  }
+  out.push_str(indent);
+  if description.contains('\n') || description.ends_with('"') || description.ends_with('\\') {
+    out.push_str("\"\"\"\n");
+    for line in description.lines() {
+      if !line.is_empty() {
+        out.push_str(indent);
+        out.push_str(line);
+      }
+      out.push('\n');
+    }
+    out.push_str(indent);
+    out.push_str("\"\"\"\n");
+  } else {
+    out.push_str(&format!("\"\"\"{}\"\"\"\n", description));
+  }
+}
+
+/// Prints a constant value, as in `{episode: JEDI, ids: ["a"]}`.
+pub fn print_value(value: &Value) -> String {
+  let mut printed = String::new();
+  write_value(&mut printed, value);
+  printed
+}
+
+fn write_value(out: &mut String, value: &Value) {
+  match *value {
+    Value::Variable(ref v) => out.push_str(&format!("${}", v.name.value)),
+    Value::Int(ref v) => out.push_str(&v.value),
+    Value::Float(ref v) => out.push_str(&v.value),
+    Value::String(ref v) => out.push_str(&string_literal(&v.value)),
+    Value::Boolean(ref v) => out.push_str(if v.value { "true" } else { "false" }),
+    Value::Enum(ref v) => out.push_str(&v.name.value),
+    Value::List(ref v) => {
+      out.push('[');
+      for (i, value) in v.values.iter().enumerate() {
+        if i > 0 {
+          out.push_str(", ");
+        }
+        write_value(out, value);
+      }
+      out.push(']');
+    }
+    Value::Object(ref v) => {
+      out.push('{');
+      for (i, field) in v.fields.iter().enumerate() {
+        if i > 0 {
+          out.push_str(", ");
+        }
+        out.push_str(&format!("{}: ", field.name.value));
+        write_value(out, &field.value);
+      }
+      out.push('}');
+    }
+  }
+}
+
+fn string_literal(value: &str) -> String {
+  let mut literal = String::from("\"");
+  for c in value.chars() {
+    match c {
+      '"' => literal.push_str("\\\""),
+      '\\' => literal.push_str("\\\\"),
+      '\n' => literal.push_str("\\n"),
+      '\r' => literal.push_str("\\r"),
+      '\t' => literal.push_str("\\t"),
+      c if c < ' ' => literal.push_str(&format!("\\u{:04X}", c as u32)),
+      c => literal.push(c),
+    }
+  }
+  literal.push('"');
+  literal
+}
+
+type Result<T> = result::Result<T, Diagnostic>;
+
+fn tokenize(input: &str) -> Result<Vec<(Token<'_>, Span)>> {
+  let mut tokens = Vec::new();
+  let mut lexer = Lexer::new(input);
+  while let Some(token) = lexer.next() {
+    let span = Span::new(lexer.position(), lexer.offset());
+    match token {
+      Ok(token) => tokens.push((token, span)),
+      Err(e) => return Err(Diagnostic::new(format!("{}.", e), span, e.hint())),
+    }
+  }
+  Ok(tokens)
+}
+
+static DEFINITION_HINT: &str = "Define types as in `type Name { field: Type }`, or start the \
+                                document with a `schema` or a `directive` definition.";
+static FIELD_HINT: &str = "Define fields as in `name: Type`.";
+static INPUT_VALUE_HINT: &str = "Define arguments and input fields as in `name: Type`.";
+
+struct Parser<'a> {
+  // Ends with `Eof`.
+  tokens: Vec<(Token<'a>, Span)>,
+  pos: usize,
+}
+
+impl<'a> Parser<'a> {
+  fn peek(&self) -> &Token<'a> {
+    &self.tokens[self.pos].0
+  }
+
+  fn span(&self) -> Span {
+    self.tokens[self.pos].1
+  }
+
+  fn bump(&mut self) -> Span {
+    let span = self.span();
+    if self.pos + 1 < self.tokens.len() {
+      self.pos += 1;
+    }
+    span
+  }
+
+  fn at(&self, punctuator: Punctuator) -> bool {
+    matches!(self.peek(), Token::Punctuator(p, _, _) if *p == punctuator)
+  }
+
+  fn at_keyword(&self, keyword: &str) -> bool {
+    matches!(self.peek(), Token::Name(name, _, _) if *name == keyword)
+  }
+
+  fn error<T>(&self, expected: &str, hint: &str) -> Result<T> {
+    let found = match *self.peek() {
+      Token::Eof => "<EOF>".to_owned(),
+      Token::Name(name, _, _) => format!("Name \"{}\"", name),
+      Token::IntValue(value, _, _) => format!("Int \"{}\"", value),
+      Token::FloatValue(value, _, _) => format!("Float \"{}\"", value),
+      Token::StringValue(ref value, _, _) => format!("String {:?}", value),
+      Token::Punctuator(p, _, _) => format!("\"{}\"", punctuator_str(p)),
+      // Trivia is not emitted without `with_trivia`.
+      _ => unreachable!(),
+    };
+    Err(Diagnostic::new(format!("Expected {}, found {}.", expected, found), self.span(), hint))
+  }
+
+  fn expect(&mut self, punctuator: Punctuator, hint: &str) -> Result<Span> {
+    if self.at(punctuator) {
+      Ok(self.bump())
+    } else {
+      self.error(&format!("\"{}\"", punctuator_str(punctuator)), hint)
+    }
+  }
+
+  fn expect_keyword(&mut self, keyword: &str, hint: &str) -> Result<()> {
+    if self.at_keyword(keyword) {
+      self.bump();
+      Ok(())
+    } else {
+      self.error(&format!("\"{}\"", keyword), hint)
+    }
+  }
+
+  fn name(&mut self, hint: &str) -> Result<(String, Span)> {
+    match *self.peek() {
+      Token::Name(name, _, _) => {
+        let name = name.to_owned();
+        Ok((name, self.bump()))
+      }
+      _ => self.error("Name", hint),
+    }
+  }
+
+  fn description(&mut self) -> Option<String> {
+    match *self.peek() {
+      Token::StringValue(ref value, _, _) => {
+        let value = value.clone();
+        self.bump();
+        Some(value)
+      }
+      _ => None,
+    }
+  }
+
+  fn definition(&mut self, document: &mut SchemaDocument) -> Result<()> {
+    let description = self.description();
+    let keyword = match *self.peek() {
+      Token::Name(name, _, _) => name,
+      _ => return self.error("a definition", DEFINITION_HINT),
+    };
+    match keyword {
+      "schema" => {
+        if document.schema.is_some() {
+          return Err(Diagnostic::new("Must provide only one schema definition.".to_owned(),
+                                     self.span(),
+                                     "Name every root operation type in one definition."));
+        }
+        document.schema = Some(self.schema_definition(description)?);
+      }
+      "directive" => document.directives.push(self.directive_definition(description)?),
+      "scalar" | "type" | "interface" | "union" | "enum" | "input" => {
+        document.types.push(self.type_definition(description)?);
+      }
+      "extend" => {
+        return Err(Diagnostic::new("Type extensions are not supported.".to_owned(),
+                                   self.span(),
+                                   "Move the definitions to the type being extended."));
+      }
+      _ => return self.error("a definition", DEFINITION_HINT),
+    }
+    Ok(())
+  }
+
+  // SchemaDefinition : Description? schema Directives? { RootOperationTypeDefinition+ }
+  fn schema_definition(&mut self, description: Option<String>) -> Result<SchemaDefinition> {
+    let span = self.bump();
+    self.directives()?;
+    let hint = "Name the root operation types, as in `schema { query: Query }`.";
+    self.expect(Punctuator::LeftBrace, hint)?;
+    let mut schema = SchemaDefinition {
+      description,
+      query: None,
+      mutation: None,
+      subscription: None,
+      span,
+    };
+    loop {
+      let operation = match *self.peek() {
+        Token::Name(name @ "query", _, _) |
+        Token::Name(name @ "mutation", _, _) |
+        Token::Name(name @ "subscription", _, _) => name,
+        _ => return self.error("\"query\", \"mutation\" or \"subscription\"", hint),
+      };
+      self.bump();
+      self.expect(Punctuator::Colon, hint)?;
+      let (name, _) = self.name(hint)?;
+      match operation {
+        "query" => schema.query = Some(name),
+        "mutation" => schema.mutation = Some(name),
+        _ => schema.subscription = Some(name),
+      }
+      if self.at(Punctuator::RightBrace) {
+        self.bump();
+        return Ok(schema);
+      }
+    }
+  }
+
+  fn type_definition(&mut self, description: Option<String>) -> Result<TypeDefinition> {
+    let keyword = match *self.peek() {
+      Token::Name(keyword, _, _) => keyword,
+      _ => unreachable!(),
+    };
+    self.bump();
+    let (name, span) = self.name("Name the type after its kind, as in `type Name`.")?;
+    let kind = match keyword {
+      "scalar" => {
+        self.directives()?;
+        TypeDefinitionKind::Scalar
+      }
+      "type" | "interface" => {
+        let interfaces = self.implements_interfaces()?;
+        self.directives()?;
+        let fields = self.fields_definition()?;
+        if keyword == "type" {
+          TypeDefinitionKind::Object { interfaces, fields }
+        } else {
+          TypeDefinitionKind::Interface { interfaces, fields }
+        }
+      }
+      "union" => {
+        self.directives()?;
+        TypeDefinitionKind::Union { types: self.union_member_types()? }
+      }
+      "enum" => {
+        self.directives()?;
+        TypeDefinitionKind::Enum { values: self.enum_values_definition()? }
+      }
+      _ => {
+        self.directives()?;
+        let mut fields = Vec::new();
+        if self.at(Punctuator::LeftBrace) {
+          self.bump();
+          while !self.at(Punctuator::RightBrace) {
+            fields.push(self.input_value_definition()?);
+          }
+          self.bump();
+        }
+        TypeDefinitionKind::InputObject { fields }
+      }
+    };
+    Ok(TypeDefinition {
+      description,
+      name,
+      span,
+      kind,
+    })
+  }
+
+  // ImplementsInterfaces : implements &? NamedType (& NamedType)*
+  fn implements_interfaces(&mut self) -> Result<Vec<String>> {
+    let mut interfaces = Vec::new();
+    if !self.at_keyword("implements") {
+      return Ok(interfaces);
+    }
+    self.bump();
+    if self.at(Punctuator::Ampersand) {
+      self.bump();
+    }
+    loop {
+      interfaces.push(self.name("Name the interfaces, as in `implements Node & Entity`.")?.0);
+      if !self.at(Punctuator::Ampersand) {
+        return Ok(interfaces);
+      }
+      self.bump();
+    }
+  }
+
+  // FieldsDefinition : { FieldDefinition+ }
+  fn fields_definition(&mut self) -> Result<Vec<FieldDefinition>> {
+    let mut fields = Vec::new();
+    if !self.at(Punctuator::LeftBrace) {
+      return Ok(fields);
+    }
+    self.bump();
+    while !self.at(Punctuator::RightBrace) {
+      // FieldDefinition : Description? Name ArgumentsDefinition? : Type Directives?
+      let description = self.description();
+      let (name, span) = self.name(FIELD_HINT)?;
+      let arguments = self.arguments_definition()?;
+      self.expect(Punctuator::Colon, FIELD_HINT)?;
+      let type_ = self.type_()?;
+      let deprecation_reason = self.directives()?;
+      fields.push(FieldDefinition {
+        description,
+        name,
+        span,
+        arguments,
+        type_,
+        deprecation_reason,
+      });
+    }
+    self.bump();
+    Ok(fields)
+  }
+
+  // ArgumentsDefinition : ( InputValueDefinition+ )
+  fn arguments_definition(&mut self) -> Result<Vec<InputValueDefinition>> {
+    let mut arguments = Vec::new();
+    if !self.at(Punctuator::LeftParen) {
+      return Ok(arguments);
+    }
+    self.bump();
+    while !self.at(Punctuator::RightParen) {
+      arguments.push(self.input_value_definition()?);
+    }
+    self.bump();
+    Ok(arguments)
+  }
+
+  // InputValueDefinition : Description? Name : Type DefaultValue? Directives?
+  fn input_value_definition(&mut self) -> Result<InputValueDefinition> {
+    let description = self.description();
+    let (name, span) = self.name(INPUT_VALUE_HINT)?;
+    self.expect(Punctuator::Colon, INPUT_VALUE_HINT)?;
+    let type_ = self.type_()?;
+    let default_value = if self.at(Punctuator::Equals) {
+      self.bump();
+      Some(self.value()?)
+    } else {
+      None
+    };
+    let deprecation_reason = self.directives()?;
+    Ok(InputValueDefinition {
+      description,
+      name,
+      span,
+      type_,
+      default_value,
+      deprecation_reason,
+    })
+  }
+
+  // UnionMemberTypes : = |? NamedType (| NamedType)*
+  fn union_member_types(&mut self) -> Result<Vec<String>> {
+    let mut types = Vec::new();
+    if !self.at(Punctuator::Equals) {
+      return Ok(types);
+    }
+    self.bump();
+    if self.at(Punctuator::Pipe) {
+      self.bump();
+    }
+    loop {
+      types.push(self.name("Name the member types, as in `union Name = A | B`.")?.0);
+      if !self.at(Punctuator::Pipe) {
+        return Ok(types);
+      }
+      self.bump();
+    }
+  }
+
+  // EnumValuesDefinition : { EnumValueDefinition+ }
+  fn enum_values_definition(&mut self) -> Result<Vec<EnumValueDefinition>> {
+    let mut values = Vec::new();
+    if !self.at(Punctuator::LeftBrace) {
+      return Ok(values);
+    }
+    self.bump();
+    while !self.at(Punctuator::RightBrace) {
+      let description = self.description();
+      let (name, span) = self.name("Define enum values as in `NAME`.")?;
+      let deprecation_reason = self.directives()?;
+      values.push(EnumValueDefinition {
+        description,
+        name,
+        span,
+        deprecation_reason,
+      });
+    }
+    self.bump();
+    Ok(values)
+  }
+
+  // DirectiveDefinition :
+  //   Description? directive @ Name ArgumentsDefinition? repeatable? on DirectiveLocations
+  fn directive_definition(&mut self, description: Option<String>) -> Result<DirectiveDefinition> {
+    self.bump();
+    let hint = "Define directives as in `directive @name on FIELD | FRAGMENT_SPREAD`.";
+    self.expect(Punctuator::At, hint)?;
+    let (name, span) = self.name(hint)?;
+    let arguments = self.arguments_definition()?;
+    let repeatable = self.at_keyword("repeatable");
+    if repeatable {
+      self.bump();
+    }
+    self.expect_keyword("on", hint)?;
+    if self.at(Punctuator::Pipe) {
+      self.bump();
+    }
+    let mut locations = Vec::new();
+    loop {
+      locations.push(self.name(hint)?.0);
+      if !self.at(Punctuator::Pipe) {
+        break;
+      }
+      self.bump();
+    }
+    Ok(DirectiveDefinition {
+      description,
+      name,
+      span,
+      arguments,
+      repeatable,
+      locations,
+    })
+  }
+
+  // Type : NamedType | ListType | NonNullType
+  fn type_(&mut self) -> Result<TypeRef> {
+    let type_ = if self.at(Punctuator::LeftBracket) {
+      self.bump();
+      let of_type = self.type_()?;
+      self.expect(Punctuator::RightBracket, "Close the list type with `]`.")?;
+      TypeRef::List(Box::new(of_type))
+    } else {
+      TypeRef::Named(self.name("Name a type, as in `String`.")?.0)
+    };
+    if self.at(Punctuator::Bang) {
+      self.bump();
+      Ok(TypeRef::NonNull(Box::new(type_)))
+    } else {
+      Ok(type_)
+    }
+  }
+
+  // Directives[Const] : Directive[?Const]+, returning the reason of
+  // `@deprecated` if it is one of them.
+  fn directives(&mut self) -> Result<Option<String>> {
+    let mut deprecation_reason = None;
+    while self.at(Punctuator::At) {
+      self.bump();
+      let (name, _) = self.name("Name the directive after `@`, as in `@deprecated`.")?;
+      let mut arguments = Vec::new();
+      if self.at(Punctuator::LeftParen) {
+        self.bump();
+        while !self.at(Punctuator::RightParen) {
+          let hint = "Write arguments as in `name: value`.";
+          let (name, _) = self.name(hint)?;
+          self.expect(Punctuator::Colon, hint)?;
+          arguments.push((name, self.value()?));
+        }
+        self.bump();
+      }
+      if name == "deprecated" {
+        let reason = arguments.into_iter().find(|(name, _)| name == "reason");
+        deprecation_reason = Some(match reason {
+          Some((_, Value::String(reason))) => reason.value,
+          _ => DEFAULT_DEPRECATION_REASON.to_owned(),
+        });
+      }
+    }
+    Ok(deprecation_reason)
+  }
+
+  // Value[Const], with `null` as an enum value like in `ast`.
+  fn value(&mut self) -> Result<Value> {
+    let span = self.span();
+    let loc = Some(Location {
+      start: span.start,
+      end: span.end
+    });
+    let value = match *self.peek() {
+      Token::IntValue(value, _, _) => {
+        Value::Int(IntValue {
+          loc,
+          value: value.to_owned()
+        })
+      }
+      Token::FloatValue(value, _, _) => {
+        Value::Float(FloatValue {
+          loc,
+          value: value.to_owned()
+        })
+      }
+      Token::StringValue(ref value, _, _) => {
+        Value::String(StringValue {
+          loc,
+          value: value.clone()
+        })
+      }
+      Token::Name(value @ "true", _, _) |
+      Token::Name(value @ "false", _, _) => {
+        Value::Boolean(BooleanValue {
+          loc,
+          value: value == "true"
+        })
+      }
+      Token::Name(value, _, _) => {
+        Value::Enum(EnumValue {
+          loc,
+          name: Name {
+            loc,
+            value: value.to_owned()
+          }
+        })
+      }
+      Token::Punctuator(Punctuator::LeftBracket, _, _) => {
+        self.bump();
+        let mut values = Vec::new();
+        while !self.at(Punctuator::RightBracket) {
+          values.push(self.value()?);
+        }
+        let end = self.bump().end;
+        return Ok(Value::List(ListValue {
+          loc: Some(Location {
+            start: span.start,
+            end
+          }),
+          values
+        }));
+      }
+      Token::Punctuator(Punctuator::LeftBrace, _, _) => {
+        self.bump();
+        let mut fields = Vec::new();
+        while !self.at(Punctuator::RightBrace) {
+          let hint = "Write object fields as in `name: value`.";
+          let (value, name_span) = self.name(hint)?;
+          self.expect(Punctuator::Colon, hint)?;
+          let value_ = self.value()?;
+          let end = value_loc_end(&value_);
+          let name_loc = Some(Location {
+            start: name_span.start,
+            end: name_span.end
+          });
+          fields.push(ObjectField {
+            loc: Some(Location {
+              start: name_span.start,
+              end
+            }),
+            name: Name {
+              loc: name_loc,
+              value
+            },
+            value: value_
+          });
+        }
+        let end = self.bump().end;
+        return Ok(Value::Object(ObjectValue {
+          loc: Some(Location {
+            start: span.start,
+            end
+          }),
+          fields
+        }));
+      }
+      _ => {
+        return self.error("a constant value",
+                          "Write a value without variables, as in `10`, `\"text\"` or `[A, B]`.")
+      }
+    };
+    self.bump();
+    Ok(value)
+  }
+}
+
+fn value_loc_end(value: &Value) -> usize {
+  let loc = match *value {
+    Value::Variable(ref v) => v.loc,
+    Value::Int(ref v) => v.loc,
+    Value::Float(ref v) => v.loc,
+    Value::String(ref v) => v.loc,
+    Value::Boolean(ref v) => v.loc,
+    Value::Enum(ref v) => v.loc,
+    Value::List(ref v) => v.loc,
+    Value::Object(ref v) => v.loc,
+  };
+  loc.map_or(0, |loc| loc.end)
+}
+
+fn punctuator_str(punctuator: Punctuator) -> &'static str {
+  match punctuator {
+    Punctuator::Bang => "!",
+    Punctuator::Dollar => "$",
+    Punctuator::LeftParen => "(",
+    Punctuator::RightParen => ")",
+    Punctuator::Spread => "...",
+    Punctuator::Colon => ":",
+    Punctuator::Equals => "=",
+    Punctuator::At => "@",
+    Punctuator::LeftBracket => "[",
+    Punctuator::RightBracket => "]",
+    Punctuator::LeftBrace => "{",
+    Punctuator::RightBrace => "}",
+    Punctuator::Pipe => "|",
+    Punctuator::Ampersand => "&",
+  }
+}
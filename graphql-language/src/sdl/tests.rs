@@ -0,0 +1,223 @@
+use super::*;
+
+static SCHEMA: &str = r#"
+schema {
+  query: Root
+}
+
+"""
+Directs the executor to skip this field or fragment.
+"""
+directive @skip(if: Boolean!) repeatable on FIELD | FRAGMENT_SPREAD
+
+scalar Date @specifiedBy(url: "https://tools.ietf.org/html/rfc3339")
+
+"A character of the saga."
+interface Character {
+  name: String!
+  friends(first: Int = 10, after: [ID!] = ["a", "b"]): [Character]
+}
+
+type Droid implements Node & Character {
+  id: ID!
+  name: String!
+  friends(first: Int = 10, after: [ID!] = ["a", "b"]): [Character]
+  primaryFunction: String @deprecated(reason: "Use `function`.")
+  model: String @deprecated
+}
+
+union SearchResult = | Droid | Human
+
+enum Episode {
+  "Released in 1977."
+  NEWHOPE
+  EMPIRE
+  JEDI @deprecated
+}
+
+input ReviewInput {
+  stars: Int!
+  commentary: String = null
+  filter: Filter = { episode: JEDI, since: 1.5 }
+}
+"#;
+
+#[test]
+fn parses_definitions() {
+  let document = parse_schema(SCHEMA).unwrap();
+  let schema = document.schema.as_ref().unwrap();
+  assert_eq!((schema.query.as_ref().map(|q| q.as_ref()), schema.mutation.as_ref()),
+             (Some("Root"), None));
+
+  let directive = &document.directives[0];
+  assert_eq!(directive.description,
+             Some("Directs the executor to skip this field or fragment.".to_owned()));
+  assert_eq!((directive.name.as_ref(), directive.repeatable), ("skip", true));
+  assert_eq!(directive.arguments[0].type_.to_string(), "Boolean!");
+  assert_eq!(directive.locations, vec!["FIELD", "FRAGMENT_SPREAD"]);
+
+  let names: Vec<_> = document.types.iter().map(|t| t.name.as_str()).collect();
+  assert_eq!(names,
+             vec!["Date", "Character", "Droid", "SearchResult", "Episode", "ReviewInput"]);
+  let character = document.get_type("Character").unwrap();
+  assert_eq!(character.description, Some("A character of the saga.".to_owned()));
+  assert_eq!(&SCHEMA[character.span.start..character.span.end], "Character");
+
+  let droid = document.get_type("Droid").unwrap();
+  match droid.kind {
+    TypeDefinitionKind::Object { ref interfaces, ref fields } => {
+      assert_eq!(interfaces, &vec!["Node", "Character"]);
+      let friends = &fields[2];
+      assert_eq!(friends.type_, TypeRef::List(Box::new(TypeRef::Named("Character".to_owned()))));
+      assert_eq!(friends.arguments[1].type_.to_string(), "[ID!]");
+      match friends.arguments[1].default_value {
+        Some(Value::List(ref list)) => assert_eq!(list.values.len(), 2),
+        _ => panic!("Expected a list."),
+      }
+      let reasons: Vec<_> = fields.iter().map(|f| f.deprecation_reason.as_ref()).collect();
+      assert_eq!(reasons[3..],
+                 [Some(&"Use `function`.".to_owned()),
+                  Some(&DEFAULT_DEPRECATION_REASON.to_owned())]);
+    }
+    _ => panic!("Expected an object type."),
+  }
+
+  match document.get_type("SearchResult").unwrap().kind {
+    TypeDefinitionKind::Union { ref types } => assert_eq!(types, &vec!["Droid", "Human"]),
+    _ => panic!("Expected a union."),
+  }
+  match document.get_type("Episode").unwrap().kind {
+    TypeDefinitionKind::Enum { ref values } => {
+      assert_eq!(values[0].description, Some("Released in 1977.".to_owned()));
+      assert_eq!(values[2].deprecation_reason, Some(DEFAULT_DEPRECATION_REASON.to_owned()));
+    }
+    _ => panic!("Expected an enum."),
+  }
+  match document.get_type("ReviewInput").unwrap().kind {
+    TypeDefinitionKind::InputObject { ref fields } => {
+      match fields[1].default_value {
+        Some(Value::Enum(ref null)) => assert_eq!(null.name.value, "null"),
+        _ => panic!("Expected null."),
+      }
+      match fields[2].default_value {
+        Some(Value::Object(ref object)) => {
+          let names: Vec<_> = object.fields.iter().map(|f| f.name.value.as_str()).collect();
+          assert_eq!(names, vec!["episode", "since"]);
+        }
+        _ => panic!("Expected an object."),
+      }
+    }
+    _ => panic!("Expected an input object."),
+  }
+}
+
+#[test]
+fn stops_at_the_first_error() {
+  let error = |input| parse_schema(input).unwrap_err();
+
+  let e = error("type Query {\n  hero: \n}");
+  assert_eq!((e.message.as_ref(), e.span), ("Expected Name, found \"}\".", Span::new(22, 23)));
+  assert_eq!(e.hint, "Name a type, as in `String`.");
+
+  let e = error("type Query { hero(first: Int = $first): Int }");
+  assert_eq!(e.message, "Expected a constant value, found \"$\".");
+
+  let e = error("query { hero }");
+  assert_eq!(e.message, "Expected a definition, found Name \"query\".");
+
+  let e = error("extend type Query { hero: Int }");
+  assert_eq!(e.message, "Type extensions are not supported.");
+
+  let e = error("type Query { name: \"String }");
+  assert_eq!((e.message.as_ref(), e.span), ("Unterminated string.", Span::new(20, 28)));
+}
+
+#[test]
+fn parses_single_values() {
+  match parse_value("{ episode: JEDI, ids: [\"a\"] }").unwrap() {
+    Value::Object(ref object) => assert_eq!(object.fields.len(), 2),
+    _ => panic!("Expected an object."),
+  }
+  let e = parse_value("1 2").unwrap_err();
+  assert_eq!((e.message.as_ref(), e.span), ("Expected the end of the value, found Int \"2\".",
+                                           Span::new(2, 3)));
+}
+
+#[test]
+fn prints_schemas() {
+  let printed = print_schema(&parse_schema(SCHEMA).unwrap());
+  assert_eq!(printed,
+             r#"schema {
+  query: Root
+}
+
+"""Directs the executor to skip this field or fragment."""
+directive @skip(if: Boolean!) repeatable on FIELD | FRAGMENT_SPREAD
+
+scalar Date
+
+"""A character of the saga."""
+interface Character {
+  name: String!
+  friends(first: Int = 10, after: [ID!] = ["a", "b"]): [Character]
+}
+
+type Droid implements Node & Character {
+  id: ID!
+  name: String!
+  friends(first: Int = 10, after: [ID!] = ["a", "b"]): [Character]
+  primaryFunction: String @deprecated(reason: "Use `function`.")
+  model: String @deprecated
+}
+
+union SearchResult = Droid | Human
+
+enum Episode {
+  """Released in 1977."""
+  NEWHOPE
+  EMPIRE
+  JEDI @deprecated
+}
+
+input ReviewInput {
+  stars: Int!
+  commentary: String = null
+  filter: Filter = {episode: JEDI, since: 1.5}
+}
+"#);
+  assert_eq!(print_schema(&parse_schema(&printed).unwrap()), printed);
+
+  let printed = print_schema(&parse_schema(r#"
+    schema { query: Query }
+    type Query {
+      "Lines\nof text"
+      hero(
+        "The \"id\""
+        id: ID!
+        "Say \"hi\""
+        greeting: String = "hi"
+      ): String
+    }
+  "#)
+    .unwrap());
+  assert_eq!(printed,
+             r#"type Query {
+  """
+  Lines
+  of text
+  """
+  hero(
+    """
+    The "id"
+    """
+    id: ID!
+
+    """
+    Say "hi"
+    """
+    greeting: String = "hi"
+  ): String
+}
+"#);
+  assert_eq!(print_schema(&parse_schema(&printed).unwrap()), printed);
+}
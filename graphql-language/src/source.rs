@@ -1,4 +1,4 @@
-static DEFAULT_SOURCE_NAME: &'static str = "GraphQL";
+static DEFAULT_SOURCE_NAME: &str = "GraphQL";
 
 #[derive(Debug)]
 pub struct Source<'a> {
@@ -10,7 +10,7 @@ impl<'a> Source<'a> {
   pub fn new(body: &'a str) -> Self {
     Source {
       name: DEFAULT_SOURCE_NAME,
-      body: body,
+      body,
     }
   }
 
@@ -18,6 +18,37 @@ impl<'a> Source<'a> {
     self.name = name;
     self
   }
+
+  /// Line and column of the byte offset `position` in the body.
+  pub fn location(&self, position: usize) -> SourceLocation {
+    let position = ::std::cmp::min(position, self.body.len());
+    let mut line = 1;
+    let mut line_start = 0;
+    let mut chars = self.body[..position].char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+      match c {
+        '\n' => line_start = i + 1,
+        '\r' if chars.peek().map(|&(_, c)| c) == Some('\n') => {
+          chars.next();
+          line_start = i + 2;
+        }
+        '\r' => line_start = i + 1,
+        _ => continue,
+      }
+      line += 1;
+    }
+    SourceLocation {
+      line,
+      column: self.body[line_start..position].chars().count() + 1,
+    }
+  }
+}
+
+/// 1-indexed line and column in a source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceLocation {
+  pub line: usize,
+  pub column: usize,
 }
 
 use std::io::Error;
@@ -28,8 +59,8 @@ pub fn from_file<'a>(path: &'a Path, buf: &'a mut String) -> Result<Source<'a>,
   use std::io::Read;
   use std::ffi::OsStr;
 
-  let mut f = try!(File::open(path.clone()));
-  try!(f.read_to_string(buf));
+  let mut f = File::open(path)?;
+  f.read_to_string(buf)?;
   Ok(Source::new(buf.as_str()).name(path
     .file_name()
     .and_then(OsStr::to_str)
@@ -0,0 +1,477 @@
+//! What the server tells of documents: their errors, and the schema
+//! definitions of what is at an offset in them.
+//!
+//! Offsets are found in the concrete syntax tree, which documents have even
+//! while they are being typed and are not valid.
+
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use graphql_language::cst::{self, NodeKind, SyntaxNode, TokenKind};
+use graphql_language::diagnostic::Span;
+use graphql_language::lexer::Punctuator;
+use graphql_language::parser;
+use graphql_language::source::Source;
+use graphql_rs::schema::{GraphQLSchema, SchemaType};
+use graphql_rs::types::*;
+use graphql_rs::validation;
+
+use protocol::{CompletionItem, FIELD, KEYWORD, VARIABLE};
+
+// Directive which can be used in documents, with its arguments and their
+// types.
+struct Directive {
+  name: &'static str,
+  description: &'static str,
+  args: &'static [(&'static str, &'static str)],
+}
+
+static DIRECTIVES: [Directive; 4] = [
+  Directive {
+    name: "skip",
+    description: "Directs the executor to skip this field or fragment when the `if` argument is \
+                  true.",
+    args: &[("if", "Boolean!")],
+  },
+  Directive {
+    name: "include",
+    description: "Directs the executor to include this field or fragment only when the `if` \
+                  argument is true.",
+    args: &[("if", "Boolean!")],
+  },
+  Directive {
+    name: "defer",
+    description: "Directs the executor to deliver this fragment in a later payload.",
+    args: &[("if", "Boolean! = true"), ("label", "String")],
+  },
+  Directive {
+    name: "stream",
+    description: "Directs the executor to deliver the items of this list field in later \
+                  payloads.",
+    args: &[("if", "Boolean! = true"), ("label", "String"), ("initialCount", "Int = 0")],
+  },
+];
+
+/// Errors of the document `text`: its syntax errors, else the errors of its
+/// validation against `schema` if there is one.
+///
+/// Spreads of `fragments`, which are defined elsewhere in the project, are
+/// not errors.
+pub fn diagnostics(schema: Option<&GraphQLSchema>,
+                   text: &str,
+                   fragments: &HashSet<String>)
+                   -> Vec<(Span, String)> {
+  let source = Source::new(text);
+  let (document, errors) = parser::parse_tolerant(&source);
+  if !errors.is_empty() {
+    return errors.into_iter().map(|e| (e.span, e.message)).collect();
+  }
+  let schema = match schema {
+    Some(schema) => schema,
+    None => return Vec::new(),
+  };
+
+  let parse = cst::parse(text);
+  let mut known_spreads = HashSet::new();
+  visit(&parse.root, &mut |node| {
+    if let Some(name) = cst::FragmentSpread::cast(node).and_then(|spread| spread.name()) {
+      if fragments.contains(name.text) {
+        known_spreads.insert(name.start);
+      }
+    }
+  });
+
+  let mut errors = validation::validate_names(schema, &document);
+  errors.extend(validation::validate(schema, &document));
+  errors.into_iter()
+    .filter_map(|e| {
+      let offset = e.locations().first().map_or(0, |l| line_offset(text, l.line, l.column));
+      if known_spreads.contains(&offset) {
+        return None;
+      }
+      Some((name_span(text, offset), e.message().to_owned()))
+    })
+    .collect()
+}
+
+/// Fragments defined in the document `text`, with the spans of their names.
+pub fn fragment_definitions(text: &str) -> Vec<(String, Span)> {
+  let parse = cst::parse(text);
+  let document = match cst::Document::cast(&parse.root) {
+    Some(document) => document,
+    None => return Vec::new(),
+  };
+  document.definitions()
+    .into_iter()
+    .filter_map(|definition| {
+      match definition {
+        cst::Definition::Fragment(fragment) => fragment.name(),
+        cst::Definition::Operation(_) => None,
+      }
+    })
+    .map(|name| (name.text.to_owned(), Span::new(name.start, name.end())))
+    .collect()
+}
+
+/// Completions of the fields, arguments or directives which can be typed at
+/// `offset`.
+pub fn completions(schema: &GraphQLSchema, text: &str, offset: usize) -> Vec<CompletionItem> {
+  let parse = cst::parse(text);
+  // Nodes end where their closing token does, or after their last token
+  // while they are not closed yet.
+  let path = path_at(&parse.root, &|node| {
+    node.start < offset && (offset < node.end() || !is_closed(node))
+  });
+  let tokens = parse.root.tokens();
+  let mut before = tokens.iter().rev().filter(|t| !t.kind.is_trivia() && t.end() <= offset);
+  let previous = match before.next() {
+    Some(token) if token.kind == TokenKind::Name && token.end() == offset => before.next(),
+    token => token,
+  };
+
+  match previous.map(|t| t.kind) {
+    Some(TokenKind::Punctuator(Punctuator::At)) => return directive_completions(),
+    Some(TokenKind::Punctuator(Punctuator::Spread)) => return Vec::new(),
+    _ => {}
+  }
+  for (i, node) in path.iter().enumerate().rev() {
+    match node.kind {
+      NodeKind::Argument | NodeKind::ObjectValue | NodeKind::ListValue | NodeKind::Variable |
+      NodeKind::VariableDefinitions | NodeKind::TypeCondition => return Vec::new(),
+      NodeKind::Arguments => return argument_completions(schema, &path[..i]),
+      NodeKind::SelectionSet => {
+        return match parent_type(schema, &path[..=i]) {
+          Some(parent) => field_completions(&*parent),
+          None => Vec::new(),
+        }
+      }
+      _ => {}
+    }
+  }
+  Vec::new()
+}
+
+/// Markdown describing the definition of the name at `offset`, with the
+/// span of the name.
+pub fn hover(schema: &GraphQLSchema, text: &str, offset: usize) -> Option<(Span, String)> {
+  let parse = cst::parse(text);
+  let path = path_at(&parse.root, &|node| node.start <= offset && offset < node.end());
+  let node = *path.last()?;
+  let name = node.child_tokens()
+    .find(|t| t.kind == TokenKind::Name && t.start <= offset && offset < t.end())?;
+  let span = Span::new(name.start, name.end());
+
+  let (signature, description) = match node.kind {
+    NodeKind::Field => {
+      let parent = parent_type(schema, &path[..path.len() - 1])?;
+      with_field(&*parent, name.text, |field| {
+        (format!("{:}.{:}", parent.name(), field_signature(field)),
+         describe(field.description(), field.deprecation_reason()))
+      })?
+    }
+    NodeKind::Argument => {
+      let owner = path.get(path.len().checked_sub(3)?)?;
+      match owner.kind {
+        NodeKind::Field => {
+          let parent = parent_type(schema, &path[..path.len() - 3])?;
+          let field_name = cst::Field::cast(owner)?.name()?.text;
+          with_field(&*parent, field_name, |field| {
+            let arg = field.args()?.get(name.text)?;
+            Some((argument_signature(arg), describe(arg.description(), arg.deprecation_reason())))
+          })??
+        }
+        NodeKind::Directive => {
+          let directive = cst::Directive::cast(owner)?.name()?.text;
+          let directive = DIRECTIVES.iter().find(|d| d.name == directive)?;
+          let &(arg, typ) = directive.args.iter().find(|a| a.0 == name.text)?;
+          (format!("{:}: {:}", arg, typ), None)
+        }
+        _ => return None,
+      }
+    }
+    NodeKind::NamedType => {
+      let typ = schema.get_type(name.text)?;
+      (format!("{:} {:}", type_keyword(typ), typ.name()), typ.description().map(|d| d.to_owned()))
+    }
+    NodeKind::Directive => {
+      let directive = DIRECTIVES.iter().find(|d| d.name == name.text)?;
+      (directive_signature(directive), Some(directive.description.to_owned()))
+    }
+    _ => return None,
+  };
+
+  let mut contents = format!("```graphql\n{:}\n```", signature);
+  if let Some(description) = description {
+    contents.push_str("\n\n");
+    contents.push_str(&description);
+  }
+  Some((span, contents))
+}
+
+/// Name whose definition the server can find.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+  Fragment(String),
+  Type(String),
+}
+
+/// Fragment or type named at `offset`.
+pub fn definition_target(text: &str, offset: usize) -> Option<Target> {
+  let parse = cst::parse(text);
+  let path = path_at(&parse.root, &|node| node.start <= offset && offset < node.end());
+  let node = *path.last()?;
+  let name = node.child_tokens()
+    .find(|t| t.kind == TokenKind::Name && t.start <= offset && offset < t.end())?;
+  match node.kind {
+    NodeKind::FragmentSpread => Some(Target::Fragment(name.text.to_owned())),
+    NodeKind::NamedType => Some(Target::Type(name.text.to_owned())),
+    _ => None,
+  }
+}
+
+// Nodes from the root down to the innermost one which is `inside`.
+fn path_at<'a, F>(root: &'a SyntaxNode<'a>, inside: &F) -> Vec<&'a SyntaxNode<'a>>
+  where F: Fn(&SyntaxNode) -> bool
+{
+  let mut path = vec![root];
+  let mut node = root;
+  while let Some(child) = node.child_nodes().find(|child| inside(child)) {
+    path.push(child);
+    node = child;
+  }
+  path
+}
+
+fn visit<'a, F: FnMut(&'a SyntaxNode<'a>)>(node: &'a SyntaxNode<'a>, f: &mut F) {
+  f(node);
+  for child in node.child_nodes() {
+    visit(child, f);
+  }
+}
+
+fn is_closed(node: &SyntaxNode) -> bool {
+  let last = node.tokens().into_iter().rev().find(|t| !t.kind.is_trivia());
+  matches!(last.map(|t| t.kind),
+           Some(TokenKind::Punctuator(Punctuator::RightBrace)) |
+           Some(TokenKind::Punctuator(Punctuator::RightParen)) |
+           Some(TokenKind::Punctuator(Punctuator::RightBracket)))
+}
+
+// Byte offset of a 1-indexed line and column, as found in errors.
+fn line_offset(text: &str, line: usize, column: usize) -> usize {
+  let mut offset = 0;
+  for _ in 1..line {
+    match text[offset..].find('\n') {
+      Some(i) => offset += i + 1,
+      None => return text.len(),
+    }
+  }
+  text[offset..].char_indices().nth(column - 1).map_or(text.len(), |(i, _)| offset + i)
+}
+
+// Span of the name at `offset`, the `@` of directives and the `$` of
+// variables included.
+fn name_span(text: &str, offset: usize) -> Span {
+  let rest = &text[offset..];
+  let sigil = if rest.starts_with(['@', '$']) { 1 } else { 0 };
+  let name = rest[sigil..].find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'));
+  Span::new(offset, offset + sigil + name.unwrap_or(rest.len() - sigil))
+}
+
+// Named type of the selection set at the end of `path`.
+fn parent_type(schema: &GraphQLSchema, path: &[&SyntaxNode]) -> Option<Rc<dyn GraphQLOutput>> {
+  let mut typ = None;
+  for (i, node) in path.iter().enumerate() {
+    match node.kind {
+      NodeKind::OperationDefinition => {
+        let operation = cst::OperationDefinition::cast(node)?.operation_type().map(|t| t.text);
+        let root = match operation {
+          Some("mutation") => schema.mutation_type(),
+          Some("subscription") => schema.subscription_type(),
+          _ => Some(schema.query_type()),
+        };
+        typ = root.map(|root| root.clone() as Rc<dyn GraphQLOutput>);
+      }
+      NodeKind::FragmentDefinition => {
+        let condition = cst::FragmentDefinition::cast(node)?.type_condition();
+        typ = output_type(schema, condition.and_then(|c| c.name())?.text);
+      }
+      NodeKind::InlineFragment => {
+        if let Some(condition) = cst::InlineFragment::cast(node)?.type_condition() {
+          typ = output_type(schema, condition.name()?.text);
+        }
+      }
+      NodeKind::Field if path.get(i + 1).is_some_and(|n| n.kind == NodeKind::SelectionSet) => {
+        let name = cst::Field::cast(node)?.name()?.text;
+        typ = with_field(&*typ?, name, |field| named_type(field.typ()));
+      }
+      _ => {}
+    }
+  }
+  typ
+}
+
+fn output_type(schema: &GraphQLSchema, name: &str) -> Option<Rc<dyn GraphQLOutput>> {
+  match schema.get_type(name) {
+    Some(SchemaType::Output(typ)) => Some(typ.clone()),
+    _ => None,
+  }
+}
+
+fn named_type(typ: &Rc<dyn GraphQLOutput>) -> Rc<dyn GraphQLOutput> {
+  match typ.output_kind() {
+    OutputKind::List(list) => named_type(list.of_type()),
+    OutputKind::Optional(optional) => named_type(optional.of_type()),
+    _ => typ.clone(),
+  }
+}
+
+fn with_field<T, F>(parent: &dyn GraphQLOutput, name: &str, f: F) -> Option<T>
+  where F: FnOnce(&GraphQLField) -> T
+{
+  match parent.output_kind() {
+    OutputKind::Object(object) => object.fields().get(name).map(f),
+    OutputKind::Interface(interface) => interface.fields().get(name).map(f),
+    _ => None,
+  }
+}
+
+fn field_completions(parent: &dyn GraphQLOutput) -> Vec<CompletionItem> {
+  let mut items = Vec::new();
+  let mut add = |field: &GraphQLField| {
+    items.push(CompletionItem {
+      label: field.name().to_owned(),
+      kind: FIELD,
+      detail: Some(output_type_ref(&**field.typ())),
+      documentation: field.description().map(|d| d.to_owned()),
+      deprecated: field.deprecation_reason().is_some(),
+    })
+  };
+  match parent.output_kind() {
+    OutputKind::Object(object) => object.fields().values().for_each(&mut add),
+    OutputKind::Interface(interface) => interface.fields().values().for_each(&mut add),
+    _ => {}
+  }
+  items.push(CompletionItem {
+    label: "__typename".to_owned(),
+    kind: FIELD,
+    detail: Some("String".to_owned()),
+    documentation: Some("The name of the object type.".to_owned()),
+    deprecated: false,
+  });
+  items
+}
+
+// Arguments of the field or directive at the end of `path`.
+fn argument_completions(schema: &GraphQLSchema, path: &[&SyntaxNode]) -> Vec<CompletionItem> {
+  let owner = match path.last() {
+    Some(owner) => owner,
+    None => return Vec::new(),
+  };
+  if let Some(directive) = cst::Directive::cast(owner) {
+    let args = directive.name()
+      .and_then(|name| DIRECTIVES.iter().find(|d| d.name == name.text))
+      .map_or(&[][..], |d| d.args);
+    return args.iter()
+      .map(|&(name, typ)| {
+        CompletionItem {
+          label: name.to_owned(),
+          kind: VARIABLE,
+          detail: Some(typ.to_owned()),
+          documentation: None,
+          deprecated: false,
+        }
+      })
+      .collect();
+  }
+
+  let field = cst::Field::cast(owner).and_then(|field| field.name()).map(|name| name.text);
+  let parent = parent_type(schema, &path[..path.len() - 1]);
+  let items = field.and_then(|field| {
+    with_field(&*parent?, field, |field| {
+      field.args()
+        .into_iter()
+        .flat_map(|args| args.values())
+        .map(|arg| {
+          CompletionItem {
+            label: arg.name().to_owned(),
+            kind: VARIABLE,
+            detail: Some(input_type_ref(&**arg.typ())),
+            documentation: arg.description().map(|d| d.to_owned()),
+            deprecated: arg.deprecation_reason().is_some(),
+          }
+        })
+        .collect()
+    })
+  });
+  items.unwrap_or_default()
+}
+
+fn directive_completions() -> Vec<CompletionItem> {
+  DIRECTIVES.iter()
+    .map(|directive| {
+      CompletionItem {
+        label: directive.name.to_owned(),
+        kind: KEYWORD,
+        detail: Some(directive_signature(directive)),
+        documentation: Some(directive.description.to_owned()),
+        deprecated: false,
+      }
+    })
+    .collect()
+}
+
+fn field_signature(field: &GraphQLField) -> String {
+  let args = field.args()
+    .map(|args| args.values().map(argument_signature).collect::<Vec<_>>().join(", "))
+    .unwrap_or_default();
+  if args.is_empty() {
+    format!("{:}: {:}", field.name(), output_type_ref(&**field.typ()))
+  } else {
+    format!("{:}({:}): {:}", field.name(), args, output_type_ref(&**field.typ()))
+  }
+}
+
+fn argument_signature(arg: &GraphQLArgument) -> String {
+  match arg.default_value() {
+    Some(value) => format!("{:}: {:} = {:}", arg.name(), input_type_ref(&**arg.typ()), value),
+    None => format!("{:}: {:}", arg.name(), input_type_ref(&**arg.typ())),
+  }
+}
+
+fn directive_signature(directive: &Directive) -> String {
+  let args = directive.args.iter().map(|&(arg, typ)| format!("{:}: {:}", arg, typ));
+  format!("@{:}({:})", directive.name, args.collect::<Vec<_>>().join(", "))
+}
+
+fn describe(description: Option<&str>, deprecation_reason: Option<&str>) -> Option<String> {
+  match (description, deprecation_reason) {
+    (Some(description), Some(reason)) => {
+      Some(format!("{:}\n\n_Deprecated: {:}_", description, reason))
+    }
+    (None, Some(reason)) => Some(format!("_Deprecated: {:}_", reason)),
+    (description, None) => description.map(|d| d.to_owned()),
+  }
+}
+
+fn type_keyword(typ: &SchemaType) -> &'static str {
+  match *typ {
+    SchemaType::Output(ref typ) => {
+      match typ.output_kind() {
+        OutputKind::Object(_) => "type",
+        OutputKind::Interface(_) => "interface",
+        OutputKind::Union(_) => "union",
+        OutputKind::Enum(_) => "enum",
+        _ => "scalar",
+      }
+    }
+    SchemaType::Input(ref typ) => {
+      match typ.input_kind() {
+        InputKind::InputObject(_) => "input",
+        InputKind::Enum(_) => "enum",
+        _ => "scalar",
+      }
+    }
+  }
+}
+
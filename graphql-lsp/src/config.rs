@@ -0,0 +1,98 @@
+//! Project configuration, read from the `.graphqlrc.json` file at the root of
+//! the workspace:
+//!
+//! ```json
+//! { "schema": "schema/*.graphql", "documents": ["src/**/*.graphql"] }
+//! ```
+//!
+//! Both are globs, or lists of globs, relative to the directory of the file.
+//! Without the file, every `.graphql` file is a document and there is no
+//! schema.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Deserializer};
+
+pub static CONFIG_FILE: &str = ".graphqlrc.json";
+pub static DEFAULT_DOCUMENTS: &str = "**/*.graphql";
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+  #[serde(default, deserialize_with = "globs")]
+  pub schema: Vec<String>,
+  #[serde(default = "default_documents", deserialize_with = "globs")]
+  pub documents: Vec<String>,
+}
+
+impl Default for Config {
+  fn default() -> Config {
+    Config {
+      schema: Vec::new(),
+      documents: default_documents(),
+    }
+  }
+}
+
+fn default_documents() -> Vec<String> {
+  vec![DEFAULT_DOCUMENTS.to_owned()]
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Globs {
+  One(String),
+  Many(Vec<String>),
+}
+
+fn globs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+  Ok(match Globs::deserialize(deserializer)? {
+    Globs::One(glob) => vec![glob],
+    Globs::Many(globs) => globs,
+  })
+}
+
+impl Config {
+  /// Reads the configuration of the project at `root`, the default one if
+  /// the project has no configuration file.
+  pub fn load(root: &Path) -> Result<Config, String> {
+    let path = root.join(CONFIG_FILE);
+    let text = match fs::read_to_string(&path) {
+      Ok(text) => text,
+      Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+      Err(e) => return Err(format!("Cannot read {:}: {:}", path.display(), e)),
+    };
+    serde_json::from_str(&text).map_err(|e| format!("Invalid {:}: {:}", path.display(), e))
+  }
+
+  pub fn schema_files(&self, root: &Path) -> Vec<PathBuf> {
+    expand(root, &self.schema)
+  }
+
+  /// Files of the documents, which the schema files are not part of.
+  pub fn document_files(&self, root: &Path) -> Vec<PathBuf> {
+    let schema_files = self.schema_files(root);
+    let mut files = expand(root, &self.documents);
+    files.retain(|file| !schema_files.contains(file));
+    files
+  }
+}
+
+// Files matching any of `patterns`, sorted. Patterns which are not valid
+// globs match nothing.
+fn expand(root: &Path, patterns: &[String]) -> Vec<PathBuf> {
+  let mut files = Vec::new();
+  for pattern in patterns {
+    let pattern = root.join(pattern);
+    let paths = match pattern.to_str().map(glob::glob) {
+      Some(Ok(paths)) => paths,
+      _ => continue,
+    };
+    files.extend(paths.filter_map(Result::ok).filter(|path| path.is_file()));
+  }
+  files.sort();
+  files.dedup();
+  files
+}
@@ -0,0 +1,22 @@
+//! Language server for GraphQL documents, speaking the Language Server
+//! Protocol over stdio in the `graphql-lsp` binary.
+//!
+//! It publishes the syntax and validation errors of documents, completes the
+//! fields, arguments and directives they can use, describes what is under
+//! the cursor, finds the definitions of fragments and types, and formats
+//! documents. The schema and documents of a project are found with its
+//! `.graphqlrc.json` configuration file, see `config`.
+
+extern crate glob;
+extern crate graphql_language;
+extern crate graphql_rs;
+#[macro_use]
+extern crate serde;
+#[macro_use]
+extern crate serde_json;
+
+pub mod analysis;
+pub mod config;
+pub mod protocol;
+pub mod rpc;
+pub mod server;
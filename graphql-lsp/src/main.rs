@@ -0,0 +1,16 @@
+extern crate graphql_lsp;
+
+use std::io;
+use std::process;
+
+fn main() {
+  let stdin = io::stdin();
+  let code = match graphql_lsp::server::run(stdin.lock(), io::stdout()) {
+    Ok(code) => code,
+    Err(e) => {
+      eprintln!("graphql-lsp: {}", e);
+      1
+    }
+  };
+  process::exit(code);
+}
@@ -0,0 +1,130 @@
+//! Types of the Language Server Protocol the server uses, and conversions
+//! between their positions and byte offsets.
+
+use std::path::{Path, PathBuf};
+
+use graphql_language::diagnostic::Span;
+
+/// Zero-based line, and character in UTF-16 code units, of a text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Position {
+  pub line: u32,
+  pub character: u32,
+}
+
+impl Position {
+  /// Position of the byte offset `offset` in `text`.
+  pub fn of(text: &str, offset: usize) -> Position {
+    let mut position = Position {
+      line: 0,
+      character: 0,
+    };
+    for c in text[..offset.min(text.len())].chars() {
+      if c == '\n' {
+        position.line += 1;
+        position.character = 0;
+      } else {
+        position.character += c.len_utf16() as u32;
+      }
+    }
+    position
+  }
+
+  /// Byte offset of the position in `text`, the end of the line for
+  /// characters past it and the end of the text for lines past it.
+  pub fn offset(self, text: &str) -> usize {
+    let mut line = 0;
+    let mut character = 0;
+    for (i, c) in text.char_indices() {
+      if line == self.line && (character >= self.character || c == '\n') {
+        return i;
+      }
+      if c == '\n' {
+        line += 1;
+      } else if line == self.line {
+        character += c.len_utf16() as u32;
+      }
+    }
+    text.len()
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Range {
+  pub start: Position,
+  pub end: Position,
+}
+
+impl Range {
+  pub fn of(text: &str, span: Span) -> Range {
+    Range {
+      start: Position::of(text, span.start),
+      end: Position::of(text, span.end),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Location {
+  pub uri: String,
+  pub range: Range,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionItem {
+  pub label: String,
+  pub kind: u32,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub detail: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub documentation: Option<String>,
+  #[serde(skip_serializing_if = "is_false")]
+  pub deprecated: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+  !value
+}
+
+pub static FIELD: u32 = 5;
+pub static VARIABLE: u32 = 6;
+pub static KEYWORD: u32 = 14;
+
+/// Path of a `file` URI.
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+  let path = uri.strip_prefix("file://")?;
+  let mut bytes = Vec::with_capacity(path.len());
+  let mut rest = path.as_bytes();
+  while let Some((&b, tail)) = rest.split_first() {
+    let escaped = if b == b'%' && tail.len() >= 2 {
+      std::str::from_utf8(&tail[..2]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok())
+    } else {
+      None
+    };
+    match escaped {
+      Some(escaped) => {
+        bytes.push(escaped);
+        rest = &tail[2..];
+      }
+      None => {
+        bytes.push(b);
+        rest = tail;
+      }
+    }
+  }
+  String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+pub fn path_to_uri(path: &Path) -> String {
+  let mut uri = String::from("file://");
+  for b in path.to_string_lossy().bytes() {
+    match b {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+        uri.push(b as char)
+      }
+      _ => uri.push_str(&format!("%{:02X}", b)),
+    }
+  }
+  uri
+}
@@ -0,0 +1,53 @@
+//! Messages of JSON-RPC, framed by the `Content-Length` header of the base
+//! protocol of the Language Server Protocol.
+
+use std::io::{self, BufRead, Read, Write};
+
+use serde_json::Value;
+
+/// Reads the next message, none at the end of the input.
+pub fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<Value>> {
+  match read_body(input)? {
+    Some(body) => serde_json::from_slice(&body).map(Some).map_err(io::Error::from),
+    None => Ok(None),
+  }
+}
+
+/// Reads the body of the next message, none at the end of the input. The body
+/// is read as it arrives, whatever length the header announces.
+pub fn read_body<R: BufRead>(input: &mut R) -> io::Result<Option<Vec<u8>>> {
+  let mut length = None;
+  loop {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+      return Ok(None);
+    }
+    let line = line.trim_end();
+    if line.is_empty() {
+      if length.is_some() {
+        break;
+      }
+      continue;
+    }
+    if let Some((name, value)) = line.split_once(':') {
+      if name.eq_ignore_ascii_case("Content-Length") {
+        let value = value.trim().parse();
+        length = Some(value.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?);
+      }
+    }
+  }
+
+  let length = length.unwrap_or(0);
+  let mut body = Vec::new();
+  input.take(length).read_to_end(&mut body)?;
+  if body.len() as u64 != length {
+    return Err(io::ErrorKind::UnexpectedEof.into());
+  }
+  Ok(Some(body))
+}
+
+pub fn write_message<W: Write>(output: &mut W, message: &Value) -> io::Result<()> {
+  let body = message.to_string();
+  write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+  output.flush()
+}
@@ -0,0 +1,512 @@
+//! Server answering the requests of an editor, one message at a time.
+//!
+//! The schema is built from the schema files of the project configuration,
+//! their text in the editor taking the place of their contents on disk while
+//! they are open. Other files are documents, checked against the schema.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use graphql_language::diagnostic::Span;
+use graphql_language::format::format;
+use graphql_language::sdl::{parse_schema, SchemaDocument, TypeDefinitionKind};
+use graphql_rs::schema::GraphQLSchema;
+use graphql_rs::sdl::build_schema;
+use serde::Deserialize;
+use serde_json::Value;
+
+use analysis::{self, Target};
+use config::Config;
+use protocol::*;
+use rpc;
+
+// Error codes of JSON-RPC and of the protocol.
+static PARSE_ERROR: i64 = -32700;
+static INVALID_REQUEST: i64 = -32600;
+static METHOD_NOT_FOUND: i64 = -32601;
+static INVALID_PARAMS: i64 = -32602;
+
+/// Serves the messages of `input` until the editor asks the server to exit,
+/// returning the exit code: 0 if the editor shut the server down first, 1
+/// otherwise.
+pub fn run<R: BufRead, W: Write>(mut input: R, mut output: W) -> io::Result<i32> {
+  let mut server = Server::new();
+  while let Some(body) = rpc::read_body(&mut input)? {
+    let message = match serde_json::from_slice(&body) {
+      Ok(message) => message,
+      Err(e) => {
+        let error = json!({ "code": PARSE_ERROR, "message": e.to_string() });
+        rpc::write_message(&mut output, &json!({ "jsonrpc": "2.0", "id": null, "error": error }))?;
+        continue;
+      }
+    };
+    for reply in server.handle(&message) {
+      rpc::write_message(&mut output, &reply)?;
+    }
+    if let Some(code) = server.exit_code() {
+      return Ok(code);
+    }
+  }
+  Ok(1)
+}
+
+struct SchemaFile {
+  uri: String,
+  text: String,
+  document: Option<SchemaDocument>,
+}
+
+pub struct Server {
+  root: Option<PathBuf>,
+  config: Config,
+  // Text of the open files by URI.
+  open: HashMap<String, String>,
+  schema_files: Vec<SchemaFile>,
+  schema: Option<Rc<GraphQLSchema>>,
+  // Problems of the schema files by URI, published with their syntax errors.
+  schema_errors: HashMap<String, Vec<(Span, String)>>,
+  shutdown: bool,
+  exit_code: Option<i32>,
+}
+
+impl Default for Server {
+  fn default() -> Server {
+    Server::new()
+  }
+}
+
+impl Server {
+  pub fn new() -> Server {
+    Server {
+      root: None,
+      config: Config::default(),
+      open: HashMap::new(),
+      schema_files: Vec::new(),
+      schema: None,
+      schema_errors: HashMap::new(),
+      shutdown: false,
+      exit_code: None,
+    }
+  }
+
+  /// Code to exit with once the editor sent `exit`.
+  pub fn exit_code(&self) -> Option<i32> {
+    self.exit_code
+  }
+
+  /// Handles a request or notification, returning the messages to send back:
+  /// the response to a request, and notifications.
+  pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+    let method = match message["method"].as_str() {
+      Some(method) => method,
+      // Responses to requests of the server, which sends none.
+      None => return Vec::new(),
+    };
+    let params = &message["params"];
+    let id = match message.get("id") {
+      Some(id) => id.clone(),
+      None => return self.notification(method, params),
+    };
+
+    let mut notifications = Vec::new();
+    let result = if self.shutdown && method != "shutdown" {
+      Err((INVALID_REQUEST, "The server is shut down.".to_owned()))
+    } else {
+      self.request(method, params, &mut notifications)
+    };
+    let response = match result {
+      Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+      Err((code, message)) => {
+        json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+      }
+    };
+    let mut replies = vec![response];
+    replies.extend(notifications);
+    replies
+  }
+
+  fn request(&mut self,
+             method: &str,
+             params: &Value,
+             notifications: &mut Vec<Value>)
+             -> Result<Value, (i64, String)> {
+    match method {
+      "initialize" => {
+        self.initialize(params, notifications);
+        Ok(json!({
+          "capabilities": {
+            "textDocumentSync": 1,
+            "completionProvider": { "triggerCharacters": ["@", "(", "{"] },
+            "hoverProvider": true,
+            "definitionProvider": true,
+            "documentFormattingProvider": true,
+          },
+          "serverInfo": { "name": "graphql-lsp", "version": env!("CARGO_PKG_VERSION") },
+        }))
+      }
+      "shutdown" => {
+        self.shutdown = true;
+        Ok(Value::Null)
+      }
+      "textDocument/completion" => {
+        let (uri, text, offset) = self.position_params(params)?;
+        if self.is_schema_file(&uri) {
+          return Ok(json!([]));
+        }
+        Ok(match self.schema {
+          Some(ref schema) => json!(analysis::completions(schema, &text, offset)),
+          None => json!([]),
+        })
+      }
+      "textDocument/hover" => {
+        let (uri, text, offset) = self.position_params(params)?;
+        let hover = match self.schema {
+          Some(ref schema) if !self.is_schema_file(&uri) => {
+            analysis::hover(schema, &text, offset)
+          }
+          _ => None,
+        };
+        Ok(match hover {
+          Some((span, contents)) => {
+            json!({
+              "contents": { "kind": "markdown", "value": contents },
+              "range": Range::of(&text, span),
+            })
+          }
+          None => Value::Null,
+        })
+      }
+      "textDocument/definition" => {
+        let (uri, text, offset) = self.position_params(params)?;
+        if self.is_schema_file(&uri) {
+          return Ok(Value::Null);
+        }
+        let location = match analysis::definition_target(&text, offset) {
+          Some(Target::Fragment(name)) => self.fragment_location(&uri, &name),
+          Some(Target::Type(name)) => self.type_location(&name),
+          None => None,
+        };
+        Ok(location.map_or(Value::Null, |location| json!(location)))
+      }
+      "textDocument/formatting" => {
+        let uri = text_document_uri(params)?;
+        let text = self.text(&uri).ok_or_else(|| unknown_document(&uri))?;
+        Ok(match format(&text) {
+          Ok(formatted) if formatted != text => {
+            json!([{
+              "range": Range::of(&text, Span::new(0, text.len())),
+              "newText": formatted,
+            }])
+          }
+          Ok(_) => json!([]),
+          // Documents with syntax errors are left as they are.
+          Err(_) => Value::Null,
+        })
+      }
+      _ => Err((METHOD_NOT_FOUND, format!("Unknown method {:}.", method))),
+    }
+  }
+
+  fn notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+    match method {
+      "initialized" | "workspace/didChangeWatchedFiles" => self.load_schema(),
+      "exit" => {
+        self.exit_code = Some(if self.shutdown { 0 } else { 1 });
+        Vec::new()
+      }
+      "textDocument/didOpen" => {
+        let document = &params["textDocument"];
+        match (document["uri"].as_str(), document["text"].as_str()) {
+          (Some(uri), Some(text)) => self.update(uri, Some(text.to_owned())),
+          _ => Vec::new(),
+        }
+      }
+      "textDocument/didChange" => {
+        // Changes are full texts, the sync kind the server asks for.
+        let uri = params["textDocument"]["uri"].as_str();
+        let text = params["contentChanges"].as_array().and_then(|c| c.last());
+        match (uri, text.and_then(|change| change["text"].as_str())) {
+          (Some(uri), Some(text)) => self.update(uri, Some(text.to_owned())),
+          _ => Vec::new(),
+        }
+      }
+      "textDocument/didClose" => {
+        match params["textDocument"]["uri"].as_str() {
+          Some(uri) => self.update(uri, None),
+          None => Vec::new(),
+        }
+      }
+      _ => Vec::new(),
+    }
+  }
+
+  fn initialize(&mut self, params: &Value, notifications: &mut Vec<Value>) {
+    let root = match params["rootUri"].as_str() {
+      Some(uri) => uri_to_path(uri),
+      None => params["rootPath"].as_str().map(PathBuf::from),
+    };
+    if let Some(ref root) = root {
+      match Config::load(root) {
+        Ok(config) => self.config = config,
+        Err(message) => {
+          notifications.push(json!({
+            "jsonrpc": "2.0",
+            "method": "window/showMessage",
+            "params": { "type": 1, "message": message },
+          }))
+        }
+      }
+    }
+    self.root = root;
+  }
+
+  // Keeps the text of an open file, or forgets it once closed, and
+  // publishes the diagnostics which change with it.
+  fn update(&mut self, uri: &str, text: Option<String>) -> Vec<Value> {
+    let closed = text.is_none();
+    match text {
+      Some(text) => self.open.insert(uri.to_owned(), text),
+      None => self.open.remove(uri),
+    };
+    if self.is_schema_file(uri) {
+      return self.load_schema();
+    }
+    if closed {
+      return vec![publish_diagnostics(uri, "", Vec::new())];
+    }
+    vec![self.document_diagnostics(uri)]
+  }
+
+  fn is_schema_file(&self, uri: &str) -> bool {
+    self.schema_files.iter().any(|file| file.uri == uri)
+  }
+
+  fn text(&self, uri: &str) -> Option<String> {
+    match self.open.get(uri) {
+      Some(text) => Some(text.clone()),
+      None => uri_to_path(uri).and_then(|path| fs::read_to_string(path).ok()),
+    }
+  }
+
+  // Builds the schema again, then publishes the diagnostics of the schema
+  // files and of the open documents.
+  fn load_schema(&mut self) -> Vec<Value> {
+    let paths = match self.root {
+      Some(ref root) => self.config.schema_files(root),
+      None => Vec::new(),
+    };
+    self.schema_files = paths.iter()
+      .map(|path| {
+        let uri = path_to_uri(path);
+        let text = self.text(&uri).unwrap_or_default();
+        SchemaFile {
+          uri,
+          text,
+          document: None,
+        }
+      })
+      .collect();
+
+    self.schema_errors.clear();
+    let mut merged = SchemaDocument::default();
+    for file in &mut self.schema_files {
+      match parse_schema(&file.text) {
+        Ok(document) => {
+          merged.schema = merged.schema.take().or_else(|| document.schema.clone());
+          merged.types.extend(document.types.iter().cloned());
+          merged.directives.extend(document.directives.iter().cloned());
+          file.document = Some(document);
+        }
+        Err(e) => {
+          self.schema_errors.entry(file.uri.clone()).or_default().push((e.span, e.message));
+        }
+      }
+    }
+    self.schema = None;
+    if self.schema_errors.is_empty() && !self.schema_files.is_empty() {
+      match build_schema(&merged) {
+        Ok(schema) => self.schema = Some(schema),
+        // Errors are shown on the definition they are about, or else at the
+        // start of the first file.
+        Err(errors) => {
+          for error in errors {
+            let (uri, span) = error.coordinate()
+              .and_then(|coordinate| self.coordinate_location(coordinate))
+              .unwrap_or_else(|| (self.schema_files[0].uri.clone(), Span::new(0, 0)));
+            self.schema_errors.entry(uri).or_default().push((span, error.message().to_owned()));
+          }
+        }
+      }
+    }
+
+    let mut notifications = Vec::new();
+    for file in &self.schema_files {
+      let errors = self.schema_errors.get(&file.uri).cloned().unwrap_or_default();
+      notifications.push(publish_diagnostics(&file.uri, &file.text, errors));
+    }
+    let mut documents =
+      self.open.keys().filter(|uri| !self.is_schema_file(uri)).collect::<Vec<_>>();
+    documents.sort();
+    for uri in documents {
+      notifications.push(self.document_diagnostics(uri));
+    }
+    notifications
+  }
+
+  fn document_diagnostics(&self, uri: &str) -> Value {
+    let text = self.open.get(uri).map_or("", |text| text.as_str());
+    let fragments = self.project_fragments(uri)
+      .into_iter()
+      .map(|(name, _)| name)
+      .collect::<HashSet<_>>();
+    let diagnostics = analysis::diagnostics(self.schema.as_deref(), text, &fragments);
+    publish_diagnostics(uri, text, diagnostics)
+  }
+
+  // Fragments defined in the documents of the project other than `uri`.
+  fn project_fragments(&self, uri: &str) -> Vec<(String, Location)> {
+    let mut uris = match self.root {
+      Some(ref root) => {
+        self.config.document_files(root).iter().map(|path| path_to_uri(path)).collect()
+      }
+      None => Vec::new(),
+    };
+    uris.extend(self.open.keys().filter(|uri| !self.is_schema_file(uri)).cloned());
+    uris.sort();
+    uris.dedup();
+
+    let mut fragments = Vec::new();
+    for other in uris.into_iter().filter(|other| other != uri) {
+      if let Some(text) = self.text(&other) {
+        for (name, span) in analysis::fragment_definitions(&text) {
+          fragments.push((name,
+                          Location {
+                            uri: other.clone(),
+                            range: Range::of(&text, span),
+                          }));
+        }
+      }
+    }
+    fragments
+  }
+
+  // Definition of the fragment `name` in the document `uri`, or else in
+  // another document of the project.
+  fn fragment_location(&self, uri: &str, name: &str) -> Option<Location> {
+    let text = self.text(uri)?;
+    let fragments = analysis::fragment_definitions(&text);
+    if let Some(&(_, span)) = fragments.iter().find(|f| f.0 == name) {
+      return Some(Location {
+        uri: uri.to_owned(),
+        range: Range::of(&text, span),
+      });
+    }
+    self.project_fragments(uri).into_iter().find(|f| f.0 == name).map(|f| f.1)
+  }
+
+  fn type_location(&self, name: &str) -> Option<Location> {
+    self.schema_files.iter().find_map(|file| {
+      let definition = file.document.as_ref()?.get_type(name)?;
+      Some(Location {
+        uri: file.uri.clone(),
+        range: Range::of(&file.text, definition.span),
+      })
+    })
+  }
+
+  // File and span of the definition at `coordinate`, the last one when it is
+  // defined more than once.
+  fn coordinate_location(&self, coordinate: &str) -> Option<(String, Span)> {
+    self.schema_files.iter().rev().find_map(|file| {
+      let span = coordinate_span(file.document.as_ref()?, coordinate)?;
+      Some((file.uri.clone(), span))
+    })
+  }
+
+  fn position_params(&self, params: &Value) -> Result<(String, String, usize), (i64, String)> {
+    let uri = text_document_uri(params)?;
+    let position = Position::deserialize(&params["position"])
+      .map_err(|e| (INVALID_PARAMS, format!("Invalid position: {:}", e)))?;
+    let text = self.text(&uri).ok_or_else(|| unknown_document(&uri))?;
+    let offset = position.offset(&text);
+    Ok((uri, text, offset))
+  }
+}
+
+// Span of the definition a schema coordinate such as `Hero.friends(first:)`
+// or `@auth(role:)` names in `document`.
+fn coordinate_span(document: &SchemaDocument, coordinate: &str) -> Option<Span> {
+  let (path, argument) = match coordinate.find('(') {
+    Some(i) => (&coordinate[..i], Some(coordinate[i + 1..].trim_end_matches(":)"))),
+    None => (coordinate, None),
+  };
+  if let Some(name) = path.strip_prefix('@') {
+    let directive = document.directives.iter().rev().find(|d| d.name == name)?;
+    return match argument {
+      Some(argument) => {
+        directive.arguments.iter().rev().find(|a| a.name == argument).map(|a| a.span)
+      }
+      None => Some(directive.span),
+    };
+  }
+
+  let (type_name, member) = match path.find('.') {
+    Some(i) => (&path[..i], &path[i + 1..]),
+    None => (path, ""),
+  };
+  let definition = document.types.iter().rev().find(|t| t.name == type_name)?;
+  if member.is_empty() {
+    return Some(definition.span);
+  }
+  match definition.kind {
+    TypeDefinitionKind::Object { ref fields, .. } |
+    TypeDefinitionKind::Interface { ref fields, .. } => {
+      let field = fields.iter().rev().find(|f| f.name == member)?;
+      match argument {
+        Some(argument) => {
+          field.arguments.iter().rev().find(|a| a.name == argument).map(|a| a.span)
+        }
+        None => Some(field.span),
+      }
+    }
+    TypeDefinitionKind::Enum { ref values } => {
+      values.iter().rev().find(|v| v.name == member).map(|v| v.span)
+    }
+    TypeDefinitionKind::InputObject { ref fields } => {
+      fields.iter().rev().find(|f| f.name == member).map(|f| f.span)
+    }
+    _ => Some(definition.span),
+  }
+}
+
+fn text_document_uri(params: &Value) -> Result<String, (i64, String)> {
+  params["textDocument"]["uri"]
+    .as_str()
+    .map(|uri| uri.to_owned())
+    .ok_or_else(|| (INVALID_PARAMS, "Missing textDocument.uri.".to_owned()))
+}
+
+fn unknown_document(uri: &str) -> (i64, String) {
+  (INVALID_PARAMS, format!("Cannot read {:}.", uri))
+}
+
+fn publish_diagnostics(uri: &str, text: &str, diagnostics: Vec<(Span, String)>) -> Value {
+  let diagnostics = diagnostics.into_iter()
+    .map(|(span, message)| {
+      json!({
+        "range": Range::of(text, span),
+        "severity": 1,
+        "source": "graphql",
+        "message": message,
+      })
+    })
+    .collect::<Vec<_>>();
+  json!({
+    "jsonrpc": "2.0",
+    "method": "textDocument/publishDiagnostics",
+    "params": { "uri": uri, "diagnostics": diagnostics },
+  })
+}
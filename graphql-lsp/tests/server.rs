@@ -0,0 +1,276 @@
+extern crate graphql_lsp;
+#[macro_use]
+extern crate serde_json;
+extern crate tempfile;
+
+use std::fs;
+use std::io::{BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use graphql_lsp::protocol::path_to_uri;
+use graphql_lsp::rpc::{read_message, write_message};
+use serde_json::Value;
+
+static SCHEMA: &str = r#"
+"A character of the saga"
+interface Character {
+  name: String!
+}
+
+type Hero implements Character {
+  name: String!
+  "Friends, closest first"
+  friends(first: Int = 10, after: String): [Hero!]
+  age: Int @deprecated(reason: "Use birthYear")
+}
+
+type Query {
+  hero(id: ID!): Hero
+}
+"#;
+
+// Server process driven over its standard input and output.
+struct Client {
+  child: Child,
+  stdin: ChildStdin,
+  stdout: BufReader<ChildStdout>,
+  next_id: u64,
+}
+
+impl Client {
+  fn start() -> Client {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_graphql-lsp"))
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .spawn()
+      .unwrap();
+    let stdin = child.stdin.take().unwrap();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    Client {
+      child,
+      stdin,
+      stdout,
+      next_id: 1,
+    }
+  }
+
+  fn notify(&mut self, method: &str, params: Value) {
+    let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+    write_message(&mut self.stdin, &message).unwrap();
+  }
+
+  // Sends a request and returns its result, along with the notifications
+  // sent before the response.
+  fn request(&mut self, method: &str, params: Value) -> Value {
+    let id = self.next_id;
+    self.next_id += 1;
+    let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+    write_message(&mut self.stdin, &message).unwrap();
+    loop {
+      let message = self.receive();
+      if message["id"] == json!(id) {
+        return message["result"].clone();
+      }
+    }
+  }
+
+  fn receive(&mut self) -> Value {
+    read_message(&mut self.stdout).unwrap().expect("the server closed its output")
+  }
+
+  // Diagnostics published next, as (line, character, message).
+  fn diagnostics(&mut self, uri: &str) -> Vec<(u64, u64, String)> {
+    let message = self.receive();
+    assert_eq!("textDocument/publishDiagnostics", message["method"]);
+    assert_eq!(uri, message["params"]["uri"]);
+    message["params"]["diagnostics"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|d| {
+        (d["range"]["start"]["line"].as_u64().unwrap(),
+         d["range"]["start"]["character"].as_u64().unwrap(),
+         d["message"].as_str().unwrap().to_owned())
+      })
+      .collect()
+  }
+}
+
+fn position(uri: &str, line: u64, character: u64) -> Value {
+  json!({ "textDocument": { "uri": uri }, "position": { "line": line, "character": character } })
+}
+
+fn labels(items: &Value) -> Vec<&str> {
+  items.as_array().unwrap().iter().map(|item| item["label"].as_str().unwrap()).collect()
+}
+
+fn initialize(client: &mut Client, root: &Path) -> Value {
+  let result = client.request("initialize",
+                              json!({ "processId": null, "rootUri": path_to_uri(root) }));
+  client.notify("initialized", json!({}));
+  result
+}
+
+#[test]
+fn serves_a_project() {
+  let root = tempfile::tempdir().unwrap();
+  let root = root.path();
+  fs::write(root.join(".graphqlrc.json"),
+            r#"{ "schema": "schema.graphql", "documents": "src/*.graphql" }"#)
+    .unwrap();
+  fs::write(root.join("schema.graphql"), SCHEMA).unwrap();
+  fs::create_dir(root.join("src")).unwrap();
+  fs::write(root.join("src/fragments.graphql"),
+            "fragment HeroName on Hero {\n  name\n}\n")
+    .unwrap();
+  let schema_uri = path_to_uri(&root.join("schema.graphql"));
+  let fragments_uri = path_to_uri(&root.join("src/fragments.graphql"));
+  let uri = path_to_uri(&root.join("src/query.graphql"));
+
+  let mut client = Client::start();
+  let result = initialize(&mut client, root);
+  assert_eq!(json!(true), result["capabilities"]["hoverProvider"]);
+  assert_eq!(Vec::<(u64, u64, String)>::new(), client.diagnostics(&schema_uri));
+
+  let text = "query {\n  hero(id: 1, last: 2) {\n    ...HeroName\n    age { years }\n  }\n}\n";
+  client.notify("textDocument/didOpen",
+                json!({ "textDocument": { "uri": uri, "languageId": "graphql", "version": 1,
+                                          "text": text } }));
+  assert_eq!(vec![(1, 14, "Unknown argument \"last\" on field \"Query.hero\".".to_owned()),
+                  (3, 4,
+                   "Field \"age\" must not have a selection since type \"Int\" has no \
+                    subfields."
+                     .to_owned())],
+             client.diagnostics(&uri));
+
+  let text = "query {\n  hero(id: 1) {\n    \n  }\n}\n";
+  client.notify("textDocument/didChange",
+                json!({ "textDocument": { "uri": uri, "version": 2 },
+                        "contentChanges": [{ "text": text }] }));
+  assert_eq!(vec![(3, 2, "Expected a selection, found \"}\".".to_owned())],
+             client.diagnostics(&uri));
+  let items = client.request("textDocument/completion", position(&uri, 2, 4));
+  assert_eq!(vec!["name", "friends", "age", "__typename"], labels(&items));
+  assert_eq!(json!({ "label": "age", "kind": 5, "detail": "Int", "deprecated": true }),
+             items[2]);
+
+  let text = "query {\n  hero(id: 1) {\n    friends() @\n  }\n}\n";
+  client.notify("textDocument/didChange",
+                json!({ "textDocument": { "uri": uri, "version": 3 },
+                        "contentChanges": [{ "text": text }] }));
+  client.diagnostics(&uri);
+  let items = client.request("textDocument/completion", position(&uri, 2, 12));
+  assert_eq!(vec!["first", "after"], labels(&items));
+  assert_eq!(json!("Int"), items[0]["detail"]);
+  let items = client.request("textDocument/completion", position(&uri, 2, 15));
+  assert_eq!(vec!["skip", "include", "defer", "stream"], labels(&items));
+
+  let text = "query {\n  hero(id: 1) {\n    ...HeroName\n    \
+              friends { ... on Character { name } }\n  }\n}\n";
+  client.notify("textDocument/didChange",
+                json!({ "textDocument": { "uri": uri, "version": 4 },
+                        "contentChanges": [{ "text": text }] }));
+  assert_eq!(Vec::<(u64, u64, String)>::new(), client.diagnostics(&uri));
+
+  let hover = client.request("textDocument/hover", position(&uri, 3, 6));
+  assert_eq!(json!({
+               "contents": {
+                 "kind": "markdown",
+                 "value": "```graphql\nHero.friends(first: Int = 10, after: String): [Hero!]\n```\
+                           \n\nFriends, closest first"
+               },
+               "range": { "start": { "line": 3, "character": 4 },
+                          "end": { "line": 3, "character": 11 } }
+             }),
+             hover);
+  let hover = client.request("textDocument/hover", position(&uri, 3, 25));
+  assert_eq!(json!("```graphql\ninterface Character\n```\n\nA character of the saga"),
+             hover["contents"]["value"]);
+
+  let definition = client.request("textDocument/definition", position(&uri, 2, 10));
+  assert_eq!(json!({ "uri": fragments_uri,
+                     "range": { "start": { "line": 0, "character": 9 },
+                                "end": { "line": 0, "character": 17 } } }),
+             definition);
+  let definition = client.request("textDocument/definition", position(&uri, 3, 25));
+  assert_eq!(json!({ "uri": schema_uri,
+                     "range": { "start": { "line": 2, "character": 10 },
+                                "end": { "line": 2, "character": 19 } } }),
+             definition);
+
+  let text = "{hero(id:1){name,...HeroName}}";
+  client.notify("textDocument/didChange",
+                json!({ "textDocument": { "uri": uri, "version": 5 },
+                        "contentChanges": [{ "text": text }] }));
+  client.diagnostics(&uri);
+  let edits = client.request("textDocument/formatting",
+                             json!({ "textDocument": { "uri": uri },
+                                     "options": { "tabSize": 2, "insertSpaces": true } }));
+  assert_eq!(json!([{
+               "range": { "start": { "line": 0, "character": 0 },
+                          "end": { "line": 0, "character": 30 } },
+               "newText": "{\n  hero(id: 1) {\n    name\n    ...HeroName\n  }\n}\n"
+             }]),
+             edits);
+
+  // Messages which are not JSON are answered with an error, and the server
+  // carries on.
+  client.stdin.write_all(b"Content-Length: 5\r\n\r\n{oops").unwrap();
+  let message = client.receive();
+  assert_eq!(json!(-32700), message["error"]["code"]);
+  assert_eq!(Value::Null, message["id"]);
+
+  assert_eq!(Value::Null, client.request("shutdown", Value::Null));
+  client.notify("exit", Value::Null);
+  assert_eq!(Some(0), client.child.wait().unwrap().code());
+}
+
+#[test]
+fn reports_schema_errors() {
+  let root = tempfile::tempdir().unwrap();
+  let root = root.path();
+  fs::write(root.join(".graphqlrc.json"), r#"{ "schema": ["*.graphqls"] }"#).unwrap();
+  fs::write(root.join("a.graphqls"), "type Query {\n  hero: Hero\n}\n").unwrap();
+  fs::write(root.join("b.graphqls"), "type Hero {\n  name: String\n}\n").unwrap();
+  let a = path_to_uri(&root.join("a.graphqls"));
+  let b = path_to_uri(&root.join("b.graphqls"));
+
+  let mut client = Client::start();
+  initialize(&mut client, root);
+  assert_eq!(Vec::<(u64, u64, String)>::new(), client.diagnostics(&a));
+  assert_eq!(Vec::<(u64, u64, String)>::new(), client.diagnostics(&b));
+
+  // The open text of a schema file takes the place of the file.
+  client.notify("textDocument/didOpen",
+                json!({ "textDocument": { "uri": b, "languageId": "graphql", "version": 1,
+                                          "text": "type Hero {\n  name: Strin\n}\n" } }));
+  assert_eq!(Vec::<(u64, u64, String)>::new(), client.diagnostics(&a));
+  assert_eq!(vec![(1, 2, "Field Hero.name references unknown type Strin.".to_owned())],
+             client.diagnostics(&b));
+  client.notify("textDocument/didChange",
+                json!({ "textDocument": { "uri": b, "version": 2 },
+                        "contentChanges": [{ "text": "type Hero {\n  name String\n}\n" }] }));
+  assert_eq!(Vec::<(u64, u64, String)>::new(), client.diagnostics(&a));
+  assert_eq!(vec![(1, 7, "Expected \":\", found Name \"String\".".to_owned())],
+             client.diagnostics(&b));
+
+  client.notify("textDocument/didChange",
+                json!({ "textDocument": { "uri": b, "version": 3 },
+                        "contentChanges": [{ "text": "type Hero{name:String}" }] }));
+  assert_eq!(Vec::<(u64, u64, String)>::new(), client.diagnostics(&a));
+  assert_eq!(Vec::<(u64, u64, String)>::new(), client.diagnostics(&b));
+  let edits = client.request("textDocument/formatting",
+                             json!({ "textDocument": { "uri": b },
+                                     "options": { "tabSize": 2, "insertSpaces": true } }));
+  assert_eq!(json!([{
+               "range": { "start": { "line": 0, "character": 0 },
+                          "end": { "line": 0, "character": 22 } },
+               "newText": "type Hero {\n  name: String\n}\n"
+             }]),
+             edits);
+
+  // Exiting without a shutdown is an error.
+  client.notify("exit", Value::Null);
+  assert_eq!(Some(1), client.child.wait().unwrap().code());
+}
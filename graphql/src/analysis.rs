@@ -0,0 +1,323 @@
+use std::cmp;
+use std::collections::HashMap;
+
+use graphql_language::ast;
+
+use error::GraphQLError;
+use execution::{coerce_variables, select_operation};
+use schema::{named_output_type, GraphQLSchema, SchemaType};
+use types::{GraphQLType, OutputKind};
+use validation::{field_type, is_list};
+use value::{Arguments, Value};
+
+/// Size of an operation, as measured by `QueryLimits::analyze`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryAnalysis {
+  /// Maximum nesting of fields, fragments not counting as a level.
+  pub depth: usize,
+  pub aliases: usize,
+  pub cost: u64,
+}
+
+/// Limits on the size of operations, checked against the document of a
+/// request before it is executed.
+///
+/// The cost of a field is its own cost plus the cost of its selections, times
+/// the size of the list for list fields. The size is taken from the first of
+/// the size arguments the field is given, `first`, `last` and `limit` by
+/// default, then from the first of them with a default in the schema.
+pub struct QueryLimits {
+  max_depth: Option<usize>,
+  max_aliases: Option<usize>,
+  max_cost: Option<u64>,
+  default_field_cost: u64,
+  field_costs: HashMap<(String, String), u64>,
+  list_size_arguments: Vec<String>,
+  default_list_size: u64,
+}
+
+impl Default for QueryLimits {
+  fn default() -> QueryLimits {
+    QueryLimits::new()
+  }
+}
+
+impl QueryLimits {
+  /// No limits, with a cost of 1 per field and lists of 1 item unless sized
+  /// by an argument.
+  pub fn new() -> QueryLimits {
+    QueryLimits {
+      max_depth: None,
+      max_aliases: None,
+      max_cost: None,
+      default_field_cost: 1,
+      field_costs: HashMap::new(),
+      list_size_arguments: vec!["first".to_owned(), "last".to_owned(), "limit".to_owned()],
+      default_list_size: 1,
+    }
+  }
+
+  pub fn max_depth(mut self, max_depth: usize) -> QueryLimits {
+    self.max_depth = Some(max_depth);
+    self
+  }
+
+  pub fn max_aliases(mut self, max_aliases: usize) -> QueryLimits {
+    self.max_aliases = Some(max_aliases);
+    self
+  }
+
+  pub fn max_cost(mut self, max_cost: u64) -> QueryLimits {
+    self.max_cost = Some(max_cost);
+    self
+  }
+
+  pub fn default_field_cost(mut self, cost: u64) -> QueryLimits {
+    self.default_field_cost = cost;
+    self
+  }
+
+  /// Cost of the field `field` of the type named `type_name`.
+  pub fn field_cost(mut self, type_name: &str, field: &str, cost: u64) -> QueryLimits {
+    self.field_costs.insert((type_name.to_owned(), field.to_owned()), cost);
+    self
+  }
+
+  /// Arguments giving the size of the list returned by a field.
+  pub fn list_size_arguments(mut self, names: &[&str]) -> QueryLimits {
+    self.list_size_arguments = names.iter().map(|&name| name.to_owned()).collect();
+    self
+  }
+
+  /// Size of the lists of fields without a size argument.
+  pub fn default_list_size(mut self, size: u64) -> QueryLimits {
+    self.default_list_size = size;
+    self
+  }
+
+  /// Measures the operation of `document` selected by `operation_name`, with
+  /// `variables` coerced as they are for execution, defaults included. Fields
+  /// unknown to the schema are counted with the default cost.
+  pub fn analyze(&self,
+                 schema: &GraphQLSchema,
+                 document: &ast::Document,
+                 operation_name: Option<&str>,
+                 variables: &Arguments)
+                 -> Result<QueryAnalysis, GraphQLError> {
+    let mut fragments = HashMap::new();
+    let mut operations = Vec::new();
+    for definition in &document.definitions {
+      match *definition {
+        ast::Definition::Operation(ref operation) => operations.push(operation),
+        ast::Definition::Fragment(ref fragment) => {
+          fragments.insert(fragment.name.value, fragment);
+        }
+      }
+    }
+    let operation = select_operation(&operations, operation_name)?;
+    let variables = coerce_variables(schema, operation, variables)?;
+    let root = match operation.operation {
+      ast::OperationType::Query => Some(schema.query_type()),
+      ast::OperationType::Mutation => schema.mutation_type(),
+      ast::OperationType::Subscription => schema.subscription_type(),
+    };
+
+    let mut analyzer = Analyzer {
+      schema,
+      limits: self,
+      fragments,
+      variables,
+      aliases: 0,
+      spreads: Vec::new(),
+      measured: HashMap::new(),
+    };
+    let (depth, cost) = analyzer.selection_set(root.map(|r| r.name()), &operation.selection_set);
+    Ok(QueryAnalysis {
+      depth,
+      aliases: analyzer.aliases,
+      cost,
+    })
+  }
+
+  /// Measures the operation like `analyze`, and rejects it when it exceeds a
+  /// limit. The error has a `code` extension, along with the `maximum` and the
+  /// `actual` value.
+  pub fn check(&self,
+               schema: &GraphQLSchema,
+               document: &ast::Document,
+               operation_name: Option<&str>,
+               variables: &Arguments)
+               -> Result<QueryAnalysis, GraphQLError> {
+    let analysis = self.analyze(schema, document, operation_name, variables)?;
+    let exceeded = |code: &str, message: String, maximum: u64, actual: u64| {
+      Err(GraphQLError::new(&message)
+        .extension("code", Value::String(code.to_owned()))
+        .extension("maximum", Value::Int(maximum as i64))
+        .extension("actual", Value::Int(actual as i64)))
+    };
+
+    if let Some(max_depth) = self.max_depth.filter(|&max| analysis.depth > max) {
+      return exceeded("MAX_DEPTH_EXCEEDED",
+                      format!("Query depth of {:} exceeds the maximum depth of {:}.",
+                              analysis.depth,
+                              max_depth),
+                      max_depth as u64,
+                      analysis.depth as u64);
+    }
+    if let Some(max_aliases) = self.max_aliases.filter(|&max| analysis.aliases > max) {
+      return exceeded("MAX_ALIASES_EXCEEDED",
+                      format!("Query uses {:} aliases, exceeding the maximum of {:}.",
+                              analysis.aliases,
+                              max_aliases),
+                      max_aliases as u64,
+                      analysis.aliases as u64);
+    }
+    if let Some(max_cost) = self.max_cost.filter(|&max| analysis.cost > max) {
+      return exceeded("MAX_COST_EXCEEDED",
+                      format!("Query cost of {:} exceeds the maximum cost of {:}.",
+                              analysis.cost,
+                              max_cost),
+                      max_cost,
+                      analysis.cost);
+    }
+    Ok(analysis)
+  }
+}
+
+struct Analyzer<'a> {
+  schema: &'a GraphQLSchema,
+  limits: &'a QueryLimits,
+  fragments: HashMap<&'a str, &'a ast::FragmentDefinition<'a>>,
+  variables: Arguments,
+  aliases: usize,
+  // Fragments being spread, which are not spread again within themselves.
+  spreads: Vec<&'a str>,
+  // Depth, cost and aliases of the fragments measured already, which are the
+  // same wherever they are spread since they are measured on their type
+  // condition.
+  measured: HashMap<&'a str, (usize, u64, usize)>,
+}
+
+impl<'a> Analyzer<'a> {
+  // Depth and cost of a selection set on values of the named type `parent`.
+  fn selection_set(&mut self,
+                   parent: Option<&str>,
+                   selection_set: &'a ast::SelectionSet<'a>)
+                   -> (usize, u64) {
+    let mut depth = 0;
+    let mut cost: u64 = 0;
+    for selection in &selection_set.selections {
+      let (selection_depth, selection_cost) = match *selection {
+        ast::Selection::Field(ref field) => self.field(parent, field),
+        ast::Selection::InlineFragment(ref fragment) => {
+          let typ = fragment.type_condition.as_ref().map(|t| t.name.value).or(parent);
+          self.selection_set(typ, &fragment.selection_set)
+        }
+        ast::Selection::FragmentSpread(ref spread) => {
+          let name = spread.name.value;
+          match self.fragments.get(name).cloned() {
+            Some(fragment) if !self.spreads.contains(&name) => self.fragment(fragment),
+            _ => (0, 0),
+          }
+        }
+      };
+      depth = cmp::max(depth, selection_depth);
+      cost = cost.saturating_add(selection_cost);
+    }
+    (depth, cost)
+  }
+
+  // Fragments are measured once, however many times they are spread.
+  fn fragment(&mut self, fragment: &'a ast::FragmentDefinition<'a>) -> (usize, u64) {
+    let name = fragment.name.value;
+    if let Some(&(depth, cost, aliases)) = self.measured.get(name) {
+      self.aliases = self.aliases.saturating_add(aliases);
+      return (depth, cost);
+    }
+    let aliases = self.aliases;
+    self.spreads.push(name);
+    let (depth, cost) = self.selection_set(Some(fragment.type_condition.name.value),
+                                           &fragment.selection_set);
+    self.spreads.pop();
+    self.measured.insert(name, (depth, cost, self.aliases - aliases));
+    (depth, cost)
+  }
+
+  fn field(&mut self, parent: Option<&str>, field: &'a ast::Field<'a>) -> (usize, u64) {
+    if field.alias.is_some() {
+      self.aliases = self.aliases.saturating_add(1);
+    }
+    let name = field.name.value;
+    if name == "__typename" {
+      return (1, 0);
+    }
+
+    let typ = parent.and_then(|parent| field_type(self.schema, parent, name));
+    let (depth, cost) = match field.selection_set {
+      Some(ref selection_set) => {
+        let named = typ.as_ref().map(|t| named_output_type(t).name().to_owned());
+        self.selection_set(named.as_ref().map(|n| n.as_ref()), selection_set)
+      }
+      None => (0, 0),
+    };
+    let key = (parent.unwrap_or("").to_owned(), name.to_owned());
+    let own_cost = self.limits.field_costs.get(&key).cloned();
+    let own_cost = own_cost.unwrap_or(self.limits.default_field_cost);
+    let size = match typ {
+      Some(ref typ) if is_list(&**typ) => {
+        self.list_size(field)
+          .or_else(|| self.default_list_size(parent.unwrap_or(""), name))
+          .unwrap_or(self.limits.default_list_size)
+      }
+      _ => 1,
+    };
+    (depth + 1, own_cost.saturating_add(cost).saturating_mul(size))
+  }
+
+  // Size given by the first size argument of `field`.
+  fn list_size(&self, field: &ast::Field) -> Option<u64> {
+    let arguments = field.arguments.iter().flat_map(|args| args.iter());
+    for argument in arguments {
+      if !self.limits.list_size_arguments.iter().any(|name| name == argument.name.value) {
+        continue;
+      }
+      let size = match argument.value {
+        ast::Value::Int(ref i) => i.value.parse().ok(),
+        ast::Value::Variable(ref v) => {
+          match self.variables.get(v.name.value) {
+            Some(&Value::Int(size)) => Some(size),
+            _ => None,
+          }
+        }
+        _ => None,
+      };
+      if let Some(size) = size {
+        return Some(cmp::max(size, 0) as u64);
+      }
+    }
+    None
+  }
+
+  // Size given by the first size argument with a default of the field `name`
+  // of the named type `parent`.
+  fn default_list_size(&self, parent: &str, name: &str) -> Option<u64> {
+    let parent = match self.schema.get_type(parent) {
+      Some(SchemaType::Output(parent)) => parent,
+      _ => return None,
+    };
+    let fields = match parent.output_kind() {
+      OutputKind::Object(o) => o.fields(),
+      OutputKind::Interface(i) => i.fields(),
+      _ => return None,
+    };
+    let args = fields.get(name)?.args()?;
+    self.limits
+      .list_size_arguments
+      .iter()
+      .filter_map(|name| match args.get(name)?.default_value() {
+        Some(&Value::Int(size)) => Some(cmp::max(size, 0) as u64),
+        _ => None,
+      })
+      .next()
+  }
+}
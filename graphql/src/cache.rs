@@ -0,0 +1,158 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+
+use graphql_language::ast::Document;
+use graphql_language::source::Source;
+
+use error::GraphQLError;
+use handler::ParseFn;
+use schema::GraphQLSchema;
+use validation::validate;
+
+self_cell!(
+  struct SourceCell {
+    owner: String,
+
+    #[covariant]
+    dependent: Source,
+  }
+);
+
+self_cell!(
+  struct DocumentCell {
+    owner: SourceCell,
+
+    #[covariant]
+    dependent: Document,
+  }
+);
+
+/// A query parsed and validated against a schema, owning its text so that it
+/// can outlive the request it came with.
+pub struct PreparedDocument {
+  schema: Rc<GraphQLSchema>,
+  document: DocumentCell,
+  errors: Vec<GraphQLError>,
+}
+
+impl PreparedDocument {
+  /// Parses `query` with `parse`, and validates the document against
+  /// `schema`. Only syntax errors are returned, validation errors being kept
+  /// with the document.
+  pub fn new(schema: &Rc<GraphQLSchema>,
+             parse: &ParseFn,
+             query: &str)
+             -> Result<PreparedDocument, GraphQLError> {
+    let source = SourceCell::new(query.to_owned(), |query| Source::new(query));
+    let document = DocumentCell::try_new(source, |source| parse(source.borrow_dependent()))?;
+    let errors = validate(schema, document.borrow_dependent());
+    Ok(PreparedDocument {
+      schema: schema.clone(),
+      document,
+      errors,
+    })
+  }
+
+  pub fn query(&self) -> &str {
+    self.document.borrow_owner().borrow_owner()
+  }
+
+  pub fn document(&self) -> &Document<'_> {
+    self.document.borrow_dependent()
+  }
+
+  pub fn schema(&self) -> &Rc<GraphQLSchema> {
+    &self.schema
+  }
+
+  /// Validation errors of the document, empty when it can be executed.
+  pub fn errors(&self) -> &[GraphQLError] {
+    &self.errors
+  }
+}
+
+/// Hits and misses of a `DocumentCache` since it was created.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CacheStats {
+  pub hits: u64,
+  pub misses: u64,
+  pub evictions: u64,
+  /// Number of documents in the cache.
+  pub len: usize,
+}
+
+/// Prepared documents by query text, up to `capacity` of them, evicting the
+/// least recently used one when full.
+///
+/// Documents are prepared for the schema they are requested with. A document
+/// requested with another schema, for instance after the schema is rebuilt, is
+/// prepared again and replaces the cached one. Queries failing to parse are not
+/// cached.
+pub struct DocumentCache {
+  capacity: usize,
+  // The most recently used document is last.
+  documents: RefCell<IndexMap<String, Rc<PreparedDocument>>>,
+  hits: Cell<u64>,
+  misses: Cell<u64>,
+  evictions: Cell<u64>,
+}
+
+impl DocumentCache {
+  pub fn new(capacity: usize) -> DocumentCache {
+    DocumentCache {
+      capacity,
+      documents: RefCell::new(IndexMap::new()),
+      hits: Cell::new(0),
+      misses: Cell::new(0),
+      evictions: Cell::new(0),
+    }
+  }
+
+  /// The document prepared for `query` and `schema`, parsing it with `parse`
+  /// on a miss.
+  pub fn get(&self,
+             schema: &Rc<GraphQLSchema>,
+             parse: &ParseFn,
+             query: &str)
+             -> Result<Rc<PreparedDocument>, GraphQLError> {
+    {
+      let mut documents = self.documents.borrow_mut();
+      if let Some(index) = documents.get_index_of(query) {
+        let last = documents.len() - 1;
+        documents.move_index(index, last);
+        let prepared = &documents[last];
+        if Rc::ptr_eq(&prepared.schema, schema) {
+          self.hits.set(self.hits.get() + 1);
+          return Ok(prepared.clone());
+        }
+      }
+    }
+
+    self.misses.set(self.misses.get() + 1);
+    let prepared = Rc::new(PreparedDocument::new(schema, parse, query)?);
+    if self.capacity > 0 {
+      let mut documents = self.documents.borrow_mut();
+      if !documents.contains_key(query) && documents.len() == self.capacity {
+        documents.shift_remove_index(0);
+        self.evictions.set(self.evictions.get() + 1);
+      }
+      documents.insert(query.to_owned(), prepared.clone());
+    }
+    Ok(prepared)
+  }
+
+  pub fn stats(&self) -> CacheStats {
+    CacheStats {
+      hits: self.hits.get(),
+      misses: self.misses.get(),
+      evictions: self.evictions.get(),
+      len: self.documents.borrow().len(),
+    }
+  }
+
+  pub fn clear(&self) {
+    self.documents.borrow_mut().clear();
+  }
+}
@@ -0,0 +1,248 @@
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use graphql_language::ast;
+use graphql_language::diagnostic::Diagnostic;
+use graphql_language::lexer;
+use graphql_language::source::{Source, SourceLocation};
+
+use value::Value;
+
+/// Segment of the response path leading to the field an error occurred in,
+/// either a response key (field alias or name) or a list index.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum PathSegment {
+  Key(String),
+  Index(usize),
+}
+
+impl<'a> From<&'a str> for PathSegment {
+  fn from(key: &'a str) -> PathSegment {
+    PathSegment::Key(key.to_owned())
+  }
+}
+
+impl From<usize> for PathSegment {
+  fn from(index: usize) -> PathSegment {
+    PathSegment::Index(index)
+  }
+}
+
+impl Serialize for PathSegment {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    match *self {
+      PathSegment::Key(ref key) => serializer.serialize_str(key),
+      PathSegment::Index(index) => serializer.serialize_u64(index as u64),
+    }
+  }
+}
+
+/// Error reported in the `errors` entry of a response, by parsing, validation
+/// or execution.
+#[derive(Debug, Clone)]
+pub struct GraphQLError {
+  // Boxed to keep `Result`s returning it small.
+  inner: Box<ErrorInner>,
+}
+
+#[derive(Debug, Clone)]
+struct ErrorInner {
+  message: String,
+  locations: Vec<SourceLocation>,
+  path: Option<Vec<PathSegment>>,
+  extensions: IndexMap<String, Value>,
+  source: Option<Rc<dyn Error>>,
+}
+
+impl GraphQLError {
+  pub fn new(message: &str) -> GraphQLError {
+    GraphQLError {
+      inner: Box::new(ErrorInner {
+        message: message.to_owned(),
+        locations: Vec::new(),
+        path: None,
+        extensions: IndexMap::new(),
+        source: None,
+      }),
+    }
+  }
+
+  /// Error for invalid input found by the lexer at `position` of `source`.
+  pub fn syntax_error(source: &Source, position: usize, error: lexer::Error) -> GraphQLError {
+    GraphQLError::new(&format!("Syntax Error: {}.", error))
+      .location(&ast::Location {
+        start: position,
+        end: position,
+        source: Some(source),
+      })
+      .with_source(error)
+  }
+
+  /// Error of a document failing to parse as `diagnostic` tells.
+  pub fn from_diagnostic(source: &Source, diagnostic: &Diagnostic) -> GraphQLError {
+    GraphQLError::new(&format!("Syntax Error: {}", diagnostic.message)).location(&ast::Location {
+      start: diagnostic.span.start,
+      end: diagnostic.span.end,
+      source: Some(source),
+    })
+  }
+
+  /// Adds the location of an AST node the error relates to. Locations
+  /// without a source cannot be resolved to a line and column and are ignored.
+  pub fn location(mut self, location: &ast::Location) -> GraphQLError {
+    if let Some(source) = location.source {
+      self.inner.locations.push(source.location(location.start));
+    }
+    self
+  }
+
+  pub fn path(mut self, path: Vec<PathSegment>) -> GraphQLError {
+    self.inner.path = Some(path);
+    self
+  }
+
+  pub fn extension(mut self, key: &str, value: Value) -> GraphQLError {
+    self.inner.extensions.insert(key.to_owned(), value);
+    self
+  }
+
+  /// Keeps the original error, which is available through `Error::source`.
+  pub fn with_source<E: Error + 'static>(mut self, source: E) -> GraphQLError {
+    self.inner.source = Some(Rc::new(source));
+    self
+  }
+
+  pub fn message(&self) -> &str {
+    self.inner.message.as_ref()
+  }
+
+  pub fn locations(&self) -> &[SourceLocation] {
+    &self.inner.locations
+  }
+
+  pub fn response_path(&self) -> Option<&[PathSegment]> {
+    self.inner.path.as_ref().map(|p| p.as_ref())
+  }
+
+  pub fn extensions(&self) -> &IndexMap<String, Value> {
+    &self.inner.extensions
+  }
+}
+
+impl fmt::Display for GraphQLError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.inner.message)
+  }
+}
+
+impl Error for GraphQLError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    self.inner.source.as_deref()
+  }
+}
+
+impl From<String> for GraphQLError {
+  fn from(message: String) -> GraphQLError {
+    GraphQLError::new(&message)
+  }
+}
+
+impl<'a> From<&'a str> for GraphQLError {
+  fn from(message: &'a str) -> GraphQLError {
+    GraphQLError::new(message)
+  }
+}
+
+impl From<Box<dyn Error>> for GraphQLError {
+  fn from(error: Box<dyn Error>) -> GraphQLError {
+    let mut e = GraphQLError::new(&error.to_string());
+    e.inner.source = Some(Rc::from(error));
+    e
+  }
+}
+
+impl From<Box<dyn Error + Send + Sync>> for GraphQLError {
+  fn from(error: Box<dyn Error + Send + Sync>) -> GraphQLError {
+    let error: Box<dyn Error> = error;
+    GraphQLError::from(error)
+  }
+}
+
+/// Serializes to the error format of a GraphQL response, leaving out empty
+/// entries.
+impl Serialize for GraphQLError {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(None)?;
+    map.serialize_entry("message", &self.inner.message)?;
+    if !self.inner.locations.is_empty() {
+      let locations: Vec<_> = self.inner.locations.iter().map(Location::from).collect();
+      map.serialize_entry("locations", &locations)?;
+    }
+    if let Some(ref path) = self.inner.path {
+      map.serialize_entry("path", path)?;
+    }
+    if !self.inner.extensions.is_empty() {
+      map.serialize_entry("extensions", &self.inner.extensions)?;
+    }
+    map.end()
+  }
+}
+
+/// Deserializes from the error format of a GraphQL response, such as one
+/// returned by a remote server.
+impl<'de> Deserialize<'de> for GraphQLError {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<GraphQLError, D::Error> {
+    let error = ErrorFormat::deserialize(deserializer)?;
+    Ok(GraphQLError {
+      inner: Box::new(ErrorInner {
+        message: error.message,
+        locations: error.locations
+          .into_iter()
+          .map(|l| {
+            SourceLocation {
+              line: l.line,
+              column: l.column,
+            }
+          })
+          .collect(),
+        path: error.path,
+        extensions: error.extensions,
+        source: None,
+      }),
+    })
+  }
+}
+
+#[derive(Deserialize)]
+struct ErrorFormat {
+  message: String,
+  #[serde(default)]
+  locations: Vec<Location>,
+  #[serde(default)]
+  path: Option<Vec<PathSegment>>,
+  #[serde(default)]
+  extensions: IndexMap<String, Value>,
+}
+
+// `SourceLocation` is part of the language crate, which does not depend on
+// serde.
+#[derive(Serialize, Deserialize)]
+struct Location {
+  line: usize,
+  column: usize,
+}
+
+impl<'a> From<&'a SourceLocation> for Location {
+  fn from(location: &'a SourceLocation) -> Location {
+    Location {
+      line: location.line,
+      column: location.column,
+    }
+  }
+}
@@ -0,0 +1,1126 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::mem;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use futures_core::Stream;
+use indexmap::IndexMap;
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use graphql_language::ast;
+
+use error::{GraphQLError, PathSegment};
+use schema::{GraphQLSchema, SchemaType};
+use types::*;
+use value::{Arguments, Value};
+
+/// Outcome of executing an operation, in the shape of a GraphQL response.
+/// `data` is `None` when execution could not start, and `Value::Null` when a
+/// field error propagated up to the root.
+///
+/// Operations using `@defer` or `@stream` are answered with several payloads:
+/// the initial one has `has_next` set, the following ones carry their results
+/// in `incremental`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionResult {
+  #[serde(default, deserialize_with = "deserialize_data")]
+  pub data: Option<Value>,
+  #[serde(default)]
+  pub errors: Vec<GraphQLError>,
+  #[serde(default)]
+  pub incremental: Vec<IncrementalResult>,
+  #[serde(default)]
+  pub has_next: Option<bool>,
+}
+
+impl From<GraphQLError> for ExecutionResult {
+  fn from(error: GraphQLError) -> ExecutionResult {
+    ExecutionResult {
+      data: None,
+      errors: vec![error],
+      incremental: Vec::new(),
+      has_next: None,
+    }
+  }
+}
+
+// Keeps `"data": null` apart from a missing entry.
+fn deserialize_data<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+  Value::deserialize(deserializer).map(Some)
+}
+
+impl Serialize for ExecutionResult {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(None)?;
+    if !self.errors.is_empty() {
+      map.serialize_entry("errors", &self.errors)?;
+    }
+    if let Some(ref data) = self.data {
+      map.serialize_entry("data", data)?;
+    }
+    if !self.incremental.is_empty() {
+      map.serialize_entry("incremental", &self.incremental)?;
+    }
+    if let Some(has_next) = self.has_next {
+      map.serialize_entry("hasNext", &has_next)?;
+    }
+    map.end()
+  }
+}
+
+/// Result of a fragment marked with `@defer`, in `data`, or of a list item
+/// beyond the `initialCount` of `@stream`, in `items`. Either is null when a
+/// field error propagated up to it.
+#[derive(Debug, Deserialize)]
+pub struct IncrementalResult {
+  #[serde(default, deserialize_with = "deserialize_data")]
+  pub data: Option<Value>,
+  #[serde(default, deserialize_with = "deserialize_data")]
+  pub items: Option<Value>,
+  pub path: Vec<PathSegment>,
+  #[serde(default)]
+  pub label: Option<String>,
+  #[serde(default)]
+  pub errors: Vec<GraphQLError>,
+}
+
+impl Serialize for IncrementalResult {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(None)?;
+    if !self.errors.is_empty() {
+      map.serialize_entry("errors", &self.errors)?;
+    }
+    if let Some(ref data) = self.data {
+      map.serialize_entry("data", data)?;
+    }
+    if let Some(ref items) = self.items {
+      map.serialize_entry("items", items)?;
+    }
+    map.serialize_entry("path", &self.path)?;
+    if let Some(ref label) = self.label {
+      map.serialize_entry("label", label)?;
+    }
+    map.end()
+  }
+}
+
+/// Executes an operation of `document`, selected by `operation_name` when the
+/// document contains several.
+///
+/// A field error is recorded once and nulls out the field; when the field is
+/// not optional, the null propagates to the nearest optional parent field,
+/// or to `data` itself.
+///
+/// `@defer` and `@stream` are ignored, their fields being part of the single
+/// result.
+pub fn execute(schema: &GraphQLSchema,
+               document: &ast::Document,
+               operation_name: Option<&str>,
+               variables: &Arguments,
+               root_value: &dyn Any,
+               context: &dyn Any)
+               -> ExecutionResult {
+  execute_operation(schema, document, operation_name, variables, root_value, context, false).0
+}
+
+/// Executes an operation like `execute`, delivering the fragments marked with
+/// `@defer` and the list items beyond the `initialCount` of `@stream` in
+/// payloads following the initial result, one each.
+///
+/// Resolvers are synchronous, so every payload is computed before this
+/// returns; only their delivery is incremental. Results which end up under a
+/// null, because of a field error, are not delivered.
+pub fn execute_incremental(schema: &GraphQLSchema,
+                           document: &ast::Document,
+                           operation_name: Option<&str>,
+                           variables: &Arguments,
+                           root_value: &dyn Any,
+                           context: &dyn Any)
+                           -> Vec<ExecutionResult> {
+  let (mut initial, records) =
+    execute_operation(schema, document, operation_name, variables, root_value, context, true);
+
+  // Records come after the record they are part of.
+  let mut delivered = Vec::with_capacity(records.len());
+  for record in &records {
+    let base = match record.parent {
+      None => initial.data.as_ref().map(|data| (data, &[][..])),
+      Some(parent) if delivered[parent] => {
+        let parent = &records[parent].result;
+        match (&parent.data, &parent.items) {
+          (Some(data), _) => Some((data, &parent.path[..])),
+          // Items of a stream record are a list of one.
+          (_, Some(Value::List(items))) => items.first().map(|item| (item, &parent.path[..])),
+          _ => None,
+        }
+      }
+      Some(_) => None,
+    };
+    let result = &record.result;
+    let streamed = result.items.is_some();
+    let target = &result.path[..result.path.len() - streamed as usize];
+    let target = base.and_then(|(value, path)| value_at(value, &target[path.len()..]));
+    delivered.push(matches!((target, streamed),
+                            (Some(&Value::Object(_)), false) | (Some(&Value::List(_)), true)));
+  }
+
+  let mut results: Vec<_> = records.into_iter()
+    .zip(delivered)
+    .filter(|&(_, delivered)| delivered)
+    .map(|(record, _)| {
+      ExecutionResult {
+        data: None,
+        errors: Vec::new(),
+        incremental: vec![record.result],
+        has_next: Some(true),
+      }
+    })
+    .collect();
+  if let Some(last) = results.last_mut() {
+    last.has_next = Some(false);
+    initial.has_next = Some(true);
+  }
+  results.insert(0, initial);
+  results
+}
+
+fn value_at<'v>(value: &'v Value, path: &[PathSegment]) -> Option<&'v Value> {
+  path.iter().try_fold(value, |value, segment| {
+    match (value, segment) {
+      (Value::Object(fields), PathSegment::Key(key)) => fields.get(key),
+      (Value::List(items), PathSegment::Index(i)) => items.get(*i),
+      _ => None,
+    }
+  })
+}
+
+fn value_at_mut<'v>(value: &'v mut Value, path: &[PathSegment]) -> Option<&'v mut Value> {
+  path.iter().try_fold(value, |value, segment| {
+    match (value, segment) {
+      (Value::Object(fields), PathSegment::Key(key)) => fields.get_mut(key),
+      (Value::List(items), PathSegment::Index(i)) => items.get_mut(*i),
+      _ => None,
+    }
+  })
+}
+
+// Result of the initial execution or of a record.
+fn tree<'v>(data: &'v mut Value,
+            records: &'v mut [Record],
+            record: Option<usize>)
+            -> &'v mut Value {
+  match record {
+    Some(i) => {
+      let result = &mut records[i].result;
+      result.data.as_mut().or(result.items.as_mut()).unwrap()
+    }
+    None => data,
+  }
+}
+
+fn execute_operation(schema: &GraphQLSchema,
+                     document: &ast::Document,
+                     operation_name: Option<&str>,
+                     variables: &Arguments,
+                     root_value: &dyn Any,
+                     context: &dyn Any,
+                     incremental: bool)
+                     -> (ExecutionResult, Vec<Record>) {
+  let (mut executor, operation, root_type) =
+    match prepare(schema, document, operation_name, variables, context) {
+      Ok(prepared) => prepared,
+      Err(e) => return (e.into(), Vec::new()),
+    };
+  if matches!(operation.operation, ast::OperationType::Subscription) {
+    let e = GraphQLError::new("Subscription operations must be started with subscribe.");
+    return (e.into(), Vec::new());
+  }
+  executor.incremental = incremental;
+
+  let data = executor.execute_root(root_type, root_value, &operation.selection_set);
+  let result = ExecutionResult {
+    data: Some(data),
+    errors: executor.errors,
+    incremental: Vec::new(),
+    has_next: None,
+  };
+  (result, executor.records)
+}
+
+/// Source event stream, returned boxed by the resolver of a subscription root
+/// field.
+pub type SourceStream = Pin<Box<dyn Stream<Item = Box<dyn Any>>>>;
+
+/// Value of a field which is not available yet, returned boxed by its
+/// resolver, typically from a `DataLoader`.
+///
+/// The executor completes such fields once it has reached every other field
+/// it can, so that the loads they wait on are batched together.
+pub struct Deferred(Box<dyn FnOnce() -> Result<Box<dyn Any>, GraphQLError>>);
+
+impl Deferred {
+  pub fn new<F>(f: F) -> Deferred
+    where F: FnOnce() -> Result<Box<dyn Any>, GraphQLError> + 'static
+  {
+    Deferred(Box::new(f))
+  }
+}
+
+/// Starts a subscription operation of `document` by resolving its single root
+/// field to a `SourceStream`. Each event is then turned into a response with
+/// `execute_event`.
+#[allow(clippy::result_large_err)]
+pub fn subscribe(schema: &GraphQLSchema,
+                 document: &ast::Document,
+                 operation_name: Option<&str>,
+                 variables: &Arguments,
+                 root_value: &dyn Any,
+                 context: &dyn Any)
+                 -> Result<SourceStream, ExecutionResult> {
+  let (mut executor, operation, root_type) =
+    prepare(schema, document, operation_name, variables, context).map_err(ExecutionResult::from)?;
+  if !matches!(operation.operation, ast::OperationType::Subscription) {
+    return Err(GraphQLError::new("Operation is not a subscription.").into());
+  }
+
+  let mut fields = IndexMap::new();
+  executor.collect_fields(root_type,
+                          &operation.selection_set,
+                          &mut fields,
+                          &mut Vec::new(),
+                          &mut HashSet::new());
+  if fields.len() != 1 {
+    return Err(GraphQLError::new("Subscription must select only one top level field.").into());
+  }
+  let (key, nodes) = fields.swap_remove_index(0).unwrap();
+  let name = nodes[0].name.value;
+  let root_fields = root_type.fields();
+  let resolved = match root_fields.get(name) {
+    Some(field) => {
+      executor.coerce_arguments(field, nodes[0])
+        .and_then(|args| field.resolve(root_value, context, &args))
+        .and_then(|value| {
+          value.downcast::<SourceStream>()
+            .map(|stream| *stream)
+            .map_err(|_| {
+              format!("Subscription field \"{:}\" must return a source stream.", name).into()
+            })
+        })
+    }
+    None => {
+      Err(format!("Cannot query field \"{:}\" on type \"{:}\".", name, root_type.name()).into())
+    }
+  };
+  resolved.map_err(|e| {
+    executor.field_error(e, &nodes, &[PathSegment::Key(key.to_owned())]);
+    ExecutionResult {
+      data: None,
+      errors: executor.errors,
+      incremental: Vec::new(),
+      has_next: None,
+    }
+  })
+}
+
+/// Executes a subscription operation for one event of its source stream. The
+/// event is the value of the root field, whose resolver is not called again.
+pub fn execute_event(schema: &GraphQLSchema,
+                     document: &ast::Document,
+                     operation_name: Option<&str>,
+                     variables: &Arguments,
+                     event: &dyn Any,
+                     context: &dyn Any)
+                     -> ExecutionResult {
+  let (mut executor, operation, root_type) =
+    match prepare(schema, document, operation_name, variables, context) {
+      Ok(prepared) => prepared,
+      Err(e) => return e.into(),
+    };
+  executor.root_event = Some(event);
+
+  let data = executor.execute_root(root_type, &(), &operation.selection_set);
+  ExecutionResult {
+    data: Some(data),
+    errors: executor.errors,
+    incremental: Vec::new(),
+    has_next: None,
+  }
+}
+
+// Selects the operation to execute and coerces its variables.
+fn prepare<'a>(schema: &'a GraphQLSchema,
+               document: &'a ast::Document<'a>,
+               operation_name: Option<&str>,
+               variables: &Arguments,
+               context: &'a dyn Any)
+               -> Result<(Executor<'a>, &'a ast::OperationDefinition<'a>, &'a GraphQLObject),
+                         GraphQLError> {
+  let mut fragments = HashMap::new();
+  let mut operations = Vec::new();
+  for definition in &document.definitions {
+    match *definition {
+      ast::Definition::Operation(ref operation) => operations.push(operation),
+      ast::Definition::Fragment(ref fragment) => {
+        fragments.insert(fragment.name.value, fragment);
+      }
+    }
+  }
+
+  let operation = select_operation(&operations, operation_name)?;
+  let root_type = match operation.operation {
+    ast::OperationType::Query => schema.query_type(),
+    ast::OperationType::Mutation => {
+      schema.mutation_type().ok_or("Schema is not configured for mutations.")?
+    }
+    ast::OperationType::Subscription => {
+      schema.subscription_type().ok_or("Schema is not configured for subscriptions.")?
+    }
+  };
+  let variables = coerce_variables(schema, operation, variables)?;
+
+  let executor = Executor {
+    schema,
+    fragments,
+    variables,
+    context,
+    root_event: None,
+    errors: Vec::new(),
+    incremental: false,
+    records: Vec::new(),
+    record: None,
+    pending: VecDeque::new(),
+    boundary: None,
+  };
+  Ok((executor, operation, root_type))
+}
+
+pub(crate) fn select_operation<'a>(operations: &[&'a ast::OperationDefinition<'a>],
+                        operation_name: Option<&str>)
+                        -> Result<&'a ast::OperationDefinition<'a>, GraphQLError> {
+  match operation_name {
+    Some(name) => {
+      operations.iter()
+        .find(|o| o.name.as_ref().map(|n| n.value) == Some(name))
+        .cloned()
+        .ok_or_else(|| format!("Unknown operation named \"{:}\".", name).into())
+    }
+    None => {
+      match operations.len() {
+        0 => Err("Must provide an operation.".into()),
+        1 => Ok(operations[0]),
+        _ => Err("Must provide operation name if query contains multiple operations.".into()),
+      }
+    }
+  }
+}
+
+// Variable values are coerced to the types of their definitions, defaults
+// being taken as they are.
+pub(crate) fn coerce_variables(schema: &GraphQLSchema,
+                               operation: &ast::OperationDefinition,
+                               values: &Arguments)
+                               -> Result<Arguments, GraphQLError> {
+  let mut variables = IndexMap::new();
+  for definition in operation.variable_definitions.iter().flat_map(|d| d.iter()) {
+    let name = definition.variable.name.value;
+    let located = |message: String| {
+      let e = GraphQLError::new(&message);
+      match definition.loc {
+        Some(ref loc) => e.location(loc),
+        None => e,
+      }
+    };
+    let typ = match variable_type(schema, &definition.type_) {
+      Some(typ) => typ,
+      None => {
+        return Err(located(format!("Variable \"${:}\" cannot be of non-input type \"{:}\".",
+                                   name,
+                                   ast_type_ref(&definition.type_))));
+      }
+    };
+    let value = match values.get(name) {
+      Some(value) => {
+        let coerced = coerce_variable_value(&*typ, value);
+        if let Err(e) = validate_input_value(&*typ, &coerced) {
+          return Err(located(format!("Variable \"${:}\" got invalid value {:}; {:}",
+                                     name,
+                                     value,
+                                     e)));
+        }
+        Some(coerced)
+      }
+      None => definition.default_value.as_ref().map(|v| literal_value(v, &variables)),
+    };
+    match value {
+      Some(value) => {
+        variables.insert(name.to_owned(), value);
+      }
+      None => {
+        if let ast::Type::NonNullNamed(_) | ast::Type::NonNullList(_) = definition.type_ {
+          return Err(located(format!("Variable \"${:}\" of required type \"{:}\" was not \
+                                      provided.",
+                                     name,
+                                     ast_type_ref(&definition.type_))));
+        }
+      }
+    }
+  }
+  Ok(variables)
+}
+
+// Input type of a variable declared as `typ`, if it names an input type.
+fn variable_type(schema: &GraphQLSchema, typ: &ast::Type) -> Option<Rc<dyn GraphQLInput>> {
+  Some(match *typ {
+    ast::Type::Named(ref t) => {
+      GraphQLOptionalType::dyn_input(schema.input_type(t.name.value)?.clone())
+    }
+    ast::Type::List(ref t) => {
+      GraphQLOptionalType::dyn_input(GraphQLListType::dyn_input(variable_type(schema, &t.type_)?))
+    }
+    ast::Type::NonNullNamed(ref t) => schema.input_type(t.type_.name.value)?.clone(),
+    ast::Type::NonNullList(ref t) => {
+      GraphQLListType::dyn_input(variable_type(schema, &t.type_.type_)?)
+    }
+  })
+}
+
+fn ast_type_ref(typ: &ast::Type) -> String {
+  match *typ {
+    ast::Type::Named(ref t) => t.name.value.to_owned(),
+    ast::Type::List(ref t) => format!("[{:}]", ast_type_ref(&t.type_)),
+    ast::Type::NonNullNamed(ref t) => format!("{:}!", t.type_.name.value),
+    ast::Type::NonNullList(ref t) => format!("[{:}]!", ast_type_ref(&t.type_.type_)),
+  }
+}
+
+// Marks a field error which has been recorded, and whose null has to
+// propagate to the nearest optional parent.
+struct NullPropagation;
+
+type FieldResult = Result<Value, NullPropagation>;
+
+// Deferred fragment or streamed item, executed with its own errors.
+struct Record {
+  // Index of the record this one is part of, if not of the initial result.
+  parent: Option<usize>,
+  // Null boundary of the parent, while the record is executed.
+  parent_boundary: Option<usize>,
+  result: IncrementalResult,
+}
+
+// Field whose resolver returned a `Deferred`, with what completing it needs.
+struct PendingField<'a> {
+  deferred: Deferred,
+  typ: Rc<dyn GraphQLOutput>,
+  parent: String,
+  nodes: Vec<&'a ast::Field<'a>>,
+  path: Vec<PathSegment>,
+  boundary: Option<usize>,
+  record: Option<usize>,
+}
+
+// Selection sets of a fragment marked with `@defer`, by label.
+type DeferredFragment<'a> = (Option<String>, Vec<&'a ast::SelectionSet<'a>>);
+
+struct Executor<'a> {
+  schema: &'a GraphQLSchema,
+  fragments: HashMap<&'a str, &'a ast::FragmentDefinition<'a>>,
+  variables: Arguments,
+  context: &'a dyn Any,
+  // Value of the root field when executing a subscription event.
+  root_event: Option<&'a dyn Any>,
+  errors: Vec<GraphQLError>,
+  // Whether `@defer` and `@stream` apply, or are ignored.
+  incremental: bool,
+  records: Vec<Record>,
+  // Index of the record being executed.
+  record: Option<usize>,
+  pending: VecDeque<PendingField<'a>>,
+  // Length of the path where the null of a field error lands, `None` for the
+  // root of the result or record being executed.
+  boundary: Option<usize>,
+}
+
+impl<'a> Executor<'a> {
+  fn execute_root(&mut self,
+                  object: &GraphQLObject,
+                  source: &dyn Any,
+                  selection_set: &'a ast::SelectionSet<'a>)
+                  -> Value {
+    let mut data = self.execute_fields(object, source, &[selection_set], &mut Vec::new())
+      .unwrap_or(Value::Null);
+    self.complete_pending(&mut data);
+    data
+  }
+
+  // Completes the fields waiting on a `Deferred`, in the order they were
+  // reached. Forcing the first one dispatches the loads queued until then, and
+  // those queued while completing them are dispatched together next.
+  fn complete_pending(&mut self, data: &mut Value) {
+    while let Some(pending) = self.pending.pop_front() {
+      let PendingField { deferred, typ, parent, nodes, mut path, boundary, record } = pending;
+      // Paths are relative to the result or record the field is part of. A
+      // streamed item is the single item of its record.
+      let (root_len, prefix) = match record {
+        Some(i) if self.records[i].result.items.is_some() => {
+          (self.records[i].result.path.len(), vec![PathSegment::Index(0)])
+        }
+        Some(i) => (self.records[i].result.path.len(), vec![]),
+        None => (0, vec![]),
+      };
+      let relative = |path: &[PathSegment]| {
+        prefix.iter().chain(&path[root_len..]).cloned().collect::<Vec<_>>()
+      };
+
+      // Fields nulled out in the meantime are dropped.
+      let parent_path = relative(&path[..path.len() - 1]);
+      if !matches!(value_at(tree(data, &mut self.records, record), &parent_path),
+                   Some(&Value::Object(_))) {
+        continue;
+      }
+
+      let parent_record = mem::replace(&mut self.record, record);
+      let parent_boundary = mem::replace(&mut self.boundary, boundary);
+      if let Some(i) = record {
+        mem::swap(&mut self.errors, &mut self.records[i].result.errors);
+      }
+      let completed = match (deferred.0)() {
+        Ok(value) => self.complete_value(&*typ, &parent, &nodes, &*value, &mut path),
+        Err(e) => {
+          self.field_error(e, &nodes, &path);
+          null_for(&*typ)
+        }
+      };
+      if let Some(i) = record {
+        mem::swap(&mut self.errors, &mut self.records[i].result.errors);
+      }
+      self.record = parent_record;
+      self.boundary = parent_boundary;
+
+      let (path, value) = match completed {
+        Ok(value) => (relative(&path), value),
+        Err(NullPropagation) => {
+          match boundary {
+            Some(len) => (relative(&path[..len]), Value::Null),
+            None => (Vec::new(), Value::Null),
+          }
+        }
+      };
+      if let Some(target) = value_at_mut(tree(data, &mut self.records, record), &path) {
+        *target = value;
+      }
+    }
+  }
+
+  fn execute_fields(&mut self,
+                    object: &GraphQLObject,
+                    source: &dyn Any,
+                    selection_sets: &[&'a ast::SelectionSet<'a>],
+                    path: &mut Vec<PathSegment>)
+                    -> FieldResult {
+    let mut fields = IndexMap::new();
+    let mut deferred = Vec::new();
+    let mut visited_fragments = HashSet::new();
+    for selection_set in selection_sets {
+      self.collect_fields(object,
+                          selection_set,
+                          &mut fields,
+                          &mut deferred,
+                          &mut visited_fragments);
+    }
+
+    let mut result = IndexMap::new();
+    for (key, nodes) in fields {
+      path.push(PathSegment::Key(key.to_owned()));
+      let value = self.execute_field(object, source, &nodes, path);
+      path.pop();
+      result.insert(key.to_owned(), value?);
+    }
+
+    for (label, selection_sets) in deferred {
+      let index = self.start_record(label, path.clone(), false);
+      let data = self.execute_fields(object, source, &selection_sets, path);
+      self.end_record(index, data.unwrap_or(Value::Null));
+    }
+    Ok(Value::Object(result))
+  }
+
+  // Fragments marked with `@defer` are collected apart from the fields, when
+  // executing incrementally.
+  fn collect_fields(&self,
+                    object: &GraphQLObject,
+                    selection_set: &'a ast::SelectionSet<'a>,
+                    fields: &mut IndexMap<&'a str, Vec<&'a ast::Field<'a>>>,
+                    deferred: &mut Vec<DeferredFragment<'a>>,
+                    visited_fragments: &mut HashSet<&'a str>) {
+    for selection in &selection_set.selections {
+      match *selection {
+        ast::Selection::Field(ref field) => {
+          if self.should_include(&field.directives) {
+            let key = field.alias.as_ref().unwrap_or(&field.name).value;
+            fields.entry(key).or_default().push(field);
+          }
+        }
+        ast::Selection::FragmentSpread(ref spread) => {
+          if !self.should_include(&spread.directives) ||
+             !visited_fragments.insert(spread.name.value) {
+            continue;
+          }
+          if let Some(fragment) = self.fragments.get(spread.name.value).cloned() {
+            if !self.does_type_apply(object, fragment.type_condition.name.value) {
+              continue;
+            }
+            match self.incremental_directive(&spread.directives, "defer") {
+              Some(args) => deferred.push((label(&args), vec![&fragment.selection_set])),
+              None => {
+                self.collect_fields(object,
+                                    &fragment.selection_set,
+                                    fields,
+                                    deferred,
+                                    visited_fragments)
+              }
+            }
+          }
+        }
+        ast::Selection::InlineFragment(ref fragment) => {
+          let applies = fragment.type_condition
+            .as_ref()
+            .map(|t| self.does_type_apply(object, t.name.value))
+            .unwrap_or(true);
+          if !applies || !self.should_include(&fragment.directives) {
+            continue;
+          }
+          match self.incremental_directive(&fragment.directives, "defer") {
+            Some(args) => deferred.push((label(&args), vec![&fragment.selection_set])),
+            None => {
+              self.collect_fields(object,
+                                  &fragment.selection_set,
+                                  fields,
+                                  deferred,
+                                  visited_fragments)
+            }
+          }
+        }
+      }
+    }
+  }
+
+  // Applies `@skip(if:)` and `@include(if:)`.
+  fn should_include(&self, directives: &Option<ast::Directives>) -> bool {
+    for directive in directives.iter().flat_map(|d| d.iter()) {
+      let condition = directive.arguments
+        .iter()
+        .flat_map(|args| args.iter())
+        .find(|arg| arg.name.value == "if")
+        .map(|arg| literal_value(&arg.value, &self.variables));
+      match (directive.name.value, condition) {
+        ("skip", Some(Value::Boolean(true))) |
+        ("include", Some(Value::Boolean(false))) => return false,
+        _ => {}
+      }
+    }
+    true
+  }
+
+  // Arguments of `@defer` or `@stream`, unless disabled with `if: false` or
+  // when not executing incrementally.
+  fn incremental_directive(&self,
+                           directives: &Option<ast::Directives>,
+                           name: &str)
+                           -> Option<Arguments> {
+    if !self.incremental {
+      return None;
+    }
+    let directive = directives.iter().flat_map(|d| d.iter()).find(|d| d.name.value == name)?;
+    let args: Arguments = directive.arguments
+      .iter()
+      .flat_map(|args| args.iter())
+      .map(|arg| (arg.name.value.to_owned(), literal_value(&arg.value, &self.variables)))
+      .collect();
+    match args.get("if") {
+      Some(&Value::Boolean(false)) => None,
+      _ => Some(args),
+    }
+  }
+
+  fn start_record(&mut self, label: Option<String>, path: Vec<PathSegment>, items: bool) -> usize {
+    self.records.push(Record {
+      parent: self.record,
+      parent_boundary: self.boundary.take(),
+      result: IncrementalResult {
+        data: None,
+        items: if items { Some(Value::Null) } else { None },
+        path,
+        label,
+        errors: mem::take(&mut self.errors),
+      },
+    });
+    self.record = Some(self.records.len() - 1);
+    self.records.len() - 1
+  }
+
+  // Restores the errors and null boundary of the parent, which were kept by
+  // `start_record`.
+  fn end_record(&mut self, index: usize, value: Value) {
+    let record = &mut self.records[index];
+    let errors = mem::replace(&mut record.result.errors, mem::take(&mut self.errors));
+    self.errors = errors;
+    self.boundary = record.parent_boundary;
+    match record.result.items {
+      Some(ref mut items) => *items = value,
+      None => record.result.data = Some(value),
+    }
+    self.record = record.parent;
+  }
+
+  fn does_type_apply(&self, object: &GraphQLObject, type_condition: &str) -> bool {
+    if object.name() == type_condition ||
+       object.interfaces().map(|is| is.contains_key(type_condition)).unwrap_or(false) {
+      return true;
+    }
+    match self.schema.get_type(type_condition) {
+      Some(SchemaType::Output(t)) => {
+        match t.output_kind() {
+          OutputKind::Union(u) => u.types().contains_key(object.name()),
+          _ => false,
+        }
+      }
+      _ => false,
+    }
+  }
+
+  fn execute_field(&mut self,
+                   object: &GraphQLObject,
+                   source: &dyn Any,
+                   nodes: &[&'a ast::Field<'a>],
+                   path: &mut Vec<PathSegment>)
+                   -> FieldResult {
+    let name = nodes[0].name.value;
+    if name == "__typename" {
+      return Ok(Value::String(object.name().to_owned()));
+    }
+
+    let fields = object.fields();
+    let field = match fields.get(name) {
+      Some(field) => field,
+      None => {
+        let e = format!("Cannot query field \"{:}\" on type \"{:}\".", name, object.name());
+        self.field_error(e.into(), nodes, path);
+        return Ok(Value::Null);
+      }
+    };
+
+    if let (Some(event), 1) = (self.root_event, path.len()) {
+      return self.complete_value(&**field.typ(), object.name(), nodes, event, path);
+    }
+
+    let resolved = self.coerce_arguments(field, nodes[0])
+      .and_then(|args| field.resolve(source, self.context, &args));
+    match resolved.map(|value| value.downcast::<Deferred>()) {
+      Ok(Ok(deferred)) => {
+        self.pending.push_back(PendingField {
+          deferred: *deferred,
+          typ: field.typ().clone(),
+          parent: object.name().to_owned(),
+          nodes: nodes.to_vec(),
+          path: path.clone(),
+          boundary: self.boundary,
+          record: self.record,
+        });
+        Ok(Value::Null)
+      }
+      Ok(Err(value)) => self.complete_value(&**field.typ(), object.name(), nodes, &*value, path),
+      Err(e) => {
+        self.field_error(e, nodes, path);
+        null_for(&**field.typ())
+      }
+    }
+  }
+
+  fn coerce_arguments(&self,
+                      field: &GraphQLField,
+                      node: &ast::Field)
+                      -> Result<Arguments, GraphQLError> {
+    let mut args = IndexMap::new();
+    for (name, arg) in field.args().into_iter().flat_map(|args| args.iter()) {
+      let value = node.arguments
+        .iter()
+        .flat_map(|args| args.iter())
+        .find(|a| a.name.value == name)
+        .and_then(|a| value_from_ast(&a.value, &**arg.typ(), &self.variables))
+        .or_else(|| arg.default_value().cloned());
+      let value = match value {
+        Some(value) => value,
+        None if arg.is_required() => {
+          return Err(format!("Argument \"{:}\" of required type \"{:}\" was not provided.",
+                             name,
+                             input_type_ref(&**arg.typ()))
+            .into());
+        }
+        None => continue,
+      };
+      if let Err(e) = validate_input_value(&**arg.typ(), &value) {
+        return Err(format!("Argument \"{:}\" has invalid value: {:}", name, e).into());
+      }
+      args.insert(name.clone(), value);
+    }
+    Ok(args)
+  }
+
+  fn complete_value(&mut self,
+                    typ: &dyn GraphQLOutput,
+                    parent: &str,
+                    nodes: &[&'a ast::Field<'a>],
+                    value: &dyn Any,
+                    path: &mut Vec<PathSegment>)
+                    -> FieldResult {
+    if let OutputKind::Optional(t) = typ.output_kind() {
+      return Ok(match t.value_of(value) {
+        Some(value) => {
+          let boundary = self.boundary.replace(path.len());
+          let value = self.complete_value(&**t.of_type(), parent, nodes, value, path);
+          self.boundary = boundary;
+          value.unwrap_or(Value::Null)
+        }
+        None => Value::Null,
+      });
+    }
+
+    let field_name = nodes[0].name.value;
+    if is_null(value) {
+      let e = format!("Cannot return null for non-nullable field {:}.{:}.",
+                      parent,
+                      field_name);
+      return Err(self.field_error(e.into(), nodes, path));
+    }
+
+    match typ.output_kind() {
+      OutputKind::List(t) => {
+        let items = match t.values_of(value) {
+          Some(items) => items,
+          None => {
+            let e = format!("Expected a list, but did not find one for field {:}.{:}.",
+                            parent,
+                            field_name);
+            return Err(self.field_error(e.into(), nodes, path));
+          }
+        };
+        // Only the list of the field itself is streamed, not nested ones.
+        let stream = match path.last() {
+          Some(&PathSegment::Key(_)) => self.incremental_directive(&nodes[0].directives, "stream"),
+          _ => None,
+        };
+        let initial_count = match stream.as_ref().map(|args| args.get("initialCount")) {
+          None => items.len(),
+          Some(None) => 0,
+          Some(Some(&Value::Int(count))) if count >= 0 => count as usize,
+          Some(Some(_)) => {
+            let e = GraphQLError::new("initialCount must be a positive integer");
+            return Err(self.field_error(e, nodes, path));
+          }
+        };
+
+        let mut values = Vec::with_capacity(items.len());
+        for (i, item) in items.into_iter().enumerate() {
+          path.push(PathSegment::Index(i));
+          if i < initial_count {
+            let value = self.complete_value(&**t.of_type(), parent, nodes, item, path);
+            path.pop();
+            values.push(value?);
+            continue;
+          }
+
+          // Each streamed item is a record of its own. An item nulled by a
+          // field error ends the stream.
+          let index = self.start_record(stream.as_ref().and_then(label), path.clone(), true);
+          let value = self.complete_value(&**t.of_type(), parent, nodes, item, path);
+          path.pop();
+          let ended = value.is_err();
+          self.end_record(index, value.map(|v| Value::List(vec![v])).unwrap_or(Value::Null));
+          if ended {
+            break;
+          }
+        }
+        Ok(Value::List(values))
+      }
+      OutputKind::Scalar(scalar) => {
+        scalar.serialize_value(value).ok_or_else(|| {
+          let e = format!("Expected a value of type {:} for field {:}.{:}.",
+                          typ.name(),
+                          parent,
+                          field_name);
+          self.field_error(e.into(), nodes, path)
+        })
+      }
+      OutputKind::Enum(e) => {
+        e.serialize(value).map(|name| Value::Enum(name.to_owned())).ok_or_else(|| {
+          let e = format!("Enum {:} cannot represent the value of field {:}.{:}.",
+                          typ.name(),
+                          parent,
+                          field_name);
+          self.field_error(e.into(), nodes, path)
+        })
+      }
+      OutputKind::Object(o) => self.complete_object(o, nodes, value, path),
+      OutputKind::Interface(i) => {
+        match i.resolve_type(self.schema, value) {
+          Ok(o) => self.complete_object(o, nodes, value, path),
+          Err(e) => Err(self.field_error(e, nodes, path)),
+        }
+      }
+      OutputKind::Union(u) => {
+        match u.resolve_type(value) {
+          Ok(o) => self.complete_object(o, nodes, value, path),
+          Err(e) => Err(self.field_error(e, nodes, path)),
+        }
+      }
+      OutputKind::Placeholder(name) => {
+        let e = format!("Field {:}.{:} references unknown type {:}.",
+                        parent,
+                        field_name,
+                        name);
+        Err(self.field_error(e.into(), nodes, path))
+      }
+      OutputKind::Optional(_) => unreachable!(),
+    }
+  }
+
+  fn complete_object(&mut self,
+                     object: &GraphQLObject,
+                     nodes: &[&'a ast::Field<'a>],
+                     value: &dyn Any,
+                     path: &mut Vec<PathSegment>)
+                     -> FieldResult {
+    if object.is_type_of(value) == Some(false) {
+      let e = format!("Expected a value of type {:} for field {:}.",
+                      object.name(),
+                      nodes[0].name.value);
+      return Err(self.field_error(e.into(), nodes, path));
+    }
+
+    let selection_sets: Vec<_> = nodes.iter().filter_map(|n| n.selection_set.as_ref()).collect();
+    self.execute_fields(object, value, &selection_sets, path)
+  }
+
+  fn field_error(&mut self,
+                 error: GraphQLError,
+                 nodes: &[&'a ast::Field<'a>],
+                 path: &[PathSegment])
+                 -> NullPropagation {
+    let error = nodes.iter()
+      .filter_map(|n| n.loc.as_ref())
+      .fold(error, |e, loc| e.location(loc))
+      .path(path.to_vec());
+    self.errors.push(error);
+    NullPropagation
+  }
+}
+
+fn label(args: &Arguments) -> Option<String> {
+  match args.get("label") {
+    Some(Value::String(label)) => Some(label.clone()),
+    _ => None,
+  }
+}
+
+fn null_for(typ: &dyn GraphQLOutput) -> FieldResult {
+  match typ.output_kind() {
+    OutputKind::Optional(_) => Ok(Value::Null),
+    _ => Err(NullPropagation),
+  }
+}
+
+// Converts a literal used where a value of `typ` is expected, substituting
+// variables. Returns `None` for a variable which was not provided.
+fn value_from_ast(node: &ast::Value, typ: &dyn GraphQLInput, variables: &Arguments) -> Option<Value> {
+  if let ast::Value::Variable(ref variable) = *node {
+    return variables.get(variable.name.value).map(|value| coerce_variable_value(typ, value));
+  }
+
+  match (typ.input_kind(), node) {
+    (InputKind::Optional(of_type), _) => value_from_ast(node, &**of_type, variables),
+    (InputKind::List(of_type), ast::Value::List(list)) => {
+      let values = list.values
+        .iter()
+        .map(|v| value_from_ast(v, &**of_type, variables).unwrap_or(Value::Null))
+        .collect();
+      Some(Value::List(values))
+    }
+    (InputKind::List(of_type), _) => value_from_ast(node, &**of_type, variables),
+    (InputKind::InputObject(o), ast::Value::Object(object)) => {
+      let input_fields = o.fields();
+      let mut fields = IndexMap::new();
+      for field in &object.fields {
+        let value = match input_fields.get(field.name.value) {
+          Some(input_field) => value_from_ast(&field.value, &**input_field.typ(), variables),
+          None => Some(literal_value(&field.value, variables)),
+        };
+        if let Some(value) = value {
+          fields.insert(field.name.value.to_owned(), value);
+        }
+      }
+      Some(Value::Object(fields))
+    }
+    _ => Some(literal_value(node, variables)),
+  }
+}
+
+// Variable values are given in their JSON form, where enum values are
+// strings.
+fn coerce_variable_value(typ: &dyn GraphQLInput, value: &Value) -> Value {
+  match (typ.input_kind(), value) {
+    (InputKind::Optional(of_type), _) => coerce_variable_value(&**of_type, value),
+    (InputKind::List(of_type), Value::List(values)) => {
+      Value::List(values.iter().map(|v| coerce_variable_value(&**of_type, v)).collect())
+    }
+    (InputKind::List(of_type), _) => coerce_variable_value(&**of_type, value),
+    (InputKind::Enum(_), Value::String(name)) => Value::Enum(name.clone()),
+    (InputKind::InputObject(o), Value::Object(fields)) => {
+      let input_fields = o.fields();
+      let fields = fields.iter()
+        .map(|(name, v)| {
+          let v = match input_fields.get(name) {
+            Some(input_field) => coerce_variable_value(&**input_field.typ(), v),
+            None => v.clone(),
+          };
+          (name.clone(), v)
+        })
+        .collect();
+      Value::Object(fields)
+    }
+    _ => value.clone(),
+  }
+}
+
+// Converts a literal without knowing its type. Variables which were not
+// provided become null.
+fn literal_value(node: &ast::Value, variables: &Arguments) -> Value {
+  match *node {
+    ast::Value::Variable(ref v) => variables.get(v.name.value).cloned().unwrap_or(Value::Null),
+    // Integers out of range are kept as floats, which no Int accepts.
+    ast::Value::Int(ref i) => {
+      i.value.parse().map(Value::Int).unwrap_or_else(|_| float_value(i.value))
+    }
+    ast::Value::Float(ref f) => float_value(f.value),
+    ast::Value::String(ref s) => Value::String(s.value.clone()),
+    ast::Value::Boolean(ref b) => Value::Boolean(b.value),
+    ast::Value::Enum(ref e) if e.name.value == "null" => Value::Null,
+    ast::Value::Enum(ref e) => Value::Enum(e.name.value.to_owned()),
+    ast::Value::List(ref list) => {
+      Value::List(list.values.iter().map(|v| literal_value(v, variables)).collect())
+    }
+    ast::Value::Object(ref object) => {
+      Value::Object(object.fields
+        .iter()
+        .map(|f| (f.name.value.to_owned(), literal_value(&f.value, variables)))
+        .collect())
+    }
+  }
+}
+
+fn float_value(value: &str) -> Value {
+  value.parse().map(Value::Float).unwrap_or(Value::Null)
+}
@@ -0,0 +1,245 @@
+use std::any::Any;
+use std::rc::Rc;
+
+use http::header::{ACCEPT, ALLOW, CONTENT_TYPE};
+use http::{HeaderMap, HeaderValue, Method, Request, Response, StatusCode};
+use serde::ser::Serialize;
+
+use graphql_language::ast;
+use graphql_language::parser::parse;
+use graphql_language::source::Source;
+
+use error::GraphQLError;
+use execution::select_operation;
+use persisted::has_persisted_query;
+use request::{ExecutionConfig, GraphQLRequest, GraphQLResponse};
+use schema::GraphQLSchema;
+
+pub static GRAPHQL_RESPONSE_JSON: &str = "application/graphql-response+json";
+pub static JSON: &str = "application/json";
+
+/// Parses the query text of a request into a document.
+pub type ParseFn = dyn for<'a> Fn(&'a Source<'a>) -> Result<ast::Document<'a>, GraphQLError>;
+
+/// Parses the query text of a request with `graphql_language::parser::parse`,
+/// failing with the first syntax error.
+pub fn parse_query<'a>(source: &'a Source<'a>) -> Result<ast::Document<'a>, GraphQLError> {
+  parse(source).map_err(|diagnostics| GraphQLError::from_diagnostic(source, &diagnostics[0]))
+}
+
+/// Serves a schema over HTTP following the GraphQL-over-HTTP specification,
+/// independent of the server framework: the framework converts its requests to
+/// and from the `http` crate types.
+pub struct GraphQLHttpHandler {
+  config: Rc<ExecutionConfig>,
+}
+
+impl GraphQLHttpHandler {
+  pub fn new(config: &Rc<ExecutionConfig>) -> GraphQLHttpHandler {
+    GraphQLHttpHandler { config: config.clone() }
+  }
+
+  pub fn schema(&self) -> &Rc<GraphQLSchema> {
+    self.config.schema()
+  }
+
+  /// Handles a GET or POST request.
+  ///
+  /// Responses are `application/graphql-response+json` unless the client
+  /// only accepts `application/json`, in which case every well-formed request
+  /// gets a `200 OK` as with the legacy format. Otherwise a request which
+  /// fails before execution gets a `400 Bad Request`.
+  pub fn handle<B: AsRef<[u8]>>(&self,
+                                request: &Request<B>,
+                                root_value: &dyn Any,
+                                context: &dyn Any)
+                                -> Response<Vec<u8>> {
+    let media_type = match response_media_type(request.headers()) {
+      Some(media_type) => media_type,
+      None => {
+        return error_response(StatusCode::NOT_ACCEPTABLE,
+                              JSON,
+                              &format!("Accept header must allow {:} or {:}.",
+                                       GRAPHQL_RESPONSE_JSON,
+                                       JSON))
+      }
+    };
+
+    let graphql_request = match *request.method() {
+      Method::GET => request_from_query(request.uri().query().unwrap_or("")),
+      Method::POST => request_from_body(request.headers(), request.body().as_ref()),
+      _ => {
+        let mut response = error_response(StatusCode::METHOD_NOT_ALLOWED,
+                                          media_type,
+                                          "GraphQL only supports GET and POST requests.");
+        response.headers_mut().insert(ALLOW, HeaderValue::from_static("GET, POST"));
+        return response;
+      }
+    };
+    let mut graphql_request = match graphql_request {
+      Ok(graphql_request) => graphql_request,
+      Err((status, message)) => return error_response(status, media_type, &message),
+    };
+    if let Err(e) = self.config.resolve_query(&mut graphql_request) {
+      return graphql_response(&e.into(), media_type);
+    }
+
+    let prepared = match self.config.prepare(&graphql_request.query) {
+      Ok(prepared) => prepared,
+      Err(e) => {
+        return graphql_response(&e.into(), media_type);
+      }
+    };
+    if !prepared.errors().is_empty() {
+      let response = GraphQLResponse {
+        data: None,
+        errors: prepared.errors().to_vec(),
+        incremental: Vec::new(),
+        has_next: None,
+      };
+      return graphql_response(&response, media_type);
+    }
+    let document = prepared.document();
+
+    if request.method() == Method::GET && is_mutation(document, &graphql_request) {
+      let mut response = error_response(StatusCode::METHOD_NOT_ALLOWED,
+                                        media_type,
+                                        "Can only perform a mutation operation from a POST \
+                                         request.");
+      response.headers_mut().insert(ALLOW, HeaderValue::from_static("POST"));
+      return response;
+    }
+    if let Err(e) = self.config.check_limits(&graphql_request, document) {
+      return graphql_response(&e.into(), media_type);
+    }
+
+    let response = graphql_request.execute_prepared(&prepared, root_value, context);
+    graphql_response(&response, media_type)
+  }
+}
+
+// A missing Accept header is treated as `application/json`, for clients
+// predating the `application/graphql-response+json` media type.
+fn response_media_type(headers: &HeaderMap) -> Option<&'static str> {
+  let accept = match headers.get(ACCEPT) {
+    Some(accept) => accept.to_str().unwrap_or(""),
+    None => return Some(JSON),
+  };
+  let media_types: Vec<&str> = accept.split(',')
+    .map(|media_type| media_type.split(';').next().unwrap_or("").trim())
+    .collect();
+  let accepts = |candidates: &[&str]| {
+    media_types.iter().any(|m| candidates.iter().any(|c| m.eq_ignore_ascii_case(c)))
+  };
+  if accepts(&[GRAPHQL_RESPONSE_JSON, "application/*", "*/*"]) {
+    Some(GRAPHQL_RESPONSE_JSON)
+  } else if accepts(&[JSON]) {
+    Some(JSON)
+  } else {
+    None
+  }
+}
+
+pub(crate) type RequestResult = Result<GraphQLRequest, (StatusCode, String)>;
+
+fn bad_request(message: String) -> (StatusCode, String) {
+  (StatusCode::BAD_REQUEST, message)
+}
+
+pub(crate) fn request_from_query(query: &str) -> RequestResult {
+  let mut graphql_request = GraphQLRequest::new("");
+  let mut has_query = false;
+  for (name, value) in form_urlencoded::parse(query.as_bytes()) {
+    match name.as_ref() {
+      "query" => {
+        graphql_request.query = value.into_owned();
+        has_query = true;
+      }
+      "operationName" => graphql_request.operation_name = Some(value.into_owned()),
+      "variables" => {
+        graphql_request.variables = serde_json::from_str(&value)
+          .map_err(|e| bad_request(format!("Variables are invalid JSON: {:}.", e)))?;
+      }
+      "extensions" => {
+        graphql_request.extensions = serde_json::from_str(&value)
+          .map_err(|e| bad_request(format!("Extensions are invalid JSON: {:}.", e)))?;
+      }
+      _ => {}
+    }
+  }
+  if !has_query && !has_persisted_query(&graphql_request) {
+    return Err(bad_request("Must provide query string.".to_owned()));
+  }
+  Ok(graphql_request)
+}
+
+pub(crate) fn request_from_body(headers: &HeaderMap, body: &[u8]) -> RequestResult {
+  let content_type = headers.get(CONTENT_TYPE)
+    .and_then(|c| c.to_str().ok())
+    .and_then(|c| c.split(';').next())
+    .map(|c| c.trim());
+  match content_type {
+    Some(c) if c.eq_ignore_ascii_case(JSON) => {}
+    _ => {
+      return Err((StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                  format!("Content-Type must be {:}.", JSON)))
+    }
+  }
+  let graphql_request: GraphQLRequest = serde_json::from_slice(body)
+    .map_err(|e| bad_request(format!("Body is invalid: {:}.", e)))?;
+  if graphql_request.query.is_empty() && !has_persisted_query(&graphql_request) {
+    return Err(bad_request("Must provide query string.".to_owned()));
+  }
+  Ok(graphql_request)
+}
+
+// Operation selection errors are left to execution to report.
+pub(crate) fn is_mutation(document: &ast::Document, graphql_request: &GraphQLRequest) -> bool {
+  let operations: Vec<_> = document.definitions
+    .iter()
+    .filter_map(|d| {
+      match *d {
+        ast::Definition::Operation(ref operation) => Some(operation),
+        _ => None,
+      }
+    })
+    .collect();
+  let operation_name = graphql_request.operation_name.as_ref().map(|n| n.as_ref());
+  matches!(select_operation(&operations, operation_name),
+           Ok(&ast::OperationDefinition { operation: ast::OperationType::Mutation, .. }))
+}
+
+fn graphql_response(response: &GraphQLResponse, media_type: &'static str) -> Response<Vec<u8>> {
+  let status = if media_type == GRAPHQL_RESPONSE_JSON && response.data.is_none() {
+    StatusCode::BAD_REQUEST
+  } else {
+    StatusCode::OK
+  };
+  json_response(status, media_type, response)
+}
+
+fn error_response(status: StatusCode, media_type: &'static str, message: &str) -> Response<Vec<u8>> {
+  json_response(status, media_type, &GraphQLResponse::from(GraphQLError::new(message)))
+}
+
+fn json_response<T: Serialize>(status: StatusCode,
+                               media_type: &'static str,
+                               body: &T)
+                               -> Response<Vec<u8>> {
+  let mut response = Response::new(to_json(body).to_string().into_bytes());
+  *response.status_mut() = status;
+  let content_type = if media_type == JSON {
+    "application/json; charset=utf-8"
+  } else {
+    "application/graphql-response+json; charset=utf-8"
+  };
+  response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+  response
+}
+
+/// JSON of a response, or of an error or message made of values and errors.
+/// Values only hold JSON types and their maps are keyed by strings, so
+/// serializing them into memory cannot fail.
+pub(crate) fn to_json<T: Serialize>(body: &T) -> serde_json::Value {
+  serde_json::to_value(body).expect("values and errors serialize to JSON")
+}
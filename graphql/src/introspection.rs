@@ -0,0 +1,454 @@
+//! Introspection results of schemas, shaped as the data graphql-js returns
+//! for its `getIntrospectionQuery()`, and schema documents read back from
+//! them.
+//!
+//! Results describe deprecated fields, arguments and enum values too, as
+//! queried with `includeDeprecated: true`.
+
+use graphql_language::diagnostic::Span;
+use graphql_language::sdl::*;
+use serde_json::{Map, Value};
+
+use schema::*;
+use types::*;
+
+// Directives documents can use, described by every result and left out of
+// the documents read back.
+static DIRECTIVES: &str = r#"
+"Directs the executor to include this field or fragment only when the `if` argument is true."
+directive @include(
+  "Included when true."
+  if: Boolean!
+) on FIELD | FRAGMENT_SPREAD | INLINE_FRAGMENT
+
+"Directs the executor to skip this field or fragment when the `if` argument is true."
+directive @skip(
+  "Skipped when true."
+  if: Boolean!
+) on FIELD | FRAGMENT_SPREAD | INLINE_FRAGMENT
+
+"Marks an element of a GraphQL schema as no longer supported."
+directive @deprecated(
+  "Explains why this element was deprecated."
+  reason: String = "No longer supported"
+) on FIELD_DEFINITION | ARGUMENT_DEFINITION | INPUT_FIELD_DEFINITION | ENUM_VALUE
+
+"Directs the executor to deliver this fragment in a later payload."
+directive @defer(if: Boolean! = true, label: String) on FRAGMENT_SPREAD | INLINE_FRAGMENT
+
+"Directs the executor to deliver the items of this list field in later payloads."
+directive @stream(if: Boolean! = true, label: String, initialCount: Int = 0) on FIELD
+"#;
+
+// Types every schema has, left out of the documents read back.
+static SPECIFIED_SCALARS: [&str; 5] = ["Int", "Float", "String", "Boolean", "ID"];
+
+/// Introspection result of `schema`, as in `{ "__schema": { "types": [...] } }`.
+pub fn introspect(schema: &GraphQLSchema) -> Value {
+  let directives = parse_schema(DIRECTIVES).expect("the built-in directives to parse").directives;
+  let directives: Vec<_> = directives.iter()
+    .chain(schema.directives())
+    .map(|d| directive_json(schema, d))
+    .collect();
+  let root = |object: Option<&::std::rc::Rc<GraphQLObject>>| {
+    object.map_or(Value::Null, |object| json!({ "name": object.name() }))
+  };
+  json!({
+    "__schema": {
+      "description": schema.description(),
+      "queryType": root(Some(schema.query_type())),
+      "mutationType": root(schema.mutation_type()),
+      "subscriptionType": root(schema.subscription_type()),
+      "types": schema.types().values().map(|t| type_json(schema, t)).collect::<Vec<_>>(),
+      "directives": directives,
+    }
+  })
+}
+
+fn type_json(schema: &GraphQLSchema, typ: &SchemaType) -> Value {
+  let mut fields = Value::Null;
+  let mut input_fields = Value::Null;
+  let mut interfaces = Value::Null;
+  let mut enum_values = Value::Null;
+  let mut possible_types = Value::Null;
+  match *typ {
+    SchemaType::Output(ref t) => {
+      match t.output_kind() {
+        OutputKind::Object(object) => {
+          fields = fields_json(schema, &object.fields());
+          interfaces = named_refs(schema, object.interfaces().into_iter().flat_map(|i| i.keys()));
+        }
+        OutputKind::Interface(interface) => {
+          fields = fields_json(schema, &interface.fields());
+          interfaces =
+            named_refs(schema, interface.interfaces().into_iter().flat_map(|i| i.keys()));
+          let objects = schema.possible_types(interface);
+          possible_types = named_refs(schema, objects.iter().map(|o| o.name()));
+        }
+        OutputKind::Union(union) => possible_types = named_refs(schema, union.types().keys()),
+        OutputKind::Enum(enum_) => enum_values = enum_values_json(enum_),
+        _ => {}
+      }
+    }
+    SchemaType::Input(ref t) => {
+      match t.input_kind() {
+        InputKind::Enum(enum_) => enum_values = enum_values_json(enum_),
+        InputKind::InputObject(input_object) => {
+          let fields = input_object.fields();
+          let values = fields.values().map(|field| {
+            input_value_json(field.name(),
+                             field.description(),
+                             input_type_json(schema, &**field.typ()),
+                             field.default_value(),
+                             field.deprecation_reason())
+          });
+          input_fields = Value::Array(values.collect());
+        }
+        _ => {}
+      }
+    }
+  }
+  json!({
+    "kind": kind(typ),
+    "name": typ.name(),
+    "description": typ.description(),
+    "specifiedByURL": null,
+    "fields": fields,
+    "inputFields": input_fields,
+    "interfaces": interfaces,
+    "enumValues": enum_values,
+    "possibleTypes": possible_types,
+  })
+}
+
+fn kind(typ: &SchemaType) -> &'static str {
+  match *typ {
+    SchemaType::Output(ref t) => {
+      match t.output_kind() {
+        OutputKind::Object(_) => "OBJECT",
+        OutputKind::Interface(_) => "INTERFACE",
+        OutputKind::Union(_) => "UNION",
+        OutputKind::Enum(_) => "ENUM",
+        _ => "SCALAR",
+      }
+    }
+    SchemaType::Input(ref t) => {
+      match t.input_kind() {
+        InputKind::Enum(_) => "ENUM",
+        InputKind::InputObject(_) => "INPUT_OBJECT",
+        _ => "SCALAR",
+      }
+    }
+  }
+}
+
+fn fields_json(schema: &GraphQLSchema,
+               fields: &::indexmap::IndexMap<String, GraphQLField>)
+               -> Value {
+  let fields = fields.values().map(|field| {
+    let args = field.args().into_iter().flat_map(|args| args.values()).map(|arg| {
+      input_value_json(arg.name(),
+                       arg.description(),
+                       input_type_json(schema, &**arg.typ()),
+                       arg.default_value(),
+                       arg.deprecation_reason())
+    });
+    json!({
+      "name": field.name(),
+      "description": field.description(),
+      "args": args.collect::<Vec<_>>(),
+      "type": output_type_json(schema, &**field.typ()),
+      "isDeprecated": field.deprecation_reason().is_some(),
+      "deprecationReason": field.deprecation_reason(),
+    })
+  });
+  Value::Array(fields.collect())
+}
+
+fn enum_values_json(enum_: &GraphQLEnum) -> Value {
+  let values = enum_.values().values().map(|value| {
+    json!({
+      "name": value.value(),
+      "description": value.description(),
+      "isDeprecated": value.deprecation_reason().is_some(),
+      "deprecationReason": value.deprecation_reason(),
+    })
+  });
+  Value::Array(values.collect())
+}
+
+fn input_value_json(name: &str,
+                    description: Option<&str>,
+                    typ: Value,
+                    default_value: Option<&::value::Value>,
+                    deprecation_reason: Option<&str>)
+                    -> Value {
+  json!({
+    "name": name,
+    "description": description,
+    "type": typ,
+    "defaultValue": default_value.map(|value| value.to_string()),
+    "isDeprecated": deprecation_reason.is_some(),
+    "deprecationReason": deprecation_reason,
+  })
+}
+
+fn directive_json(schema: &GraphQLSchema, directive: &DirectiveDefinition) -> Value {
+  let args = directive.arguments.iter().map(|arg| {
+    json!({
+      "name": arg.name,
+      "description": arg.description,
+      "type": type_ref_json(schema, &arg.type_),
+      "defaultValue": arg.default_value.as_ref().map(print_value),
+      "isDeprecated": arg.deprecation_reason.is_some(),
+      "deprecationReason": arg.deprecation_reason,
+    })
+  });
+  json!({
+    "name": directive.name,
+    "description": directive.description,
+    "isRepeatable": directive.repeatable,
+    "locations": directive.locations,
+    "args": args.collect::<Vec<_>>(),
+  })
+}
+
+// Named types are non-null unless wrapped in an optional type, the other way
+// around from introspection.
+fn output_type_json(schema: &GraphQLSchema, typ: &dyn GraphQLOutput) -> Value {
+  let nullable = |typ: &dyn GraphQLOutput| match typ.output_kind() {
+    OutputKind::List(list) => {
+      json!({ "kind": "LIST", "name": null, "ofType": output_type_json(schema, &**list.of_type()) })
+    }
+    OutputKind::Placeholder(name) => named_ref(schema, name),
+    _ => named_ref(schema, typ.name()),
+  };
+  match typ.output_kind() {
+    OutputKind::Optional(optional) => nullable(&**optional.of_type()),
+    _ => json!({ "kind": "NON_NULL", "name": null, "ofType": nullable(typ) }),
+  }
+}
+
+fn input_type_json(schema: &GraphQLSchema, typ: &dyn GraphQLInput) -> Value {
+  let nullable = |typ: &dyn GraphQLInput| match typ.input_kind() {
+    InputKind::List(of_type) => {
+      json!({ "kind": "LIST", "name": null, "ofType": input_type_json(schema, &**of_type) })
+    }
+    InputKind::Placeholder(name) => named_ref(schema, name),
+    _ => named_ref(schema, typ.name()),
+  };
+  match typ.input_kind() {
+    InputKind::Optional(of_type) => nullable(&**of_type),
+    _ => json!({ "kind": "NON_NULL", "name": null, "ofType": nullable(typ) }),
+  }
+}
+
+fn type_ref_json(schema: &GraphQLSchema, typ: &TypeRef) -> Value {
+  match *typ {
+    TypeRef::Named(ref name) => named_ref(schema, name),
+    TypeRef::List(ref of_type) => {
+      json!({ "kind": "LIST", "name": null, "ofType": type_ref_json(schema, of_type) })
+    }
+    TypeRef::NonNull(ref of_type) => {
+      json!({ "kind": "NON_NULL", "name": null, "ofType": type_ref_json(schema, of_type) })
+    }
+  }
+}
+
+fn named_ref(schema: &GraphQLSchema, name: &str) -> Value {
+  json!({ "kind": schema.get_type(name).map_or("SCALAR", kind), "name": name, "ofType": null })
+}
+
+fn named_refs<I>(schema: &GraphQLSchema, names: I) -> Value
+  where I: IntoIterator,
+        I::Item: AsRef<str>
+{
+  Value::Array(names.into_iter().map(|name| named_ref(schema, name.as_ref())).collect())
+}
+
+/// Reads the schema document an introspection result describes, the result
+/// being either the data of the response, `{ "__schema": ... }`, or the
+/// whole response.
+///
+/// Built-in scalars and directives are left out, as `print_schema` would
+/// leave them out, and spans are empty.
+pub fn schema_document(result: &Value) -> Result<SchemaDocument, String> {
+  let schema = result.get("data").unwrap_or(result).get("__schema");
+  let schema = schema.and_then(Value::as_object)
+    .ok_or_else(|| "Expected an introspection result with a \"__schema\" object.".to_owned())?;
+  let root = |key: &str| {
+    schema.get(key).and_then(|root| root.get("name")).and_then(Value::as_str).map(str::to_owned)
+  };
+
+  let mut document = SchemaDocument {
+    schema: Some(SchemaDefinition {
+      description: string(schema, "description"),
+      query: root("queryType"),
+      mutation: root("mutationType"),
+      subscription: root("subscriptionType"),
+      span: Span::new(0, 0),
+    }),
+    types: Vec::new(),
+    directives: Vec::new(),
+  };
+  for typ in array(schema, "types")? {
+    let typ = object(typ, "a type")?;
+    let name = required_string(typ, "name")?;
+    if name.starts_with("__") || SPECIFIED_SCALARS.contains(&name.as_str()) {
+      continue;
+    }
+    let kind = match required_string(typ, "kind")?.as_ref() {
+      "SCALAR" => TypeDefinitionKind::Scalar,
+      "OBJECT" => {
+        TypeDefinitionKind::Object {
+          interfaces: names(typ, "interfaces")?,
+          fields: fields(typ)?,
+        }
+      }
+      "INTERFACE" => {
+        TypeDefinitionKind::Interface {
+          interfaces: names(typ, "interfaces")?,
+          fields: fields(typ)?,
+        }
+      }
+      "UNION" => TypeDefinitionKind::Union { types: names(typ, "possibleTypes")? },
+      "ENUM" => {
+        let mut values = Vec::new();
+        for value in array(typ, "enumValues")? {
+          let value = object(value, "an enum value")?;
+          values.push(EnumValueDefinition {
+            description: string(value, "description"),
+            name: required_string(value, "name")?,
+            span: Span::new(0, 0),
+            deprecation_reason: deprecation_reason(value),
+          });
+        }
+        TypeDefinitionKind::Enum { values }
+      }
+      "INPUT_OBJECT" => {
+        TypeDefinitionKind::InputObject { fields: input_values(typ, "inputFields")? }
+      }
+      kind => return Err(format!("Unknown kind \"{}\" of type \"{}\".", kind, name)),
+    };
+    document.types.push(TypeDefinition {
+      description: string(typ, "description"),
+      name,
+      span: Span::new(0, 0),
+      kind,
+    });
+  }
+
+  let built_in = parse_schema(DIRECTIVES).expect("the built-in directives to parse").directives;
+  let directives = match schema.get("directives") {
+    Some(&Value::Null) | None => &[][..],
+    Some(_) => array(schema, "directives")?,
+  };
+  for directive in directives {
+    let directive = object(directive, "a directive")?;
+    let name = required_string(directive, "name")?;
+    if name == "specifiedBy" || name == "oneOf" || built_in.iter().any(|d| d.name == name) {
+      continue;
+    }
+    let mut locations = Vec::new();
+    for location in array(directive, "locations")? {
+      let location = location.as_str().ok_or("Expected a directive location.")?;
+      locations.push(location.to_owned());
+    }
+    document.directives.push(DirectiveDefinition {
+      description: string(directive, "description"),
+      name,
+      span: Span::new(0, 0),
+      arguments: input_values(directive, "args")?,
+      repeatable: directive.get("isRepeatable").and_then(Value::as_bool).unwrap_or(false),
+      locations,
+    });
+  }
+  Ok(document)
+}
+
+type Object = Map<String, Value>;
+
+fn object<'a>(value: &'a Value, what: &str) -> Result<&'a Object, String> {
+  value.as_object().ok_or_else(|| format!("Expected {}, found {}.", what, value))
+}
+
+fn array<'a>(object: &'a Object, key: &str) -> Result<&'a [Value], String> {
+  match object.get(key) {
+    Some(Value::Array(values)) => Ok(values),
+    _ => Err(format!("Expected a \"{}\" list in {}.", key, Value::Object(object.clone()))),
+  }
+}
+
+fn string(object: &Object, key: &str) -> Option<String> {
+  object.get(key).and_then(Value::as_str).map(str::to_owned)
+}
+
+fn required_string(object: &Object, key: &str) -> Result<String, String> {
+  string(object, key)
+    .ok_or_else(|| format!("Expected a \"{}\" string in {}.", key, Value::Object(object.clone())))
+}
+
+fn names(object: &Object, key: &str) -> Result<Vec<String>, String> {
+  let mut names = Vec::new();
+  for typ in array(object, key)? {
+    names.push(required_string(self::object(typ, "a type")?, "name")?);
+  }
+  Ok(names)
+}
+
+fn deprecation_reason(object: &Object) -> Option<String> {
+  if object.get("isDeprecated").and_then(Value::as_bool) != Some(true) {
+    return None;
+  }
+  Some(string(object, "deprecationReason").unwrap_or_else(|| DEFAULT_DEPRECATION_REASON.to_owned()))
+}
+
+fn fields(typ: &Object) -> Result<Vec<FieldDefinition>, String> {
+  let mut fields = Vec::new();
+  for field in array(typ, "fields")? {
+    let field = object(field, "a field")?;
+    fields.push(FieldDefinition {
+      description: string(field, "description"),
+      name: required_string(field, "name")?,
+      span: Span::new(0, 0),
+      arguments: input_values(field, "args")?,
+      type_: type_ref(field.get("type").unwrap_or(&Value::Null))?,
+      deprecation_reason: deprecation_reason(field),
+    });
+  }
+  Ok(fields)
+}
+
+fn input_values(object: &Object, key: &str) -> Result<Vec<InputValueDefinition>, String> {
+  let mut input_values = Vec::new();
+  for input_value in array(object, key)? {
+    let input_value = self::object(input_value, "an input value")?;
+    let name = required_string(input_value, "name")?;
+    let default_value = match string(input_value, "defaultValue") {
+      Some(value) => {
+        Some(parse_value(&value)
+          .map_err(|e| format!("Invalid default value of \"{}\": {}", name, e.message))?)
+      }
+      None => None,
+    };
+    input_values.push(InputValueDefinition {
+      description: string(input_value, "description"),
+      name,
+      span: Span::new(0, 0),
+      type_: type_ref(input_value.get("type").unwrap_or(&Value::Null))?,
+      default_value,
+      deprecation_reason: deprecation_reason(input_value),
+    });
+  }
+  Ok(input_values)
+}
+
+fn type_ref(value: &Value) -> Result<TypeRef, String> {
+  let typ = object(value, "a type reference")?;
+  let of_type = || type_ref(typ.get("ofType").unwrap_or(&Value::Null)).map(Box::new);
+  match required_string(typ, "kind")?.as_ref() {
+    "LIST" => Ok(TypeRef::List(of_type()?)),
+    "NON_NULL" => Ok(TypeRef::NonNull(of_type()?)),
+    _ => Ok(TypeRef::Named(required_string(typ, "name")?)),
+  }
+}
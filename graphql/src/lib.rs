@@ -1,12 +1,56 @@
+extern crate form_urlencoded;
+extern crate futures_core;
+extern crate getrandom;
 extern crate graphql_language;
+extern crate http;
+extern crate indexmap;
+#[macro_use]
+extern crate serde;
+#[macro_use]
+extern crate serde_json;
+#[macro_use]
+extern crate self_cell;
+extern crate sha2;
 
+pub mod analysis;
+pub mod cache;
+pub mod error;
+pub mod execution;
+pub mod handler;
+pub mod introspection;
+pub mod loader;
+pub mod persisted;
+pub mod request;
+pub mod schema;
+pub mod sdl;
+pub mod streaming;
 pub mod types;
+pub mod validation;
+pub mod value;
+pub mod ws;
 
 pub use graphql_language::*;
 
 #[cfg(test)]
 mod tests {
+  use std::any::Any;
+
+  use analysis::*;
+  use cache::*;
+  use error::*;
+  use execution::*;
+  use handler::*;
+  use loader;
+  use persisted::*;
+  use request::*;
+  use schema::*;
   use types::*;
+  use validation;
+  use value::{Arguments, Value};
+  use ws::*;
+  use streaming::*;
+  use graphql_language::ast;
+  use graphql_language::source::Source;
   use std::str::FromStr;
 
   // Custom Scalar type
@@ -29,6 +73,7 @@ mod tests {
   }
 
   #[test]
+  #[allow(clippy::legacy_numeric_constants)]
   fn test_scalar_type() {
     let int = GraphQLScalarType::int();
     assert_eq!("Int", int.name());
@@ -104,6 +149,51 @@ mod tests {
     author.replace_field_placeholder_type("recentArticle", article);
   }
 
+  #[test]
+  fn test_declaration_order() {
+    let int = &GraphQLScalarType::int();
+    let string = &GraphQLScalarType::string();
+
+    let zebra = &GraphQLObjectType::new("Zebra")
+                   .field("stripes", |f| f.type_of(int))
+                   .field("name", |f| f.type_of(string))
+                   .field("age", |f| {
+                     f.type_of(int)
+                      .arg("unit", |a| a.type_of(string))
+                      .arg("at", |a| a.type_of(int))
+                   })
+                   .field("mane", |f| f.placeholder_type_of("Apple"))
+                   .build();
+    let apple = &GraphQLObjectType::new("Apple")
+                   .field("color", |f| f.type_of(string))
+                   .build();
+    zebra.replace_field_placeholder_type("mane", apple);
+    assert_eq!(vec!["stripes", "name", "age", "mane"],
+               zebra.fields().keys().collect::<Vec<_>>());
+    assert_eq!(vec!["unit", "at"],
+               zebra.fields()["age"].args().unwrap().keys().collect::<Vec<_>>());
+
+    let search_result = GraphQLUnionType::new("SearchResult")
+                          .maybe_type_of(zebra)
+                          .maybe_type_of(apple)
+                          .build();
+    assert_eq!(vec!["Zebra", "Apple"],
+               search_result.types().keys().collect::<Vec<_>>());
+
+    let days = GraphQLEnumType::new("DAYS")
+                 .value("SUN", |v| v)
+                 .value("MON", |v| v)
+                 .value("TUE", |v| v)
+                 .build();
+    assert_eq!(vec!["SUN", "MON", "TUE"], days.values().keys().collect::<Vec<_>>());
+
+    let point = GraphQLInputObjectType::new("Point")
+                  .field("y", |f| f.type_of(int))
+                  .field("x", |f| f.type_of(int))
+                  .build();
+    assert_eq!(vec!["y", "x"], point.fields().keys().collect::<Vec<_>>());
+  }
+
   #[test]
   fn test_interface_type() {
     let int = &GraphQLScalarType::int();
@@ -129,6 +219,202 @@ mod tests {
     assert_eq!("Business", business.name());
   }
 
+  #[test]
+  fn test_interface_implements_interface() {
+    let int = &GraphQLScalarType::int();
+    let string = &GraphQLScalarType::string();
+    let optional_string = &GraphQLOptionalType::input(string);
+
+    let node = &GraphQLInterfaceType::new("Node")
+                  .field("id", |f| f.type_of(string))
+                  .build();
+    let resource = &GraphQLInterfaceType::new("Resource")
+                      .field("url", |f| f.type_of(string).arg("size", |a| a.type_of(int)))
+                      .build();
+    let image = &GraphQLInterfaceType::new("Image")
+                   .field("id", |f| f.type_of(string))
+                   .field("url", |f| f.type_of(string).arg("size", |a| a.type_of(int)))
+                   .field("thumbnail", |f| f.type_of(&GraphQLOptionalType::output(resource)))
+                   .impl_interface(node)
+                   .impl_interface(resource)
+                   .build();
+    assert_eq!(vec!["Node", "Resource"],
+               image.interfaces().unwrap().keys().collect::<Vec<_>>());
+
+    let photo = &GraphQLObjectType::new("Photo")
+                   .field("id", |f| f.type_of(string))
+                   .field("url", |f| {
+                     f.type_of(string)
+                      .arg("size", |a| a.type_of(int))
+                      .arg("format", |a| a.type_of(optional_string))
+                      .arg("quality", |a| a.type_of(int).default_value(Value::Int(80)))
+                   })
+                   .field("thumbnail", |f| f.type_of(image))
+                   .impl_interface(image)
+                   .impl_interface(node)
+                   .impl_interface(resource)
+                   .build();
+    let query = &GraphQLObjectType::new("Query").field("node", |f| f.type_of(node)).build();
+    let schema = GraphQLSchemaType::new(query).output_type(photo).build().unwrap();
+    assert_eq!(vec!["Photo"],
+               schema.possible_types(node).iter().map(|t| t.name()).collect::<Vec<_>>());
+    assert_eq!("Photo", photo.name());
+  }
+
+  #[test]
+  #[should_panic(expected = "Type Photo must implement Node because it is implemented by Image.")]
+  fn test_transitive_interface_required() {
+    let string = &GraphQLScalarType::string();
+    let node = &GraphQLInterfaceType::new("Node")
+                  .field("id", |f| f.type_of(string))
+                  .build();
+    let image = &GraphQLInterfaceType::new("Image")
+                   .field("id", |f| f.type_of(string))
+                   .impl_interface(node)
+                   .build();
+    GraphQLObjectType::new("Photo")
+      .field("id", |f| f.type_of(string))
+      .impl_interface(image)
+      .build();
+  }
+
+  #[test]
+  #[should_panic(expected = "Interface field Node.id expected but Image does not provide it.")]
+  fn test_interface_field_required() {
+    let string = &GraphQLScalarType::string();
+    let node = &GraphQLInterfaceType::new("Node")
+                  .field("id", |f| f.type_of(string))
+                  .build();
+    GraphQLInterfaceType::new("Image")
+      .field("url", |f| f.type_of(string))
+      .impl_interface(node)
+      .build();
+  }
+
+  #[test]
+  #[should_panic(expected = "Interface field Node.id expects type String! but Photo.id is type \
+                             String.")]
+  fn test_interface_field_type_covariance() {
+    let string = &GraphQLScalarType::string();
+    let node = &GraphQLInterfaceType::new("Node")
+                  .field("id", |f| f.type_of(string))
+                  .build();
+    GraphQLObjectType::new("Photo")
+      .field("id", |f| f.type_of(&GraphQLOptionalType::output(string)))
+      .impl_interface(node)
+      .build();
+  }
+
+  #[test]
+  #[should_panic(expected = "Interface field argument Resource.url(size:) expects type Int! but \
+                             Photo.url(size:) is type Int.")]
+  fn test_interface_argument_type() {
+    let int = &GraphQLScalarType::int();
+    let string = &GraphQLScalarType::string();
+    let resource = &GraphQLInterfaceType::new("Resource")
+                      .field("url", |f| f.type_of(string).arg("size", |a| a.type_of(int)))
+                      .build();
+    GraphQLObjectType::new("Photo")
+      .field("url", |f| {
+        f.type_of(string).arg("size", |a| a.type_of(&GraphQLOptionalType::input(int)))
+      })
+      .impl_interface(resource)
+      .build();
+  }
+
+  #[test]
+  #[should_panic(expected = "Field Photo.url includes required argument format that is missing \
+                             from the Interface field Resource.url.")]
+  fn test_interface_extra_argument_optional() {
+    let string = &GraphQLScalarType::string();
+    let resource = &GraphQLInterfaceType::new("Resource")
+                      .field("url", |f| f.type_of(string))
+                      .build();
+    GraphQLObjectType::new("Photo")
+      .field("url", |f| f.type_of(string).arg("format", |a| a.type_of(string)))
+      .impl_interface(resource)
+      .build();
+  }
+
+  #[test]
+  fn test_abstract_type_resolution() {
+    struct Person;
+    struct Business;
+    struct Photo;
+
+    let int = &GraphQLScalarType::int();
+    let string = &GraphQLScalarType::string();
+
+    let named_entity = &GraphQLInterfaceType::new("NamedEntity")
+                          .field("name", |f| f.type_of(string))
+                          .build();
+    let person = &GraphQLObjectType::new("Person")
+                    .field("name", |f| f.type_of(string))
+                    .impl_interface(named_entity)
+                    .is_type_of(|v| v.is::<Person>())
+                    .build();
+    let business = &GraphQLObjectType::new("Business")
+                      .field("name", |f| f.type_of(string))
+                      .impl_interface(named_entity)
+                      .is_type_of(|v| v.is::<Business>())
+                      .build();
+    let photo = &GraphQLObjectType::new("Photo")
+                   .field("width", |f| f.type_of(int))
+                   .build();
+    let query = &GraphQLObjectType::new("Query")
+                   .field("entity", |f| f.type_of(named_entity))
+                   .build();
+    let schema = GraphQLSchemaType::new(query)
+                   .output_type(person)
+                   .output_type(business)
+                   .build()
+                   .unwrap();
+    assert_eq!(vec!["Person", "Business"],
+               schema.possible_types(named_entity).iter().map(|t| t.name()).collect::<Vec<_>>());
+    assert_eq!(Some(true), person.is_type_of(&Person));
+    assert_eq!(Some(false), business.is_type_of(&Person));
+    assert_eq!(None, photo.is_type_of(&Photo));
+
+    assert_eq!("Person", named_entity.resolve_type(&schema, &Person).unwrap().name());
+    assert_eq!("Business", named_entity.resolve_type(&schema, &Business).unwrap().name());
+    assert!(named_entity.resolve_type(&schema, &Photo).is_err());
+
+    // Possible types are the ones of each schema, whichever objects implement
+    // the interface elsewhere.
+    let other = GraphQLSchemaType::new(query).output_type(person).build().unwrap();
+    assert_eq!(vec!["Person"],
+               other.possible_types(named_entity).iter().map(|t| t.name()).collect::<Vec<_>>());
+    assert!(named_entity.resolve_type(&other, &Business).is_err());
+
+    let search_result = &GraphQLUnionType::new("SearchResult")
+                           .maybe_type_of(person)
+                           .maybe_type_of(photo)
+                           .resolve_type(|v| {
+                             if v.is::<Person>() {
+                               Some("Person".to_owned())
+                             } else if v.is::<Photo>() {
+                               Some("Photo".to_owned())
+                             } else if v.is::<Business>() {
+                               Some("Business".to_owned())
+                             } else {
+                               None
+                             }
+                           })
+                           .build();
+    assert_eq!("Person", search_result.resolve_type(&Person).unwrap().name());
+    assert_eq!("Photo", search_result.resolve_type(&Photo).unwrap().name());
+    assert_eq!(Err("Runtime Object type Business is not a possible type for SearchResult."
+                     .to_owned()),
+               search_result.resolve_type(&Business)
+                 .map(|t| t.name().to_owned())
+                 .map_err(|e| e.message().to_owned()));
+    assert_eq!(Err("Abstract type SearchResult must resolve to an Object type at runtime."
+                     .to_owned()),
+               search_result.resolve_type(&1)
+                 .map(|t| t.name().to_owned())
+                 .map_err(|e| e.message().to_owned()));
+  }
+
   #[test]
   fn test_union_type() {
     let int = &GraphQLScalarType::int();
@@ -177,6 +463,46 @@ mod tests {
     assert_eq!("DAYS", days.name());
   }
 
+  #[test]
+  fn test_enum_internal_values() {
+    #[derive(Debug, PartialEq)]
+    enum Color {
+      Red,
+      Green,
+    }
+
+    let rgb = GraphQLEnumType::new("RGB")
+                .value("RED", |v| v.internal_value(Color::Red))
+                .value("GREEN", |v| v.internal_value(Color::Green))
+                .value("BLUE", |v| v)
+                .build();
+    assert_eq!(Some(&Color::Red),
+               rgb.coerce_literal("RED").and_then(|v| v.downcast_ref::<Color>()));
+    assert_eq!(Some(&Color::Green),
+               rgb.coerce_variable(&Value::String("GREEN".to_owned()))
+                  .and_then(|v| v.downcast_ref::<Color>()));
+    assert_eq!(Some(&"BLUE".to_owned()),
+               rgb.coerce_literal("BLUE").and_then(|v| v.downcast_ref::<String>()));
+    assert!(rgb.coerce_literal("PURPLE").is_none());
+    assert!(rgb.coerce_variable(&Value::Enum("RED".to_owned())).is_none());
+
+    assert_eq!(Some("RED"), rgb.serialize(&Color::Red));
+    assert_eq!(Some("GREEN"), rgb.serialize(&Color::Green));
+    assert_eq!(Some("BLUE"), rgb.serialize(&"BLUE".to_owned()));
+    assert_eq!(Some("BLUE"), rgb.serialize(&"BLUE"));
+    assert_eq!(None, rgb.serialize(&"PURPLE"));
+    assert_eq!(None, rgb.serialize(&1));
+
+    let priority = GraphQLEnumType::new("Priority")
+                     .value("LOW", |v| v.internal_value(0))
+                     .value("HIGH", |v| v.internal_value(10))
+                     .build();
+    assert_eq!(Some(&10),
+               priority.coerce_literal("HIGH").and_then(|v| v.downcast_ref::<i32>()));
+    assert_eq!(Some("LOW"), priority.serialize(&0));
+    assert_eq!(None, priority.serialize(&5));
+  }
+
   #[test]
   fn test_input_object_type() {
     let float = &GraphQLScalarType::float();
@@ -189,4 +515,1890 @@ mod tests {
                        .build();
     assert_eq!("GeoPoint", geo_point.name());
   }
+
+  #[test]
+  fn test_argument_and_input_field_metadata() {
+    let int = &GraphQLScalarType::int();
+    let string = &GraphQLScalarType::string();
+    let optional_int = &GraphQLOptionalType::input(int);
+    let list_of_string = &GraphQLListType::input(string);
+
+    let filter = &GraphQLInputObjectType::new("Filter")
+                   .field("tags", |f| {
+                     f.type_of(list_of_string)
+                      .description("Tags to match")
+                      .default_value(Value::String("all".to_owned()))
+                   })
+                   .field("limit", |f| f.type_of(optional_int).mark_deprecated("Use `first`"))
+                   .build();
+    let fields = filter.fields();
+    assert_eq!(Some("Tags to match"), fields["tags"].description());
+    assert_eq!(Some(&Value::String("all".to_owned())),
+               fields["tags"].default_value());
+    assert!(!fields["tags"].is_required());
+    assert_eq!(Some("Use `first`"), fields["limit"].deprecation_reason());
+    drop(fields);
+
+    let query = &GraphQLObjectType::new("Query")
+                   .field("search", |f| {
+                     f.type_of(string)
+                      .arg("filter", |a| a.type_of(filter).description("Search filter"))
+                      .arg("first", |a| a.type_of(int).default_value(Value::Int(10)))
+                      .arg("page", |a| a.type_of(optional_int).mark_deprecated("Use `first`"))
+                   })
+                   .build();
+    let fields = query.fields();
+    let args = fields["search"].args().unwrap();
+    assert_eq!(Some("Search filter"), args["filter"].description());
+    assert!(args["filter"].is_required());
+    assert_eq!(Some(&Value::Int(10)), args["first"].default_value());
+    assert!(!args["first"].is_required());
+    assert_eq!(Some("Use `first`"), args["page"].deprecation_reason());
+
+    assert_eq!(Ok(()), validate_input_value(&**filter, &Value::Object(Default::default())));
+    let mut value = indexmap::IndexMap::new();
+    value.insert("size".to_owned(), Value::Int(1));
+    assert_eq!(Err("Field size is not defined by type Filter.".to_owned()),
+               validate_input_value(&**filter, &Value::Object(value)));
+    assert_eq!(Err("Expected type Int!, found \"10\".".to_owned()),
+               validate_input_value(&**int, &Value::String("10".to_owned())));
+    assert_eq!(Err("Expected type Int, found \"10\".".to_owned()),
+               validate_input_value(&**optional_int, &Value::String("10".to_owned())));
+  }
+
+  #[test]
+  #[should_panic(expected = "Argument first has invalid default value: Expected type Int!, found \
+                             \"ten\".")]
+  fn test_argument_invalid_default_value() {
+    let int = &GraphQLScalarType::int();
+    GraphQLObjectType::new("Query")
+      .field("search", |f| {
+        f.type_of(int).arg("first", |a| a.type_of(int).default_value(Value::String("ten".to_owned())))
+      })
+      .build();
+  }
+
+  #[test]
+  #[should_panic(expected = "Required input object field lat cannot be deprecated.")]
+  fn test_required_input_field_deprecated() {
+    let float = &GraphQLScalarType::float();
+    GraphQLInputObjectType::new("GeoPoint")
+      .field("lat", |f| f.type_of(float).mark_deprecated("Use `latitude`"))
+      .build();
+  }
+
+  #[test]
+  fn test_value_display() {
+    let mut fields = indexmap::IndexMap::new();
+    fields.insert("name".to_owned(), Value::String("a \"b\"\n".to_owned()));
+    fields.insert("tags".to_owned(),
+                  Value::List(vec![Value::Enum("RED".to_owned()), Value::Null]));
+    fields.insert("ratio".to_owned(), Value::Float(1.0));
+    assert_eq!(r#"{name: "a \"b\"\n", tags: [RED, null], ratio: 1.0}"#,
+               Value::Object(fields).to_string());
+  }
+
+  #[test]
+  fn test_schema() {
+    let int = &GraphQLScalarType::int();
+    let string = &GraphQLScalarType::string();
+    let node = &GraphQLInterfaceType::new("Node")
+                  .field("id", |f| f.type_of(string))
+                  .build();
+    let photo = &GraphQLObjectType::new("Photo")
+                   .field("id", |f| f.type_of(string))
+                   .field("width", |f| f.type_of(int))
+                   .impl_interface(node)
+                   .build();
+    let size = &GraphQLEnumType::new("Size")
+                  .value("SMALL", |v| v)
+                  .value("LARGE", |v| v)
+                  .build();
+    let query = &GraphQLObjectType::new("Query")
+                   .field("node", |f| f.type_of(node).arg("id", |a| a.type_of(string)))
+                   .field("photos", |f| {
+                     f.type_of(&GraphQLListType::output(photo)).arg("size", |a| a.type_of(size))
+                   })
+                   .build();
+
+    let schema = GraphQLSchemaType::new(query).build().unwrap();
+    assert_eq!("Query", schema.query_type().name());
+    assert!(schema.mutation_type().is_none());
+    assert_eq!(vec!["Query", "String", "Node", "Size", "Photo", "Int"],
+               schema.types().keys().collect::<Vec<_>>());
+    assert_eq!(Some("Size"), schema.get_type("Size").map(|t| t.name()));
+  }
+
+  #[test]
+  fn test_schema_validation() {
+    let string = &GraphQLScalarType::string();
+    let color = &GraphQLEnumType::new("Color")
+                   .value("RED", |v| v)
+                   .value("true", |v| v)
+                   .value("__GREEN", |v| v)
+                   .build();
+    let query = &GraphQLObjectType::new("my query")
+                   .field("__color", |f| f.type_of(color))
+                   .field("author", |f| f.placeholder_type_of("Author"))
+                   .field("hello", |f| f.type_of(string).arg("1st", |a| a.type_of(string)))
+                   .build();
+
+    let errors = GraphQLSchemaType::new(query).build().err().unwrap();
+    assert_eq!(vec!["Names must only contain [_a-zA-Z0-9] but \"my query\" does not.",
+                    "Name \"__color\" must not begin with \"__\", which is reserved by GraphQL \
+                     introspection.",
+                    "Field my query.author references unknown type Author.",
+                    "Names must start with [_a-zA-Z] but \"1st\" does not.",
+                    "Enum type Color cannot include value: true.",
+                    "Name \"__GREEN\" must not begin with \"__\", which is reserved by GraphQL \
+                     introspection."],
+               errors.iter().map(|e| e.message()).collect::<Vec<_>>());
+
+    // Two different types of one name, reached twice each.
+    let author = || GraphQLObjectType::new("Author").field("name", |f| f.type_of(string)).build();
+    let (author, other_author) = (&author(), &author());
+    let query = &GraphQLObjectType::new("Query")
+                   .field("author", |f| f.type_of(author))
+                   .field("authors", |f| f.type_of(&GraphQLListType::output(author)))
+                   .field("editor", |f| f.type_of(other_author))
+                   .field("reviewer", |f| f.type_of(other_author))
+                   .build();
+    let errors = GraphQLSchemaType::new(query).build().err().unwrap();
+    assert_eq!(vec!["Schema must contain uniquely named types but contains multiple types named \
+                     \"Author\"."],
+               errors.iter().map(|e| e.message()).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn test_schema_input_object_cycle() {
+    let string = &GraphQLScalarType::string();
+    let author_filter = &GraphQLInputObjectType::new("AuthorFilter")
+                           .field("name", |f| f.type_of(string))
+                           .field("book", |f| f.placeholder_type_of("BookFilter"))
+                           .build();
+    let book_filter = &GraphQLInputObjectType::new("BookFilter")
+                         .field("author", |f| f.type_of(author_filter))
+                         .build();
+    author_filter.replace_field_placeholder_type("book", book_filter);
+    let query = &GraphQLObjectType::new("Query")
+                   .field("books", |f| f.type_of(string).arg("filter", |a| a.type_of(book_filter)))
+                   .build();
+
+    let errors = GraphQLSchemaType::new(query).build().err().unwrap();
+    assert_eq!(vec!["Cannot reference Input Object \"BookFilter\" within itself through a series \
+                     of non-null fields: \"author.book\"."],
+               errors.iter().map(|e| e.message()).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn test_optional_values() {
+    let optional = GraphQLOptionalType::output(&GraphQLScalarType::string());
+    let name = "Luke".to_owned();
+    assert_eq!(Some(&name),
+               optional.value_of(&Some(name.clone())).and_then(|v| v.downcast_ref::<String>()));
+    assert!(optional.value_of(&None::<String>).is_none());
+    assert!(optional.value_of(&None::<Box<dyn Any>>).is_none());
+    assert!(optional.value_of(&()).is_none());
+    assert!(is_null(&None::<i32>));
+    assert!(!is_null(&Some(1)));
+  }
+
+  #[test]
+  fn test_build_schema() {
+    use sdl::build_schema;
+    use graphql_language::sdl::parse_schema;
+
+    let document = parse_schema(r#"
+      "A character"
+      interface Character { name: String! }
+      type Human implements Character {
+        name: String!
+        friends(first: Int = 10, episodes: [Episode!]): [Character]
+        height: Float @deprecated
+      }
+      enum Episode { NEWHOPE, EMPIRE @deprecated(reason: "Too dark") }
+      union Result = Human
+      input Filter { name: String = "Luke", episode: Episode }
+      scalar Date
+      type Query { hero(filter: Filter): Character, search: [Result!]!, today: Date }
+    "#)
+      .unwrap();
+    let schema = build_schema(&document).unwrap();
+    assert_eq!("Query", schema.query_type().name());
+    assert_eq!(vec!["Query", "Filter", "String", "Episode", "Character", "Result", "Human", "Int",
+                    "Float", "Date"],
+               schema.types().keys().collect::<Vec<_>>());
+
+    let character = schema.get_type("Character").unwrap();
+    assert_eq!(Some("A character"), character.description());
+    let human = match schema.get_type("Human") {
+      Some(SchemaType::Output(human)) => human.clone(),
+      _ => panic!("Human is not an output type"),
+    };
+    let human = match human.output_kind() {
+      OutputKind::Object(human) => human,
+      _ => panic!("Human is not an object type"),
+    };
+    let fields = human.fields();
+    let friends = &fields["friends"];
+    assert_eq!("[Character]", output_type_ref(friends.typ().as_ref()));
+    let args = friends.args().unwrap();
+    assert_eq!(Some(&Value::Int(10)), args["first"].default_value());
+    assert_eq!("[Episode!]", input_type_ref(args["episodes"].typ().as_ref()));
+    assert_eq!(Some("No longer supported"), fields["height"].deprecation_reason());
+    assert!(human.interfaces().unwrap().contains_key("Character"));
+
+    let document = parse_schema("type Query { hero: Hero, ok(id: ID! @deprecated): Boolean }\n\
+                                 interface Node implements Node { id: ID! }\n\
+                                 union Empty")
+      .unwrap();
+    let errors = build_schema(&document).err().unwrap();
+    assert_eq!(vec!["Required argument Query.ok(id:) cannot be deprecated.",
+                    "Union type Empty must define one or more member types."],
+               errors.iter().map(|e| e.message()).collect::<Vec<_>>());
+    let document = parse_schema("type Query { hero: Hero }\n\
+                                 interface Node implements Node { id: ID! }")
+      .unwrap();
+    let errors = build_schema(&document).err().unwrap();
+    assert_eq!(vec!["Type Node cannot implement itself, through Node."],
+               errors.iter().map(|e| e.message()).collect::<Vec<_>>());
+    let document = parse_schema("type Query { hero: Hero }").unwrap();
+    let errors = build_schema(&document).err().unwrap();
+    assert_eq!(vec!["Field Query.hero references unknown type Hero."],
+               errors.iter().map(|e| e.message()).collect::<Vec<_>>());
+    assert_eq!(Some("Query.hero"), errors[0].coordinate());
+    let document = parse_schema("type Query { a: Int, a: String, b(x: Int, x: Int): Int }\n\
+                                 enum E { A, A }\n\
+                                 input I { f: Int, f: Int }\n\
+                                 directive @d(y: Int, y: Int) on FIELD")
+      .unwrap();
+    let errors = build_schema(&document).err().unwrap();
+    assert_eq!(vec![("Query.a", "Field \"Query.a\" can only be defined once."),
+                    ("Query.b(x:)", "Argument \"Query.b(x:)\" can only be defined once."),
+                    ("E.A", "Enum value \"E.A\" can only be defined once."),
+                    ("I.f", "Field \"I.f\" can only be defined once."),
+                    ("@d(y:)", "Argument \"@d(y:)\" can only be defined once.")],
+               errors.iter()
+                 .map(|e| (e.coordinate().unwrap(), e.message()))
+                 .collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn test_error_serialization() {
+    use std::error::Error;
+    use std::io;
+    use lexer::Lexer;
+    use source::Source;
+
+    let source = Source::new("{\n  me {\r\n    name ?\n  }\n}");
+    let mut lexer = Lexer::new(source.body);
+    let error = lexer.find(|t| t.is_err()).unwrap().unwrap_err();
+    let error = GraphQLError::syntax_error(&source, lexer.position(), error);
+    assert_eq!(r#"{"message":"Syntax Error: Unexpected character.","locations":[{"line":3,"column":10}]}"#,
+               serde_json::to_string(&error).unwrap());
+    assert!(error.source().is_some());
+
+    let error = GraphQLError::new("Not authorized")
+      .path(vec!["me".into(), "friends".into(), 1.into()])
+      .extension("code", Value::String("FORBIDDEN".to_owned()))
+      .with_source(io::Error::new(io::ErrorKind::PermissionDenied, "token expired"));
+    assert_eq!(r#"{"message":"Not authorized","path":["me","friends",1],"extensions":{"code":"FORBIDDEN"}}"#,
+               serde_json::to_string(&error).unwrap());
+    assert_eq!("token expired", error.source().unwrap().to_string());
+  }
+
+  fn ast_name(value: &str) -> ast::Name<'_> {
+    ast::Name {
+      loc: None,
+      value,
+    }
+  }
+
+  fn ast_field<'a>(alias: Option<&'a str>,
+                   name: &'a str,
+                   arguments: Vec<(&'a str, ast::Value<'a>)>,
+                   selections: Vec<ast::Selection<'a>>)
+                   -> ast::Selection<'a> {
+    let arguments = arguments.into_iter()
+      .map(|(name, value)| {
+        ast::Argument {
+          loc: None,
+          name: ast_name(name),
+          value,
+        }
+      })
+      .collect::<Vec<_>>();
+    ast::Selection::Field(ast::Field {
+      loc: None,
+      alias: alias.map(ast_name),
+      name: ast_name(name),
+      arguments: if arguments.is_empty() { None } else { Some(arguments) },
+      directives: None,
+      selection_set: if selections.is_empty() {
+        None
+      } else {
+        Some(ast::SelectionSet {
+          loc: None,
+          selections,
+        })
+      },
+    })
+  }
+
+  fn ast_query<'a>(variable_definitions: Option<ast::VariableDefinitions<'a>>,
+                   selections: Vec<ast::Selection<'a>>)
+                   -> ast::Document<'a> {
+    ast_operation(ast::OperationType::Query, variable_definitions, selections)
+  }
+
+  fn ast_operation<'a>(operation: ast::OperationType,
+                       variable_definitions: Option<ast::VariableDefinitions<'a>>,
+                       selections: Vec<ast::Selection<'a>>)
+                       -> ast::Document<'a> {
+    ast::Document {
+      loc: None,
+      definitions: vec![ast::Definition::Operation(ast::OperationDefinition {
+                          loc: None,
+                          operation,
+                          name: None,
+                          variable_definitions,
+                          directives: None,
+                          selection_set: ast::SelectionSet {
+                            loc: None,
+                            selections,
+                          },
+                        })],
+    }
+  }
+
+  struct Hero {
+    name: &'static str,
+    secret: Option<&'static str>,
+  }
+
+  fn hero_type() -> std::rc::Rc<GraphQLObject> {
+    let string = &GraphQLScalarType::string();
+    let optional_string = &GraphQLOptionalType::output(string);
+    GraphQLObjectType::new("Hero")
+      .field("name", |f| {
+        f.type_of(string).resolve(|hero, _, _| {
+          Ok(Box::new(hero.downcast_ref::<Hero>().unwrap().name.to_owned()))
+        })
+      })
+      .field("secret", |f| {
+        f.type_of(string).resolve(|hero, _, _| {
+          match hero.downcast_ref::<Hero>().unwrap().secret {
+            Some(secret) => Ok(Box::new(secret.to_owned())),
+            None => Err("Secret is classified".into()),
+          }
+        })
+      })
+      .field("nickname", |f| {
+        f.type_of(optional_string)
+          .resolve(|_, _, _| Err("Nickname service unavailable".into()))
+      })
+      .build()
+  }
+
+  fn hero_schema() -> std::rc::Rc<GraphQLSchema> {
+    let string = &GraphQLScalarType::string();
+    let hero = &hero_type();
+    let optional_hero = &GraphQLOptionalType::output(hero);
+    let query = &GraphQLObjectType::new("Query")
+                   .field("hero", |f| {
+                     f.type_of(optional_hero).resolve(|_, _, _| {
+                       Ok(Box::new(Hero {
+                         name: "Luke",
+                         secret: None,
+                       }))
+                     })
+                   })
+                   .field("heroes", |f| {
+                     f.type_of(&GraphQLListType::output(optional_hero)).resolve(|_, _, _| {
+                       let heroes: Vec<Box<dyn Any>> = vec![Box::new(Hero {
+                                                              name: "Leia",
+                                                              secret: Some("Jedi"),
+                                                            }),
+                                                            Box::new(Hero {
+                                                              name: "Han",
+                                                              secret: None,
+                                                            })];
+                       Ok(Box::new(heroes))
+                     })
+                   })
+                   .field("me", |f| {
+                     f.type_of(hero).resolve(|_, _, _| {
+                       Ok(Box::new(Hero {
+                         name: "Rey",
+                         secret: None,
+                       }))
+                     })
+                   })
+                   .field("nobody", |f| f.type_of(string).resolve(|_, _, _| Ok(Box::new(()))))
+                   .field("greet", |f| {
+                     f.type_of(string)
+                       .arg("name", |a| a.type_of(string))
+                       .arg("greeting", |a| a.type_of(&GraphQLOptionalType::input(string)))
+                       .resolve(|_, _, args| {
+                         let greeting = match args.get("greeting") {
+                           Some(Value::String(g)) => g.clone(),
+                           _ => "Hello".to_owned(),
+                         };
+                         match args["name"] {
+                           Value::String(ref name) => Ok(Box::new(format!("{}, {}!", greeting, name))),
+                           _ => unreachable!(),
+                         }
+                       })
+                   })
+                   .build();
+    GraphQLSchemaType::new(query).build().unwrap()
+  }
+
+
+  fn execute_query(document: &ast::Document, variables: &Arguments) -> serde_json::Value {
+    let result = execute(&hero_schema(), document, None, variables, &(), &());
+    serde_json::to_value(&result).unwrap()
+  }
+
+  #[test]
+  fn test_execute() {
+    let document = ast_query(None,
+                             vec![ast_field(None, "hero", vec![], vec![ast_field(None, "name", vec![], vec![])]),
+                                  ast_field(Some("rey"),
+                                            "me",
+                                            vec![],
+                                            vec![ast_field(None, "__typename", vec![], vec![]),
+                                                 ast_field(Some("alias"), "name", vec![], vec![])]),
+                                  ast_field(None,
+                                            "greet",
+                                            vec![("name",
+                                                  ast::Value::String(ast::StringValue {
+                                                    loc: None,
+                                                    value: "Leia".to_owned(),
+                                                  }))],
+                                            vec![])]);
+    assert_eq!(execute_query(&document, &Arguments::new()),
+               json!({
+                 "data": {
+                   "hero": { "name": "Luke" },
+                   "rey": { "__typename": "Hero", "alias": "Rey" },
+                   "greet": "Hello, Leia!"
+                 }
+               }));
+  }
+
+  #[test]
+  fn test_execute_variables() {
+    let variable = |name| {
+      ast::Variable {
+        loc: None,
+        name: ast_name(name),
+      }
+    };
+    let named_type = |name| {
+      ast::NamedType {
+        loc: None,
+        name: ast_name(name),
+      }
+    };
+    let definitions = vec![ast::VariableDefinition {
+                             loc: None,
+                             variable: variable("name"),
+                             type_: ast::Type::NonNullNamed(Box::new(ast::NonNullNamedType {
+                               loc: None,
+                               type_: named_type("String"),
+                             })),
+                             default_value: None,
+                           },
+                           ast::VariableDefinition {
+                             loc: None,
+                             variable: variable("greeting"),
+                             type_: ast::Type::Named(named_type("String")),
+                             default_value: None,
+                           }];
+    let document = ast_query(Some(definitions),
+                             vec![ast_field(None,
+                                            "greet",
+                                            vec![("name", ast::Value::Variable(variable("name"))),
+                                                 ("greeting",
+                                                  ast::Value::Variable(variable("greeting")))],
+                                            vec![])]);
+
+    let mut variables = Arguments::new();
+    variables.insert("name".to_owned(), Value::from("Han".to_owned()));
+    variables.insert("greeting".to_owned(), Value::from("Hi".to_owned()));
+    assert_eq!(execute_query(&document, &variables),
+               json!({ "data": { "greet": "Hi, Han!" } }));
+
+    variables.swap_remove("greeting");
+    assert_eq!(execute_query(&document, &variables),
+               json!({ "data": { "greet": "Hello, Han!" } }));
+
+    assert_eq!(execute_query(&document, &Arguments::new()),
+               json!({
+                 "errors": [{
+                   "message": "Variable \"$name\" of required type \"String!\" was not provided."
+                 }]
+               }));
+
+    // Values not of the declared type fail the request rather than the field.
+    variables.insert("name".to_owned(), Value::Null);
+    assert_eq!(execute_query(&document, &variables),
+               json!({
+                 "errors": [{
+                   "message": "Variable \"$name\" got invalid value null; Expected type String!, \
+                               found null."
+                 }]
+               }));
+    variables.insert("name".to_owned(), Value::from("Han".to_owned()));
+    variables.insert("greeting".to_owned(), Value::Int(1));
+    assert_eq!(execute_query(&document, &variables),
+               json!({
+                 "errors": [{
+                   "message": "Variable \"$greeting\" got invalid value 1; Expected type String, \
+                               found 1."
+                 }]
+               }));
+  }
+
+  #[test]
+  fn test_execute_null_propagation() {
+    let name = || ast_field(None, "name", vec![], vec![]);
+    let secret = || ast_field(None, "secret", vec![], vec![]);
+    let no_args = Arguments::new();
+
+    // The error of a non-null field nulls its nearest optional parent.
+    let document = ast_query(None, vec![ast_field(None, "hero", vec![], vec![name(), secret()])]);
+    assert_eq!(execute_query(&document, &no_args),
+               json!({
+                 "errors": [{ "message": "Secret is classified", "path": ["hero", "secret"] }],
+                 "data": { "hero": null }
+               }));
+
+    // Only the failing item of a list of optional values is nulled.
+    let document = ast_query(None, vec![ast_field(None, "heroes", vec![], vec![name(), secret()])]);
+    assert_eq!(execute_query(&document, &no_args),
+               json!({
+                 "errors": [{ "message": "Secret is classified", "path": ["heroes", 1, "secret"] }],
+                 "data": { "heroes": [{ "name": "Leia", "secret": "Jedi" }, null] }
+               }));
+
+    // The error of an optional field leaves its siblings intact.
+    let document = ast_query(None,
+                             vec![ast_field(None,
+                                            "hero",
+                                            vec![],
+                                            vec![name(), ast_field(None, "nickname", vec![], vec![])])]);
+    assert_eq!(execute_query(&document, &no_args),
+               json!({
+                 "errors": [{
+                   "message": "Nickname service unavailable",
+                   "path": ["hero", "nickname"]
+                 }],
+                 "data": { "hero": { "name": "Luke", "nickname": null } }
+               }));
+
+    // Without an optional parent, `data` itself becomes null.
+    let document = ast_query(None,
+                             vec![ast_field(None, "me", vec![], vec![secret()]),
+                                  ast_field(None, "hero", vec![], vec![name()])]);
+    assert_eq!(execute_query(&document, &no_args),
+               json!({
+                 "errors": [{ "message": "Secret is classified", "path": ["me", "secret"] }],
+                 "data": null
+               }));
+
+    let document = ast_query(None, vec![ast_field(None, "nobody", vec![], vec![])]);
+    assert_eq!(execute_query(&document, &no_args),
+               json!({
+                 "errors": [{
+                   "message": "Cannot return null for non-nullable field Query.nobody.",
+                   "path": ["nobody"]
+                 }],
+                 "data": null
+               }));
+  }
+
+  fn ast_directive<'a>(name: &'a str, arguments: Vec<(&'a str, ast::Value<'a>)>) -> ast::Directive<'a> {
+    let arguments = arguments.into_iter()
+      .map(|(name, value)| {
+        ast::Argument {
+          loc: None,
+          name: ast_name(name),
+          value,
+        }
+      })
+      .collect::<Vec<_>>();
+    ast::Directive {
+      loc: None,
+      name: ast_name(name),
+      arguments: if arguments.is_empty() { None } else { Some(arguments) },
+    }
+  }
+
+  fn ast_inline_fragment<'a>(directives: Vec<ast::Directive<'a>>,
+                             selections: Vec<ast::Selection<'a>>)
+                             -> ast::Selection<'a> {
+    ast::Selection::InlineFragment(ast::InlineFragment {
+      loc: None,
+      type_condition: None,
+      directives: Some(directives),
+      selection_set: ast::SelectionSet {
+        loc: None,
+        selections,
+      },
+    })
+  }
+
+  fn with_directives<'a>(selection: ast::Selection<'a>,
+                         directives: Vec<ast::Directive<'a>>)
+                         -> ast::Selection<'a> {
+    match selection {
+      ast::Selection::Field(field) => {
+        ast::Selection::Field(ast::Field {
+          directives: Some(directives),
+          ..field
+        })
+      }
+      _ => unreachable!(),
+    }
+  }
+
+  fn ast_string<'a>(value: &str) -> ast::Value<'a> {
+    ast::Value::String(ast::StringValue {
+      loc: None,
+      value: value.to_owned(),
+    })
+  }
+
+  fn execute_incremental_query(document: &ast::Document) -> Vec<serde_json::Value> {
+    execute_incremental(&hero_schema(), document, None, &Arguments::new(), &(), &())
+      .iter()
+      .map(|result| serde_json::to_value(result).unwrap())
+      .collect()
+  }
+
+  #[test]
+  fn test_execute_incremental() {
+    let field = |name| ast_field(None, name, vec![], vec![]);
+    let defer = |label| {
+      let args = match label {
+        Some(label) => vec![("label", ast_string(label))],
+        None => vec![],
+      };
+      ast_directive("defer", args)
+    };
+
+    // A deferred fragment comes after the initial result, with its own errors.
+    let document = ast_query(None,
+                             vec![ast_field(None,
+                                            "me",
+                                            vec![],
+                                            vec![field("name"),
+                                                 ast_inline_fragment(vec![defer(Some("nick"))],
+                                                                     vec![field("nickname")])])]);
+    assert_eq!(execute_incremental_query(&document),
+               vec![json!({ "data": { "me": { "name": "Rey" } }, "hasNext": true }),
+                    json!({
+                      "incremental": [{
+                        "errors": [{
+                          "message": "Nickname service unavailable",
+                          "path": ["me", "nickname"]
+                        }],
+                        "data": { "nickname": null },
+                        "path": ["me"],
+                        "label": "nick"
+                      }],
+                      "hasNext": false
+                    })]);
+    // Without incremental delivery, the fragment is part of the result.
+    assert_eq!(execute_query(&document, &Arguments::new()),
+               json!({
+                 "errors": [{ "message": "Nickname service unavailable", "path": ["me", "nickname"] }],
+                 "data": { "me": { "name": "Rey", "nickname": null } }
+               }));
+
+    // Errors of non-null fields propagate up to the fragment.
+    let document = ast_query(None,
+                             vec![ast_field(None,
+                                            "hero",
+                                            vec![],
+                                            vec![ast_inline_fragment(vec![defer(None)],
+                                                                     vec![field("secret")])])]);
+    assert_eq!(execute_incremental_query(&document),
+               vec![json!({ "data": { "hero": {} }, "hasNext": true }),
+                    json!({
+                      "incremental": [{
+                        "errors": [{ "message": "Secret is classified", "path": ["hero", "secret"] }],
+                        "data": null,
+                        "path": ["hero"]
+                      }],
+                      "hasNext": false
+                    })]);
+
+    // A fragment under a field nulled by an error is not delivered.
+    let document = ast_query(None,
+                             vec![ast_field(None,
+                                            "hero",
+                                            vec![],
+                                            vec![ast_inline_fragment(vec![defer(None)],
+                                                                     vec![field("name")])]),
+                                  field("nobody")]);
+    assert_eq!(execute_incremental_query(&document),
+               vec![json!({
+                      "errors": [{
+                        "message": "Cannot return null for non-nullable field Query.nobody.",
+                        "path": ["nobody"]
+                      }],
+                      "data": null
+                    })]);
+
+    // Fragments are not deferred with `if: false`.
+    let disabled = ast::Value::Boolean(ast::BooleanValue {
+      loc: None,
+      value: false,
+    });
+    let document = ast_query(None,
+                             vec![ast_field(None,
+                                            "hero",
+                                            vec![],
+                                            vec![ast_inline_fragment(vec![ast_directive("defer",
+                                                                                        vec![("if", disabled)])],
+                                                                     vec![field("name")])])]);
+    assert_eq!(execute_incremental_query(&document),
+               vec![json!({ "data": { "hero": { "name": "Luke" } } })]);
+  }
+
+  #[test]
+  fn test_execute_stream() {
+    let heroes = |initial_count| {
+      let args = vec![("label", ast_string("heroes")),
+                      ("initialCount", ast::Value::Int(ast::IntValue {
+                        loc: None,
+                        value: initial_count,
+                      }))];
+      let field = ast_field(None,
+                            "heroes",
+                            vec![],
+                            vec![ast_field(None, "name", vec![], vec![]),
+                                 ast_field(None, "secret", vec![], vec![])]);
+      ast_query(None, vec![with_directives(field, vec![ast_directive("stream", args)])])
+    };
+
+    assert_eq!(execute_incremental_query(&heroes("1")),
+               vec![json!({
+                      "data": { "heroes": [{ "name": "Leia", "secret": "Jedi" }] },
+                      "hasNext": true
+                    }),
+                    json!({
+                      "incremental": [{
+                        "errors": [{ "message": "Secret is classified", "path": ["heroes", 1, "secret"] }],
+                        "items": [null],
+                        "path": ["heroes", 1],
+                        "label": "heroes"
+                      }],
+                      "hasNext": false
+                    })]);
+    assert_eq!(execute_incremental_query(&heroes("0")).len(), 3);
+    assert_eq!(execute_incremental_query(&heroes("-1")),
+               vec![json!({
+                      "errors": [{ "message": "initialCount must be a positive integer", "path": ["heroes"] }],
+                      "data": null
+                    })]);
+  }
+
+  #[derive(Clone)]
+  struct Author {
+    name: &'static str,
+    country: &'static str,
+  }
+
+  struct Loaders {
+    authors: loader::DataLoader<u32, Author>,
+    countries: loader::DataLoader<&'static str, &'static str>,
+    batches: std::rc::Rc<std::cell::RefCell<Vec<String>>>,
+  }
+
+  fn loaders() -> Loaders {
+    let batches = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let log = batches.clone();
+    let authors = loader::DataLoader::new(move |ids: &[u32]| {
+      log.borrow_mut().push(format!("authors {:?}", ids));
+      let authors = vec![(1, Author { name: "Ada", country: "uk" }),
+                         (2, Author { name: "Grace", country: "us" })];
+      Ok(authors.into_iter().filter(|&(id, _)| ids.contains(&id)).collect())
+    });
+    let log = batches.clone();
+    let countries = loader::DataLoader::new(move |codes: &[&'static str]| {
+      log.borrow_mut().push(format!("countries {:?}", codes));
+      Ok(vec![("uk", "United Kingdom")].into_iter().collect())
+    });
+    Loaders { authors, countries, batches }
+  }
+
+  #[test]
+  fn test_data_loader() {
+    let string = &GraphQLScalarType::string();
+    let country = &GraphQLObjectType::new("Country")
+                     .field("name", |f| {
+                       f.type_of(string).resolve(|name, _, _| {
+                         Ok(Box::new(name.downcast_ref::<&str>().unwrap().to_string()))
+                       })
+                     })
+                     .build();
+    let author = &GraphQLObjectType::new("Author")
+                    .field("name", |f| {
+                      f.type_of(string).resolve(|author, _, _| {
+                        Ok(Box::new(author.downcast_ref::<Author>().unwrap().name.to_owned()))
+                      })
+                    })
+                    .field("country", |f| {
+                      f.type_of(&GraphQLOptionalType::output(country)).resolve(|author, context, _| {
+                        let code = author.downcast_ref::<Author>().unwrap().country;
+                        Ok(Box::new(context.downcast_ref::<Loaders>().unwrap().countries.load_optional(code)))
+                      })
+                    })
+                    .build();
+    let article = &GraphQLObjectType::new("Article")
+                     .field("title", |f| {
+                       f.type_of(string).resolve(|article, _, _| {
+                         Ok(Box::new(article.downcast_ref::<(&str, u32)>().unwrap().0.to_owned()))
+                       })
+                     })
+                     .field("author", |f| {
+                       f.type_of(author).resolve(|article, context, _| {
+                         let id = article.downcast_ref::<(&str, u32)>().unwrap().1;
+                         Ok(Box::new(context.downcast_ref::<Loaders>().unwrap().authors.load(id)))
+                       })
+                     })
+                     .build();
+    let query = &GraphQLObjectType::new("Query")
+                   .field("articles", |f| {
+                     f.type_of(&GraphQLListType::output(&GraphQLOptionalType::output(article)))
+                       .resolve(|_, _, _| {
+                         let articles: Vec<Box<dyn Any>> =
+                           vec![Box::new(("A", 1u32)), Box::new(("B", 2u32)), Box::new(("C", 1u32)),
+                                Box::new(("D", 9u32))];
+                         Ok(Box::new(articles))
+                       })
+                   })
+                   .build();
+    let schema = GraphQLSchemaType::new(query).build().unwrap();
+
+    let field = |name, selections| ast_field(None, name, vec![], selections);
+    let document = ast_query(None,
+                             vec![field("articles",
+                                        vec![field("title", vec![]),
+                                             field("author",
+                                                   vec![field("name", vec![]),
+                                                        field("country", vec![field("name", vec![])])])])]);
+    let context = loaders();
+    let result = execute(&schema, &document, None, &Arguments::new(), &(), &context);
+    assert_eq!(serde_json::to_value(&result).unwrap(),
+               json!({
+                 "errors": [{ "message": "No value was loaded for the key.", "path": ["articles", 3, "author"] }],
+                 "data": {
+                   "articles": [
+                     { "title": "A", "author": { "name": "Ada", "country": { "name": "United Kingdom" } } },
+                     { "title": "B", "author": { "name": "Grace", "country": null } },
+                     { "title": "C", "author": { "name": "Ada", "country": { "name": "United Kingdom" } } },
+                     null
+                   ]
+                 }
+               }));
+    // One batch per loader and level, and none for cached keys.
+    assert_eq!(*context.batches.borrow(),
+               vec!["authors [1, 2, 9]".to_owned(), "countries [\"uk\", \"us\"]".to_owned()]);
+    execute(&schema, &document, None, &Arguments::new(), &(), &context);
+    assert_eq!(context.batches.borrow().len(), 2);
+  }
+
+  #[test]
+  fn test_validate_defer_stream() {
+    let schema = subscription_schema(&HeroQueue::default());
+    let field = |name| ast_field(None, name, vec![], vec![]);
+    let defer = |args| ast_directive("defer", args);
+    let messages = |document: &ast::Document| {
+      validation::validate(&schema, document).into_iter().map(|e| e.message().to_owned()).collect::<Vec<_>>()
+    };
+
+    let document = ast_operation(ast::OperationType::Subscription,
+                                 None,
+                                 vec![ast_inline_fragment(vec![defer(vec![])],
+                                                          vec![ast_field(None,
+                                                                         "heroAdded",
+                                                                         vec![],
+                                                                         vec![field("name")])])]);
+    assert_eq!(messages(&document),
+               vec!["Defer directive cannot be used on root subscription type \"Subscription\".",
+                    "Defer directive not supported on subscription operations. Disable `@defer` \
+                     by setting the `if` argument to `false`."]);
+
+    let variable = ast::Value::Variable(ast::Variable {
+      loc: None,
+      name: ast_name("defer"),
+    });
+    let document = ast_operation(ast::OperationType::Subscription,
+                                 None,
+                                 vec![ast_field(None,
+                                                "heroAdded",
+                                                vec![],
+                                                vec![ast_inline_fragment(vec![defer(vec![("if", variable)])],
+                                                                         vec![field("name")])])]);
+    assert_eq!(messages(&document), Vec::<String>::new());
+
+    let label = |label| vec![("label", ast_string(label))];
+    let document = ast_query(None,
+                             vec![with_directives(ast_field(None, "hero", vec![], vec![field("name")]),
+                                                  vec![ast_directive("stream", vec![])]),
+                                  ast_field(None,
+                                            "hero",
+                                            vec![],
+                                            vec![ast_inline_fragment(vec![defer(label("a"))],
+                                                                     vec![field("name")]),
+                                                 ast_inline_fragment(vec![defer(label("a"))],
+                                                                     vec![field("name")])])]);
+    assert_eq!(messages(&document),
+               vec!["Stream directive cannot be used on non-list field \"hero\" on type \"Query\".",
+                    "Defer/Stream directive label argument must be unique."]);
+  }
+
+  #[test]
+  fn test_introspection() {
+    use introspection::{introspect, schema_document};
+    use sdl::build_schema;
+    use graphql_language::sdl::{parse_schema, print_schema};
+
+    let document = parse_schema(r#"
+      schema { query: Root }
+      "A character"
+      interface Character { name: String! }
+      type Human implements Character {
+        name: String!
+        friends(first: Int = 10, episodes: [Episode!]): [Character]
+        height: Float @deprecated
+      }
+      enum Episode { NEWHOPE, EMPIRE @deprecated(reason: "Too dark") }
+      input Filter { name: String = "Luke", episode: Episode = NEWHOPE }
+      type Root { hero(filter: Filter): Character, humans: [Human!]! }
+    "#)
+      .unwrap();
+    let result = introspect(&build_schema(&document).unwrap());
+    let schema = &result["__schema"];
+    assert_eq!(json!({ "name": "Root" }), schema["queryType"]);
+    assert_eq!(json!(null), schema["mutationType"]);
+    let human = schema["types"].as_array().unwrap().iter().find(|t| t["name"] == "Human").unwrap();
+    assert_eq!(json!([{ "kind": "INTERFACE", "name": "Character", "ofType": null }]),
+               human["interfaces"]);
+    assert_eq!(json!({ "kind": "LIST", "name": null,
+                       "ofType": { "kind": "INTERFACE", "name": "Character", "ofType": null } }),
+               human["fields"][1]["type"]);
+    assert_eq!(json!("10"), human["fields"][1]["args"][0]["defaultValue"]);
+    assert_eq!(json!("No longer supported"), human["fields"][2]["deprecationReason"]);
+    let directives: Vec<_> = schema["directives"].as_array().unwrap().iter()
+      .map(|d| d["name"].as_str().unwrap())
+      .collect();
+    assert_eq!(vec!["include", "skip", "deprecated", "defer", "stream"], directives);
+
+    let document = schema_document(&json!({ "data": result })).unwrap();
+    assert_eq!(r#"schema {
+  query: Root
+}
+
+type Root {
+  hero(filter: Filter): Character
+  humans: [Human!]!
+}
+
+input Filter {
+  name: String = "Luke"
+  episode: Episode = NEWHOPE
+}
+
+enum Episode {
+  NEWHOPE
+  EMPIRE @deprecated(reason: "Too dark")
+}
+
+"""A character"""
+interface Character {
+  name: String!
+}
+
+type Human implements Character {
+  name: String!
+  friends(first: Int = 10, episodes: [Episode!]): [Character]
+  height: Float @deprecated
+}
+"#,
+               print_schema(&document));
+    assert_eq!("Expected an introspection result with a \"__schema\" object.",
+               schema_document(&json!({ "data": null })).unwrap_err());
+  }
+
+  #[test]
+  fn test_validate_names() {
+    use graphql_language::parser::parse;
+    use graphql_language::sdl::parse_schema;
+    use sdl::build_schema;
+
+    let document = parse_schema("type Query { hero(id: ID): Hero }\n\
+                                 type Hero { name: String, friends: [Hero] }")
+      .unwrap();
+    let schema = build_schema(&document).unwrap();
+    let source = Source::new("query Q($skip: Boolean!, $id: Id) {\n  \
+                                hero(id: $id, first: 2) { name { first } friends @skip(if: $skip) \
+                                ...Rest @live }\n  \
+                                hero { age __typename ... on Droid { name } }\n\
+                              }\n\
+                              mutation { hero }");
+    let document = parse(&source).unwrap();
+    let errors = validation::validate_names(&schema, &document);
+    assert_eq!(vec![("Unknown type \"Id\".", 1, 31),
+                    ("Unknown argument \"first\" on field \"Query.hero\".", 2, 17),
+                    ("Field \"name\" must not have a selection since type \"String\" has no \
+                      subfields.",
+                     2,
+                     29),
+                    ("Field \"friends\" of type \"[Hero]\" must have a selection of subfields. \
+                      Did you mean \"friends { ... }\"?",
+                     2,
+                     44),
+                    ("Unknown directive \"@live\".", 2, 78),
+                    ("Unknown fragment \"Rest\".", 2, 72),
+                    ("Cannot query field \"age\" on type \"Hero\".", 3, 10),
+                    ("Unknown type \"Droid\".", 3, 32),
+                    ("Schema is not configured for mutations.", 5, 1)],
+               errors.iter()
+                 .map(|e| {
+                   let location = &e.locations()[0];
+                   (e.message(), location.line, location.column)
+                 })
+                 .collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn test_request_serialization() {
+    let request: GraphQLRequest = serde_json::from_value(json!({
+      "query": "query Q($color: Color) { hero { name } }",
+      "operationName": "Q",
+      "variables": { "color": "RED", "limit": 10, "ratio": 0.5, "flags": [true, null] }
+    }))
+      .unwrap();
+    let mut variables = Arguments::new();
+    variables.insert("color".to_owned(), Value::String("RED".to_owned()));
+    variables.insert("limit".to_owned(), Value::Int(10));
+    variables.insert("ratio".to_owned(), Value::Float(0.5));
+    variables.insert("flags".to_owned(),
+                     Value::List(vec![Value::Boolean(true), Value::Null]));
+    assert_eq!(request,
+               GraphQLRequest::new("query Q($color: Color) { hero { name } }")
+                 .operation_name("Q")
+                 .variables(variables));
+
+    let request: GraphQLRequest = serde_json::from_value(json!({
+      "query": "{ hero { name } }",
+      "variables": null
+    }))
+      .unwrap();
+    assert_eq!(request, GraphQLRequest::new("{ hero { name } }"));
+    assert_eq!(serde_json::to_value(&request).unwrap(),
+               json!({ "query": "{ hero { name } }" }));
+
+    let json = json!({
+      "errors": [{
+        "message": "Secret is classified",
+        "locations": [{ "line": 1, "column": 10 }],
+        "path": ["heroes", 1, "secret"],
+        "extensions": { "code": "FORBIDDEN" }
+      }],
+      "data": null
+    });
+    let response: GraphQLResponse = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(response.data, Some(Value::Null));
+    assert_eq!(response.errors[0].response_path(),
+               Some(&[PathSegment::from("heroes"), PathSegment::from(1), PathSegment::from("secret")]
+                      [..]));
+    assert_eq!(serde_json::to_value(&response).unwrap(), json);
+
+    let response: GraphQLResponse =
+      serde_json::from_value(json!({ "errors": [{ "message": "Must provide an operation." }] }))
+        .unwrap();
+    assert_eq!(response.data, None);
+  }
+
+  fn hero_handler() -> GraphQLHttpHandler {
+    GraphQLHttpHandler::new(&std::rc::Rc::new(hero_config()))
+  }
+
+  fn hero_config() -> ExecutionConfig {
+    ExecutionConfig::new(&hero_schema(), parse_query)
+  }
+
+
+  fn handle(request: http::request::Builder, body: &str) -> (u16, String, serde_json::Value) {
+    let response = hero_handler().handle(&request.body(body).unwrap(), &(), &());
+    let content_type = response.headers()
+      .get(http::header::CONTENT_TYPE)
+      .map(|c| c.to_str().unwrap().to_owned())
+      .unwrap_or_default();
+    (response.status().as_u16(), content_type, serde_json::from_slice(response.body()).unwrap())
+  }
+
+  fn get(params: &[(&str, &str)], accept: &str) -> http::request::Builder {
+    let query = form_urlencoded::Serializer::new(String::new()).extend_pairs(params).finish();
+    http::Request::get(format!("/graphql?{}", query)).header("Accept", accept)
+  }
+
+  fn post(content_type: &str, accept: &str) -> http::request::Builder {
+    http::Request::post("/graphql").header("Content-Type", content_type).header("Accept", accept)
+  }
+
+  #[test]
+  fn test_http_handler() {
+    let hero = json!({ "data": { "hero": { "name": "Luke" } } });
+    let graphql_json = "application/graphql-response+json; charset=utf-8";
+    let json = "application/json; charset=utf-8";
+
+    assert_eq!(handle(get(&[("query", "{ hero { name } }")], "application/graphql-response+json"),
+                      ""),
+               (200, graphql_json.to_owned(), hero.clone()));
+    assert_eq!(handle(post("application/json; charset=utf-8", "application/json"),
+                      r#"{"query": "{ hero { name } }", "variables": {}}"#),
+               (200, json.to_owned(), hero.clone()));
+    assert_eq!(handle(http::Request::post("/graphql").header("Content-Type", "application/json"),
+                      r#"{"query": "{ hero { name } }"}"#),
+               (200, json.to_owned(), hero.clone()));
+
+    // Requests failing before execution are only 200 OK with the legacy
+    // media type.
+    let syntax_error = json!({ "errors": [{ "message": "Syntax Error: Unexpected Name \"garbage\".",
+                                            "locations": [{ "line": 1, "column": 1 }] }] });
+    assert_eq!(handle(post("application/json", "application/graphql-response+json, */*;q=0.8"),
+                      r#"{"query": "garbage"}"#),
+               (400, graphql_json.to_owned(), syntax_error.clone()));
+    assert_eq!(handle(post("application/json", "application/json"), r#"{"query": "garbage"}"#),
+               (200, json.to_owned(), syntax_error));
+
+    assert_eq!(handle(get(&[("query", "{ hero { name } }"), ("variables", "{")], "*/*"), "").0,
+               400);
+    assert_eq!(handle(get(&[("operationName", "Q")], "*/*"), ""),
+               (400,
+                graphql_json.to_owned(),
+                json!({ "errors": [{ "message": "Must provide query string." }] })));
+    assert_eq!(handle(post("application/json", "*/*"), "{").0, 400);
+    assert_eq!(handle(post("application/json", "*/*"), r#"{"variables": {}}"#).0, 400);
+    assert_eq!(handle(post("text/plain", "*/*"), "{ hero { name } }").0, 415);
+    assert_eq!(handle(get(&[("query", "{ hero { name } }")], "text/html"), "").0, 406);
+
+    let response = hero_handler().handle(&http::Request::put("/graphql").body("").unwrap(), &(), &());
+    assert_eq!(response.status().as_u16(), 405);
+    assert_eq!(response.headers()[http::header::ALLOW], "GET, POST");
+
+    let request = get(&[("query", "mutation { hero { name } }")], "*/*").body("").unwrap();
+    let response = hero_handler().handle(&request, &(), &());
+    assert_eq!(response.status().as_u16(), 405);
+    assert_eq!(response.headers()[http::header::ALLOW], "POST");
+    assert_eq!(handle(post("application/json", "*/*"),
+                      r#"{"query": "mutation { hero { name } }"}"#),
+               (400,
+                graphql_json.to_owned(),
+                json!({ "errors": [{ "message": "Schema is not configured for mutations." }] })));
+  }
+
+  #[test]
+  fn test_persisted_queries() {
+    let query = "{ hero { name } }";
+    let hash = sha256(query);
+    assert_eq!(sha256(""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    let by_hash = |hash: &str| {
+      json!({ "extensions": { "persistedQuery": { "version": 1, "sha256Hash": hash } } })
+    };
+    let with_query = |hash: &str| {
+      let mut body = by_hash(hash);
+      body["query"] = json!(query);
+      body
+    };
+    let send = |handler: &GraphQLHttpHandler, body: &serde_json::Value| {
+      let request = post("application/json", "application/json").body(body.to_string()).unwrap();
+      let response = handler.handle(&request, &(), &());
+      serde_json::from_slice::<serde_json::Value>(response.body()).unwrap()
+    };
+    let hero = json!({ "data": { "hero": { "name": "Luke" } } });
+    let error = |message: &str, code: &str| {
+      json!({ "errors": [{ "message": message, "extensions": { "code": code } }] })
+    };
+
+    let store = std::rc::Rc::new(PersistedQueries::automatic(LruQueryStore::new(1)));
+    let config = hero_config().persisted_queries(&store);
+    let handler = GraphQLHttpHandler::new(&std::rc::Rc::new(config));
+    assert_eq!(send(&handler, &by_hash(&hash)),
+               error("PersistedQueryNotFound", "PERSISTED_QUERY_NOT_FOUND"));
+    assert_eq!(send(&handler, &with_query(&sha256("{ hero }"))),
+               error("provided sha does not match query", "PERSISTED_QUERY_HASH_MISMATCH"));
+    assert_eq!(send(&handler, &with_query(&hash)), hero);
+    assert_eq!(send(&handler, &by_hash(&hash)), hero);
+    let extensions = by_hash(&hash)["extensions"].to_string();
+    let request = get(&[("extensions", &extensions)], "*/*").body("").unwrap();
+    assert_eq!(handler.handle(&request, &(), &()).status().as_u16(), 200);
+    assert_eq!(send(&handler, &json!({ "query": query })), hero);
+    let version_2 = json!({ "persistedQuery": { "version": 2, "sha256Hash": hash } });
+    assert_eq!(send(&handler, &json!({ "extensions": version_2 })),
+               error("Unsupported persisted query version", "BAD_REQUEST"));
+    assert_eq!(send(&hero_handler(), &by_hash(&hash)),
+               error("PersistedQueryNotSupported", "PERSISTED_QUERY_NOT_SUPPORTED"));
+    assert_eq!(send(&hero_handler(), &json!({ "extensions": {} })),
+               json!({ "errors": [{ "message": "Must provide query string." }] }));
+
+    let lru = LruQueryStore::new(2);
+    lru.insert("a", "{ a }");
+    lru.insert("b", "{ b }");
+    assert_eq!(lru.get("a"), Some("{ a }".to_owned()));
+    lru.insert("c", "{ c }");
+    assert_eq!((lru.len(), lru.get("a"), lru.get("b")), (2, Some("{ a }".to_owned()), None));
+
+    let manifest = QueryManifest::from_json(&json!({
+        "format": "apollo-persisted-query-manifest",
+        "version": 1,
+        "operations": [{ "id": hash, "name": "Hero", "type": "query", "body": query }],
+      })
+      .to_string())
+      .unwrap();
+    assert_eq!(manifest.get(&hash), Some(query.to_owned()));
+    assert_eq!(QueryManifest::from_json(&json!({ "abc": "{ a }" }).to_string()).unwrap().len(), 1);
+    assert!(QueryManifest::from_json("[]").is_err());
+
+    let allowlist = std::rc::Rc::new(PersistedQueries::allowlist(manifest));
+    let config = hero_config().persisted_queries(&allowlist);
+    let handler = GraphQLHttpHandler::new(&std::rc::Rc::new(config));
+    assert_eq!(send(&handler, &by_hash(&hash)), hero);
+    assert_eq!(send(&handler, &json!({ "query": query })), hero);
+    let not_allowed = error("Operation is not in the allowlist.", "OPERATION_NOT_IN_ALLOWLIST");
+    let mutation = "mutation { hero { name } }";
+    assert_eq!(send(&handler, &json!({ "query": mutation })), not_allowed);
+    let mut mutation_with_hash = by_hash(&sha256(mutation));
+    mutation_with_hash["query"] = json!(mutation);
+    assert_eq!(send(&handler, &mutation_with_hash), not_allowed);
+    assert_eq!(send(&handler, &by_hash(&sha256(mutation))),
+               error("PersistedQueryNotFound", "PERSISTED_QUERY_NOT_FOUND"));
+  }
+
+  #[test]
+  fn test_document_cache() {
+    let parses = std::rc::Rc::new(std::cell::Cell::new(0));
+    let counted = parses.clone();
+    let cache = std::rc::Rc::new(DocumentCache::new(1));
+    let config = ExecutionConfig::new(&hero_schema(), move |source| {
+        counted.set(counted.get() + 1);
+        parse_query(source)
+      })
+      .document_cache(&cache);
+    let config = std::rc::Rc::new(config);
+    let handler = GraphQLHttpHandler::new(&config);
+    let send = |query: &str| {
+      let request = get(&[("query", query)], "application/json").body("").unwrap();
+      serde_json::from_slice::<serde_json::Value>(handler.handle(&request, &(), &()).body()).unwrap()
+    };
+    let hero = json!({ "data": { "hero": { "name": "Luke" } } });
+
+    assert_eq!(send("{ hero { name } }"), hero);
+    assert_eq!(send("{ hero { name } }"), hero);
+    assert_eq!(parses.get(), 1);
+    assert_eq!(send("garbage")["errors"][0]["message"], "Syntax Error: Unexpected Name \"garbage\".");
+    assert_eq!(send("garbage")["errors"][0]["message"], "Syntax Error: Unexpected Name \"garbage\".");
+    assert_eq!(cache.stats(),
+               CacheStats {
+                 hits: 1,
+                 misses: 3,
+                 evictions: 0,
+                 len: 1,
+               });
+
+    // Documents are prepared again for another schema.
+    let schema = hero_schema();
+    fn parse<'a>(source: &'a Source<'a>) -> Result<ast::Document<'a>, GraphQLError> {
+      Ok(ast_query(None, vec![ast_field(None, source.body, vec![], vec![])]))
+    }
+    let prepared = cache.get(&schema, &parse, "{ hero { name } }").unwrap();
+    assert!(std::rc::Rc::ptr_eq(prepared.schema(), &schema));
+    assert_eq!(prepared.query(), "{ hero { name } }");
+    assert_eq!(cache.stats().misses, 4);
+    let hit = cache.get(&schema, &parse, "{ hero { name } }").unwrap();
+    assert!(std::rc::Rc::ptr_eq(&hit, &prepared));
+
+    let other = cache.get(&schema, &parse, "hero").unwrap();
+    assert_eq!(cache.stats(),
+               CacheStats {
+                 hits: 2,
+                 misses: 5,
+                 evictions: 1,
+                 len: 1,
+               });
+    assert!(other.errors().is_empty());
+    assert_eq!(other.document().definitions.len(), 1);
+    cache.clear();
+    assert_eq!(cache.stats().len, 0);
+
+    // Streamed responses share the cache of the configuration.
+    let request = http::Request::post("/graphql")
+      .header("Accept", "text/event-stream")
+      .header("Content-Type", "application/json")
+      .body(r#"{"query": "{ hero { name } }"}"#)
+      .unwrap();
+    let response = GraphQLStreamHandler::new(&config).handle(&request, std::rc::Rc::new(()));
+    assert_eq!(response.status(), 200);
+    assert_eq!(send("{ hero { name } }"), hero);
+    assert_eq!(parses.get(), 4);
+  }
+
+  #[test]
+  fn test_query_limits() {
+    let schema = hero_schema();
+    let field = |alias, name, args, selections| ast_field(alias, name, args, selections);
+    let first = ast::Value::Variable(ast::Variable {
+      loc: None,
+      name: ast_name("first"),
+    });
+    let definitions = vec![ast::VariableDefinition {
+                             loc: None,
+                             variable: ast::Variable {
+                               loc: None,
+                               name: ast_name("first"),
+                             },
+                             type_: ast::Type::Named(ast::NamedType {
+                               loc: None,
+                               name: ast_name("Int"),
+                             }),
+                             default_value: None,
+                           }];
+    let document = ast_query(Some(definitions),
+                             vec![field(None, "hero", vec![], vec![field(None, "name", vec![], vec![])]),
+                                  field(None,
+                                        "heroes",
+                                        vec![("first", first)],
+                                        vec![field(None, "name", vec![], vec![]),
+                                             field(None, "__typename", vec![], vec![])]),
+                                  field(Some("rey"), "me", vec![], vec![field(None, "name", vec![], vec![])])]);
+    let limits = QueryLimits::new().field_cost("Query", "heroes", 5).default_list_size(10);
+    let mut variables = Arguments::new();
+
+    // Without its size argument, a list has the default size.
+    assert_eq!(limits.analyze(&schema, &document, None, &variables).unwrap(),
+               QueryAnalysis {
+                 depth: 2,
+                 aliases: 1,
+                 cost: 2 + (5 + 1) * 10 + 2,
+               });
+    variables.insert("first".to_owned(), Value::Int(3));
+    assert_eq!(limits.analyze(&schema, &document, None, &variables).unwrap().cost,
+               2 + (5 + 1) * 3 + 2);
+
+    let rejection = |limits: QueryLimits| {
+      let e = limits.check(&schema, &document, None, &variables).unwrap_err();
+      serde_json::to_value(&e).unwrap()
+    };
+    assert_eq!(rejection(QueryLimits::new().max_depth(1)),
+               json!({
+                 "message": "Query depth of 2 exceeds the maximum depth of 1.",
+                 "extensions": { "code": "MAX_DEPTH_EXCEEDED", "maximum": 1, "actual": 2 }
+               }));
+    assert_eq!(rejection(QueryLimits::new().max_aliases(0))["extensions"]["code"],
+               "MAX_ALIASES_EXCEEDED");
+    assert_eq!(rejection(QueryLimits::new().max_cost(5))["message"],
+               "Query cost of 10 exceeds the maximum cost of 5.");
+    assert!(QueryLimits::new().max_depth(2).max_cost(10).check(&schema, &document, None, &variables).is_ok());
+
+    let limited = std::rc::Rc::new(hero_config().limits(QueryLimits::new().max_depth(1)));
+    let response = GraphQLHttpHandler::new(&limited)
+      .handle(&get(&[("query", "{ hero { name } }")], "application/graphql-response+json")
+                .body("")
+                .unwrap(),
+              &(),
+              &());
+    assert_eq!(response.status(), 400);
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(response.body()).unwrap()["errors"][0]
+                 ["extensions"]["code"],
+               "MAX_DEPTH_EXCEEDED");
+
+    // Invalid documents are reported as such rather than measured.
+    let response = GraphQLHttpHandler::new(&limited)
+      .handle(&get(&[("query", "{ hero @stream { name } }")], "application/graphql-response+json")
+                .body("")
+                .unwrap(),
+              &(),
+              &());
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(response.body()).unwrap()["errors"][0]
+                 ["message"],
+               "Stream directive cannot be used on non-list field \"hero\" on type \"Query\".");
+  }
+
+  #[test]
+  fn test_query_limits_sizes() {
+    use graphql_language::parser::parse;
+    use graphql_language::sdl::parse_schema;
+    use sdl::build_schema;
+
+    let document = parse_schema("type Query { items(first: Int): [Item], page(first: Int = 50): [Item], \
+                                 item: Item }\n\
+                                 type Item { id: String, next: Item }")
+      .unwrap();
+    let schema = build_schema(&document).unwrap();
+    let analyze = |query: &str, limits: QueryLimits| {
+      let source = Source::new(query);
+      let document = parse(&source).unwrap();
+      limits.analyze(&schema, &document, None, &Arguments::new())
+    };
+
+    // Variable defaults and argument defaults size lists too.
+    let query = "query($n: Int = 1000000) { items(first: $n) { id } }";
+    assert_eq!(analyze(query, QueryLimits::new()).unwrap().cost, 2 * 1000000);
+    let source = Source::new(query);
+    let document = parse(&source).unwrap();
+    assert!(QueryLimits::new().max_cost(100).check(&schema, &document, None, &Arguments::new()).is_err());
+    assert_eq!(analyze("{ page { id } }", QueryLimits::new().default_list_size(10)).unwrap().cost,
+               2 * 50);
+    assert_eq!(analyze("{ items { id } }", QueryLimits::new().default_list_size(10)).unwrap().cost,
+               2 * 10);
+
+    // Fragments are measured once however often they are spread, so each of
+    // these doubling the cost of the next is not walked 2^25 times.
+    let mut query = String::from("{ item { ...F0 } }\n");
+    for i in 0..25 {
+      query.push_str(&format!("fragment F{} on Item {{ next {{ ...F{} ...F{} }} }}\n", i, i + 1, i + 1));
+    }
+    query.push_str("fragment F25 on Item { id }");
+    assert_eq!(analyze(&query, QueryLimits::new()).unwrap(),
+               QueryAnalysis {
+                 depth: 27,
+                 aliases: 0,
+                 cost: 1 << 26,
+               });
+  }
+
+  type HeroQueue = std::rc::Rc<std::cell::RefCell<(std::collections::VecDeque<Hero>, bool)>>;
+
+  // Source stream of `heroAdded`, which ends once the queue is closed. Tests
+  // poll the connection again after publishing, so there is no waker.
+  struct HeroEvents(HeroQueue);
+
+  impl futures_core::Stream for HeroEvents {
+    type Item = Box<dyn Any>;
+
+    fn poll_next(self: std::pin::Pin<&mut Self>,
+                 _: &mut std::task::Context)
+                 -> std::task::Poll<Option<Box<dyn Any>>> {
+      let mut queue = self.0.borrow_mut();
+      match queue.0.pop_front() {
+        Some(hero) => std::task::Poll::Ready(Some(Box::new(hero))),
+        None if queue.1 => std::task::Poll::Ready(None),
+        None => std::task::Poll::Pending,
+      }
+    }
+  }
+
+  fn subscription_schema(queue: &HeroQueue) -> std::rc::Rc<GraphQLSchema> {
+    let hero = &hero_type();
+    let query = &GraphQLObjectType::new("Query")
+                   .field("hero", |f| {
+                     f.type_of(hero).resolve(|_, _, _| {
+                       Ok(Box::new(Hero {
+                         name: "Luke",
+                         secret: None,
+                       }))
+                     })
+                   })
+                   .build();
+    let queue = queue.clone();
+    let subscription = &GraphQLObjectType::new("Subscription")
+                          .field("heroAdded", move |f| {
+                            let queue = queue.clone();
+                            f.type_of(hero).resolve(move |_, _, _| {
+                              let events: SourceStream = Box::pin(HeroEvents(queue.clone()));
+                              Ok(Box::new(events))
+                            })
+                          })
+                          .build();
+    GraphQLSchemaType::new(query).subscription(subscription).build().unwrap()
+  }
+
+  fn ws_connection(protocol: Protocol, queue: &HeroQueue) -> GraphQLWsConnection {
+    let config = ExecutionConfig::new(&subscription_schema(queue), parse_query);
+    GraphQLWsConnection::new(protocol, &std::rc::Rc::new(config))
+      .on_connect(|payload| {
+        match payload.and_then(|p| p.to_string().find("secret")) {
+          Some(_) => Ok(()),
+          None => Err(GraphQLError::new("Invalid token")),
+        }
+      })
+  }
+
+  // Frames sent by the connection until it waits for more events, with text
+  // frames parsed.
+  fn ws_poll(connection: &mut GraphQLWsConnection) -> Vec<serde_json::Value> {
+    use futures_core::Stream;
+
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+    let mut messages = Vec::new();
+    while let std::task::Poll::Ready(Some(message)) =
+      std::pin::Pin::new(&mut *connection).poll_next(&mut cx) {
+      messages.push(match message {
+        WsMessage::Text(text) => serde_json::from_str(&text).unwrap(),
+        WsMessage::Close(code, reason) => json!({ "close": code, "reason": reason }),
+      });
+    }
+    messages
+  }
+
+  fn publish(queue: &HeroQueue, name: &'static str, secret: Option<&'static str>) {
+    queue.borrow_mut().0.push_back(Hero { name, secret });
+  }
+
+  #[test]
+  fn test_graphql_transport_ws() {
+    let queue = HeroQueue::default();
+    let mut connection = ws_connection(Protocol::GraphQLTransportWs, &queue);
+    assert!(connection.deadline().is_some());
+    connection.receive(r#"{"type": "connection_init", "payload": {"token": "secret"}}"#);
+    assert_eq!(connection.deadline(), None);
+    connection.receive(r#"{"type": "ping"}"#);
+    connection.receive(r#"{"type": "pong"}"#);
+    assert_eq!(ws_poll(&mut connection),
+               vec![json!({ "type": "connection_ack" }), json!({ "type": "pong" })]);
+
+    connection.receive(r#"{"type": "subscribe", "id": "1",
+                           "payload": {"query": "subscription { heroAdded { name } }"}}"#);
+    connection.receive(r#"{"type": "subscribe", "id": "2",
+                           "payload": {"query": "subscription { heroAdded { secret } }"}}"#);
+    assert_eq!(ws_poll(&mut connection), Vec::<serde_json::Value>::new());
+    publish(&queue, "Leia", Some("Jedi"));
+    publish(&queue, "Han", None);
+    assert_eq!(ws_poll(&mut connection),
+               vec![json!({ "type": "next", "id": "1",
+                            "payload": { "data": { "heroAdded": { "name": "Leia" } } } }),
+                    json!({
+                      "type": "next",
+                      "id": "2",
+                      "payload": {
+                        "errors": [{ "message": "Secret is classified",
+                                     "locations": [{ "line": 1, "column": 28 }],
+                                     "path": ["heroAdded", "secret"] }],
+                        "data": null
+                      }
+                    })]);
+
+    // Once a subscription is completed by the client, others get its events.
+    connection.receive(r#"{"type": "complete", "id": "1"}"#);
+    publish(&queue, "Han", None);
+    assert_eq!(ws_poll(&mut connection),
+               vec![json!({
+                 "type": "next",
+                 "id": "2",
+                 "payload": {
+                   "errors": [{ "message": "Secret is classified",
+                                "locations": [{ "line": 1, "column": 28 }],
+                                "path": ["heroAdded", "secret"] }],
+                   "data": null
+                 }
+               })]);
+
+    // Queries are answered at once.
+    connection.receive(r#"{"type": "subscribe", "id": "3", "payload": {"query": "{ hero { name } }"}}"#);
+    assert_eq!(ws_poll(&mut connection),
+               vec![json!({ "type": "next", "id": "3",
+                            "payload": { "data": { "hero": { "name": "Luke" } } } }),
+                    json!({ "type": "complete", "id": "3" })]);
+    connection.receive(r#"{"type": "subscribe", "id": "4", "payload": {"query": "garbage"}}"#);
+    assert_eq!(ws_poll(&mut connection),
+               vec![json!({ "type": "error", "id": "4",
+                            "payload": [{ "message": "Syntax Error: Unexpected Name \"garbage\".",
+                                          "locations": [{ "line": 1, "column": 1 }] }] })]);
+
+    queue.borrow_mut().1 = true;
+    assert_eq!(ws_poll(&mut connection), vec![json!({ "type": "complete", "id": "2" })]);
+
+    connection.receive(r#"{"type": "subscribe", "id": "5",
+                           "payload": {"query": "subscription { heroAdded { name } }"}}"#);
+    connection.receive(r#"{"type": "subscribe", "id": "5",
+                           "payload": {"query": "subscription { heroAdded { name } }"}}"#);
+    connection.receive(r#"{"type": "ping"}"#);
+    assert_eq!(ws_poll(&mut connection),
+               vec![json!({ "close": 4409, "reason": "Subscriber for 5 already exists" })]);
+    assert!(connection.is_closed());
+  }
+
+  #[test]
+  fn test_graphql_transport_ws_fairness() {
+    let queue = HeroQueue::default();
+    let mut connection = ws_connection(Protocol::GraphQLTransportWs, &queue);
+    connection.receive(r#"{"type": "connection_init", "payload": {"token": "secret"}}"#);
+    for id in 1..4 {
+      let payload = json!({ "query": "subscription { heroAdded { name } }" });
+      connection.receive(&json!({ "type": "subscribe", "id": id.to_string(), "payload": payload })
+                           .to_string());
+    }
+    assert_eq!(ws_poll(&mut connection), vec![json!({ "type": "connection_ack" })]);
+
+    // Every subscription is ready while heroes are queued, and they take
+    // turns delivering them.
+    for name in &["Leia", "Han", "Luke", "Rey", "Finn"] {
+      publish(&queue, name, None);
+    }
+    let ids = ws_poll(&mut connection).iter().map(|m| m["id"].clone()).collect::<Vec<_>>();
+    assert_eq!(ids, vec!["1", "2", "3", "1", "2"]);
+    connection.receive(r#"{"type": "complete", "id": "2"}"#);
+    publish(&queue, "Poe", None);
+    publish(&queue, "Lando", None);
+    let mut ids = ws_poll(&mut connection)
+      .iter()
+      .map(|m| m["id"].as_str().unwrap().to_owned())
+      .collect::<Vec<_>>();
+    ids.sort();
+    assert_eq!(ids, vec!["1", "3"]);
+  }
+
+  #[test]
+  fn test_graphql_transport_ws_closing() {
+    let queue = HeroQueue::default();
+    let close = |messages: &[&str]| {
+      let mut connection = ws_connection(Protocol::GraphQLTransportWs, &queue);
+      for message in messages {
+        connection.receive(message);
+      }
+      ws_poll(&mut connection).pop().unwrap()
+    };
+    let init = r#"{"type": "connection_init", "payload": {"token": "secret"}}"#;
+
+    assert_eq!(close(&[r#"{"type": "subscribe", "id": "1", "payload": {"query": "{ hero { name } }"}}"#]),
+               json!({ "close": 4401, "reason": "Unauthorized" }));
+    assert_eq!(close(&[init, init]),
+               json!({ "close": 4429, "reason": "Too many initialisation requests" }));
+    assert_eq!(close(&[r#"{"type": "connection_init"}"#]),
+               json!({ "close": 4403, "reason": "Forbidden" }));
+    assert_eq!(close(&[init, r#"{"type": "start", "id": "1"}"#])["close"], 4400);
+
+    // Reasons are cut to fit in a close frame, even where they hold input.
+    let message = close(&[init, r#"{"type": "unknown"}"#]);
+    let reason = message["reason"].as_str().unwrap();
+    assert_eq!(message["close"], 4400);
+    assert!(reason.starts_with("Invalid message received: unknown variant `unknown`"));
+    assert!(reason.len() <= 123);
+    let subscribe = json!({ "type": "subscribe", "id": "é".repeat(100),
+                            "payload": { "query": "subscription { heroAdded { name } }" } })
+      .to_string();
+    let message = close(&[init, &subscribe, &subscribe]);
+    let reason = message["reason"].as_str().unwrap();
+    assert_eq!(message["close"], 4409);
+    assert_eq!(reason, format!("Subscriber for {}", "é".repeat(54)));
+
+    let mut connection = ws_connection(Protocol::GraphQLTransportWs, &queue)
+      .connection_init_timeout(std::time::Duration::from_secs(1));
+    let deadline = connection.deadline().unwrap();
+    connection.handle_timeout(deadline - std::time::Duration::from_millis(1));
+    assert_eq!(ws_poll(&mut connection), Vec::<serde_json::Value>::new());
+    connection.handle_timeout(deadline);
+    assert_eq!(ws_poll(&mut connection),
+               vec![json!({ "close": 4408, "reason": "Connection initialisation timeout" })]);
+  }
+
+  #[cfg(feature = "subscriptions-transport-ws")]
+  #[test]
+  fn test_subscriptions_transport_ws() {
+    assert_eq!(Protocol::from_header("graphql-ws"),
+               Some(Protocol::SubscriptionsTransportWs));
+
+    let queue = HeroQueue::default();
+    let mut connection = ws_connection(Protocol::SubscriptionsTransportWs, &queue);
+    connection.receive(r#"{"type": "connection_init", "payload": {"token": "secret"}}"#);
+    connection.receive(r#"{"type": "start", "id": "1",
+                           "payload": {"query": "subscription { heroAdded { name } }"}}"#);
+    publish(&queue, "Leia", None);
+    assert_eq!(ws_poll(&mut connection),
+               vec![json!({ "type": "connection_ack" }),
+                    json!({ "type": "ka" }),
+                    json!({ "type": "data", "id": "1",
+                            "payload": { "data": { "heroAdded": { "name": "Leia" } } } })]);
+
+    connection.receive(r#"{"type": "stop", "id": "1"}"#);
+    connection.receive(r#"{"type": "connection_terminate"}"#);
+    assert_eq!(ws_poll(&mut connection),
+               vec![json!({ "type": "complete", "id": "1" }),
+                    json!({ "close": 1000, "reason": "Normal Closure" })]);
+  }
+
+  fn stream_handler(queue: &HeroQueue) -> GraphQLStreamHandler {
+    let config = ExecutionConfig::new(&subscription_schema(queue), parse_query);
+    GraphQLStreamHandler::new(&std::rc::Rc::new(config))
+  }
+
+  fn stream_request(handler: &GraphQLStreamHandler,
+                    request: http::request::Builder,
+                    body: &str)
+                    -> http::Response<BodyStream> {
+    handler.handle(&request.body(body).unwrap(), std::rc::Rc::new(()))
+  }
+
+  // Chunks of a body until it waits for more events.
+  fn body_poll(body: &mut BodyStream) -> Vec<String> {
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+    let mut chunks = Vec::new();
+    while let std::task::Poll::Ready(Some(chunk)) = body.as_mut().poll_next(&mut cx) {
+      chunks.push(String::from_utf8(chunk).unwrap());
+    }
+    chunks
+  }
+
+  #[test]
+  fn test_sse_distinct_connections() {
+    let queue = HeroQueue::default();
+    let handler = stream_handler(&queue);
+    let subscription = r#"{"query": "subscription { heroAdded { name } }"}"#;
+
+    let request = post("application/json", "text/event-stream");
+    assert!(GraphQLStreamHandler::accepts(&request.body(()).unwrap()));
+    assert!(!GraphQLStreamHandler::accepts(&post("application/json", "application/json")
+                                              .body(())
+                                              .unwrap()));
+
+    let mut response = stream_request(&handler, post("application/json", "text/event-stream"),
+                                      subscription);
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    assert_eq!(body_poll(response.body_mut()), Vec::<String>::new());
+    publish(&queue, "Leia", None);
+    publish(&queue, "Han", None);
+    queue.borrow_mut().1 = true;
+    assert_eq!(body_poll(response.body_mut()),
+               vec!["event: next\ndata: {\"data\":{\"heroAdded\":{\"name\":\"Leia\"}}}\n\n",
+                    "event: next\ndata: {\"data\":{\"heroAdded\":{\"name\":\"Han\"}}}\n\n",
+                    "event: complete\ndata: \n\n"]);
+
+    let mut response = stream_request(&handler,
+                                      get(&[("query", "{ hero { name } }")], "text/event-stream"),
+                                      "");
+    assert_eq!(body_poll(response.body_mut()),
+               vec!["event: next\ndata: {\"data\":{\"hero\":{\"name\":\"Luke\"}}}\n\n",
+                    "event: complete\ndata: \n\n"]);
+
+    let response = stream_request(&handler,
+                                  get(&[("query", "mutation { hero { name } }")], "text/event-stream"),
+                                  "");
+    assert_eq!(response.status(), 405);
+    let mut response = stream_request(&handler, post("application/json", "text/event-stream"),
+                                      r#"{"query": "garbage"}"#);
+    assert_eq!(response.status(), 400);
+    assert_eq!(body_poll(response.body_mut()),
+               vec![r#"{"errors":[{"locations":[{"column":1,"line":1}],"message":"Syntax Error: Unexpected Name \"garbage\"."}]}"#]);
+    response = stream_request(&handler, post("application/json", "text/html"), subscription);
+    assert_eq!(response.status(), 406);
+  }
+
+  #[test]
+  fn test_multipart_mixed() {
+    let queue = HeroQueue::default();
+    let handler = stream_handler(&queue);
+    let part = "\r\n---\r\nContent-Type: application/json; charset=utf-8\r\n\r\n";
+
+    let mut response = stream_request(&handler, post("application/json", "multipart/mixed"),
+                                      r#"{"query": "subscription { heroAdded { name } }"}"#);
+    assert_eq!(response.headers()["content-type"], "multipart/mixed; boundary=\"-\"");
+    publish(&queue, "Leia", None);
+    queue.borrow_mut().1 = true;
+    assert_eq!(body_poll(response.body_mut()),
+               vec![format!("{}{}", part, r#"{"data":{"heroAdded":{"name":"Leia"}},"hasNext":true}"#),
+                    format!("{}{}\r\n-----\r\n", part, r#"{"hasNext":false}"#)]);
+  }
+
+  #[test]
+  fn test_sse_single_connection() {
+    let queue = HeroQueue::default();
+    let handler = stream_handler(&queue);
+    let with_token = |request: http::request::Builder, token: &str| {
+      request.header(STREAM_TOKEN, token)
+    };
+
+    let mut response = stream_request(&handler, http::Request::put("/graphql/stream"), "");
+    assert_eq!(response.status(), 201);
+    let token = body_poll(response.body_mut()).concat();
+    assert_eq!(token.len(), 32);
+
+    let mut events = stream_request(&handler,
+                                    with_token(http::Request::get("/graphql/stream"), &token),
+                                    "");
+    assert_eq!(events.status(), 200);
+    assert_eq!(stream_request(&handler,
+                              with_token(http::Request::get("/graphql/stream"), &token),
+                              "")
+                 .status(),
+               409);
+
+    let operation = |id: &str, query: &str| {
+      let body = json!({ "query": query, "extensions": { "operationId": id } }).to_string();
+      stream_request(&handler,
+                     with_token(post("application/json", "application/json"), &token),
+                     &body)
+        .status()
+    };
+    assert_eq!(operation("a", "subscription { heroAdded { name } }"), 202);
+    assert_eq!(operation("a", "subscription { heroAdded { name } }"), 409);
+    assert_eq!(operation("b", "{ hero { name } }"), 202);
+    publish(&queue, "Leia", None);
+    assert_eq!(body_poll(events.body_mut()),
+               vec!["event: next\ndata: {\"id\":\"a\",\"payload\":{\"data\":{\"heroAdded\":\
+                     {\"name\":\"Leia\"}}}}\n\n",
+                    "event: next\ndata: {\"id\":\"b\",\"payload\":{\"data\":{\"hero\":\
+                     {\"name\":\"Luke\"}}}}\n\n",
+                    "event: complete\ndata: {\"id\":\"b\"}\n\n"]);
+
+    let delete = http::Request::delete("/graphql/stream?operationId=a");
+    assert_eq!(stream_request(&handler, with_token(delete, &token), "").status(), 200);
+    assert_eq!(body_poll(events.body_mut()), vec!["event: complete\ndata: {\"id\":\"a\"}\n\n"]);
+
+    let missing = stream_request(&handler,
+                                 with_token(post("application/json", "application/json"), &token),
+                                 r#"{"query": "{ hero { name } }"}"#);
+    assert_eq!(missing.status(), 400);
+    let delete = http::Request::delete("/graphql/stream?operationId=a");
+    assert_eq!(stream_request(&handler, delete, "").status(), 401);
+
+    // Closing the event stream releases the reservation.
+    drop(events);
+    assert_eq!(stream_request(&handler,
+                              with_token(http::Request::get("/graphql/stream"), &token),
+                              "")
+                 .status(),
+               404);
+  }
+
+  #[test]
+  fn test_sse_reservation_limits() {
+    let queue = HeroQueue::default();
+    let reserve = |handler: &GraphQLStreamHandler| {
+      let mut response = stream_request(handler, http::Request::put("/graphql/stream"), "");
+      (response.status(), body_poll(response.body_mut()).concat())
+    };
+    let open = |handler: &GraphQLStreamHandler, token: &str| {
+      let request = http::Request::get("/graphql/stream").header(STREAM_TOKEN, token);
+      stream_request(handler, request, "")
+    };
+
+    let handler = stream_handler(&queue).max_reservations(2);
+    let (_, first) = reserve(&handler);
+    let (_, second) = reserve(&handler);
+    assert_ne!(first, second);
+    assert_eq!(reserve(&handler).0, 503);
+    let events = open(&handler, &first);
+    assert_eq!(reserve(&handler).0, 503);
+    drop(events);
+    assert_eq!(reserve(&handler).0, 201);
+
+    // Streams not opened in time are released.
+    let handler = stream_handler(&queue)
+      .max_reservations(1)
+      .reservation_timeout(std::time::Duration::from_secs(0));
+    let (_, token) = reserve(&handler);
+    assert_eq!(open(&handler, &token).status(), 404);
+    let (_, token) = reserve(&handler);
+    assert_eq!(reserve(&handler).0, 201);
+    assert_eq!(open(&handler, &token).status(), 404);
+  }
 }
@@ -0,0 +1,143 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::Rc;
+
+use indexmap::IndexSet;
+
+use error::GraphQLError;
+use execution::Deferred;
+
+type BatchFn<K, V> = dyn Fn(&[K]) -> Result<HashMap<K, V>, GraphQLError>;
+
+/// Batches the loads of values by key, and caches them, against the N+1
+/// problem of resolving the same field for each item of a list.
+///
+/// Resolvers return the `Deferred` of `load` instead of a value. Keys queued
+/// while the executor goes through the fields it can reach are loaded with a
+/// single call of the batch function, once the first of those fields is
+/// completed. Values are cached for the lifetime of the loader, which is meant
+/// to be created with the context of each request.
+pub struct DataLoader<K, V> {
+  inner: Rc<Inner<K, V>>,
+}
+
+struct Inner<K, V> {
+  batch: Box<BatchFn<K, V>>,
+  // `None` for keys the batch function has no value for.
+  cache: RefCell<HashMap<K, Result<Option<V>, GraphQLError>>>,
+  queue: RefCell<IndexSet<K>>,
+}
+
+impl<K, V> Clone for DataLoader<K, V> {
+  fn clone(&self) -> DataLoader<K, V> {
+    DataLoader { inner: self.inner.clone() }
+  }
+}
+
+impl<K, V> DataLoader<K, V>
+  where K: Clone + Eq + Hash + 'static,
+        V: Clone + 'static
+{
+  /// `batch` gets distinct keys, and leaves the keys without a value out of
+  /// the map it returns. Its error is the error of every key of the batch.
+  pub fn new<F>(batch: F) -> DataLoader<K, V>
+    where F: Fn(&[K]) -> Result<HashMap<K, V>, GraphQLError> + 'static
+  {
+    DataLoader {
+      inner: Rc::new(Inner {
+        batch: Box::new(batch),
+        cache: RefCell::new(HashMap::new()),
+        queue: RefCell::new(IndexSet::new()),
+      }),
+    }
+  }
+
+  /// Queues `key`, for a field of the type of `V`. A key without a value is a
+  /// field error.
+  pub fn load(&self, key: K) -> Deferred {
+    self.enqueue(key.clone());
+    let loader = self.clone();
+    Deferred::new(move || {
+      match loader.get(&key)? {
+        Some(value) => Ok(Box::new(value)),
+        None => Err("No value was loaded for the key.".into()),
+      }
+    })
+  }
+
+  /// Queues `key`, for an optional field, which is null for a key without a
+  /// value.
+  pub fn load_optional(&self, key: K) -> Deferred {
+    self.enqueue(key.clone());
+    let loader = self.clone();
+    Deferred::new(move || {
+      let value = loader.get(&key)?.map(|value| Box::new(value) as Box<dyn Any>);
+      Ok(Box::new(value))
+    })
+  }
+
+  /// Queues `keys`, for a list field of the values of those keys which have
+  /// one.
+  pub fn load_many(&self, keys: Vec<K>) -> Deferred {
+    for key in &keys {
+      self.enqueue(key.clone());
+    }
+    let loader = self.clone();
+    Deferred::new(move || {
+      let mut values: Vec<Box<dyn Any>> = Vec::with_capacity(keys.len());
+      for key in &keys {
+        if let Some(value) = loader.get(key)? {
+          values.push(Box::new(value));
+        }
+      }
+      Ok(Box::new(values))
+    })
+  }
+
+  /// Value of `key`, dispatching the queued keys along with it unless it is
+  /// cached.
+  pub fn get(&self, key: &K) -> Result<Option<V>, GraphQLError> {
+    if !self.inner.cache.borrow().contains_key(key) {
+      self.inner.queue.borrow_mut().insert(key.clone());
+      self.dispatch();
+    }
+    self.inner.cache.borrow()[key].clone()
+  }
+
+  /// Caches the value of `key`, unless it is already.
+  pub fn prime(&self, key: K, value: V) {
+    self.inner.cache.borrow_mut().entry(key).or_insert(Ok(Some(value)));
+  }
+
+  /// Removes `key` from the cache, so that it is loaded again.
+  pub fn clear(&self, key: &K) {
+    self.inner.cache.borrow_mut().remove(key);
+  }
+
+  fn enqueue(&self, key: K) {
+    if !self.inner.cache.borrow().contains_key(&key) {
+      self.inner.queue.borrow_mut().insert(key);
+    }
+  }
+
+  fn dispatch(&self) {
+    let keys: Vec<K> = self.inner.queue.borrow_mut().drain(..).collect();
+    let result = (self.inner.batch)(&keys);
+    let mut cache = self.inner.cache.borrow_mut();
+    match result {
+      Ok(mut values) => {
+        for key in keys {
+          let value = values.remove(&key);
+          cache.insert(key, Ok(value));
+        }
+      }
+      Err(e) => {
+        for key in keys {
+          cache.insert(key, Err(e.clone()));
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,235 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use indexmap::IndexMap;
+use sha2::{Digest, Sha256};
+
+use error::GraphQLError;
+use request::GraphQLRequest;
+use value::Value;
+
+/// Query texts by the hex SHA-256 hash of the text.
+pub trait PersistedQueryStore {
+  fn get(&self, hash: &str) -> Option<String>;
+
+  fn insert(&self, hash: &str, query: &str);
+}
+
+/// Store keeping up to `capacity` queries in memory, evicting the least
+/// recently used one when full.
+pub struct LruQueryStore {
+  capacity: usize,
+  queries: RefCell<IndexMap<String, String>>,
+}
+
+impl LruQueryStore {
+  pub fn new(capacity: usize) -> LruQueryStore {
+    LruQueryStore {
+      capacity,
+      queries: RefCell::new(IndexMap::new()),
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.queries.borrow().len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.queries.borrow().is_empty()
+  }
+}
+
+// The most recently used query is last.
+impl PersistedQueryStore for LruQueryStore {
+  fn get(&self, hash: &str) -> Option<String> {
+    let mut queries = self.queries.borrow_mut();
+    let index = queries.get_index_of(hash)?;
+    let last = queries.len() - 1;
+    queries.move_index(index, last);
+    queries.get_index(last).map(|(_, query)| query.clone())
+  }
+
+  fn insert(&self, hash: &str, query: &str) {
+    if self.capacity == 0 {
+      return;
+    }
+    let mut queries = self.queries.borrow_mut();
+    queries.shift_remove(hash);
+    if queries.len() == self.capacity {
+      queries.shift_remove_index(0);
+    }
+    queries.insert(hash.to_owned(), query.to_owned());
+  }
+}
+
+/// Operations registered ahead of time, which never change once loaded.
+///
+/// Manifests are JSON, either in the format of Apollo persisted query lists,
+/// with an `operations` array of objects with an `id` and a `body`, or as a
+/// single object of query texts by id. Ids are the hex SHA-256 hashes of the
+/// query texts.
+pub struct QueryManifest {
+  queries: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Manifest {
+  Operations { operations: Vec<ManifestOperation> },
+  Queries(HashMap<String, String>),
+}
+
+#[derive(Deserialize)]
+struct ManifestOperation {
+  id: String,
+  body: String,
+}
+
+impl QueryManifest {
+  pub fn from_json(json: &str) -> serde_json::Result<QueryManifest> {
+    let queries = match serde_json::from_str(json)? {
+      Manifest::Operations { operations } => {
+        operations.into_iter().map(|operation| (operation.id, operation.body)).collect()
+      }
+      Manifest::Queries(queries) => queries,
+    };
+    Ok(QueryManifest { queries })
+  }
+
+  /// Reads the manifest file at `path`, an invalid manifest being an
+  /// `InvalidData` error.
+  pub fn read<P: AsRef<Path>>(path: P) -> io::Result<QueryManifest> {
+    let json = fs::read_to_string(path)?;
+    QueryManifest::from_json(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+  }
+
+  pub fn len(&self) -> usize {
+    self.queries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.queries.is_empty()
+  }
+}
+
+impl PersistedQueryStore for QueryManifest {
+  fn get(&self, hash: &str) -> Option<String> {
+    self.queries.get(hash).cloned()
+  }
+
+  fn insert(&self, _: &str, _: &str) {}
+}
+
+/// Resolves the query text of requests sending the hash of a persisted query
+/// in their `persistedQuery` extension, as with Apollo automatic persisted
+/// queries.
+///
+/// A request with a hash and no query gets a `PersistedQueryNotFound` error
+/// when its query is not in the store, upon which the client sends the query
+/// along with the hash.
+pub struct PersistedQueries {
+  store: Box<dyn PersistedQueryStore>,
+  allowlist: bool,
+}
+
+impl PersistedQueries {
+  /// Persists the queries sent with their hash in `store`. Requests without
+  /// a hash are executed as usual.
+  pub fn automatic<S: PersistedQueryStore + 'static>(store: S) -> PersistedQueries {
+    PersistedQueries {
+      store: Box::new(store),
+      allowlist: false,
+    }
+  }
+
+  /// Only executes the queries of `store`, requested by hash or by text.
+  /// Nothing is persisted.
+  pub fn allowlist<S: PersistedQueryStore + 'static>(store: S) -> PersistedQueries {
+    PersistedQueries {
+      store: Box::new(store),
+      allowlist: true,
+    }
+  }
+
+  /// Sets the query of `request` from the store when it is only requested by
+  /// hash. Errors have a `code` extension.
+  pub fn resolve(&self, request: &mut GraphQLRequest) -> Result<(), GraphQLError> {
+    let hash = match persisted_query_hash(request)? {
+      Some(hash) => hash,
+      None if self.allowlist => {
+        let hash = sha256(&request.query);
+        return match self.store.get(&hash) {
+          Some(ref query) if *query == request.query => Ok(()),
+          _ => Err(code_error("Operation is not in the allowlist.", "OPERATION_NOT_IN_ALLOWLIST")),
+        };
+      }
+      None => return Ok(()),
+    };
+
+    if request.query.is_empty() {
+      match self.store.get(&hash) {
+        Some(query) => {
+          request.query = query;
+          Ok(())
+        }
+        None => Err(code_error("PersistedQueryNotFound", "PERSISTED_QUERY_NOT_FOUND")),
+      }
+    } else if !sha256(&request.query).eq_ignore_ascii_case(&hash) {
+      Err(code_error("provided sha does not match query", "PERSISTED_QUERY_HASH_MISMATCH"))
+    } else if self.allowlist {
+      match self.store.get(&hash) {
+        Some(_) => Ok(()),
+        None => Err(code_error("Operation is not in the allowlist.", "OPERATION_NOT_IN_ALLOWLIST")),
+      }
+    } else {
+      self.store.insert(&hash, &request.query);
+      Ok(())
+    }
+  }
+}
+
+/// Resolves the query of `request` with `persisted_queries` if any. Without
+/// them, a request sent by hash only gets a `PersistedQueryNotSupported` error.
+pub(crate) fn resolve_query(persisted_queries: Option<&PersistedQueries>,
+                            request: &mut GraphQLRequest)
+                            -> Result<(), GraphQLError> {
+  match persisted_queries {
+    Some(persisted_queries) => persisted_queries.resolve(request),
+    None if request.query.is_empty() && persisted_query_hash(request)?.is_some() => {
+      Err(code_error("PersistedQueryNotSupported", "PERSISTED_QUERY_NOT_SUPPORTED"))
+    }
+    None => Ok(()),
+  }
+}
+
+pub(crate) fn has_persisted_query(request: &GraphQLRequest) -> bool {
+  request.extensions.as_ref().is_some_and(|e| e.contains_key("persistedQuery"))
+}
+
+fn persisted_query_hash(request: &GraphQLRequest) -> Result<Option<String>, GraphQLError> {
+  let persisted_query = match request.extensions.as_ref().and_then(|e| e.get("persistedQuery")) {
+    Some(Value::Object(persisted_query)) => persisted_query,
+    Some(_) => return Err(code_error("Invalid persisted query extension.", "BAD_REQUEST")),
+    None => return Ok(None),
+  };
+  match persisted_query.get("version") {
+    Some(&Value::Int(1)) => {}
+    _ => return Err(code_error("Unsupported persisted query version", "BAD_REQUEST")),
+  }
+  match persisted_query.get("sha256Hash") {
+    Some(Value::String(hash)) => Ok(Some(hash.clone())),
+    _ => Err(code_error("Invalid persisted query extension.", "BAD_REQUEST")),
+  }
+}
+
+/// Hex SHA-256 hash of `query`, identifying it as a persisted query.
+pub fn sha256(query: &str) -> String {
+  Sha256::digest(query.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn code_error(message: &str, code: &str) -> GraphQLError {
+  GraphQLError::new(message).extension("code", Value::String(code.to_owned()))
+}
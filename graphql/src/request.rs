@@ -0,0 +1,300 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use indexmap::IndexMap;
+
+use graphql_language::ast;
+use graphql_language::source::Source;
+
+use analysis::{QueryAnalysis, QueryLimits};
+use cache::{DocumentCache, PreparedDocument};
+use error::GraphQLError;
+use execution::{execute, execute_event, execute_incremental, select_operation, subscribe,
+                ExecutionResult, SourceStream};
+use handler::ParseFn;
+use persisted::{resolve_query, PersistedQueries};
+use schema::GraphQLSchema;
+use validation::validate;
+use value::{Arguments, Value};
+
+/// Request parameters of GraphQL over HTTP, as sent in a POST body or the
+/// query string of a GET request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQLRequest {
+  /// Empty for requests only sending the hash of a persisted query.
+  #[serde(default)]
+  pub query: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub operation_name: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub variables: Option<Arguments>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub extensions: Option<IndexMap<String, Value>>,
+}
+
+impl GraphQLRequest {
+  pub fn new(query: &str) -> GraphQLRequest {
+    GraphQLRequest {
+      query: query.to_owned(),
+      operation_name: None,
+      variables: None,
+      extensions: None,
+    }
+  }
+
+  pub fn operation_name(mut self, operation_name: &str) -> GraphQLRequest {
+    self.operation_name = Some(operation_name.to_owned());
+    self
+  }
+
+  pub fn variables(mut self, variables: Arguments) -> GraphQLRequest {
+    self.variables = Some(variables);
+    self
+  }
+
+  /// Checks the requested operation of `document`, which is parsed from
+  /// `query`, against `limits`.
+  pub fn check_limits(&self,
+                      limits: &QueryLimits,
+                      schema: &GraphQLSchema,
+                      document: &ast::Document)
+                      -> Result<QueryAnalysis, GraphQLError> {
+    limits.check(schema,
+                 document,
+                 self.operation_name.as_ref().map(|n| n.as_ref()),
+                 self.variables.as_ref().unwrap_or(&Arguments::new()))
+  }
+
+  /// Validates and executes the requested operation of `document`, which is
+  /// parsed from `query`.
+  pub fn execute(&self,
+                 schema: &GraphQLSchema,
+                 document: &ast::Document,
+                 root_value: &dyn Any,
+                 context: &dyn Any)
+                 -> GraphQLResponse {
+    if let Some(response) = validation_errors(schema, document) {
+      return response;
+    }
+    execute(schema,
+            document,
+            self.operation_name.as_ref().map(|n| n.as_ref()),
+            self.variables.as_ref().unwrap_or(&Arguments::new()),
+            root_value,
+            context)
+  }
+
+  /// Executes the requested operation of `prepared`, which is prepared from
+  /// `query`, unless it failed validation.
+  pub fn execute_prepared(&self,
+                          prepared: &PreparedDocument,
+                          root_value: &dyn Any,
+                          context: &dyn Any)
+                          -> GraphQLResponse {
+    if !prepared.errors().is_empty() {
+      return GraphQLResponse {
+        data: None,
+        errors: prepared.errors().to_vec(),
+        incremental: Vec::new(),
+        has_next: None,
+      };
+    }
+    execute(prepared.schema(),
+            prepared.document(),
+            self.operation_name.as_ref().map(|n| n.as_ref()),
+            self.variables.as_ref().unwrap_or(&Arguments::new()),
+            root_value,
+            context)
+  }
+}
+
+/// Response to a `GraphQLRequest`, serializing to and from the `errors` and
+/// `data` entries.
+pub type GraphQLResponse = ExecutionResult;
+
+fn validation_errors(schema: &GraphQLSchema, document: &ast::Document) -> Option<GraphQLResponse> {
+  let errors = validate(schema, document);
+  if errors.is_empty() {
+    return None;
+  }
+  Some(GraphQLResponse {
+    data: None,
+    errors,
+    incremental: Vec::new(),
+    has_next: None,
+  })
+}
+
+/// How the transports turn requests into responses: the schema, how queries
+/// are parsed, and what is checked and reused before execution.
+pub struct ExecutionConfig {
+  schema: Rc<GraphQLSchema>,
+  parse: Rc<ParseFn>,
+  limits: Option<QueryLimits>,
+  persisted_queries: Option<Rc<PersistedQueries>>,
+  document_cache: Option<Rc<DocumentCache>>,
+}
+
+impl ExecutionConfig {
+  pub fn new<F>(schema: &Rc<GraphQLSchema>, parse: F) -> ExecutionConfig
+    where F: for<'a> Fn(&'a Source<'a>) -> Result<ast::Document<'a>, GraphQLError> + 'static
+  {
+    ExecutionConfig {
+      schema: schema.clone(),
+      parse: Rc::new(parse),
+      limits: None,
+      persisted_queries: None,
+      document_cache: None,
+    }
+  }
+
+  /// Rejects operations exceeding `limits` before executing them.
+  pub fn limits(mut self, limits: QueryLimits) -> ExecutionConfig {
+    self.limits = Some(limits);
+    self
+  }
+
+  /// Resolves the queries of requests sent by hash from `persisted_queries`.
+  pub fn persisted_queries(mut self, persisted_queries: &Rc<PersistedQueries>) -> ExecutionConfig {
+    self.persisted_queries = Some(persisted_queries.clone());
+    self
+  }
+
+  /// Reuses the documents of `document_cache` instead of parsing and
+  /// validating the query of every request.
+  pub fn document_cache(mut self, document_cache: &Rc<DocumentCache>) -> ExecutionConfig {
+    self.document_cache = Some(document_cache.clone());
+    self
+  }
+
+  pub fn schema(&self) -> &Rc<GraphQLSchema> {
+    &self.schema
+  }
+
+  /// Replaces the query of a request sent by hash with the persisted one.
+  pub fn resolve_query(&self, request: &mut GraphQLRequest) -> Result<(), GraphQLError> {
+    resolve_query(self.persisted_queries.as_deref(), request)
+  }
+
+  /// The document prepared for `query`, from the document cache if any.
+  pub fn prepare(&self, query: &str) -> Result<Rc<PreparedDocument>, GraphQLError> {
+    match self.document_cache {
+      Some(ref cache) => cache.get(&self.schema, &*self.parse, query),
+      None => PreparedDocument::new(&self.schema, &*self.parse, query).map(Rc::new),
+    }
+  }
+
+  /// Checks the requested operation of `document` against the limits if any.
+  pub fn check_limits(&self,
+                      request: &GraphQLRequest,
+                      document: &ast::Document)
+                      -> Result<(), GraphQLError> {
+    match self.limits {
+      Some(ref limits) => request.check_limits(limits, &self.schema, document).map(|_| ()),
+      None => Ok(()),
+    }
+  }
+}
+
+/// Responses to a request, as delivered by streaming transports: one per
+/// source event for subscriptions, and for queries and mutations a single one,
+/// or an initial one followed by those of `@defer` and `@stream`.
+pub struct ResponseStream {
+  prepared: Rc<PreparedDocument>,
+  request: GraphQLRequest,
+  context: Rc<dyn Any>,
+  state: ResponseStreamState,
+}
+
+enum ResponseStreamState {
+  Payloads(VecDeque<GraphQLResponse>),
+  Events(SourceStream),
+}
+
+impl ResponseStream {
+  /// Starts executing `request` as `config` tells. When execution cannot
+  /// start, for instance for a syntax error, the response reporting it is
+  /// returned instead.
+  #[allow(clippy::result_large_err)]
+  pub fn new(config: &ExecutionConfig,
+             request: GraphQLRequest,
+             context: Rc<dyn Any>)
+             -> Result<ResponseStream, GraphQLResponse> {
+    let prepared = config.prepare(&request.query)?;
+    if !prepared.errors().is_empty() {
+      return Err(GraphQLResponse {
+        data: None,
+        errors: prepared.errors().to_vec(),
+        incremental: Vec::new(),
+        has_next: None,
+      });
+    }
+    let state = {
+      let schema = prepared.schema();
+      let document = prepared.document();
+      config.check_limits(&request, document)?;
+
+      let operations: Vec<_> = document.definitions
+        .iter()
+        .filter_map(|d| {
+          match *d {
+            ast::Definition::Operation(ref operation) => Some(operation),
+            _ => None,
+          }
+        })
+        .collect();
+      let operation_name = request.operation_name.as_ref().map(|n| n.as_ref());
+      let variables = request.variables.clone().unwrap_or_default();
+      if matches!(select_operation(&operations, operation_name),
+                  Ok(&ast::OperationDefinition { operation: ast::OperationType::Subscription, .. })) {
+        let stream = subscribe(schema, document, operation_name, &variables, &(), &*context)?;
+        ResponseStreamState::Events(stream)
+      } else {
+        let mut payloads =
+          execute_incremental(schema, document, operation_name, &variables, &(), &*context);
+        if payloads[0].data.is_none() {
+          return Err(payloads.remove(0));
+        }
+        ResponseStreamState::Payloads(payloads.into())
+      }
+    };
+
+    Ok(ResponseStream {
+      prepared,
+      request,
+      context,
+      state,
+    })
+  }
+
+  pub fn request(&self) -> &GraphQLRequest {
+    &self.request
+  }
+
+  fn execute_event(&self, event: &dyn Any) -> GraphQLResponse {
+    execute_event(self.prepared.schema(),
+                  self.prepared.document(),
+                  self.request.operation_name.as_ref().map(|n| n.as_ref()),
+                  self.request.variables.as_ref().unwrap_or(&Arguments::new()),
+                  event,
+                  &*self.context)
+  }
+}
+
+impl Stream for ResponseStream {
+  type Item = GraphQLResponse;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<GraphQLResponse>> {
+    let this = self.get_mut();
+    let event = match this.state {
+      ResponseStreamState::Payloads(ref mut payloads) => return Poll::Ready(payloads.pop_front()),
+      ResponseStreamState::Events(ref mut stream) => stream.as_mut().poll_next(cx),
+    };
+    event.map(|event| event.map(|event| this.execute_event(&*event)))
+  }
+}
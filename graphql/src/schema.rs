@@ -0,0 +1,578 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+use graphql_language::sdl::DirectiveDefinition;
+use indexmap::IndexMap;
+
+use types::*;
+use value::Value;
+
+/// A type system rule violated by a schema definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+  message: String,
+  coordinate: Option<String>,
+}
+
+impl SchemaError {
+  pub(crate) fn new(message: String) -> SchemaError {
+    SchemaError {
+      message,
+      coordinate: None,
+    }
+  }
+
+  pub(crate) fn at(coordinate: &str, message: String) -> SchemaError {
+    SchemaError {
+      message,
+      coordinate: Some(coordinate.to_owned()),
+    }
+  }
+
+  pub fn message(&self) -> &str {
+    self.message.as_ref()
+  }
+
+  /// Schema coordinate of the definition at fault, as in `Hero`, `Hero.name`,
+  /// `Hero.friends(first:)` or `Episode.JEDI`, if the error is about one.
+  pub fn coordinate(&self) -> Option<&str> {
+    self.coordinate.as_ref().map(|c| c.as_ref())
+  }
+}
+
+impl fmt::Display for SchemaError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.message)
+  }
+}
+
+impl Error for SchemaError {}
+
+/// Named type reachable from the root operation types.
+#[derive(Clone)]
+pub enum SchemaType {
+  Output(Rc<dyn GraphQLOutput>),
+  Input(Rc<dyn GraphQLInput>),
+}
+
+impl SchemaType {
+  pub fn name(&self) -> &str {
+    match *self {
+      SchemaType::Output(ref t) => t.name(),
+      SchemaType::Input(ref t) => t.name(),
+    }
+  }
+
+  pub fn description(&self) -> Option<&str> {
+    match *self {
+      SchemaType::Output(ref t) => t.description(),
+      SchemaType::Input(ref t) => t.description(),
+    }
+  }
+  // Address of the type, the same for a type reached as an output and as an
+  // input type.
+  fn address(&self) -> *const () {
+    match *self {
+      SchemaType::Output(ref t) => Rc::as_ptr(t) as *const (),
+      SchemaType::Input(ref t) => Rc::as_ptr(t) as *const (),
+    }
+  }
+}
+
+/// Schema
+pub struct GraphQLSchema {
+  query: Rc<GraphQLObject>,
+  mutation: Option<Rc<GraphQLObject>>,
+  subscription: Option<Rc<GraphQLObject>>,
+  description: Option<String>,
+  directives: Vec<DirectiveDefinition>,
+  types: IndexMap<String, SchemaType>,
+  input_types: HashMap<String, Rc<dyn GraphQLInput>>,
+  // Object types of the schema by the interfaces they implement.
+  implementations: HashMap<String, Vec<Rc<dyn GraphQLOutput>>>,
+}
+
+impl GraphQLSchema {
+  pub fn query_type(&self) -> &Rc<GraphQLObject> {
+    &self.query
+  }
+
+  pub fn mutation_type(&self) -> Option<&Rc<GraphQLObject>> {
+    self.mutation.as_ref()
+  }
+
+  pub fn subscription_type(&self) -> Option<&Rc<GraphQLObject>> {
+    self.subscription.as_ref()
+  }
+
+  pub fn description(&self) -> Option<&str> {
+    self.description.as_ref().map(|s| s.as_ref())
+  }
+
+  /// Directives defined for the schema, besides the built-in ones.
+  pub fn directives(&self) -> &[DirectiveDefinition] {
+    &self.directives
+  }
+
+  /// Named types in the order they are first reached from the root types and
+  /// then from the types added to the schema.
+  pub fn types(&self) -> &IndexMap<String, SchemaType> {
+    &self.types
+  }
+
+  pub fn get_type(&self, name: &str) -> Option<&SchemaType> {
+    self.types.get(name)
+  }
+
+  /// Named input type, such as the type of a variable: one of the types of
+  /// arguments and input fields, or a built-in scalar.
+  pub fn input_type(&self, name: &str) -> Option<&Rc<dyn GraphQLInput>> {
+    self.input_types.get(name)
+  }
+
+  /// Object types of the schema implementing `interface`.
+  pub fn possible_types(&self, interface: &GraphQLInterface) -> Vec<&GraphQLObject> {
+    let objects = self.implementations.get(interface.name()).into_iter().flatten();
+    objects.filter_map(|t| {
+        match t.output_kind() {
+          OutputKind::Object(o) => Some(o),
+          _ => None,
+        }
+      })
+      .collect()
+  }
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// Schema Builder
+// /////////////////////////////////////////////////////////////////////////////
+
+/// Schema builder
+pub struct GraphQLSchemaType {
+  query: Rc<GraphQLObject>,
+  mutation: Option<Rc<GraphQLObject>>,
+  subscription: Option<Rc<GraphQLObject>>,
+  description: Option<String>,
+  directives: Vec<DirectiveDefinition>,
+  types: Vec<SchemaType>,
+}
+
+impl GraphQLSchemaType {
+  pub fn new(query: &Rc<GraphQLObject>) -> GraphQLSchemaType {
+    GraphQLSchemaType {
+      query: query.clone(),
+      mutation: None,
+      subscription: None,
+      description: None,
+      directives: Vec::new(),
+      types: Vec::new(),
+    }
+  }
+
+  pub fn mutation(mut self, mutation: &Rc<GraphQLObject>) -> GraphQLSchemaType {
+    self.mutation = Some(mutation.clone());
+    self
+  }
+
+  pub fn subscription(mut self, subscription: &Rc<GraphQLObject>) -> GraphQLSchemaType {
+    self.subscription = Some(subscription.clone());
+    self
+  }
+
+  pub fn description(mut self, description: &str) -> GraphQLSchemaType {
+    self.description = Some(description.to_owned());
+    self
+  }
+
+  /// Defines a directive besides the built-in ones, which is described by
+  /// introspection but not acted upon.
+  pub fn directive(mut self, directive: DirectiveDefinition) -> GraphQLSchemaType {
+    self.directives.push(directive);
+    self
+  }
+
+  /// Adds `typ` to the schema, for types the root types do not reach, such as
+  /// the object types implementing an interface which fields return.
+  pub fn output_type<T: GraphQLOutput + 'static>(mut self, typ: &Rc<T>) -> GraphQLSchemaType {
+    self.types.push(SchemaType::Output(typ.clone()));
+    self
+  }
+
+  /// Adds `typ` to the schema, see `output_type`.
+  pub fn input_type<T: GraphQLInput + 'static>(mut self, typ: &Rc<T>) -> GraphQLSchemaType {
+    self.types.push(SchemaType::Input(typ.clone()));
+    self
+  }
+
+  pub(crate) fn dyn_type(mut self, typ: SchemaType) -> GraphQLSchemaType {
+    self.types.push(typ);
+    self
+  }
+
+  /// Collects every type reachable from the root types and the added types,
+  /// and validates them against the type system rules.
+  pub fn build(self) -> Result<Rc<GraphQLSchema>, Vec<SchemaError>> {
+    let mut types = IndexMap::new();
+    let mut errors = Vec::new();
+    let roots = Some(&self.query).into_iter().chain(&self.mutation).chain(&self.subscription);
+    for root in roots {
+      let root: Rc<dyn GraphQLOutput> = root.clone();
+      collect_output_type(&root, &mut types, &mut errors);
+    }
+    for typ in &self.types {
+      match *typ {
+        SchemaType::Output(ref t) => collect_output_type(t, &mut types, &mut errors),
+        SchemaType::Input(ref t) => collect_input_type(t, &mut types, &mut errors),
+      }
+    }
+
+    errors.extend(validate_types(&types));
+    if !errors.is_empty() {
+      return Err(errors);
+    }
+
+    let input_types = collect_input_types(&types);
+    let mut implementations: HashMap<String, Vec<Rc<dyn GraphQLOutput>>> = HashMap::new();
+    for typ in types.values() {
+      if let SchemaType::Output(ref t) = *typ {
+        if let OutputKind::Object(o) = t.output_kind() {
+          for interface in o.interfaces().into_iter().flat_map(|is| is.keys()) {
+            implementations.entry(interface.clone()).or_default().push(t.clone());
+          }
+        }
+      }
+    }
+    Ok(Rc::new(GraphQLSchema {
+      query: self.query,
+      mutation: self.mutation,
+      subscription: self.subscription,
+      description: self.description,
+      directives: self.directives,
+      types,
+      input_types,
+      implementations,
+    }))
+  }
+}
+
+pub(crate) fn named_output_type(typ: &Rc<dyn GraphQLOutput>) -> &Rc<dyn GraphQLOutput> {
+  match typ.output_kind() {
+    OutputKind::List(t) => named_output_type(t.of_type()),
+    OutputKind::Optional(t) => named_output_type(t.of_type()),
+    _ => typ,
+  }
+}
+
+fn named_input_type(typ: &Rc<dyn GraphQLInput>) -> &Rc<dyn GraphQLInput> {
+  match typ.input_kind() {
+    InputKind::List(of_type) |
+    InputKind::Optional(of_type) => named_input_type(of_type),
+    _ => typ,
+  }
+}
+
+fn collect_output_type(typ: &Rc<dyn GraphQLOutput>,
+                       types: &mut IndexMap<String, SchemaType>,
+                       errors: &mut Vec<SchemaError>) {
+  let typ = named_output_type(typ);
+  if let OutputKind::Placeholder(_) = typ.output_kind() {
+    return;
+  }
+  if !insert_type(SchemaType::Output(typ.clone()), types, errors) {
+    return;
+  }
+
+  match typ.output_kind() {
+    OutputKind::Object(o) => {
+      collect_field_types(&o.fields(), types, errors);
+      for interface in o.interfaces().into_iter().flat_map(|is| is.values()) {
+        collect_output_type(&(interface.clone() as Rc<dyn GraphQLOutput>), types, errors);
+      }
+    }
+    OutputKind::Interface(i) => {
+      collect_field_types(&i.fields(), types, errors);
+      for interface in i.interfaces().into_iter().flat_map(|is| is.values()) {
+        collect_output_type(&(interface.clone() as Rc<dyn GraphQLOutput>), types, errors);
+      }
+    }
+    OutputKind::Union(u) => {
+      for object in u.types().values() {
+        collect_output_type(&(object.clone() as Rc<dyn GraphQLOutput>), types, errors);
+      }
+    }
+    _ => {}
+  }
+}
+
+fn collect_field_types(fields: &IndexMap<String, GraphQLField>,
+                       types: &mut IndexMap<String, SchemaType>,
+                       errors: &mut Vec<SchemaError>) {
+  for field in fields.values() {
+    for arg in field.args().into_iter().flat_map(|args| args.values()) {
+      collect_input_type(arg.typ(), types, errors);
+    }
+    collect_output_type(field.typ(), types, errors);
+  }
+}
+
+fn collect_input_type(typ: &Rc<dyn GraphQLInput>,
+                      types: &mut IndexMap<String, SchemaType>,
+                      errors: &mut Vec<SchemaError>) {
+  let typ = named_input_type(typ);
+  if let InputKind::Placeholder(_) = typ.input_kind() {
+    return;
+  }
+  if !insert_type(SchemaType::Input(typ.clone()), types, errors) {
+    return;
+  }
+
+  if let InputKind::InputObject(o) = typ.input_kind() {
+    for field in o.fields().values() {
+      collect_input_type(field.typ(), types, errors);
+    }
+  }
+}
+
+// Input types by name, which include the scalars and enums collected as output
+// types when they are also used as input types.
+fn collect_input_types(types: &IndexMap<String, SchemaType>)
+                       -> HashMap<String, Rc<dyn GraphQLInput>> {
+  let mut input_types: HashMap<String, Rc<dyn GraphQLInput>> = HashMap::new();
+  input_types.insert("Int".to_owned(), GraphQLScalarType::int());
+  input_types.insert("Float".to_owned(), GraphQLScalarType::float());
+  input_types.insert("String".to_owned(), GraphQLScalarType::string());
+  input_types.insert("Boolean".to_owned(), GraphQLScalarType::boolean());
+  let mut insert = |typ: &Rc<dyn GraphQLInput>| {
+    let typ = named_input_type(typ);
+    if let InputKind::Placeholder(_) = typ.input_kind() {
+      return;
+    }
+    input_types.insert(typ.name().to_owned(), typ.clone());
+  };
+  for typ in types.values() {
+    match *typ {
+      SchemaType::Output(ref t) => {
+        let fields = match t.output_kind() {
+          OutputKind::Object(o) => o.fields(),
+          OutputKind::Interface(i) => i.fields(),
+          _ => continue,
+        };
+        for arg in fields.values().flat_map(|f| f.args()).flat_map(|args| args.values()) {
+          insert(arg.typ());
+        }
+      }
+      SchemaType::Input(ref t) => insert(t),
+    }
+  }
+  input_types
+}
+
+// Adds a type not collected yet, returning whether it was. Another type of
+// the same name is reported once.
+fn insert_type(typ: SchemaType,
+               types: &mut IndexMap<String, SchemaType>,
+               errors: &mut Vec<SchemaError>)
+               -> bool {
+  match types.get(typ.name()) {
+    Some(existing) if existing.address() == typ.address() => false,
+    Some(_) => {
+      let message = format!("Schema must contain uniquely named types but contains multiple \
+                             types named \"{:}\".",
+                            typ.name());
+      let error = SchemaError::at(typ.name(), message);
+      if !errors.contains(&error) {
+        errors.push(error);
+      }
+      false
+    }
+    None => {
+      types.insert(typ.name().to_owned(), typ);
+      true
+    }
+  }
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// Type Validation
+// /////////////////////////////////////////////////////////////////////////////
+
+fn validate_types(types: &IndexMap<String, SchemaType>) -> Vec<SchemaError> {
+  let mut errors = Vec::new();
+  for typ in types.values() {
+    validate_name(typ.name(), typ.name(), &mut errors);
+    match *typ {
+      SchemaType::Output(ref t) => {
+        match t.output_kind() {
+          OutputKind::Object(o) => {
+            let fields = o.fields();
+            validate_fields(o.name(), &fields, &mut errors);
+            if let Some(interfaces) = o.interfaces() {
+              let messages = validate_implementations(o.name(), &fields, interfaces);
+              errors.extend(messages.into_iter().map(|m| SchemaError::at(o.name(), m)));
+            }
+          }
+          OutputKind::Interface(i) => {
+            let fields = i.fields();
+            validate_fields(i.name(), &fields, &mut errors);
+            if let Some(interfaces) = i.interfaces() {
+              let messages = validate_implementations(i.name(), &fields, interfaces);
+              errors.extend(messages.into_iter().map(|m| SchemaError::at(i.name(), m)));
+            }
+          }
+          OutputKind::Enum(e) => validate_enum_values(e, &mut errors),
+          _ => {}
+        }
+      }
+      SchemaType::Input(ref t) => {
+        match t.input_kind() {
+          InputKind::Enum(e) => validate_enum_values(e, &mut errors),
+          InputKind::InputObject(o) => {
+            for (name, field) in o.fields().iter() {
+              let coordinate = format!("{:}.{:}", o.name(), name);
+              validate_name(name, &coordinate, &mut errors);
+              validate_input_type(&format!("Input field {:}", coordinate),
+                                  &coordinate,
+                                  field.typ(),
+                                  field.default_value(),
+                                  &mut errors);
+            }
+          }
+          _ => {}
+        }
+      }
+    }
+  }
+  validate_input_object_cycles(types, &mut errors);
+  errors
+}
+
+fn validate_name(name: &str, coordinate: &str, errors: &mut Vec<SchemaError>) {
+  if name.starts_with("__") {
+    errors.push(SchemaError::at(coordinate,
+                                format!("Name \"{:}\" must not begin with \"__\", which is \
+                                         reserved by GraphQL introspection.",
+                                        name)));
+    return;
+  }
+
+  let mut chars = name.chars();
+  match chars.next() {
+    Some(c) if c == '_' || c.is_ascii_alphabetic() => {}
+    _ => {
+      errors.push(SchemaError::at(coordinate,
+                                  format!("Names must start with [_a-zA-Z] but \"{:}\" does not.",
+                                          name)));
+      return;
+    }
+  }
+  if !chars.all(|c| c == '_' || c.is_ascii_alphanumeric()) {
+    errors.push(SchemaError::at(coordinate,
+                                format!("Names must only contain [_a-zA-Z0-9] but \"{:}\" does \
+                                         not.",
+                                        name)));
+  }
+}
+
+fn validate_fields(type_name: &str,
+                   fields: &IndexMap<String, GraphQLField>,
+                   errors: &mut Vec<SchemaError>) {
+  for (name, field) in fields.iter() {
+    let coordinate = format!("{:}.{:}", type_name, name);
+    validate_name(name, &coordinate, errors);
+    if let OutputKind::Placeholder(target) = named_output_type(field.typ()).output_kind() {
+      errors.push(SchemaError::at(&coordinate,
+                                  format!("Field {:} references unknown type {:}.",
+                                          coordinate,
+                                          target)));
+    }
+    for (arg_name, arg) in field.args().into_iter().flat_map(|args| args.iter()) {
+      let coordinate = format!("{:}.{:}({:}:)", type_name, name, arg_name);
+      validate_name(arg_name, &coordinate, errors);
+      validate_input_type(&format!("Argument {:}", coordinate),
+                          &coordinate,
+                          arg.typ(),
+                          arg.default_value(),
+                          errors);
+    }
+  }
+}
+
+// Placeholders must have been replaced by now, which also allows default
+// values referring to them to be checked.
+fn validate_input_type(subject: &str,
+                       coordinate: &str,
+                       typ: &Rc<dyn GraphQLInput>,
+                       default_value: Option<&Value>,
+                       errors: &mut Vec<SchemaError>) {
+  if let InputKind::Placeholder(target) = named_input_type(typ).input_kind() {
+    errors.push(SchemaError::at(coordinate,
+                                format!("{:} references unknown type {:}.", subject, target)));
+  } else if let Some(default_value) = default_value {
+    if let Err(e) = validate_input_value(&**typ, default_value) {
+      errors.push(SchemaError::at(coordinate,
+                                  format!("{:} has invalid default value: {:}", subject, e)));
+    }
+  }
+}
+
+fn validate_enum_values(e: &GraphQLEnum, errors: &mut Vec<SchemaError>) {
+  for name in e.values().keys() {
+    let coordinate = format!("{:}.{:}", e.name(), name);
+    if name == "true" || name == "false" || name == "null" {
+      errors.push(SchemaError::at(&coordinate,
+                                  format!("Enum type {:} cannot include value: {:}.",
+                                          e.name(),
+                                          name)));
+    } else {
+      validate_name(name, &coordinate, errors);
+    }
+  }
+}
+
+// An input object may not reference itself through non-null fields only, as
+// no finite value could then be provided for it.
+fn validate_input_object_cycles(types: &IndexMap<String, SchemaType>,
+                                errors: &mut Vec<SchemaError>) {
+  let mut visited = HashSet::new();
+  for typ in types.values() {
+    if let SchemaType::Input(ref t) = *typ {
+      if let InputKind::InputObject(o) = t.input_kind() {
+        let mut path = Vec::new();
+        let mut path_index = HashMap::new();
+        detect_input_object_cycle(o, &mut visited, &mut path, &mut path_index, errors);
+      }
+    }
+  }
+}
+
+fn detect_input_object_cycle(input_object: &GraphQLInputObject,
+                             visited: &mut HashSet<String>,
+                             path: &mut Vec<String>,
+                             path_index: &mut HashMap<String, usize>,
+                             errors: &mut Vec<SchemaError>) {
+  if !visited.insert(input_object.name().to_owned()) {
+    return;
+  }
+
+  path_index.insert(input_object.name().to_owned(), path.len());
+  for (name, field) in input_object.fields().iter() {
+    if let InputKind::InputObject(field_type) = field.typ().input_kind() {
+      path.push(name.to_owned());
+      match path_index.get(field_type.name()).cloned() {
+        None => detect_input_object_cycle(field_type, visited, path, path_index, errors),
+        Some(index) => {
+          errors.push(SchemaError::at(field_type.name(),
+                                      format!("Cannot reference Input Object \"{:}\" within \
+                                               itself through a series of non-null fields: \
+                                               \"{:}\".",
+                                              field_type.name(),
+                                              path[index..].join("."))));
+        }
+      }
+      path.pop();
+    }
+  }
+  path_index.remove(input_object.name());
+}
@@ -0,0 +1,539 @@
+//! Builds schemas from documents of the schema definition language, parsed
+//! with `graphql_language::sdl::parse_schema`.
+//!
+//! Built types have no resolvers, which makes the schemas suited to checking
+//! documents and to tools rather than to executing them. Scalars besides the
+//! built-in ones accept any value, `ID` being one of them unless the document
+//! defines it. Directive definitions are kept for introspection, but are not
+//! acted upon.
+
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use graphql_language::ast::owned;
+use graphql_language::sdl::*;
+
+use schema::*;
+use types::*;
+use value::Value;
+
+/// Builds the schema `document` defines, failing with the definitions which
+/// break the type system rules.
+///
+/// The root operation types are the ones of the `schema` definition, or else
+/// the types named `Query`, `Mutation` and `Subscription`.
+pub fn build_schema(document: &SchemaDocument) -> Result<Rc<GraphQLSchema>, Vec<SchemaError>> {
+  let mut builder = Builder {
+    document,
+    types: NamedTypes {
+      outputs: HashMap::new(),
+      inputs: HashMap::new(),
+    },
+    objects: HashMap::new(),
+    interfaces: HashMap::new(),
+    input_objects: Vec::new(),
+    errors: Vec::new(),
+  };
+  builder.check_definitions();
+  if !builder.errors.is_empty() {
+    return Err(builder.errors);
+  }
+
+  builder.scalar("Int", GraphQLScalarType::int());
+  builder.scalar("Float", GraphQLScalarType::float());
+  builder.scalar("String", GraphQLScalarType::string());
+  builder.scalar("Boolean", GraphQLScalarType::boolean());
+  builder.scalar("ID",
+                 Rc::new(DefinedScalar {
+                   name: "ID".to_owned(),
+                   description: Some("The `ID` scalar type represents a unique identifier."
+                     .to_owned()),
+                 }));
+  for definition in &document.types {
+    builder.named_type(definition);
+  }
+  for definition in &document.types {
+    if let TypeDefinitionKind::Interface { .. } = definition.kind {
+      builder.interface(&definition.name, &mut HashSet::new());
+    }
+  }
+  for definition in &document.types {
+    if let TypeDefinitionKind::Object { .. } = definition.kind {
+      builder.object(definition);
+    }
+  }
+  for definition in &document.types {
+    if let TypeDefinitionKind::Union { ref types } = definition.kind {
+      builder.union(definition, types);
+    }
+  }
+  if !builder.errors.is_empty() {
+    return Err(builder.errors);
+  }
+
+  for object in builder.objects.values() {
+    object.replace_placeholders(&builder.types);
+  }
+  for interface in builder.interfaces.values() {
+    interface.replace_placeholders(&builder.types);
+  }
+  for input_object in &builder.input_objects {
+    input_object.replace_placeholders(&builder.types);
+  }
+  builder.schema()
+}
+
+// Scalar defined by a document, which accepts any value and serializes the
+// `Value`s resolved for it.
+struct DefinedScalar {
+  name: String,
+  description: Option<String>,
+}
+
+impl GraphQLType for DefinedScalar {
+  fn name(&self) -> &str {
+    self.name.as_ref()
+  }
+
+  fn description(&self) -> Option<&str> {
+    self.description.as_ref().map(|s| s.as_ref())
+  }
+}
+
+impl GraphQLScalar for DefinedScalar {
+  type ValueType = Value;
+  fn coerce_literal(&self, value: &str) -> Option<Value> {
+    Some(Value::String(value.to_owned()))
+  }
+
+  fn coerce_value(&self, value: &Value) -> Option<Value> {
+    Some(value.clone())
+  }
+
+  fn serialize(&self, value: &dyn Any) -> Option<Value> {
+    value.downcast_ref::<Value>().cloned()
+  }
+}
+
+struct Builder<'a> {
+  document: &'a SchemaDocument,
+  types: NamedTypes,
+  objects: HashMap<String, Rc<GraphQLObject>>,
+  interfaces: HashMap<String, Rc<GraphQLInterface>>,
+  input_objects: Vec<Rc<GraphQLInputObject>>,
+  errors: Vec<SchemaError>,
+}
+
+impl<'a> Builder<'a> {
+  fn error(&mut self, coordinate: &str, message: String) {
+    self.errors.push(SchemaError::at(coordinate, message));
+  }
+
+  // Checks what the type builders panic on or would silently overwrite.
+  fn check_definitions(&mut self) {
+    let mut names = HashSet::new();
+    for definition in &self.document.types {
+      let name = &definition.name;
+      if !names.insert(name) {
+        self.error(name, format!("There can be only one type named \"{:}\".", name));
+      }
+      let empty = match definition.kind {
+        TypeDefinitionKind::Scalar => None,
+        TypeDefinitionKind::Object { ref fields, .. } |
+        TypeDefinitionKind::Interface { ref fields, .. } => {
+          let mut field_names = HashSet::new();
+          for field in fields {
+            let coordinate = format!("{:}.{:}", name, field.name);
+            if !field_names.insert(&field.name) {
+              self.error(&coordinate,
+                         format!("Field \"{:}\" can only be defined once.", coordinate));
+            }
+            self.check_arguments(&coordinate, &field.arguments);
+          }
+          Some((fields.is_empty(), "Type", "fields"))
+        }
+        TypeDefinitionKind::Union { ref types } => {
+          Some((types.is_empty(), "Union type", "member types"))
+        }
+        TypeDefinitionKind::Enum { ref values } => {
+          let mut value_names = HashSet::new();
+          for value in values {
+            let coordinate = format!("{:}.{:}", name, value.name);
+            if !value_names.insert(&value.name) {
+              self.error(&coordinate,
+                         format!("Enum value \"{:}\" can only be defined once.", coordinate));
+            }
+            if value.deprecation_reason.as_ref().is_some_and(|r| r.trim().is_empty()) {
+              self.error(&coordinate,
+                         format!("Deprecation reason for enum value {:} cannot be empty.",
+                                 coordinate));
+            }
+          }
+          Some((values.is_empty(), "Enum type", "values"))
+        }
+        TypeDefinitionKind::InputObject { ref fields } => {
+          let mut field_names = HashSet::new();
+          for field in fields {
+            let coordinate = format!("{:}.{:}", name, field.name);
+            if !field_names.insert(&field.name) {
+              self.error(&coordinate,
+                         format!("Field \"{:}\" can only be defined once.", coordinate));
+            }
+            self.check_input_value(&coordinate, "input field", field);
+          }
+          Some((fields.is_empty(), "Input Object type", "fields"))
+        }
+      };
+      if let Some((true, kind, items)) = empty {
+        self.error(name, format!("{:} {:} must define one or more {:}.", kind, name, items));
+      }
+    }
+    for directive in &self.document.directives {
+      self.check_arguments(&format!("@{:}", directive.name), &directive.arguments);
+    }
+  }
+
+  // Checks the arguments of the field or directive at `parent`.
+  fn check_arguments(&mut self, parent: &str, arguments: &[InputValueDefinition]) {
+    let mut arg_names = HashSet::new();
+    for arg in arguments {
+      let coordinate = format!("{:}({:}:)", parent, arg.name);
+      if !arg_names.insert(&arg.name) {
+        self.error(&coordinate,
+                   format!("Argument \"{:}\" can only be defined once.", coordinate));
+      }
+      self.check_input_value(&coordinate, "argument", arg);
+    }
+  }
+
+  fn check_input_value(&mut self,
+                       coordinate: &str,
+                       subject: &str,
+                       definition: &InputValueDefinition) {
+    let required = matches!(definition.type_, TypeRef::NonNull(_)) &&
+                   definition.default_value.is_none();
+    if required && definition.deprecation_reason.is_some() {
+      self.error(coordinate,
+                 format!("Required {:} {:} cannot be deprecated.", subject, coordinate));
+    }
+  }
+
+  fn scalar<T: GraphQLScalar + 'static>(&mut self, name: &str, scalar: Rc<T>) {
+    self.types.outputs.insert(name.to_owned(), scalar.clone());
+    self.types.inputs.insert(name.to_owned(), scalar);
+  }
+
+  // Builds the types which need no other type to be built.
+  fn named_type(&mut self, definition: &TypeDefinition) {
+    let name = &definition.name;
+    match definition.kind {
+      TypeDefinitionKind::Scalar => {
+        self.scalar(name,
+                    Rc::new(DefinedScalar {
+                      name: name.to_owned(),
+                      description: definition.description.clone(),
+                    }))
+      }
+      TypeDefinitionKind::Enum { ref values } => {
+        let mut builder = GraphQLEnumType::new(name);
+        if let Some(ref description) = definition.description {
+          builder = builder.description(description);
+        }
+        for value in values {
+          builder = builder.value(&value.name, |mut builder| {
+            if let Some(ref description) = value.description {
+              builder = builder.description(description);
+            }
+            if let Some(ref reason) = value.deprecation_reason {
+              builder = builder.mark_deprecated(reason);
+            }
+            builder
+          });
+        }
+        let enum_type = builder.build();
+        self.types.outputs.insert(name.to_owned(), enum_type.clone());
+        self.types.inputs.insert(name.to_owned(), enum_type);
+      }
+      TypeDefinitionKind::InputObject { ref fields } => {
+        let mut builder = GraphQLInputObjectType::new(name);
+        if let Some(ref description) = definition.description {
+          builder = builder.description(description);
+        }
+        for field in fields {
+          builder = builder.field(&field.name, |builder| {
+            let mut builder = builder.dyn_type_of(input_type(&field.type_));
+            if let Some(ref description) = field.description {
+              builder = builder.description(description);
+            }
+            if let Some(ref reason) = field.deprecation_reason {
+              builder = builder.mark_deprecated(reason);
+            }
+            if let Some(ref default_value) = field.default_value {
+              builder = builder.default_value(value(default_value));
+            }
+            builder
+          });
+        }
+        let input_object = builder.build();
+        self.types.inputs.insert(name.to_owned(), input_object.clone());
+        self.input_objects.push(input_object);
+      }
+      _ => {}
+    }
+  }
+
+  // Builds an interface after the interfaces it implements, `visiting` being
+  // the ones it is built for.
+  fn interface(&mut self, name: &str, visiting: &mut HashSet<String>) -> Option<Rc<GraphQLInterface>> {
+    if let Some(interface) = self.interfaces.get(name) {
+      return Some(interface.clone());
+    }
+    let document = self.document;
+    let definition = document.get_type(name)?;
+    let (interfaces, fields) = match definition.kind {
+      TypeDefinitionKind::Interface { ref interfaces, ref fields } => (interfaces, fields),
+      _ => return None,
+    };
+    if !visiting.insert(name.to_owned()) {
+      self.error(name,
+                 format!("Type {:} cannot implement itself, through {:}.",
+                         name,
+                         visiting.iter().cloned().collect::<Vec<_>>().join(", ")));
+      return None;
+    }
+
+    let mut builder = GraphQLInterfaceType::new(name);
+    if let Some(ref description) = definition.description {
+      builder = builder.description(description);
+    }
+    for field in fields {
+      builder = builder.field(&field.name, |builder| field_builder(builder, field));
+    }
+    for interface_name in interfaces {
+      match self.interface(interface_name, visiting) {
+        Some(interface) => builder = builder.impl_interface(&interface),
+        None if visiting.contains(interface_name) || self.errors.is_empty() => {
+          self.not_an_interface(name, interface_name)
+        }
+        None => {}
+      }
+    }
+    visiting.remove(name);
+    let interface = builder.build_unchecked();
+    self.types.outputs.insert(name.to_owned(), interface.clone());
+    self.interfaces.insert(name.to_owned(), interface.clone());
+    Some(interface)
+  }
+
+  fn not_an_interface(&mut self, name: &str, interface_name: &str) {
+    if self.document.get_type(interface_name).is_some_and(|t| {
+      matches!(t.kind, TypeDefinitionKind::Interface { .. })
+    }) {
+      return;
+    }
+    self.error(name,
+               format!("Type {:} must only implement Interface types, it cannot implement {:}.",
+                       name,
+                       interface_name));
+  }
+
+  fn object(&mut self, definition: &TypeDefinition) {
+    let (interfaces, fields) = match definition.kind {
+      TypeDefinitionKind::Object { ref interfaces, ref fields } => (interfaces, fields),
+      _ => return,
+    };
+    let name = &definition.name;
+    let mut builder = GraphQLObjectType::new(name);
+    if let Some(ref description) = definition.description {
+      builder = builder.description(description);
+    }
+    for field in fields {
+      builder = builder.field(&field.name, |builder| field_builder(builder, field));
+    }
+    for interface_name in interfaces {
+      match self.interfaces.get(interface_name) {
+        Some(interface) => builder = builder.impl_interface(interface),
+        None => self.not_an_interface(name, interface_name),
+      }
+    }
+    let object = builder.build_unchecked();
+    self.types.outputs.insert(name.to_owned(), object.clone());
+    self.objects.insert(name.to_owned(), object);
+  }
+
+  fn union(&mut self, definition: &TypeDefinition, types: &[String]) {
+    let name = &definition.name;
+    let mut builder = GraphQLUnionType::new(name);
+    if let Some(ref description) = definition.description {
+      builder = builder.description(description);
+    }
+    for type_name in types {
+      match self.objects.get(type_name) {
+        Some(object) => builder = builder.maybe_type_of(object),
+        None => {
+          self.error(name,
+                     format!("Union type {:} can only include Object types, it cannot include \
+                              {:}.",
+                             name,
+                             type_name));
+          return;
+        }
+      }
+    }
+    self.types.outputs.insert(name.to_owned(), builder.build());
+  }
+
+  fn schema(self) -> Result<Rc<GraphQLSchema>, Vec<SchemaError>> {
+    let roots = match self.document.schema {
+      Some(ref schema) => {
+        [("Query", schema.query.as_ref().map(|s| s.as_ref()), true),
+         ("Mutation", schema.mutation.as_ref().map(|s| s.as_ref()), true),
+         ("Subscription", schema.subscription.as_ref().map(|s| s.as_ref()), true)]
+      }
+      None => {
+        [("Query", Some("Query"), true),
+         ("Mutation", Some("Mutation"), false),
+         ("Subscription", Some("Subscription"), false)]
+      }
+    };
+    let mut errors = Vec::new();
+    let mut root_types = Vec::new();
+    for &(operation, name, required) in &roots {
+      let root = match name {
+        Some(name) => self.objects.get(name),
+        None => None,
+      };
+      match (root, name) {
+        (Some(root), _) => root_types.push(Some(root)),
+        (None, Some(name)) if required || self.types.outputs.contains_key(name) => {
+          errors.push(SchemaError::new(format!("{:} root type must be Object type, it cannot \
+                                                be {:}.",
+                                               operation,
+                                               name)));
+          root_types.push(None);
+        }
+        (None, None) if operation == "Query" => {
+          errors.push(SchemaError::new("Query root type must be provided.".to_owned()));
+          root_types.push(None);
+        }
+        (None, _) => root_types.push(None),
+      }
+    }
+    if !errors.is_empty() {
+      return Err(errors);
+    }
+
+    let mut builder = GraphQLSchemaType::new(root_types[0].unwrap());
+    if let Some(mutation) = root_types[1] {
+      builder = builder.mutation(mutation);
+    }
+    if let Some(subscription) = root_types[2] {
+      builder = builder.subscription(subscription);
+    }
+    let description = self.document.schema.as_ref().and_then(|s| s.description.as_ref());
+    if let Some(description) = description {
+      builder = builder.description(description);
+    }
+    for directive in &self.document.directives {
+      builder = builder.directive(directive.clone());
+    }
+    // Types the root types do not reach are part of the schema all the same.
+    for definition in &self.document.types {
+      let name = &definition.name;
+      if let Some(typ) = self.types.outputs.get(name) {
+        builder = builder.dyn_type(SchemaType::Output(typ.clone()));
+      } else if let Some(typ) = self.types.inputs.get(name) {
+        builder = builder.dyn_type(SchemaType::Input(typ.clone()));
+      }
+    }
+    builder.build()
+  }
+}
+
+fn field_builder(builder: GraphQLFieldBuilder, field: &FieldDefinition) -> GraphQLFieldBuilder {
+  let mut builder = builder.dyn_type_of(output_type(&field.type_));
+  if let Some(ref description) = field.description {
+    builder = builder.description(description);
+  }
+  if let Some(ref reason) = field.deprecation_reason {
+    builder = builder.mark_deprecated(reason);
+  }
+  for arg in &field.arguments {
+    builder = builder.arg(&arg.name, |builder| {
+      let mut builder = builder.dyn_type_of(input_type(&arg.type_));
+      if let Some(ref description) = arg.description {
+        builder = builder.description(description);
+      }
+      if let Some(ref reason) = arg.deprecation_reason {
+        builder = builder.mark_deprecated(reason);
+      }
+      if let Some(ref default_value) = arg.default_value {
+        builder = builder.default_value(value(default_value));
+      }
+      builder
+    });
+  }
+  builder
+}
+
+// Types are non-null unless wrapped in an optional type, and named types are
+// placeholders until every type is built.
+fn output_type(type_: &TypeRef) -> Rc<dyn GraphQLOutput> {
+  match *type_ {
+    TypeRef::Named(ref name) => {
+      GraphQLOptionalType::dyn_output(Rc::new(Placeholder::new(name)), None)
+    }
+    TypeRef::List(ref of_type) => {
+      let list = GraphQLListType::dyn_output(output_type(of_type), None);
+      GraphQLOptionalType::dyn_output(list, None)
+    }
+    TypeRef::NonNull(ref of_type) => {
+      match **of_type {
+        TypeRef::Named(ref name) => Rc::new(Placeholder::new(name)),
+        TypeRef::List(ref of_type) => GraphQLListType::dyn_output(output_type(of_type), None),
+        // `T!!` is not valid syntax.
+        TypeRef::NonNull(_) => output_type(of_type),
+      }
+    }
+  }
+}
+
+fn input_type(type_: &TypeRef) -> Rc<dyn GraphQLInput> {
+  match *type_ {
+    TypeRef::Named(ref name) => GraphQLOptionalType::dyn_input(Rc::new(Placeholder::new(name))),
+    TypeRef::List(ref of_type) => {
+      GraphQLOptionalType::dyn_input(GraphQLListType::dyn_input(input_type(of_type)))
+    }
+    TypeRef::NonNull(ref of_type) => {
+      match **of_type {
+        TypeRef::Named(ref name) => Rc::new(Placeholder::new(name)),
+        TypeRef::List(ref of_type) => GraphQLListType::dyn_input(input_type(of_type)),
+        TypeRef::NonNull(_) => input_type(of_type),
+      }
+    }
+  }
+}
+
+// Integers out of range are kept as floats, which no Int accepts.
+fn value(node: &owned::Value) -> Value {
+  match *node {
+    owned::Value::Variable(_) => Value::Null,
+    owned::Value::Int(ref i) => {
+      i.value.parse().map(Value::Int).unwrap_or_else(|_| float_value(&i.value))
+    }
+    owned::Value::Float(ref f) => float_value(&f.value),
+    owned::Value::String(ref s) => Value::String(s.value.clone()),
+    owned::Value::Boolean(ref b) => Value::Boolean(b.value),
+    owned::Value::Enum(ref e) if e.name.value == "null" => Value::Null,
+    owned::Value::Enum(ref e) => Value::Enum(e.name.value.clone()),
+    owned::Value::List(ref list) => Value::List(list.values.iter().map(value).collect()),
+    owned::Value::Object(ref object) => {
+      Value::Object(object.fields.iter().map(|f| (f.name.value.clone(), value(&f.value))).collect())
+    }
+  }
+}
+
+fn float_value(value: &str) -> Value {
+  value.parse().map(Value::Float).unwrap_or(Value::Null)
+}
@@ -0,0 +1,428 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use futures_core::Stream;
+use http::header::{ACCEPT, CONTENT_TYPE};
+use http::{HeaderMap, HeaderValue, Method, Request, Response, StatusCode};
+use indexmap::IndexMap;
+
+use error::GraphQLError;
+use handler::{is_mutation, request_from_body, request_from_query, to_json, JSON};
+use request::{ExecutionConfig, GraphQLResponse, ResponseStream};
+use value::Value;
+
+pub static EVENT_STREAM: &str = "text/event-stream";
+pub static MULTIPART_MIXED: &str = "multipart/mixed";
+/// Header identifying the reserved event stream in single connection mode.
+pub static STREAM_TOKEN: &str = "x-graphql-event-stream-token";
+
+pub static DEFAULT_RESERVATION_TIMEOUT: Duration = Duration::from_secs(30);
+pub static DEFAULT_MAX_RESERVATIONS: usize = 1024;
+
+/// Body of a streamed response, in chunks.
+pub type BodyStream = Pin<Box<dyn Stream<Item = Vec<u8>>>>;
+
+/// Streams responses over HTTP, for clients which cannot use WebSockets.
+///
+/// Requests accepting `text/event-stream` follow the GraphQL over
+/// Server-Sent Events protocol. In distinct connections mode, each request
+/// gets its own event stream. In single connection mode, a stream is reserved
+/// with a PUT, opened with a GET carrying the `X-GraphQL-Event-Stream-Token`
+/// returned by the PUT, and operations are then POSTed and DELETEd with the
+/// token, their results being delivered on that one stream.
+///
+/// Requests accepting `multipart/mixed` get each response as a part of an
+/// incremental delivery response.
+pub struct GraphQLStreamHandler {
+  config: Rc<ExecutionConfig>,
+  reservation_timeout: Duration,
+  max_reservations: usize,
+  reservations: Rc<RefCell<HashMap<String, Rc<RefCell<Reservation>>>>>,
+}
+
+// Event stream of single connection mode.
+struct Reservation {
+  reserved_at: Instant,
+  connected: bool,
+  operations: IndexMap<String, ResponseStream>,
+  // Index of the operation to poll first, the one after the last polled.
+  next_operation: usize,
+  events: VecDeque<Vec<u8>>,
+  waker: Option<Waker>,
+}
+
+impl GraphQLStreamHandler {
+  pub fn new(config: &Rc<ExecutionConfig>) -> GraphQLStreamHandler {
+    GraphQLStreamHandler {
+      config: config.clone(),
+      reservation_timeout: DEFAULT_RESERVATION_TIMEOUT,
+      max_reservations: DEFAULT_MAX_RESERVATIONS,
+      reservations: Rc::new(RefCell::new(HashMap::new())),
+    }
+  }
+
+  /// Time the client has to open a reserved event stream with a GET, after
+  /// which the reservation is released.
+  pub fn reservation_timeout(mut self, timeout: Duration) -> GraphQLStreamHandler {
+    self.reservation_timeout = timeout;
+    self
+  }
+
+  /// Number of event streams which can be reserved at once, opened or not.
+  /// Reservations beyond it are refused until some are released.
+  pub fn max_reservations(mut self, max_reservations: usize) -> GraphQLStreamHandler {
+    self.max_reservations = max_reservations;
+    self
+  }
+
+  /// Whether `request` is for a streamed response, and should be handled here
+  /// rather than by the `GraphQLHttpHandler`.
+  pub fn accepts<B>(request: &Request<B>) -> bool {
+    request.headers().contains_key(STREAM_TOKEN) || request.method() == Method::PUT ||
+    request.method() == Method::DELETE ||
+    accepted_media_type(request.headers()).is_some()
+  }
+
+  pub fn handle<B: AsRef<[u8]>>(&self,
+                                request: &Request<B>,
+                                context: Rc<dyn Any>)
+                                -> Response<BodyStream> {
+    if request.method() == Method::PUT {
+      return self.reserve();
+    }
+    match request.headers().get(STREAM_TOKEN) {
+      Some(token) => {
+        let token = token.to_str().unwrap_or("");
+        let reservation = self.reservations.borrow().get(token).cloned();
+        let reservation = match reservation {
+          Some(ref reservation) if reservation.borrow().is_expired(self.reservation_timeout) => {
+            self.reservations.borrow_mut().remove(token);
+            return error_response(StatusCode::NOT_FOUND, "Stream not found.");
+          }
+          Some(reservation) => reservation,
+          None => return error_response(StatusCode::NOT_FOUND, "Stream not found."),
+        };
+        self.handle_single(request, token, &reservation, context)
+      }
+      None if request.method() == Method::DELETE => {
+        error_response(StatusCode::UNAUTHORIZED, "Missing stream token.")
+      }
+      None => self.handle_distinct(request, context),
+    }
+  }
+
+  fn reserve(&self) -> Response<BodyStream> {
+    let mut reservations = self.reservations.borrow_mut();
+    let timeout = self.reservation_timeout;
+    reservations.retain(|_, reservation| !reservation.borrow().is_expired(timeout));
+    if reservations.len() >= self.max_reservations {
+      return error_response(StatusCode::SERVICE_UNAVAILABLE, "Too many reserved streams.");
+    }
+    let token = stream_token();
+    reservations.insert(token.clone(),
+                        Rc::new(RefCell::new(Reservation {
+                          reserved_at: Instant::now(),
+                          connected: false,
+                          operations: IndexMap::new(),
+                          next_operation: 0,
+                          events: VecDeque::new(),
+                          waker: None,
+                        })));
+    response(StatusCode::CREATED, "text/plain; charset=utf-8", once(token.into_bytes()))
+  }
+
+  fn handle_single<B: AsRef<[u8]>>(&self,
+                                   request: &Request<B>,
+                                   token: &str,
+                                   reservation: &Rc<RefCell<Reservation>>,
+                                   context: Rc<dyn Any>)
+                                   -> Response<BodyStream> {
+    match *request.method() {
+      Method::GET => {
+        if reservation.borrow().connected {
+          return error_response(StatusCode::CONFLICT, "Stream already open.");
+        }
+        reservation.borrow_mut().connected = true;
+        let body = ReservationStream {
+          token: token.to_owned(),
+          reservation: reservation.clone(),
+          reservations: self.reservations.clone(),
+        };
+        response(StatusCode::OK, EVENT_STREAM, Box::pin(body))
+      }
+      Method::POST => {
+        let mut graphql_request =
+          match request_from_body(request.headers(), request.body().as_ref()) {
+            Ok(graphql_request) => graphql_request,
+            Err((status, message)) => return error_response(status, &message),
+          };
+        let id = match graphql_request.extensions.as_ref().and_then(|e| e.get("operationId")) {
+          Some(Value::String(id)) => id.clone(),
+          _ => return error_response(StatusCode::BAD_REQUEST, "Operation ID is missing."),
+        };
+        if reservation.borrow().operations.contains_key(&id) {
+          return error_response(StatusCode::CONFLICT, "Operation with ID already exists.");
+        }
+        if let Err(e) = self.config.resolve_query(&mut graphql_request) {
+          return json_response(StatusCode::BAD_REQUEST, &e.into());
+        }
+        match ResponseStream::new(&self.config, graphql_request, context) {
+          Ok(stream) => {
+            let mut reservation = reservation.borrow_mut();
+            reservation.operations.insert(id, stream);
+            reservation.wake();
+            response(StatusCode::ACCEPTED, "text/plain; charset=utf-8", once(Vec::new()))
+          }
+          Err(response) => json_response(StatusCode::BAD_REQUEST, &response),
+        }
+      }
+      Method::DELETE => {
+        let id = request.uri()
+          .query()
+          .and_then(|q| {
+            form_urlencoded::parse(q.as_bytes()).find(|(name, _)| name == "operationId")
+          })
+          .map(|(_, id)| id.into_owned());
+        let id = match id {
+          Some(id) => id,
+          None => return error_response(StatusCode::BAD_REQUEST, "Operation ID is missing."),
+        };
+        let mut reservation = reservation.borrow_mut();
+        if reservation.operations.shift_remove(&id).is_some() {
+          reservation.events.push_back(event("complete", Some(&json!({ "id": id }))));
+          reservation.wake();
+        }
+        response(StatusCode::OK, "text/plain; charset=utf-8", once(Vec::new()))
+      }
+      _ => error_response(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed."),
+    }
+  }
+
+  fn handle_distinct<B: AsRef<[u8]>>(&self,
+                                     request: &Request<B>,
+                                     context: Rc<dyn Any>)
+                                     -> Response<BodyStream> {
+    let media_type = match accepted_media_type(request.headers()) {
+      Some(media_type) => media_type,
+      None => {
+        return error_response(StatusCode::NOT_ACCEPTABLE,
+                              &format!("Accept header must allow {:} or {:}.",
+                                       EVENT_STREAM,
+                                       MULTIPART_MIXED))
+      }
+    };
+    let graphql_request = match *request.method() {
+      Method::GET => request_from_query(request.uri().query().unwrap_or("")),
+      Method::POST => request_from_body(request.headers(), request.body().as_ref()),
+      _ => return error_response(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed."),
+    };
+    let mut graphql_request = match graphql_request {
+      Ok(graphql_request) => graphql_request,
+      Err((status, message)) => return error_response(status, &message),
+    };
+    if let Err(e) = self.config.resolve_query(&mut graphql_request) {
+      return json_response(StatusCode::BAD_REQUEST, &e.into());
+    }
+
+    if request.method() == Method::GET {
+      if let Ok(prepared) = self.config.prepare(&graphql_request.query) {
+        if is_mutation(prepared.document(), &graphql_request) {
+          return error_response(StatusCode::METHOD_NOT_ALLOWED,
+                                "Can only perform a mutation operation from a POST request.");
+        }
+      }
+    }
+
+    match ResponseStream::new(&self.config, graphql_request, context) {
+      Ok(stream) => {
+        let body = FramedStream {
+          stream,
+          multipart: media_type == MULTIPART_MIXED,
+          finished: false,
+        };
+        let content_type = if body.multipart {
+          "multipart/mixed; boundary=\"-\""
+        } else {
+          EVENT_STREAM
+        };
+        response(StatusCode::OK, content_type, Box::pin(body))
+      }
+      Err(response) => json_response(StatusCode::BAD_REQUEST, &response),
+    }
+  }
+}
+
+impl Reservation {
+  // Opened streams are released when they are closed instead.
+  fn is_expired(&self, timeout: Duration) -> bool {
+    !self.connected && self.reserved_at.elapsed() >= timeout
+  }
+
+  fn wake(&mut self) {
+    if let Some(waker) = self.waker.take() {
+      waker.wake();
+    }
+  }
+}
+
+fn accepted_media_type(headers: &HeaderMap) -> Option<&'static str> {
+  let accept = headers.get(ACCEPT).and_then(|a| a.to_str().ok()).unwrap_or("");
+  accept.split(',')
+    .map(|media_type| media_type.split(';').next().unwrap_or("").trim())
+    .filter_map(|media_type| {
+      if media_type.eq_ignore_ascii_case(EVENT_STREAM) {
+        Some(EVENT_STREAM)
+      } else if media_type.eq_ignore_ascii_case(MULTIPART_MIXED) {
+        Some(MULTIPART_MIXED)
+      } else {
+        None
+      }
+    })
+    .next()
+}
+
+// 128 random bits from the operating system, which anyone holding them can
+// use the stream with.
+fn stream_token() -> String {
+  let mut bytes = [0; 16];
+  getrandom::getrandom(&mut bytes).expect("the operating system provides random bytes");
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn event(name: &str, data: Option<&serde_json::Value>) -> Vec<u8> {
+  let data = data.map(|d| d.to_string()).unwrap_or_default();
+  format!("event: {:}\ndata: {:}\n\n", name, data).into_bytes()
+}
+
+fn response(status: StatusCode,
+            content_type: &'static str,
+            body: BodyStream)
+            -> Response<BodyStream> {
+  let mut response = Response::new(body);
+  *response.status_mut() = status;
+  response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+  response
+}
+
+fn json_response(status: StatusCode, body: &GraphQLResponse) -> Response<BodyStream> {
+  response(status, JSON, once(to_json(body).to_string().into_bytes()))
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<BodyStream> {
+  json_response(status, &GraphQLError::new(message).into())
+}
+
+fn once(chunk: Vec<u8>) -> BodyStream {
+  Box::pin(Once(Some(chunk)))
+}
+
+struct Once(Option<Vec<u8>>);
+
+impl Stream for Once {
+  type Item = Vec<u8>;
+
+  fn poll_next(self: Pin<&mut Self>, _: &mut Context) -> Poll<Option<Vec<u8>>> {
+    Poll::Ready(self.get_mut().0.take())
+  }
+}
+
+// Responses of distinct connections mode, as events or as multipart parts.
+struct FramedStream {
+  stream: ResponseStream,
+  multipart: bool,
+  finished: bool,
+}
+
+impl Stream for FramedStream {
+  type Item = Vec<u8>;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Vec<u8>>> {
+    let this = self.get_mut();
+    if this.finished {
+      return Poll::Ready(None);
+    }
+    let response = match Pin::new(&mut this.stream).poll_next(cx) {
+      Poll::Ready(response) => response,
+      Poll::Pending => return Poll::Pending,
+    };
+    let mut payload = response.map(|r| to_json(&r));
+    this.finished = payload.is_none();
+
+    let chunk = if this.multipart {
+      // Payloads of `@defer` and `@stream` tell themselves whether more
+      // follow. Otherwise, the last part only tells that none do.
+      let mut part = b"\r\n---\r\nContent-Type: application/json; charset=utf-8\r\n\r\n".to_vec();
+      let payload = payload.get_or_insert_with(|| json!({ "hasNext": false }));
+      if payload.get("hasNext").is_none() {
+        payload["hasNext"] = json!(true);
+      }
+      this.finished = payload["hasNext"] == json!(false);
+      part.extend(payload.to_string().into_bytes());
+      if this.finished {
+        part.extend(b"\r\n-----\r\n");
+      }
+      part
+    } else {
+      match payload {
+        Some(ref payload) => event("next", Some(payload)),
+        None => event("complete", None),
+      }
+    };
+    Poll::Ready(Some(chunk))
+  }
+}
+
+// Responses of single connection mode, until the client disconnects.
+struct ReservationStream {
+  token: String,
+  reservation: Rc<RefCell<Reservation>>,
+  reservations: Rc<RefCell<HashMap<String, Rc<RefCell<Reservation>>>>>,
+}
+
+impl Stream for ReservationStream {
+  type Item = Vec<u8>;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Vec<u8>>> {
+    let mut reservation = self.reservation.borrow_mut();
+    let reservation = &mut *reservation;
+    // Operations take turns, as the subscriptions of a WebSocket connection.
+    let mut pending = 0;
+    while reservation.events.is_empty() && pending < reservation.operations.len() {
+      let index = reservation.next_operation % reservation.operations.len();
+      reservation.next_operation = index + 1;
+      match Pin::new(&mut reservation.operations[index]).poll_next(cx) {
+        Poll::Ready(Some(response)) => {
+          let id = reservation.operations.get_index(index).unwrap().0;
+          let data = json!({ "id": id, "payload": to_json(&response) });
+          reservation.events.push_back(event("next", Some(&data)));
+        }
+        Poll::Ready(None) => {
+          let (id, _) = reservation.operations.shift_remove_index(index).unwrap();
+          reservation.next_operation = index;
+          reservation.events.push_back(event("complete", Some(&json!({ "id": id }))));
+        }
+        Poll::Pending => pending += 1,
+      }
+    }
+
+    match reservation.events.pop_front() {
+      Some(event) => Poll::Ready(Some(event)),
+      None => {
+        reservation.waker = Some(cx.waker().clone());
+        Poll::Pending
+      }
+    }
+  }
+}
+
+// Operations stop with the event stream.
+impl Drop for ReservationStream {
+  fn drop(&mut self) {
+    self.reservations.borrow_mut().remove(&self.token);
+  }
+}
@@ -1,12 +1,24 @@
+use std::any::{Any, TypeId};
 use std::collections::HashMap;
 use std::str::FromStr;
-use std::cell::RefCell;
+use std::cell::{Ref, RefCell};
 use std::rc::Rc;
 
-macro_rules! blanket_impl {
-  ($trait_: ident for $($type_: ty),*) => {
+use indexmap::IndexMap;
+
+use error::GraphQLError;
+use schema::GraphQLSchema;
+use value::{Arguments, FromInputValue, Value};
+
+macro_rules! impl_kind_for {
+  ($trait_: ident :: $method: ident -> $kind: ident { $($type_: ty => |$this: ident| $body: expr),* }) => {
     $(
-      impl $trait_ for $type_ {}
+      impl $trait_ for $type_ {
+        fn $method(&self) -> $kind<'_> {
+          let $this = self;
+          $body
+        }
+      }
     )*
   };
 }
@@ -41,6 +53,14 @@ macro_rules! impl_scalar_type_for {
         fn coerce_literal(&self, value: &str) -> Option<Self::ValueType> {
           $value_type::from_str(value).ok()
         }
+
+        fn coerce_value(&self, value: &Value) -> Option<Self::ValueType> {
+          $value_type::from_input_value(value)
+        }
+
+        fn serialize(&self, value: &dyn Any) -> Option<Value> {
+          value.downcast_ref::<$value_type>().map(|v| Value::from(v.clone()))
+        }
       }
     };
 }
@@ -51,18 +71,187 @@ pub trait GraphQLType {
 }
 impl_graphql_type_for! { GraphQLObject, GraphQLInterface, GraphQLUnion, GraphQLEnum, GraphQLInputObject, GraphQLList, GraphQLInputList, GraphQLInputOptional, GraphQLOptional }
 
-pub trait GraphQLInput: GraphQLType {}
-impl<T: GraphQLScalar> GraphQLInput for T {}
-blanket_impl! { GraphQLInput for GraphQLEnum, GraphQLInputObject, GraphQLInputList, GraphQLInputOptional }
+pub trait GraphQLInput: GraphQLType {
+  fn input_kind(&self) -> InputKind<'_>;
+}
+
+impl<T: GraphQLScalar> GraphQLInput for T {
+  fn input_kind(&self) -> InputKind<'_> {
+    InputKind::Scalar(self)
+  }
+}
+
+impl_kind_for! { GraphQLInput::input_kind -> InputKind {
+  GraphQLEnum => |t| InputKind::Enum(t),
+  GraphQLInputObject => |t| InputKind::InputObject(t),
+  GraphQLInputList => |t| InputKind::List(&t.of_typ),
+  GraphQLInputOptional => |t| InputKind::Optional(&t.of_typ)
+}}
+
+pub trait GraphQLOutput: GraphQLType {
+  fn output_kind(&self) -> OutputKind<'_>;
+}
+
+impl<T: GraphQLScalar> GraphQLOutput for T {
+  fn output_kind(&self) -> OutputKind<'_> {
+    OutputKind::Scalar(self)
+  }
+}
+
+impl_kind_for! { GraphQLOutput::output_kind -> OutputKind {
+  GraphQLObject => |t| OutputKind::Object(t),
+  GraphQLInterface => |t| OutputKind::Interface(t),
+  GraphQLUnion => |t| OutputKind::Union(t),
+  GraphQLEnum => |t| OutputKind::Enum(t),
+  GraphQLList => |t| OutputKind::List(t),
+  GraphQLOptional => |t| OutputKind::Optional(t)
+}}
+
+/// What an input type is, so wrapped and named types can be told apart.
+pub enum InputKind<'a> {
+  Scalar(&'a dyn ScalarInput),
+  Enum(&'a GraphQLEnum),
+  InputObject(&'a GraphQLInputObject),
+  List(&'a Rc<dyn GraphQLInput>),
+  Optional(&'a Rc<dyn GraphQLInput>),
+  /// Forward reference to the named type, see `placeholder_type_of`.
+  Placeholder(&'a str),
+}
+
+/// What an output type is, so wrapped and named types can be told apart.
+pub enum OutputKind<'a> {
+  Scalar(&'a dyn ScalarOutput),
+  Object(&'a GraphQLObject),
+  Interface(&'a GraphQLInterface),
+  Union(&'a GraphQLUnion),
+  Enum(&'a GraphQLEnum),
+  List(&'a GraphQLList),
+  Optional(&'a GraphQLOptional),
+  /// Forward reference to the named type, see `placeholder_type_of`.
+  Placeholder(&'a str),
+}
+
+/// Type reference in SDL notation, e.g. `[String]!`.
+pub fn input_type_ref(typ: &dyn GraphQLInput) -> String {
+  match typ.input_kind() {
+    InputKind::List(of_type) => format!("[{:}]!", input_type_ref(&**of_type)),
+    InputKind::Optional(of_type) => input_type_ref(&**of_type).trim_end_matches('!').to_owned(),
+    InputKind::Placeholder(name) => format!("{:}!", name),
+    _ => format!("{:}!", typ.name()),
+  }
+}
+
+/// Type reference in SDL notation, e.g. `[String]!`.
+pub fn output_type_ref(typ: &dyn GraphQLOutput) -> String {
+  match typ.output_kind() {
+    OutputKind::List(t) => format!("[{:}]!", output_type_ref(&**t.of_type())),
+    OutputKind::Optional(t) => {
+      output_type_ref(&**t.of_type()).trim_end_matches('!').to_owned()
+    }
+    OutputKind::Placeholder(name) => format!("{:}!", name),
+    _ => format!("{:}!", typ.name()),
+  }
+}
 
-pub trait GraphQLOutput: GraphQLType {}
-impl<T: GraphQLScalar> GraphQLOutput for T {}
-blanket_impl! { GraphQLOutput for GraphQLObject, GraphQLInterface, GraphQLUnion, GraphQLEnum, GraphQLList, GraphQLOptional }
+/// Checks that `value` is accepted by the input type `typ`.
+pub fn validate_input_value(typ: &dyn GraphQLInput, value: &Value) -> Result<(), String> {
+  validate_input_value_of(typ, typ, value)
+}
+
+// Checks `value` against `typ`, which is `expected` or the type it makes
+// optional, mismatches being reported as expecting `expected`.
+fn validate_input_value_of(typ: &dyn GraphQLInput,
+                           expected: &dyn GraphQLInput,
+                           value: &Value)
+                           -> Result<(), String> {
+  let invalid = || Err(format!("Expected type {:}, found {:}.", input_type_ref(expected), value));
+  match (typ.input_kind(), value) {
+    (InputKind::Optional(_), Value::Null) => Ok(()),
+    (InputKind::Optional(of_type), _) => validate_input_value_of(&**of_type, typ, value),
+    // Cannot be checked until the placeholder is replaced.
+    (InputKind::Placeholder(_), _) => Ok(()),
+    (_, Value::Null) => invalid(),
+    (InputKind::List(of_type), Value::List(values)) => {
+      values.iter().try_for_each(|v| validate_input_value(&**of_type, v))
+    }
+    (InputKind::List(of_type), _) => validate_input_value(&**of_type, value),
+    (InputKind::Scalar(scalar), _) => {
+      if scalar.is_valid_value(value) {
+        Ok(())
+      } else {
+        invalid()
+      }
+    }
+    (InputKind::Enum(e), Value::Enum(name)) if e.values.contains_key(name) => Ok(()),
+    (InputKind::Enum(_), _) => invalid(),
+    (InputKind::InputObject(o), Value::Object(fields)) => {
+      for name in fields.keys() {
+        if !o.fields.borrow().contains_key(name) {
+          return Err(format!("Field {:} is not defined by type {:}.", name, o.name));
+        }
+      }
+      for (name, field) in o.fields.borrow().iter() {
+        match fields.get(name) {
+          Some(v) => validate_input_value(&*field.typ, v)?,
+          None if field.is_required() => {
+            return Err(format!("Field {:}.{:} of required type {:} was not provided.",
+                               o.name,
+                               name,
+                               input_type_ref(&*field.typ)));
+          }
+          None => {}
+        }
+      }
+      Ok(())
+    }
+    (InputKind::InputObject(_), _) => invalid(),
+  }
+}
 
 /// Scalars
 pub trait GraphQLScalar: GraphQLType {
   type ValueType;
   fn coerce_literal(&self, value: &str) -> Option<Self::ValueType>;
+
+  /// Coerces an input value, by default through `coerce_literal` on the text
+  /// of a scalar value.
+  fn coerce_value(&self, value: &Value) -> Option<Self::ValueType> {
+    match *value {
+      Value::Int(_) | Value::Float(_) | Value::Boolean(_) => {
+        self.coerce_literal(&value.to_string())
+      }
+      Value::String(ref s) => self.coerce_literal(s),
+      _ => None,
+    }
+  }
+
+  /// Serializes a runtime value resolved for a field of this type, returning
+  /// `None` when it is not a value of this scalar.
+  fn serialize(&self, _value: &dyn Any) -> Option<Value> {
+    None
+  }
+}
+
+/// Part of `GraphQLScalar` which is usable through a `dyn GraphQLInput`.
+pub trait ScalarInput {
+  fn is_valid_value(&self, value: &Value) -> bool;
+}
+
+impl<T: GraphQLScalar> ScalarInput for T {
+  fn is_valid_value(&self, value: &Value) -> bool {
+    self.coerce_value(value).is_some()
+  }
+}
+
+/// Part of `GraphQLScalar` which is usable through a `dyn GraphQLOutput`.
+pub trait ScalarOutput {
+  fn serialize_value(&self, value: &dyn Any) -> Option<Value>;
+}
+
+impl<T: GraphQLScalar> ScalarOutput for T {
+  fn serialize_value(&self, value: &dyn Any) -> Option<Value> {
+    self.serialize(value)
+  }
 }
 
 /// Built-in Scalars
@@ -90,26 +279,48 @@ impl_scalar_type_for! { GraphQLBoolean as bool where
   description = "The Boolean scalar type represents true or false."
 }
 
+/// Decides whether a runtime value belongs to an object type.
+pub type IsTypeOfFn = dyn Fn(&dyn Any) -> bool;
+
+/// Returns the name of the object type a runtime value of an abstract type
+/// belongs to.
+pub type ResolveTypeFn = dyn Fn(&dyn Any) -> Option<String>;
+
 /// Object
 pub struct GraphQLObject {
   name: String,
   description: Option<String>,
-  fields: RefCell<HashMap<String, GraphQLField>>,
-  interfaces: Option<HashMap<String, Rc<GraphQLInterface>>>,
+  fields: RefCell<IndexMap<String, GraphQLField>>,
+  interfaces: Option<IndexMap<String, Rc<GraphQLInterface>>>,
+  is_type_of: Option<Rc<IsTypeOfFn>>,
 }
 
 impl GraphQLObject {
+  /// Returns `None` when the object type does not provide `is_type_of`.
+  pub fn is_type_of(&self, value: &dyn Any) -> Option<bool> {
+    self.is_type_of.as_ref().map(|is_type_of| is_type_of(value))
+  }
+
+  pub fn fields(&self) -> Ref<'_, IndexMap<String, GraphQLField>> {
+    self.fields.borrow()
+  }
+
+  pub fn interfaces(&self) -> Option<&IndexMap<String, Rc<GraphQLInterface>>> {
+    self.interfaces.as_ref()
+  }
+
   pub fn replace_field_placeholder_type<T: GraphQLOutput + 'static>(&self,
                                                                     field_name: &str,
                                                                     other_type: &Rc<T>) {
-    let field = self.fields.borrow_mut().remove(field_name);
+    let mut fields = self.fields.borrow_mut();
+    let field = fields.get_mut(field_name);
     if field.is_none() {
       panic!("Object type {:} does not have placeholder {:} field.",
              self.name,
              field_name);
     }
 
-    if let Some(mut f) = field {
+    if let Some(f) = field {
       let f_type_name = f.typ.name().to_owned();
       if !f_type_name.ends_with("___TypePlaceholder___") {
         panic!("Field {:} in object type {:} is not a placeholder.",
@@ -117,7 +328,7 @@ impl GraphQLObject {
                self.name);
       }
 
-      let target_type = f_type_name.trim_right_matches("___TypePlaceholder___");
+      let target_type = f_type_name.trim_end_matches("___TypePlaceholder___");
       if target_type != other_type.name() {
         panic!("Placeholder {:} in object type {:} should replaced by {:} type instead of \
                 {:} type.",
@@ -128,45 +339,141 @@ impl GraphQLObject {
       }
 
       f.typ = other_type.clone();
-      self.fields.borrow_mut().insert(field_name.to_owned(), f);
     }
   }
+
+  pub(crate) fn replace_placeholders(&self, types: &NamedTypes) {
+    replace_field_placeholders(&mut self.fields.borrow_mut(), types);
+  }
 }
 
+/// Field resolver, called with the parent value, the execution context and
+/// the coerced field arguments.
+pub type ResolveFn = dyn Fn(&dyn Any, &dyn Any, &Arguments) -> Result<Box<dyn Any>, GraphQLError>;
+
 pub struct GraphQLField {
   name: String,
   description: Option<String>,
   deprecation_reason: Option<String>,
-  typ: Rc<GraphQLOutput>,
-  args: Option<HashMap<String, GraphQLArgument>>,
+  typ: Rc<dyn GraphQLOutput>,
+  args: Option<IndexMap<String, GraphQLArgument>>,
+  resolve: Option<Rc<ResolveFn>>,
+}
+
+impl GraphQLField {
+  pub fn name(&self) -> &str {
+    self.name.as_ref()
+  }
+
+  pub fn description(&self) -> Option<&str> {
+    self.description.as_ref().map(|s| s.as_ref())
+  }
+
+  pub fn deprecation_reason(&self) -> Option<&str> {
+    self.deprecation_reason.as_ref().map(|s| s.as_ref())
+  }
+
+  pub fn typ(&self) -> &Rc<dyn GraphQLOutput> {
+    &self.typ
+  }
+
+  pub fn args(&self) -> Option<&IndexMap<String, GraphQLArgument>> {
+    self.args.as_ref()
+  }
+
+  pub fn resolve(&self,
+                 source: &dyn Any,
+                 context: &dyn Any,
+                 args: &Arguments)
+                 -> Result<Box<dyn Any>, GraphQLError> {
+    match self.resolve {
+      Some(ref resolve) => resolve(source, context, args),
+      None => Err(format!("Field {:} does not have a resolver.", self.name).into()),
+    }
+  }
 }
 
 pub struct GraphQLArgument {
   name: String,
   description: Option<String>,
-  typ: Rc<GraphQLInput>,
-  default_value: Option<String>,
+  deprecation_reason: Option<String>,
+  typ: Rc<dyn GraphQLInput>,
+  default_value: Option<Value>,
+}
+
+impl GraphQLArgument {
+  /// Arguments which are not optional and have no default value must be
+  /// provided.
+  pub fn is_required(&self) -> bool {
+    match self.typ.input_kind() {
+      InputKind::Optional(_) => false,
+      _ => self.default_value.is_none(),
+    }
+  }
+
+  pub fn name(&self) -> &str {
+    self.name.as_ref()
+  }
+
+  pub fn description(&self) -> Option<&str> {
+    self.description.as_ref().map(|s| s.as_ref())
+  }
+
+  pub fn deprecation_reason(&self) -> Option<&str> {
+    self.deprecation_reason.as_ref().map(|s| s.as_ref())
+  }
+
+  pub fn typ(&self) -> &Rc<dyn GraphQLInput> {
+    &self.typ
+  }
+
+  pub fn default_value(&self) -> Option<&Value> {
+    self.default_value.as_ref()
+  }
 }
 
 /// Interfaces
 pub struct GraphQLInterface {
   name: String,
   description: Option<String>,
-  fields: RefCell<HashMap<String, GraphQLField>>,
+  fields: RefCell<IndexMap<String, GraphQLField>>,
+  interfaces: Option<IndexMap<String, Rc<GraphQLInterface>>>,
+  resolve_type: Option<Rc<ResolveTypeFn>>,
 }
 
 impl GraphQLInterface {
+  pub fn interfaces(&self) -> Option<&IndexMap<String, Rc<GraphQLInterface>>> {
+    self.interfaces.as_ref()
+  }
+
+  /// Resolves `value` to one of the object types of `schema` implementing
+  /// this interface.
+  pub fn resolve_type<'s>(&self,
+                          schema: &'s GraphQLSchema,
+                          value: &dyn Any)
+                          -> Result<&'s GraphQLObject, GraphQLError> {
+    resolve_abstract_type(&self.name,
+                          self.resolve_type.as_ref(),
+                          schema.possible_types(self),
+                          value)
+  }
+
+  pub fn fields(&self) -> Ref<'_, IndexMap<String, GraphQLField>> {
+    self.fields.borrow()
+  }
+
   pub fn replace_field_placeholder_type<T: GraphQLOutput + 'static>(&self,
                                                                     field_name: &str,
                                                                     other_type: &Rc<T>) {
-    let mut field = self.fields.borrow_mut().remove(field_name);
+    let mut fields = self.fields.borrow_mut();
+    let field = fields.get_mut(field_name);
     if field.is_none() {
       panic!("Interface type {:} does not have placeholder {:} field.",
              self.name,
              field_name);
     }
 
-    if let Some(mut f) = field {
+    if let Some(f) = field {
       let f_type_name = f.typ.name().to_owned();
       if !f_type_name.ends_with("___TypePlaceholder___") {
         panic!("Field {:} in interface type {:} is not a placeholder.",
@@ -174,7 +481,7 @@ impl GraphQLInterface {
                self.name);
       }
 
-      let target_type = f_type_name.trim_right_matches("___TypePlaceholder___");
+      let target_type = f_type_name.trim_end_matches("___TypePlaceholder___");
       if target_type != other_type.name() {
         panic!("Placeholder {:} in interface type {:} should replaced by {:} type instead of \
                 {:} type.",
@@ -185,68 +492,498 @@ impl GraphQLInterface {
       }
 
       f.typ = other_type.clone();
-      self.fields.borrow_mut().insert(field_name.to_owned(), f);
     }
   }
+
+  pub(crate) fn replace_placeholders(&self, types: &NamedTypes) {
+    replace_field_placeholders(&mut self.fields.borrow_mut(), types);
+  }
 }
 
 /// Union
 pub struct GraphQLUnion {
   name: String,
   description: Option<String>,
-  types: HashMap<String, Rc<GraphQLObject>>,
+  types: IndexMap<String, Rc<GraphQLObject>>,
+  resolve_type: Option<Rc<ResolveTypeFn>>,
+}
+
+impl GraphQLUnion {
+  pub fn types(&self) -> &IndexMap<String, Rc<GraphQLObject>> {
+    &self.types
+  }
+
+  pub fn resolve_type(&self, value: &dyn Any) -> Result<&GraphQLObject, GraphQLError> {
+    resolve_abstract_type(&self.name,
+                          self.resolve_type.as_ref(),
+                          self.types.values().map(|t| &**t).collect(),
+                          value)
+  }
+}
+
+// Checks that a type declaring `fields` correctly implements each of
+// `interfaces`, returning a message for every violation.
+pub(crate) fn validate_implementations(type_name: &str,
+                            fields: &IndexMap<String, GraphQLField>,
+                            interfaces: &IndexMap<String, Rc<GraphQLInterface>>)
+                            -> Vec<String> {
+  let mut errors = Vec::new();
+  for interface in interfaces.values() {
+    if let Some(ref transitive) = interface.interfaces {
+      for name in transitive.keys() {
+        if !interfaces.contains_key(name) {
+          errors.push(format!("Type {:} must implement {:} because it is implemented by {:}.",
+                              type_name,
+                              name,
+                              interface.name));
+        }
+      }
+    }
+
+    for (field_name, interface_field) in interface.fields().iter() {
+      let field = match fields.get(field_name) {
+        Some(field) => field,
+        None => {
+          errors.push(format!("Interface field {:}.{:} expected but {:} does not provide it.",
+                              interface.name,
+                              field_name,
+                              type_name));
+          continue;
+        }
+      };
+
+      if !is_output_sub_type(&*field.typ, &*interface_field.typ) {
+        errors.push(format!("Interface field {:}.{:} expects type {:} but {:}.{:} is type {:}.",
+                            interface.name,
+                            field_name,
+                            output_type_ref(&*interface_field.typ),
+                            type_name,
+                            field_name,
+                            output_type_ref(&*field.typ)));
+      }
+
+      let no_args = IndexMap::new();
+      let args = field.args.as_ref().unwrap_or(&no_args);
+      let interface_args = interface_field.args.as_ref().unwrap_or(&no_args);
+      for (arg_name, interface_arg) in interface_args {
+        match args.get(arg_name) {
+          None => {
+            errors.push(format!("Interface field argument {:}.{:}({:}:) expected but {:}.{:} \
+                                 does not provide it.",
+                                interface.name,
+                                field_name,
+                                arg_name,
+                                type_name,
+                                field_name));
+          }
+          Some(arg) if !is_equal_input_type(&*arg.typ, &*interface_arg.typ) => {
+            errors.push(format!("Interface field argument {:}.{:}({:}:) expects type {:} but \
+                                 {:}.{:}({:}:) is type {:}.",
+                                interface.name,
+                                field_name,
+                                arg_name,
+                                input_type_ref(&*interface_arg.typ),
+                                type_name,
+                                field_name,
+                                arg_name,
+                                input_type_ref(&*arg.typ)));
+          }
+          _ => {}
+        }
+      }
+
+      for (arg_name, arg) in args {
+        if !interface_args.contains_key(arg_name) && arg.is_required() {
+          errors.push(format!("Field {:}.{:} includes required argument {:} that is missing \
+                               from the Interface field {:}.{:}.",
+                              type_name,
+                              field_name,
+                              arg_name,
+                              interface.name,
+                              field_name));
+        }
+      }
+    }
+  }
+  errors
+}
+
+// Whether a field of type `sub` may implement an interface field of type
+// `sup`, i.e. `sub` is `sup` itself or a more specific (covariant) type.
+fn is_output_sub_type(sub: &dyn GraphQLOutput, sup: &dyn GraphQLOutput) -> bool {
+  match (sub.output_kind(), sup.output_kind()) {
+    (OutputKind::Optional(sub_t), OutputKind::Optional(sup_t)) => {
+      is_output_sub_type(&**sub_t.of_type(), &**sup_t.of_type())
+    }
+    (OutputKind::Optional(_), _) => false,
+    (_, OutputKind::Optional(sup_t)) => is_output_sub_type(sub, &**sup_t.of_type()),
+    (OutputKind::List(sub_t), OutputKind::List(sup_t)) => {
+      is_output_sub_type(&**sub_t.of_type(), &**sup_t.of_type())
+    }
+    (OutputKind::List(_), _) |
+    (_, OutputKind::List(_)) => false,
+    (sub_kind, sup_kind) => {
+      if named_output_type(sub) == named_output_type(sup) {
+        return true;
+      }
+      match (sub_kind, sup_kind) {
+        (OutputKind::Object(o), OutputKind::Interface(i)) => {
+          o.interfaces.as_ref().map(|is| is.contains_key(&i.name)).unwrap_or(false)
+        }
+        (OutputKind::Interface(si), OutputKind::Interface(i)) => {
+          si.interfaces.as_ref().map(|is| is.contains_key(&i.name)).unwrap_or(false)
+        }
+        (OutputKind::Object(o), OutputKind::Union(u)) => u.types.contains_key(&o.name),
+        _ => false,
+      }
+    }
+  }
+}
+
+fn is_equal_input_type(a: &dyn GraphQLInput, b: &dyn GraphQLInput) -> bool {
+  match (a.input_kind(), b.input_kind()) {
+    (InputKind::List(a_of), InputKind::List(b_of)) |
+    (InputKind::Optional(a_of), InputKind::Optional(b_of)) => is_equal_input_type(&**a_of, &**b_of),
+    (InputKind::List(_), _) |
+    (_, InputKind::List(_)) |
+    (InputKind::Optional(_), _) |
+    (_, InputKind::Optional(_)) => false,
+    _ => a.name() == b.name(),
+  }
+}
+
+fn named_output_type(typ: &dyn GraphQLOutput) -> &str {
+  match typ.output_kind() {
+    OutputKind::Placeholder(name) => name,
+    _ => typ.name(),
+  }
+}
+
+// Uses the resolve_type hook of an abstract type if there is one, otherwise
+// the first possible type whose is_type_of accepts the value.
+fn resolve_abstract_type<'t>(name: &str,
+                             resolve_type: Option<&Rc<ResolveTypeFn>>,
+                             possible_types: Vec<&'t GraphQLObject>,
+                             value: &dyn Any)
+                             -> Result<&'t GraphQLObject, GraphQLError> {
+  match resolve_type {
+    Some(resolve_type) => {
+      let type_name = resolve_type(value).ok_or_else(|| {
+          format!("Abstract type {:} must resolve to an Object type at runtime.", name)
+        })?;
+      possible_types.into_iter()
+        .find(|t| t.name() == type_name)
+        .ok_or_else(|| {
+          format!("Runtime Object type {:} is not a possible type for {:}.",
+                  type_name,
+                  name)
+            .into()
+        })
+    }
+    None => {
+      possible_types.into_iter()
+        .find(|t| t.is_type_of(value) == Some(true))
+        .ok_or_else(|| {
+          format!("Abstract type {:} must resolve to an Object type at runtime. Either the {:} \
+                   type should provide a resolve_type function or each possible type should \
+                   provide an is_type_of function.",
+                  name,
+                  name)
+            .into()
+        })
+    }
+  }
 }
 
 /// Enum
 pub struct GraphQLEnum {
   name: String,
   description: Option<String>,
-  values: HashMap<String, GraphQLEnumValue>,
+  values: IndexMap<String, GraphQLEnumValue>,
+}
+
+impl GraphQLEnum {
+  pub fn values(&self) -> &IndexMap<String, GraphQLEnumValue> {
+    &self.values
+  }
+
+  /// Coerces an enum literal into the internal value of the named enum value.
+  pub fn coerce_literal(&self, name: &str) -> Option<&dyn Any> {
+    self.values.get(name).map(|v| v.internal_value())
+  }
+
+  /// Coerces a variable value, given as the string name of an enum value,
+  /// into its internal value.
+  pub fn coerce_variable(&self, value: &Value) -> Option<&dyn Any> {
+    match *value {
+      Value::String(ref name) => self.coerce_literal(name),
+      _ => None,
+    }
+  }
+
+  /// Serializes an internal value back into the name of its enum value.
+  pub fn serialize(&self, value: &dyn Any) -> Option<&str> {
+    self.values.values().find(|v| v.is_internal_value(value)).map(|v| v.value())
+  }
 }
 
 pub struct GraphQLEnumValue {
   value: String,
   description: Option<String>,
   deprecation_reason: Option<String>,
+  internal_value: Option<Box<dyn InternalValue>>,
+}
+
+impl GraphQLEnumValue {
+  pub fn value(&self) -> &str {
+    self.value.as_ref()
+  }
+
+  pub fn description(&self) -> Option<&str> {
+    self.description.as_ref().map(|s| s.as_ref())
+  }
+
+  pub fn deprecation_reason(&self) -> Option<&str> {
+    self.deprecation_reason.as_ref().map(|s| s.as_ref())
+  }
+
+  /// The bound internal value, or the name as a `String` when none is bound.
+  pub fn internal_value(&self) -> &dyn Any {
+    match self.internal_value {
+      Some(ref v) => v.as_any(),
+      None => &self.value,
+    }
+  }
+
+  fn is_internal_value(&self, other: &dyn Any) -> bool {
+    match self.internal_value {
+      Some(ref v) => v.is_equal(other),
+      None => {
+        other.downcast_ref::<String>().map(|s| s == &self.value)
+          .or_else(|| other.downcast_ref::<&str>().map(|s| *s == self.value))
+          .unwrap_or(false)
+      }
+    }
+  }
+}
+
+// Type erased internal value of an enum value, which can be compared against
+// runtime values during serialization.
+trait InternalValue {
+  fn as_any(&self) -> &dyn Any;
+  fn is_equal(&self, other: &dyn Any) -> bool;
+}
+
+impl<T: Any + PartialEq> InternalValue for T {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn is_equal(&self, other: &dyn Any) -> bool {
+    other.downcast_ref::<T>().map(|o| o == self).unwrap_or(false)
+  }
 }
 
 /// Input Object
 pub struct GraphQLInputObject {
   name: String,
   description: Option<String>,
-  fields: RefCell<HashMap<String, GraphQLInputField>>,
+  fields: RefCell<IndexMap<String, GraphQLInputField>>,
+}
+
+impl GraphQLInputObject {
+  pub fn fields(&self) -> Ref<'_, IndexMap<String, GraphQLInputField>> {
+    self.fields.borrow()
+  }
+
+  pub fn replace_field_placeholder_type<T: GraphQLInput + 'static>(&self,
+                                                                   field_name: &str,
+                                                                   other_type: &Rc<T>) {
+    let mut fields = self.fields.borrow_mut();
+    let field = fields.get_mut(field_name);
+    if field.is_none() {
+      panic!("Input object type {:} does not have placeholder {:} field.",
+             self.name,
+             field_name);
+    }
+
+    if let Some(f) = field {
+      let f_type_name = f.typ.name().to_owned();
+      if !f_type_name.ends_with("___TypePlaceholder___") {
+        panic!("Field {:} in input object type {:} is not a placeholder.",
+               field_name,
+               self.name);
+      }
+
+      let target_type = f_type_name.trim_end_matches("___TypePlaceholder___");
+      if target_type != other_type.name() {
+        panic!("Placeholder {:} in input object type {:} should replaced by {:} type instead of \
+                {:} type.",
+               field_name,
+               self.name,
+               target_type,
+               other_type.name());
+      }
+
+      f.typ = other_type.clone();
+    }
+  }
+
+  pub(crate) fn replace_placeholders(&self, types: &NamedTypes) {
+    for field in self.fields.borrow_mut().values_mut() {
+      field.typ = types.input(&field.typ);
+    }
+  }
 }
 
 pub struct GraphQLInputField {
   name: String,
   description: Option<String>,
-  typ: Rc<GraphQLInput>,
+  deprecation_reason: Option<String>,
+  typ: Rc<dyn GraphQLInput>,
+  default_value: Option<Value>,
+}
+
+impl GraphQLInputField {
+  /// Fields which are not optional and have no default value must be
+  /// provided.
+  pub fn is_required(&self) -> bool {
+    match self.typ.input_kind() {
+      InputKind::Optional(_) => false,
+      _ => self.default_value.is_none(),
+    }
+  }
+
+  pub fn name(&self) -> &str {
+    self.name.as_ref()
+  }
+
+  pub fn description(&self) -> Option<&str> {
+    self.description.as_ref().map(|s| s.as_ref())
+  }
+
+  pub fn deprecation_reason(&self) -> Option<&str> {
+    self.deprecation_reason.as_ref().map(|s| s.as_ref())
+  }
+
+  pub fn typ(&self) -> &Rc<dyn GraphQLInput> {
+    &self.typ
+  }
+
+  pub fn default_value(&self) -> Option<&Value> {
+    self.default_value.as_ref()
+  }
 }
 
 /// List
 pub struct GraphQLInputList {
   name: String,
   description: Option<String>,
-  of_typ: Rc<GraphQLInput>,
+  of_typ: Rc<dyn GraphQLInput>,
+}
+
+impl GraphQLInputList {
+  pub fn of_type(&self) -> &Rc<dyn GraphQLInput> {
+    &self.of_typ
+  }
 }
 
 pub struct GraphQLList {
   name: String,
   description: Option<String>,
-  of_typ: Rc<GraphQLOutput>,
+  of_typ: Rc<dyn GraphQLOutput>,
+  values_of: Option<ListValuesFn>,
+}
+
+impl GraphQLList {
+  pub fn of_type(&self) -> &Rc<dyn GraphQLOutput> {
+    &self.of_typ
+  }
+
+  /// Items of a runtime value resolved for this type, which is either the
+  /// `Vec<T>` of the Rust type the list was declared for or a
+  /// `Vec<Box<dyn Any>>`. Returns `None` for any other value.
+  pub fn values_of<'v>(&self, value: &'v dyn Any) -> Option<Vec<&'v dyn Any>> {
+    self.values_of
+      .and_then(|values_of| values_of(value))
+      .or_else(|| list_values::<Box<dyn Any>>(value).map(|v| v.into_iter().map(unbox).collect()))
+  }
 }
 
 /// Optional
 pub struct GraphQLInputOptional {
   name: String,
   description: Option<String>,
-  of_typ: Rc<GraphQLInput>,
+  of_typ: Rc<dyn GraphQLInput>,
+}
+
+impl GraphQLInputOptional {
+  pub fn of_type(&self) -> &Rc<dyn GraphQLInput> {
+    &self.of_typ
+  }
 }
 
 pub struct GraphQLOptional {
   name: String,
   description: Option<String>,
-  of_typ: Rc<GraphQLOutput>,
+  of_typ: Rc<dyn GraphQLOutput>,
+  value_of: Option<OptionalValueFn>,
+}
+
+impl GraphQLOptional {
+  pub fn of_type(&self) -> &Rc<dyn GraphQLOutput> {
+    &self.of_typ
+  }
+
+  /// The non-null value of a runtime value resolved for this type, or `None`
+  /// for null. Besides the `Option<T>` of the Rust type the optional was
+  /// declared for, `Option<Box<dyn Any>>`, the `Option`s of `i32`, `f64`,
+  /// `String` and `bool` and `()` are understood, and any other value is
+  /// taken as non-null.
+  pub fn value_of<'v>(&self, value: &'v dyn Any) -> Option<&'v dyn Any> {
+    self.value_of
+      .and_then(|value_of| value_of(value))
+      .or_else(|| optional_value::<Box<dyn Any>>(value).map(|v| v.map(unbox)))
+      .or_else(|| scalar_optional_value(value))
+      .unwrap_or_else(|| if is_null(value) { None } else { Some(value) })
+  }
+}
+
+/// Whether a runtime value is one of the representations of null which are
+/// understood for any type: `()`, `None::<Box<dyn Any>>` and the `None` of
+/// the Rust types of the built-in scalars, such as `None::<String>`.
+pub fn is_null(value: &dyn Any) -> bool {
+  value.is::<()>() ||
+  value.downcast_ref::<Option<Box<dyn Any>>>().map(|v| v.is_none()).unwrap_or(false) ||
+  matches!(scalar_optional_value(value), Some(None))
+}
+
+// Look into runtime values of wrapping types whose Rust type is known.
+type OptionalValueFn = fn(&dyn Any) -> Option<Option<&dyn Any>>;
+type ListValuesFn = fn(&dyn Any) -> Option<Vec<&dyn Any>>;
+
+fn optional_value<T: Any>(value: &dyn Any) -> Option<Option<&T>> {
+  value.downcast_ref::<Option<T>>().map(|v| v.as_ref())
+}
+
+// Options of the Rust types of the built-in scalars, which resolvers return
+// whether or not the optional type was declared for them.
+fn scalar_optional_value(value: &dyn Any) -> Option<Option<&dyn Any>> {
+  fn any<T: Any>(value: &dyn Any) -> Option<Option<&dyn Any>> {
+    optional_value::<T>(value).map(|v| v.map(|v| v as &dyn Any))
+  }
+  any::<i32>(value)
+    .or_else(|| any::<f64>(value))
+    .or_else(|| any::<String>(value))
+    .or_else(|| any::<bool>(value))
+}
+
+fn list_values<T: Any>(value: &dyn Any) -> Option<Vec<&T>> {
+  value.downcast_ref::<Vec<T>>().map(|v| v.iter().collect())
+}
+
+fn unbox(value: &Box<dyn Any>) -> &dyn Any {
+  &**value
 }
 
 // /////////////////////////////////////////////////////////////////////////////
@@ -254,12 +991,12 @@ pub struct GraphQLOptional {
 // /////////////////////////////////////////////////////////////////////////////
 
 // Internal type placeholder for forward reference to other graphql type.
-struct Placeholder {
+pub(crate) struct Placeholder {
   name: String,
 }
 
 impl Placeholder {
-  fn new(target_type_name: &str) -> Placeholder {
+  pub(crate) fn new(target_type_name: &str) -> Placeholder {
     Placeholder { name: format!("{:}___TypePlaceholder___", target_type_name) }
   }
 }
@@ -274,28 +1011,46 @@ impl GraphQLType for Placeholder {
   }
 }
 
-impl GraphQLOutput for Placeholder {}
+impl GraphQLOutput for Placeholder {
+  fn output_kind(&self) -> OutputKind<'_> {
+    OutputKind::Placeholder(self.name.trim_end_matches("___TypePlaceholder___"))
+  }
+}
+
+impl GraphQLInput for Placeholder {
+  fn input_kind(&self) -> InputKind<'_> {
+    InputKind::Placeholder(self.name.trim_end_matches("___TypePlaceholder___"))
+  }
+}
 
 /// Scalar type builder
+///
+/// The built-in scalars are the same `Rc` on every call, since a schema
+/// rejects two different types of one name.
 pub struct GraphQLScalarType;
 
 impl GraphQLScalarType {
   pub fn int() -> Rc<GraphQLInt> {
-    Rc::new(GraphQLInt)
+    thread_local!(static INT: Rc<GraphQLInt> = Rc::new(GraphQLInt));
+    INT.with(Rc::clone)
   }
 
   pub fn float() -> Rc<GraphQLFloat> {
-    Rc::new(GraphQLFloat)
+    thread_local!(static FLOAT: Rc<GraphQLFloat> = Rc::new(GraphQLFloat));
+    FLOAT.with(Rc::clone)
   }
 
   pub fn string() -> Rc<GraphQLString> {
-    Rc::new(GraphQLString)
+    thread_local!(static STRING: Rc<GraphQLString> = Rc::new(GraphQLString));
+    STRING.with(Rc::clone)
   }
 
   pub fn boolean() -> Rc<GraphQLBoolean> {
-    Rc::new(GraphQLBoolean)
+    thread_local!(static BOOLEAN: Rc<GraphQLBoolean> = Rc::new(GraphQLBoolean));
+    BOOLEAN.with(Rc::clone)
   }
 
+  /// Builds a new scalar on every call: build it once and share the `Rc`.
   pub fn custom<T, F>(f: F) -> Rc<T>
     where T: GraphQLScalar,
           F: Fn() -> T
@@ -308,8 +1063,9 @@ impl GraphQLScalarType {
 pub struct GraphQLObjectType {
   name: String,
   description: Option<String>,
-  fields: HashMap<String, GraphQLField>,
-  interfaces: Option<HashMap<String, Rc<GraphQLInterface>>>,
+  fields: IndexMap<String, GraphQLField>,
+  interfaces: Option<IndexMap<String, Rc<GraphQLInterface>>>,
+  is_type_of: Option<Rc<IsTypeOfFn>>,
 }
 
 impl GraphQLObjectType {
@@ -317,8 +1073,9 @@ impl GraphQLObjectType {
     GraphQLObjectType {
       name: name.to_owned(),
       description: None,
-      fields: HashMap::new(),
+      fields: IndexMap::new(),
       interfaces: None,
+      is_type_of: None,
     }
   }
 
@@ -341,7 +1098,7 @@ impl GraphQLObjectType {
         interfaces.insert(interface.name().to_owned(), interface.clone());
       }
       None => {
-        let mut interfaces = HashMap::new();
+        let mut interfaces = IndexMap::new();
         interfaces.insert(interface.name().to_owned(), interface.clone());
         self.interfaces = Some(interfaces);
       }
@@ -349,17 +1106,38 @@ impl GraphQLObjectType {
     self
   }
 
+  pub fn is_type_of<F>(mut self, f: F) -> GraphQLObjectType
+    where F: Fn(&dyn Any) -> bool + 'static
+  {
+    self.is_type_of = Some(Rc::new(f));
+    self
+  }
+
   pub fn build(self) -> Rc<GraphQLObject> {
-    if self.fields.len() == 0 {
+    if self.fields.is_empty() {
       panic!("Object type {:} must contains at least one field",
              self.name);
     }
 
+    if let Some(ref interfaces) = self.interfaces {
+      let errors = validate_implementations(&self.name, &self.fields, interfaces);
+      if !errors.is_empty() {
+        panic!("{:}", errors.join("\n"));
+      }
+    }
+    self.build_unchecked()
+  }
+
+  // Builds without the checks of `build`, for types whose fields still have
+  // placeholder types. The schema checks the implementations once they are
+  // replaced.
+  pub(crate) fn build_unchecked(self) -> Rc<GraphQLObject> {
     Rc::new(GraphQLObject {
       name: self.name,
       description: self.description,
       fields: RefCell::new(self.fields),
       interfaces: self.interfaces,
+      is_type_of: self.is_type_of,
     })
   }
 }
@@ -368,7 +1146,9 @@ impl GraphQLObjectType {
 pub struct GraphQLInterfaceType {
   name: String,
   description: Option<String>,
-  fields: HashMap<String, GraphQLField>,
+  fields: IndexMap<String, GraphQLField>,
+  interfaces: Option<IndexMap<String, Rc<GraphQLInterface>>>,
+  resolve_type: Option<Rc<ResolveTypeFn>>,
 }
 
 impl GraphQLInterfaceType {
@@ -376,7 +1156,9 @@ impl GraphQLInterfaceType {
     GraphQLInterfaceType {
       name: name.to_owned(),
       description: None,
-      fields: HashMap::new(),
+      fields: IndexMap::new(),
+      interfaces: None,
+      resolve_type: None,
     }
   }
 
@@ -393,16 +1175,50 @@ impl GraphQLInterfaceType {
     self
   }
 
+  pub fn impl_interface(mut self, interface: &Rc<GraphQLInterface>) -> GraphQLInterfaceType {
+    match self.interfaces {
+      Some(ref mut interfaces) => {
+        interfaces.insert(interface.name().to_owned(), interface.clone());
+      }
+      None => {
+        let mut interfaces = IndexMap::new();
+        interfaces.insert(interface.name().to_owned(), interface.clone());
+        self.interfaces = Some(interfaces);
+      }
+    }
+    self
+  }
+
+  pub fn resolve_type<F>(mut self, f: F) -> GraphQLInterfaceType
+    where F: Fn(&dyn Any) -> Option<String> + 'static
+  {
+    self.resolve_type = Some(Rc::new(f));
+    self
+  }
+
   pub fn build(self) -> Rc<GraphQLInterface> {
-    if self.fields.len() == 0 {
+    if self.fields.is_empty() {
       panic!("Interface type {:} must contains at least one field",
              self.name);
     }
 
+    if let Some(ref interfaces) = self.interfaces {
+      let errors = validate_implementations(&self.name, &self.fields, interfaces);
+      if !errors.is_empty() {
+        panic!("{:}", errors.join("\n"));
+      }
+    }
+    self.build_unchecked()
+  }
+
+  // See `GraphQLObjectType::build_unchecked`.
+  pub(crate) fn build_unchecked(self) -> Rc<GraphQLInterface> {
     Rc::new(GraphQLInterface {
       name: self.name,
       description: self.description,
       fields: RefCell::new(self.fields),
+      interfaces: self.interfaces,
+      resolve_type: self.resolve_type,
     })
   }
 }
@@ -411,8 +1227,9 @@ pub struct GraphQLFieldBuilder {
   name: String,
   description: Option<String>,
   deprecation_reason: Option<String>,
-  typ: Option<Rc<GraphQLOutput>>,
-  args: Option<HashMap<String, GraphQLArgument>>,
+  typ: Option<Rc<dyn GraphQLOutput>>,
+  args: Option<IndexMap<String, GraphQLArgument>>,
+  resolve: Option<Rc<ResolveFn>>,
 }
 
 impl GraphQLFieldBuilder {
@@ -423,6 +1240,7 @@ impl GraphQLFieldBuilder {
       deprecation_reason: None,
       typ: None,
       args: None,
+      resolve: None,
     }
   }
 
@@ -446,6 +1264,11 @@ impl GraphQLFieldBuilder {
     self
   }
 
+  pub(crate) fn dyn_type_of(mut self, typ: Rc<dyn GraphQLOutput>) -> GraphQLFieldBuilder {
+    self.typ = Some(typ);
+    self
+  }
+
   pub fn arg<F>(mut self, name: &str, f: F) -> GraphQLFieldBuilder
     where F: Fn(GraphQLArgumentBuilder) -> GraphQLArgumentBuilder
   {
@@ -455,7 +1278,7 @@ impl GraphQLFieldBuilder {
         args.insert(name.to_owned(), arg);
       }
       None => {
-        let mut args = HashMap::new();
+        let mut args = IndexMap::new();
         args.insert(name.to_owned(), arg);
         self.args = Some(args);
       }
@@ -463,6 +1286,13 @@ impl GraphQLFieldBuilder {
     self
   }
 
+  pub fn resolve<F>(mut self, f: F) -> GraphQLFieldBuilder
+    where F: Fn(&dyn Any, &dyn Any, &Arguments) -> Result<Box<dyn Any>, GraphQLError> + 'static
+  {
+    self.resolve = Some(Rc::new(f));
+    self
+  }
+
   fn build(self) -> GraphQLField {
     if self.typ.is_none() {
       panic!("Field {:} missing type defination", self.name);
@@ -473,7 +1303,8 @@ impl GraphQLFieldBuilder {
       description: self.description,
       deprecation_reason: self.deprecation_reason,
       typ: self.typ.unwrap(),
-      args: None,
+      args: self.args,
+      resolve: self.resolve,
     }
   }
 }
@@ -481,8 +1312,9 @@ impl GraphQLFieldBuilder {
 pub struct GraphQLArgumentBuilder {
   name: String,
   description: Option<String>,
-  typ: Option<Rc<GraphQLInput>>,
-  default_value: Option<String>,
+  deprecation_reason: Option<String>,
+  typ: Option<Rc<dyn GraphQLInput>>,
+  default_value: Option<Value>,
 }
 
 impl GraphQLArgumentBuilder {
@@ -490,18 +1322,34 @@ impl GraphQLArgumentBuilder {
     GraphQLArgumentBuilder {
       name: name.to_owned(),
       description: None,
+      deprecation_reason: None,
       default_value: None,
       typ: None,
     }
   }
 
+  pub fn description(mut self, description: &str) -> GraphQLArgumentBuilder {
+    self.description = Some(description.to_owned());
+    self
+  }
+
+  pub fn mark_deprecated(mut self, reason: &str) -> GraphQLArgumentBuilder {
+    self.deprecation_reason = Some(reason.to_owned());
+    self
+  }
+
   pub fn type_of<T: GraphQLInput + 'static>(mut self, typ: &Rc<T>) -> GraphQLArgumentBuilder {
     self.typ = Some(typ.clone());
     self
   }
 
-  pub fn default_value(mut self, default_value: &str) -> GraphQLArgumentBuilder {
-    self.default_value = Some(default_value.to_owned());
+  pub(crate) fn dyn_type_of(mut self, typ: Rc<dyn GraphQLInput>) -> GraphQLArgumentBuilder {
+    self.typ = Some(typ);
+    self
+  }
+
+  pub fn default_value(mut self, default_value: Value) -> GraphQLArgumentBuilder {
+    self.default_value = Some(default_value);
     self
   }
 
@@ -510,12 +1358,22 @@ impl GraphQLArgumentBuilder {
       panic!("Argument {:} missing type defination", self.name);
     }
 
-    GraphQLArgument {
+    let arg = GraphQLArgument {
       name: self.name,
       description: self.description,
+      deprecation_reason: self.deprecation_reason,
       default_value: self.default_value,
       typ: self.typ.unwrap(),
+    };
+    if let Some(ref default_value) = arg.default_value {
+      if let Err(e) = validate_input_value(&*arg.typ, default_value) {
+        panic!("Argument {:} has invalid default value: {:}", arg.name, e);
+      }
+    }
+    if arg.deprecation_reason.is_some() && arg.is_required() {
+      panic!("Required argument {:} cannot be deprecated.", arg.name);
     }
+    arg
   }
 }
 
@@ -523,7 +1381,8 @@ impl GraphQLArgumentBuilder {
 pub struct GraphQLUnionType {
   name: String,
   description: Option<String>,
-  types: HashMap<String, Rc<GraphQLObject>>,
+  types: IndexMap<String, Rc<GraphQLObject>>,
+  resolve_type: Option<Rc<ResolveTypeFn>>,
 }
 
 impl GraphQLUnionType {
@@ -531,7 +1390,8 @@ impl GraphQLUnionType {
     GraphQLUnionType {
       name: name.to_owned(),
       description: None,
-      types: HashMap::new(),
+      types: IndexMap::new(),
+      resolve_type: None,
     }
   }
 
@@ -545,8 +1405,15 @@ impl GraphQLUnionType {
     self
   }
 
+  pub fn resolve_type<F>(mut self, f: F) -> GraphQLUnionType
+    where F: Fn(&dyn Any) -> Option<String> + 'static
+  {
+    self.resolve_type = Some(Rc::new(f));
+    self
+  }
+
   pub fn build(self) -> Rc<GraphQLUnion> {
-    if self.types.len() == 0 {
+    if self.types.is_empty() {
       panic!("Union {:} must has at least one possible type defined.",
              self.name);
     }
@@ -555,6 +1422,7 @@ impl GraphQLUnionType {
       name: self.name,
       description: self.description,
       types: self.types,
+      resolve_type: self.resolve_type,
     })
   }
 }
@@ -563,7 +1431,7 @@ impl GraphQLUnionType {
 pub struct GraphQLEnumType {
   name: String,
   description: Option<String>,
-  values: HashMap<String, GraphQLEnumValue>,
+  values: IndexMap<String, GraphQLEnumValue>,
 }
 
 impl GraphQLEnumType {
@@ -571,7 +1439,7 @@ impl GraphQLEnumType {
     GraphQLEnumType {
       name: name.to_owned(),
       description: None,
-      values: HashMap::new(),
+      values: IndexMap::new(),
     }
   }
 
@@ -589,7 +1457,7 @@ impl GraphQLEnumType {
   }
 
   pub fn build(self) -> Rc<GraphQLEnum> {
-    if self.values.len() == 0 {
+    if self.values.is_empty() {
       panic!("Enum {:} must has at least one value defined.", self.name);
     }
 
@@ -605,6 +1473,7 @@ pub struct GraphQLEnumValueBuilder {
   value: String,
   description: Option<String>,
   deprecation_reason: Option<String>,
+  internal_value: Option<Box<dyn InternalValue>>,
 }
 
 impl GraphQLEnumValueBuilder {
@@ -613,6 +1482,7 @@ impl GraphQLEnumValueBuilder {
       value: value.to_owned(),
       description: None,
       deprecation_reason: None,
+      internal_value: None,
     }
   }
 
@@ -623,7 +1493,7 @@ impl GraphQLEnumValueBuilder {
 
   pub fn mark_deprecated(mut self, deprecation_reason: &str) -> GraphQLEnumValueBuilder {
     let reason = deprecation_reason.trim().to_owned();
-    if reason.len() == 0 {
+    if reason.is_empty() {
       panic!("Deprecation reason for enum value {:} cannot be empty",
              self.value);
     }
@@ -631,11 +1501,17 @@ impl GraphQLEnumValueBuilder {
     self
   }
 
+  pub fn internal_value<T: Any + PartialEq>(mut self, value: T) -> GraphQLEnumValueBuilder {
+    self.internal_value = Some(Box::new(value));
+    self
+  }
+
   fn build(self) -> GraphQLEnumValue {
     GraphQLEnumValue {
       value: self.value,
       description: self.description,
       deprecation_reason: self.deprecation_reason,
+      internal_value: self.internal_value,
     }
   }
 }
@@ -644,7 +1520,7 @@ impl GraphQLEnumValueBuilder {
 pub struct GraphQLInputObjectType {
   name: String,
   description: Option<String>,
-  fields: HashMap<String, GraphQLInputField>,
+  fields: IndexMap<String, GraphQLInputField>,
 }
 
 impl GraphQLInputObjectType {
@@ -652,7 +1528,7 @@ impl GraphQLInputObjectType {
     GraphQLInputObjectType {
       name: name.to_owned(),
       description: None,
-      fields: HashMap::new(),
+      fields: IndexMap::new(),
     }
   }
 
@@ -670,7 +1546,7 @@ impl GraphQLInputObjectType {
   }
 
   pub fn build(self) -> Rc<GraphQLInputObject> {
-    if self.fields.len() == 0 {
+    if self.fields.is_empty() {
       panic!("Input object type {:} must contains at least one field",
              self.name);
     }
@@ -686,7 +1562,9 @@ impl GraphQLInputObjectType {
 pub struct GraphQLInputFieldBuilder {
   name: String,
   description: Option<String>,
-  typ: Option<Rc<GraphQLInput>>,
+  deprecation_reason: Option<String>,
+  typ: Option<Rc<dyn GraphQLInput>>,
+  default_value: Option<Value>,
 }
 
 impl GraphQLInputFieldBuilder {
@@ -694,25 +1572,63 @@ impl GraphQLInputFieldBuilder {
     GraphQLInputFieldBuilder {
       name: name.to_owned(),
       description: None,
+      deprecation_reason: None,
       typ: None,
+      default_value: None,
     }
   }
 
+  pub fn description(mut self, description: &str) -> GraphQLInputFieldBuilder {
+    self.description = Some(description.to_owned());
+    self
+  }
+
+  pub fn mark_deprecated(mut self, reason: &str) -> GraphQLInputFieldBuilder {
+    self.deprecation_reason = Some(reason.to_owned());
+    self
+  }
+
   pub fn type_of<T: GraphQLInput + 'static>(mut self, typ: &Rc<T>) -> GraphQLInputFieldBuilder {
     self.typ = Some(typ.clone());
     self
   }
 
+  pub fn placeholder_type_of(mut self, target_type: &str) -> GraphQLInputFieldBuilder {
+    self.typ = Some(Rc::new(Placeholder::new(target_type)));
+    self
+  }
+
+  pub(crate) fn dyn_type_of(mut self, typ: Rc<dyn GraphQLInput>) -> GraphQLInputFieldBuilder {
+    self.typ = Some(typ);
+    self
+  }
+
+  pub fn default_value(mut self, default_value: Value) -> GraphQLInputFieldBuilder {
+    self.default_value = Some(default_value);
+    self
+  }
+
   fn build(self) -> GraphQLInputField {
     if self.typ.is_none() {
       panic!("Input object field {:} missing type defination", self.name);
     }
 
-    GraphQLInputField {
+    let field = GraphQLInputField {
       name: self.name,
       description: self.description,
+      deprecation_reason: self.deprecation_reason,
       typ: self.typ.unwrap(),
+      default_value: self.default_value,
+    };
+    if let Some(ref default_value) = field.default_value {
+      if let Err(e) = validate_input_value(&*field.typ, default_value) {
+        panic!("Input object field {:} has invalid default value: {:}", field.name, e);
+      }
     }
+    if field.deprecation_reason.is_some() && field.is_required() {
+      panic!("Required input object field {:} cannot be deprecated.", field.name);
+    }
+    field
   }
 }
 
@@ -720,18 +1636,29 @@ impl GraphQLInputFieldBuilder {
 pub struct GraphQLListType;
 impl GraphQLListType {
   pub fn input<T: GraphQLInput + 'static>(of_type: &Rc<T>) -> Rc<GraphQLInputList> {
+    GraphQLListType::dyn_input(of_type.clone())
+  }
+
+  pub fn output<T: GraphQLOutput + 'static>(of_type: &Rc<T>) -> Rc<GraphQLList> {
+    GraphQLListType::dyn_output(of_type.clone(), None)
+  }
+
+  pub(crate) fn dyn_input(of_type: Rc<dyn GraphQLInput>) -> Rc<GraphQLInputList> {
     Rc::new(GraphQLInputList {
       name: of_type.name().to_owned(),
       description: Some(format!("List of {}", of_type.name())),
-      of_typ: of_type.clone(),
+      of_typ: of_type,
     })
   }
 
-  pub fn output<T: GraphQLOutput + 'static>(of_type: &Rc<T>) -> Rc<GraphQLList> {
+  pub(crate) fn dyn_output(of_type: Rc<dyn GraphQLOutput>,
+                           values_of: Option<ListValuesFn>)
+                           -> Rc<GraphQLList> {
     Rc::new(GraphQLList {
       name: of_type.name().to_owned(),
       description: Some(format!("List of {}", of_type.name())),
-      of_typ: of_type.clone(),
+      of_typ: of_type,
+      values_of,
     })
   }
 }
@@ -740,18 +1667,176 @@ impl GraphQLListType {
 pub struct GraphQLOptionalType;
 impl GraphQLOptionalType {
   pub fn input<T: GraphQLInput + 'static>(of_type: &Rc<T>) -> Rc<GraphQLInputOptional> {
+    GraphQLOptionalType::dyn_input(of_type.clone())
+  }
+
+  /// Optional of `of_type`, whose resolvers return null as `()`,
+  /// `None::<Box<dyn Any>>` or, for the built-in scalars, the `None` of their
+  /// Rust type, such as `None::<String>`. Other values are non-null, so
+  /// optionals of other Rust types are declared as
+  /// `<Option<T> as AsOutputType>::output_type()` instead, which knows `T`.
+  pub fn output<T: GraphQLOutput + 'static>(of_type: &Rc<T>) -> Rc<GraphQLOptional> {
+    GraphQLOptionalType::dyn_output(of_type.clone(), None)
+  }
+
+  pub(crate) fn dyn_input(of_type: Rc<dyn GraphQLInput>) -> Rc<GraphQLInputOptional> {
     Rc::new(GraphQLInputOptional {
       name: of_type.name().to_owned(),
       description: Some(format!("Optional {}", of_type.name())),
-      of_typ: of_type.clone(),
+      of_typ: of_type,
     })
   }
 
-  pub fn output<T: GraphQLOutput + 'static>(of_type: &Rc<T>) -> Rc<GraphQLOptional> {
+  pub(crate) fn dyn_output(of_type: Rc<dyn GraphQLOutput>,
+                           value_of: Option<OptionalValueFn>)
+                           -> Rc<GraphQLOptional> {
+    Rc::new(GraphQLOptional {
+      name: of_type.name().to_owned(),
+      description: Some(format!("Optional {}", of_type.name())),
+      of_typ: of_type,
+      value_of,
+    })
+  }
+}
+
+/// Named types replacing the placeholders of types built from their
+/// definitions, wherever the placeholders are wrapped.
+pub(crate) struct NamedTypes {
+  pub outputs: HashMap<String, Rc<dyn GraphQLOutput>>,
+  pub inputs: HashMap<String, Rc<dyn GraphQLInput>>,
+}
+
+impl NamedTypes {
+  // Unknown types are left as placeholders for the schema to report them.
+  fn output(&self, typ: &Rc<dyn GraphQLOutput>) -> Rc<dyn GraphQLOutput> {
+    match typ.output_kind() {
+      OutputKind::List(t) => GraphQLListType::dyn_output(self.output(&t.of_typ), t.values_of),
+      OutputKind::Optional(t) => {
+        GraphQLOptionalType::dyn_output(self.output(&t.of_typ), t.value_of)
+      }
+      OutputKind::Placeholder(name) => self.outputs.get(name).unwrap_or(typ).clone(),
+      _ => typ.clone(),
+    }
+  }
+
+  fn input(&self, typ: &Rc<dyn GraphQLInput>) -> Rc<dyn GraphQLInput> {
+    match typ.input_kind() {
+      InputKind::List(of_type) => GraphQLListType::dyn_input(self.input(of_type)),
+      InputKind::Optional(of_type) => GraphQLOptionalType::dyn_input(self.input(of_type)),
+      InputKind::Placeholder(name) => self.inputs.get(name).unwrap_or(typ).clone(),
+      _ => typ.clone(),
+    }
+  }
+}
+
+fn replace_field_placeholders(fields: &mut IndexMap<String, GraphQLField>, types: &NamedTypes) {
+  for field in fields.values_mut() {
+    field.typ = types.output(&field.typ);
+    for arg in field.args.iter_mut().flat_map(|args| args.values_mut()) {
+      arg.typ = types.input(&arg.typ);
+    }
+  }
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// Rust Type Mapping
+// /////////////////////////////////////////////////////////////////////////////
+
+/// Rust types which are exposed as a GraphQL output type.
+pub trait AsOutputType {
+  type Type: GraphQLOutput + 'static;
+  fn output_type() -> Rc<Self::Type>;
+}
+
+/// Rust types which are accepted as a GraphQL input type.
+pub trait AsInputType {
+  type Type: GraphQLInput + 'static;
+  fn input_type() -> Rc<Self::Type>;
+}
+
+/// Type of the Rust type `T` built by `build` the first time it is asked
+/// for on this thread, and the same `Rc` afterwards. Derived types are
+/// shared this way, as a schema rejects two different types of one name.
+pub fn shared_type<T, R, F>(build: F) -> Rc<R>
+  where T: ?Sized + 'static,
+        R: 'static,
+        F: FnOnce() -> Rc<R>
+{
+  thread_local! {
+    static TYPES: RefCell<HashMap<(TypeId, TypeId), Rc<dyn Any>>> = RefCell::new(HashMap::new());
+  }
+  let key = (TypeId::of::<T>(), TypeId::of::<R>());
+  if let Some(typ) = TYPES.with(|types| types.borrow().get(&key).cloned()) {
+    return typ.downcast().expect("types are stored under the `TypeId` of their `Rc`");
+  }
+  // Building may ask for the types of fields, so the map is not borrowed
+  // meanwhile.
+  let typ = build();
+  TYPES.with(|types| types.borrow_mut().insert(key, typ.clone()));
+  typ
+}
+
+macro_rules! impl_as_scalar_type_for {
+  ($($rust_type: ty => $scalar: ident as $ctor: ident),*) => {
+    $(
+      impl AsOutputType for $rust_type {
+        type Type = $scalar;
+        fn output_type() -> Rc<$scalar> { GraphQLScalarType::$ctor() }
+      }
+
+      impl AsInputType for $rust_type {
+        type Type = $scalar;
+        fn input_type() -> Rc<$scalar> { GraphQLScalarType::$ctor() }
+      }
+    )*
+  };
+}
+
+impl_as_scalar_type_for! {
+  i32 => GraphQLInt as int,
+  f64 => GraphQLFloat as float,
+  String => GraphQLString as string,
+  bool => GraphQLBoolean as boolean
+}
+
+impl<T: AsOutputType + 'static> AsOutputType for Option<T> {
+  type Type = GraphQLOptional;
+  fn output_type() -> Rc<GraphQLOptional> {
+    let of_type = T::output_type();
     Rc::new(GraphQLOptional {
       name: of_type.name().to_owned(),
       description: Some(format!("Optional {}", of_type.name())),
-      of_typ: of_type.clone(),
+      of_typ: of_type,
+      value_of: Some(|value| optional_value::<T>(value).map(|v| v.map(|v| v as &dyn Any))),
     })
   }
 }
+
+impl<T: AsInputType> AsInputType for Option<T> {
+  type Type = GraphQLInputOptional;
+  fn input_type() -> Rc<GraphQLInputOptional> {
+    GraphQLOptionalType::input(&T::input_type())
+  }
+}
+
+impl<T: AsOutputType + 'static> AsOutputType for Vec<T> {
+  type Type = GraphQLList;
+  fn output_type() -> Rc<GraphQLList> {
+    let of_type = T::output_type();
+    Rc::new(GraphQLList {
+      name: of_type.name().to_owned(),
+      description: Some(format!("List of {}", of_type.name())),
+      of_typ: of_type,
+      values_of: Some(|value| {
+        list_values::<T>(value).map(|v| v.into_iter().map(|v| v as &dyn Any).collect())
+      }),
+    })
+  }
+}
+
+impl<T: AsInputType> AsInputType for Vec<T> {
+  type Type = GraphQLInputList;
+  fn input_type() -> Rc<GraphQLInputList> {
+    GraphQLListType::input(&T::input_type())
+  }
+}
@@ -0,0 +1,421 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use graphql_language::ast;
+
+use error::GraphQLError;
+use schema::{named_output_type, GraphQLSchema, SchemaType};
+use types::*;
+
+/// Checks the uses of `@defer` and `@stream` in `document`:
+///
+///   - neither is used on the root fields of mutations and subscriptions,
+///   - neither is used in subscription operations, unless disabled with an
+///     `if` argument which can be false,
+///   - `@stream` is only used on list fields,
+///   - their labels are static and unique.
+pub fn validate(schema: &GraphQLSchema, document: &ast::Document) -> Vec<GraphQLError> {
+  let mut fragments = HashMap::new();
+  for definition in &document.definitions {
+    if let ast::Definition::Fragment(ref fragment) = *definition {
+      fragments.insert(fragment.name.value, fragment);
+    }
+  }
+
+  // Fragments are checked once, as part of subscriptions if any uses them.
+  let mut subscription_fragments = HashSet::new();
+  for definition in &document.definitions {
+    if let ast::Definition::Operation(ref operation) = *definition {
+      if let ast::OperationType::Subscription = operation.operation {
+        collect_spreads(&operation.selection_set, &fragments, &mut subscription_fragments);
+      }
+    }
+  }
+
+  let mut validator = Validator {
+    schema,
+    labels: HashSet::new(),
+    errors: Vec::new(),
+  };
+  for definition in &document.definitions {
+    match *definition {
+      ast::Definition::Operation(ref operation) => {
+        let (root, subscription) = match operation.operation {
+          ast::OperationType::Query => (Some(schema.query_type()), false),
+          ast::OperationType::Mutation => (schema.mutation_type(), false),
+          ast::OperationType::Subscription => (schema.subscription_type(), true),
+        };
+        if let Some(root) = root {
+          validator.visit_selection_set(root.name(), subscription, &operation.selection_set);
+        }
+      }
+      ast::Definition::Fragment(ref fragment) => {
+        let subscription = subscription_fragments.contains(fragment.name.value);
+        validator.visit_selection_set(fragment.type_condition.name.value,
+                                      subscription,
+                                      &fragment.selection_set);
+      }
+    }
+  }
+  validator.errors
+}
+
+/// Checks that the names `document` uses are defined:
+///
+///   - fields and their arguments exist on the types they are selected on,
+///   - fields have selections exactly when their types have fields,
+///   - type conditions and variable types are types of the schema,
+///   - spread fragments are defined in `document`,
+///   - directives are the ones the crate executes.
+///
+/// Execution reports most of these as it goes, which leaves the check to
+/// tools looking at documents ahead of it.
+pub fn validate_names(schema: &GraphQLSchema, document: &ast::Document) -> Vec<GraphQLError> {
+  let mut validator = NameValidator {
+    schema,
+    fragments: HashSet::new(),
+    errors: Vec::new(),
+  };
+  for definition in &document.definitions {
+    if let ast::Definition::Fragment(ref fragment) = *definition {
+      validator.fragments.insert(fragment.name.value.to_owned());
+    }
+  }
+  for definition in &document.definitions {
+    match *definition {
+      ast::Definition::Operation(ref operation) => {
+        for variable in operation.variable_definitions.iter().flat_map(|v| v.iter()) {
+          validator.check_type(type_name(&variable.type_));
+        }
+        validator.check_directives(&operation.directives);
+        let root = match operation.operation {
+          ast::OperationType::Query => Some(schema.query_type()),
+          ast::OperationType::Mutation => schema.mutation_type(),
+          ast::OperationType::Subscription => schema.subscription_type(),
+        };
+        match root {
+          Some(root) => validator.visit_selection_set(root.name(), &operation.selection_set),
+          None if operation.operation == ast::OperationType::Mutation => {
+            validator.error("Schema is not configured for mutations.", &operation.loc)
+          }
+          None => validator.error("Schema is not configured for subscriptions.", &operation.loc),
+        }
+      }
+      ast::Definition::Fragment(ref fragment) => {
+        validator.check_directives(&fragment.directives);
+        if validator.check_type(&fragment.type_condition.name) {
+          validator.visit_selection_set(fragment.type_condition.name.value,
+                                        &fragment.selection_set);
+        }
+      }
+    }
+  }
+  validator.errors
+}
+
+fn type_name<'d, 'a>(typ: &'d ast::Type<'a>) -> &'d ast::Name<'a> {
+  match *typ {
+    ast::Type::Named(ref t) => &t.name,
+    ast::Type::List(ref t) => type_name(&t.type_),
+    ast::Type::NonNullNamed(ref t) => &t.type_.name,
+    ast::Type::NonNullList(ref t) => type_name(&t.type_.type_),
+  }
+}
+
+fn collect_spreads<'a>(selection_set: &ast::SelectionSet<'a>,
+                       fragments: &HashMap<&'a str, &'a ast::FragmentDefinition<'a>>,
+                       spreads: &mut HashSet<&'a str>) {
+  for selection in &selection_set.selections {
+    match *selection {
+      ast::Selection::Field(ref field) => {
+        if let Some(ref selection_set) = field.selection_set {
+          collect_spreads(selection_set, fragments, spreads);
+        }
+      }
+      ast::Selection::FragmentSpread(ref spread) => {
+        if let Some(fragment) = fragments.get(spread.name.value) {
+          if spreads.insert(spread.name.value) {
+            collect_spreads(&fragment.selection_set, fragments, spreads);
+          }
+        }
+      }
+      ast::Selection::InlineFragment(ref fragment) => {
+        collect_spreads(&fragment.selection_set, fragments, spreads)
+      }
+    }
+  }
+}
+
+struct Validator<'a> {
+  schema: &'a GraphQLSchema,
+  labels: HashSet<String>,
+  errors: Vec<GraphQLError>,
+}
+
+impl<'a> Validator<'a> {
+  fn visit_selection_set(&mut self,
+                         parent: &str,
+                         subscription: bool,
+                         selection_set: &ast::SelectionSet) {
+    for selection in &selection_set.selections {
+      match *selection {
+        ast::Selection::Field(ref field) => {
+          let typ = field_type(self.schema, parent, field.name.value);
+          if let Some(directive) = find_directive(&field.directives, "stream") {
+            self.check_directive(directive, "Stream", parent, subscription);
+            if let Some(ref typ) = typ {
+              if !is_list(&**typ) {
+                self.error(&format!("Stream directive cannot be used on non-list field \"{:}\" \
+                                     on type \"{:}\".",
+                                    field.name.value,
+                                    parent),
+                           &directive.loc);
+              }
+            }
+          }
+          if let (Some(typ), Some(selection_set)) = (typ, field.selection_set.as_ref()) {
+            self.visit_selection_set(named_output_type(&typ).name(), subscription, selection_set);
+          }
+        }
+        ast::Selection::FragmentSpread(ref spread) => {
+          if let Some(directive) = find_directive(&spread.directives, "defer") {
+            self.check_directive(directive, "Defer", parent, subscription);
+          }
+        }
+        ast::Selection::InlineFragment(ref fragment) => {
+          if let Some(directive) = find_directive(&fragment.directives, "defer") {
+            self.check_directive(directive, "Defer", parent, subscription);
+          }
+          let typ = fragment.type_condition.as_ref().map(|t| t.name.value).unwrap_or(parent);
+          self.visit_selection_set(typ, subscription, &fragment.selection_set);
+        }
+      }
+    }
+  }
+
+  fn check_directive(&mut self,
+                     directive: &ast::Directive,
+                     name: &str,
+                     parent: &str,
+                     subscription: bool) {
+    if self.schema.mutation_type().is_some_and(|t| t.name() == parent) {
+      self.error(&format!("{:} directive cannot be used on root mutation type \"{:}\".",
+                          name,
+                          parent),
+                 &directive.loc);
+    }
+    if self.schema.subscription_type().is_some_and(|t| t.name() == parent) {
+      self.error(&format!("{:} directive cannot be used on root subscription type \"{:}\".",
+                          name,
+                          parent),
+                 &directive.loc);
+    }
+
+    let argument = |name| {
+      directive.arguments.iter().flat_map(|args| args.iter()).find(|arg| arg.name.value == name)
+    };
+    if subscription {
+      let can_be_false = match argument("if").map(|arg| &arg.value) {
+        Some(ast::Value::Boolean(b)) => !b.value,
+        Some(&ast::Value::Variable(_)) => true,
+        _ => false,
+      };
+      if !can_be_false {
+        self.error(&format!("{:} directive not supported on subscription operations. Disable \
+                             `@{:}` by setting the `if` argument to `false`.",
+                            name,
+                            directive.name.value),
+                   &directive.loc);
+      }
+    }
+
+    match argument("label").map(|arg| &arg.value) {
+      Some(ast::Value::String(label)) if !self.labels.insert(label.value.clone()) => {
+        self.error("Defer/Stream directive label argument must be unique.", &directive.loc);
+      }
+      Some(&ast::Value::Variable(_)) => {
+        self.error("Defer/Stream directive label argument must be static.", &directive.loc);
+      }
+      _ => {}
+    }
+  }
+
+  fn error(&mut self, message: &str, loc: &Option<ast::Location>) {
+    let e = GraphQLError::new(message);
+    self.errors.push(match *loc {
+      Some(ref loc) => e.location(loc),
+      None => e,
+    });
+  }
+}
+
+// Scalars which variables can use whether or not the schema does.
+static BUILT_IN_SCALARS: [&str; 4] = ["Int", "Float", "String", "Boolean"];
+
+static DIRECTIVES: [(&str, &[&str]); 4] = [("skip", &["if"]),
+                                           ("include", &["if"]),
+                                           ("defer", &["if", "label"]),
+                                           ("stream", &["if", "label", "initialCount"])];
+
+struct NameValidator<'a> {
+  schema: &'a GraphQLSchema,
+  fragments: HashSet<String>,
+  errors: Vec<GraphQLError>,
+}
+
+impl<'a> NameValidator<'a> {
+  fn visit_selection_set(&mut self, parent: &str, selection_set: &ast::SelectionSet) {
+    let parent_type = match self.schema.get_type(parent) {
+      Some(SchemaType::Output(parent)) => parent.clone(),
+      _ => return,
+    };
+    for selection in &selection_set.selections {
+      match *selection {
+        ast::Selection::Field(ref field) => {
+          self.check_directives(&field.directives);
+          self.visit_field(parent, &parent_type, field);
+        }
+        ast::Selection::FragmentSpread(ref spread) => {
+          self.check_directives(&spread.directives);
+          if !self.fragments.contains(spread.name.value) {
+            self.error(&format!("Unknown fragment \"{:}\".", spread.name.value),
+                       &spread.name.loc);
+          }
+        }
+        ast::Selection::InlineFragment(ref fragment) => {
+          self.check_directives(&fragment.directives);
+          match fragment.type_condition {
+            Some(ref condition) => {
+              if self.check_type(&condition.name) {
+                self.visit_selection_set(condition.name.value, &fragment.selection_set);
+              }
+            }
+            None => self.visit_selection_set(parent, &fragment.selection_set),
+          }
+        }
+      }
+    }
+  }
+
+  fn visit_field(&mut self,
+                 parent: &str,
+                 parent_type: &Rc<dyn GraphQLOutput>,
+                 field: &ast::Field) {
+    let name = field.name.value;
+    let fields = match parent_type.output_kind() {
+      OutputKind::Object(o) => Some(o.fields()),
+      OutputKind::Interface(i) => Some(i.fields()),
+      _ => None,
+    };
+    let definition = fields.as_ref().and_then(|fields| fields.get(name)).map(|f| {
+      (f.typ().clone(), f.args().map(|args| args.keys().cloned().collect::<Vec<_>>()))
+    });
+    let (typ, args) = match definition {
+      Some(definition) => definition,
+      None if name == "__typename" => (GraphQLScalarType::string() as Rc<dyn GraphQLOutput>, None),
+      None => {
+        self.error(&format!("Cannot query field \"{:}\" on type \"{:}\".", name, parent),
+                   &field.name.loc);
+        return;
+      }
+    };
+    for arg in field.arguments.iter().flat_map(|a| a.iter()) {
+      if !args.iter().flat_map(|a| a.iter()).any(|a| a == arg.name.value) {
+        self.error(&format!("Unknown argument \"{:}\" on field \"{:}.{:}\".",
+                            arg.name.value,
+                            parent,
+                            name),
+                   &arg.name.loc);
+      }
+    }
+
+    let named_type = named_output_type(&typ);
+    let leaf = matches!(named_type.output_kind(), OutputKind::Scalar(_) | OutputKind::Enum(_));
+    match field.selection_set {
+      Some(_) if leaf => {
+        self.error(&format!("Field \"{:}\" must not have a selection since type \"{:}\" has \
+                             no subfields.",
+                            name,
+                            output_type_ref(&*typ)),
+                   &field.name.loc)
+      }
+      Some(ref selection_set) => self.visit_selection_set(named_type.name(), selection_set),
+      None if !leaf => {
+        self.error(&format!("Field \"{:}\" of type \"{:}\" must have a selection of \
+                             subfields. Did you mean \"{:} {{ ... }}\"?",
+                            name,
+                            output_type_ref(&*typ),
+                            name),
+                   &field.name.loc)
+      }
+      None => {}
+    }
+  }
+
+  fn check_type(&mut self, name: &ast::Name) -> bool {
+    if self.schema.get_type(name.value).is_some() || BUILT_IN_SCALARS.contains(&name.value) {
+      return true;
+    }
+    self.error(&format!("Unknown type \"{:}\".", name.value), &name.loc);
+    false
+  }
+
+  fn check_directives(&mut self, directives: &Option<ast::Directives>) {
+    for directive in directives.iter().flat_map(|d| d.iter()) {
+      let name = directive.name.value;
+      let args = match DIRECTIVES.iter().find(|d| d.0 == name) {
+        Some(&(_, args)) => args,
+        None => {
+          self.error(&format!("Unknown directive \"@{:}\".", name), &directive.name.loc);
+          continue;
+        }
+      };
+      for arg in directive.arguments.iter().flat_map(|a| a.iter()) {
+        if !args.contains(&arg.name.value) {
+          self.error(&format!("Unknown argument \"{:}\" on directive \"@{:}\".",
+                              arg.name.value,
+                              name),
+                     &arg.name.loc);
+        }
+      }
+    }
+  }
+
+  fn error(&mut self, message: &str, loc: &Option<ast::Location>) {
+    let e = GraphQLError::new(message);
+    self.errors.push(match *loc {
+      Some(ref loc) => e.location(loc),
+      None => e,
+    });
+  }
+}
+
+fn find_directive<'d, 'a>(directives: &'d Option<ast::Directives<'a>>,
+                          name: &str)
+                          -> Option<&'d ast::Directive<'a>> {
+  directives.iter().flat_map(|d| d.iter()).find(|d| d.name.value == name)
+}
+
+/// Type of the field `name` of the named type `parent`, if both exist.
+pub(crate) fn field_type(schema: &GraphQLSchema,
+                         parent: &str,
+                         name: &str)
+                         -> Option<Rc<dyn GraphQLOutput>> {
+  let parent = match schema.get_type(parent) {
+    Some(SchemaType::Output(parent)) => parent,
+    _ => return None,
+  };
+  match parent.output_kind() {
+    OutputKind::Object(o) => o.fields().get(name).map(|f| f.typ().clone()),
+    OutputKind::Interface(i) => i.fields().get(name).map(|f| f.typ().clone()),
+    _ => None,
+  }
+}
+
+pub(crate) fn is_list(typ: &dyn GraphQLOutput) -> bool {
+  match typ.output_kind() {
+    OutputKind::Optional(t) => is_list(&**t.of_type()),
+    OutputKind::List(_) => true,
+    _ => false,
+  }
+}
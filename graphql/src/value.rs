@@ -0,0 +1,245 @@
+use std::fmt;
+
+use indexmap::IndexMap;
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// Coerced input value, as handed to resolvers for arguments and input object
+/// fields.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+  Null,
+  Int(i64),
+  Float(f64),
+  String(String),
+  Boolean(bool),
+  Enum(String),
+  List(Vec<Value>),
+  Object(IndexMap<String, Value>),
+}
+
+/// Formats the value as a GraphQL literal.
+impl fmt::Display for Value {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Value::Null => write!(f, "null"),
+      Value::Int(i) => write!(f, "{}", i),
+      Value::Float(v) => write!(f, "{:?}", v),
+      Value::String(ref s) => {
+        write!(f, "\"")?;
+        for c in s.chars() {
+          match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if c < ' ' => write!(f, "\\u{:04X}", c as u32)?,
+            c => write!(f, "{}", c)?,
+          }
+        }
+        write!(f, "\"")
+      }
+      Value::Boolean(b) => write!(f, "{}", b),
+      Value::Enum(ref name) => write!(f, "{}", name),
+      Value::List(ref values) => {
+        write!(f, "[")?;
+        for (i, v) in values.iter().enumerate() {
+          if i > 0 {
+            write!(f, ", ")?;
+          }
+          write!(f, "{}", v)?;
+        }
+        write!(f, "]")
+      }
+      Value::Object(ref fields) => {
+        write!(f, "{{")?;
+        for (i, (name, v)) in fields.iter().enumerate() {
+          if i > 0 {
+            write!(f, ", ")?;
+          }
+          write!(f, "{}: {}", name, v)?;
+        }
+        write!(f, "}}")
+      }
+    }
+  }
+}
+
+/// Serializes to JSON-like data, with enum values as strings.
+impl Serialize for Value {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    match *self {
+      Value::Null => serializer.serialize_unit(),
+      Value::Int(i) => serializer.serialize_i64(i),
+      Value::Float(v) => serializer.serialize_f64(v),
+      Value::String(ref s) |
+      Value::Enum(ref s) => serializer.serialize_str(s),
+      Value::Boolean(b) => serializer.serialize_bool(b),
+      Value::List(ref values) => values.serialize(serializer),
+      Value::Object(ref fields) => fields.serialize(serializer),
+    }
+  }
+}
+
+/// Deserializes from JSON-like data, such as request variables. Strings are
+/// read as `Value::String` and become enum values on coercion.
+impl<'de> Deserialize<'de> for Value {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+    deserializer.deserialize_any(ValueVisitor)
+  }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+  type Value = Value;
+
+  fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "a JSON value")
+  }
+
+  fn visit_unit<E>(self) -> Result<Value, E> {
+    Ok(Value::Null)
+  }
+
+  fn visit_none<E>(self) -> Result<Value, E> {
+    Ok(Value::Null)
+  }
+
+  fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+    Value::deserialize(deserializer)
+  }
+
+  fn visit_bool<E>(self, b: bool) -> Result<Value, E> {
+    Ok(Value::Boolean(b))
+  }
+
+  fn visit_i64<E>(self, i: i64) -> Result<Value, E> {
+    Ok(Value::Int(i))
+  }
+
+  fn visit_u64<E>(self, u: u64) -> Result<Value, E> {
+    if u <= i64::MAX as u64 {
+      Ok(Value::Int(u as i64))
+    } else {
+      Ok(Value::Float(u as f64))
+    }
+  }
+
+  fn visit_f64<E>(self, f: f64) -> Result<Value, E> {
+    Ok(Value::Float(f))
+  }
+
+  fn visit_str<E>(self, s: &str) -> Result<Value, E> {
+    Ok(Value::String(s.to_owned()))
+  }
+
+  fn visit_string<E>(self, s: String) -> Result<Value, E> {
+    Ok(Value::String(s))
+  }
+
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+    let mut values = Vec::new();
+    while let Some(value) = seq.next_element()? {
+      values.push(value);
+    }
+    Ok(Value::List(values))
+  }
+
+  fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+    let mut fields = IndexMap::new();
+    while let Some((name, value)) = map.next_entry()? {
+      fields.insert(name, value);
+    }
+    Ok(Value::Object(fields))
+  }
+}
+
+impl From<i32> for Value {
+  fn from(i: i32) -> Value {
+    Value::Int(i as i64)
+  }
+}
+
+impl From<f64> for Value {
+  fn from(f: f64) -> Value {
+    Value::Float(f)
+  }
+}
+
+impl From<String> for Value {
+  fn from(s: String) -> Value {
+    Value::String(s)
+  }
+}
+
+impl From<bool> for Value {
+  fn from(b: bool) -> Value {
+    Value::Boolean(b)
+  }
+}
+
+/// Coerced field arguments, keyed by argument name.
+pub type Arguments = IndexMap<String, Value>;
+
+/// Rust types which can be built from a coerced input value.
+pub trait FromInputValue: Sized {
+  fn from_input_value(value: &Value) -> Option<Self>;
+}
+
+impl FromInputValue for i32 {
+  fn from_input_value(value: &Value) -> Option<Self> {
+    match *value {
+      Value::Int(i) if i >= i32::MIN as i64 && i <= i32::MAX as i64 => Some(i as i32),
+      _ => None,
+    }
+  }
+}
+
+impl FromInputValue for f64 {
+  fn from_input_value(value: &Value) -> Option<Self> {
+    match *value {
+      Value::Int(i) => Some(i as f64),
+      Value::Float(f) => Some(f),
+      _ => None,
+    }
+  }
+}
+
+impl FromInputValue for String {
+  fn from_input_value(value: &Value) -> Option<Self> {
+    match *value {
+      Value::String(ref s) => Some(s.clone()),
+      _ => None,
+    }
+  }
+}
+
+impl FromInputValue for bool {
+  fn from_input_value(value: &Value) -> Option<Self> {
+    match *value {
+      Value::Boolean(b) => Some(b),
+      _ => None,
+    }
+  }
+}
+
+impl<T: FromInputValue> FromInputValue for Option<T> {
+  fn from_input_value(value: &Value) -> Option<Self> {
+    match *value {
+      Value::Null => Some(None),
+      ref v => T::from_input_value(v).map(Some),
+    }
+  }
+}
+
+impl<T: FromInputValue> FromInputValue for Vec<T> {
+  fn from_input_value(value: &Value) -> Option<Self> {
+    match *value {
+      Value::List(ref values) => values.iter().map(T::from_input_value).collect(),
+      // A single value is coerced to a list of one item.
+      ref v => T::from_input_value(v).map(|v| vec![v]),
+    }
+  }
+}
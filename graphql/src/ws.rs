@@ -0,0 +1,379 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures_core::Stream;
+use indexmap::IndexMap;
+
+use error::GraphQLError;
+use execution::ExecutionResult;
+use handler::to_json;
+use request::{ExecutionConfig, GraphQLRequest, ResponseStream};
+use value::Value;
+
+pub static DEFAULT_CONNECTION_INIT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// WebSocket subprotocol spoken by a connection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Protocol {
+  /// `graphql-transport-ws`, as implemented by the `graphql-ws` library.
+  GraphQLTransportWs,
+  /// `graphql-ws`, the legacy protocol of the `subscriptions-transport-ws`
+  /// library.
+  #[cfg(feature = "subscriptions-transport-ws")]
+  SubscriptionsTransportWs,
+}
+
+impl Protocol {
+  /// Selects the first supported protocol of a `Sec-WebSocket-Protocol`
+  /// header.
+  pub fn from_header(header: &str) -> Option<Protocol> {
+    header.split(',').filter_map(|name| Protocol::from_name(name.trim())).next()
+  }
+
+  pub fn from_name(name: &str) -> Option<Protocol> {
+    match name {
+      "graphql-transport-ws" => Some(Protocol::GraphQLTransportWs),
+      #[cfg(feature = "subscriptions-transport-ws")]
+      "graphql-ws" => Some(Protocol::SubscriptionsTransportWs),
+      _ => None,
+    }
+  }
+
+  pub fn name(&self) -> &'static str {
+    match *self {
+      Protocol::GraphQLTransportWs => "graphql-transport-ws",
+      #[cfg(feature = "subscriptions-transport-ws")]
+      Protocol::SubscriptionsTransportWs => "graphql-ws",
+    }
+  }
+}
+
+/// Frame to send to the client.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WsMessage {
+  Text(String),
+  /// Close frame with its code and reason, the reason cut to the 123 bytes
+  /// which fit in a control frame.
+  Close(u16, String),
+}
+
+// Longest reason of a close frame, whose payload holds at most 125 bytes,
+// the code included.
+const MAX_CLOSE_REASON: usize = 123;
+
+type ConnectFn = dyn Fn(Option<&Value>) -> Result<Rc<dyn Any>, GraphQLError>;
+
+/// Server side of a GraphQL over WebSocket connection, independent of the
+/// WebSocket implementation.
+///
+/// Text frames received from the client are passed to `receive`, and frames
+/// to send are polled from the connection as a `Stream`, which also delivers
+/// the events of running subscriptions. The owner of the connection calls
+/// `handle_timeout` once `deadline` has passed.
+pub struct GraphQLWsConnection {
+  protocol: Protocol,
+  config: Rc<ExecutionConfig>,
+  on_connect: Rc<ConnectFn>,
+  init_deadline: Instant,
+  init_received: bool,
+  // Set once the connection is acknowledged.
+  context: Option<Rc<dyn Any>>,
+  subscriptions: IndexMap<String, ResponseStream>,
+  // Index of the subscription to poll first, the one after the last polled.
+  next_subscription: usize,
+  outgoing: VecDeque<WsMessage>,
+  closed: bool,
+}
+
+impl GraphQLWsConnection {
+  pub fn new(protocol: Protocol, config: &Rc<ExecutionConfig>) -> GraphQLWsConnection {
+    GraphQLWsConnection {
+      protocol,
+      config: config.clone(),
+      on_connect: Rc::new(|_| Ok(Rc::new(()))),
+      init_deadline: Instant::now() + DEFAULT_CONNECTION_INIT_TIMEOUT,
+      init_received: false,
+      context: None,
+      subscriptions: IndexMap::new(),
+      next_subscription: 0,
+      outgoing: VecDeque::new(),
+      closed: false,
+    }
+  }
+
+  /// Builds the context handed to resolvers from the `connection_init`
+  /// payload. An error refuses the connection.
+  pub fn on_connect<F, C>(mut self, on_connect: F) -> GraphQLWsConnection
+    where F: Fn(Option<&Value>) -> Result<C, GraphQLError> + 'static,
+          C: Any
+  {
+    self.on_connect = Rc::new(move |payload| {
+      on_connect(payload).map(|context| Rc::new(context) as Rc<dyn Any>)
+    });
+    self
+  }
+
+  /// Time the client has to send `connection_init` after the connection is
+  /// created.
+  pub fn connection_init_timeout(mut self, timeout: Duration) -> GraphQLWsConnection {
+    self.init_deadline = Instant::now() + timeout;
+    self
+  }
+
+  pub fn protocol(&self) -> Protocol {
+    self.protocol
+  }
+
+  pub fn is_closed(&self) -> bool {
+    self.closed
+  }
+
+  /// When `handle_timeout` has to be called, if at all.
+  pub fn deadline(&self) -> Option<Instant> {
+    if self.init_received || self.closed {
+      None
+    } else {
+      Some(self.init_deadline)
+    }
+  }
+
+  pub fn handle_timeout(&mut self, now: Instant) {
+    if let Some(deadline) = self.deadline() {
+      if now >= deadline {
+        self.close(4408, "Connection initialisation timeout");
+      }
+    }
+  }
+
+  /// Handles a text frame sent by the client.
+  pub fn receive(&mut self, text: &str) {
+    if self.closed {
+      return;
+    }
+    let message = match serde_json::from_str::<ClientMessage>(text) {
+      Ok(message) => message,
+      Err(e) => return self.close(4400, &format!("Invalid message received: {}", e)),
+    };
+    match self.protocol {
+      Protocol::GraphQLTransportWs => self.receive_transport_ws(message),
+      #[cfg(feature = "subscriptions-transport-ws")]
+      Protocol::SubscriptionsTransportWs => self.receive_subscriptions_transport_ws(message),
+    }
+  }
+
+  fn receive_transport_ws(&mut self, message: ClientMessage) {
+    match message {
+      ClientMessage::ConnectionInit { payload } => {
+        if self.init_received {
+          return self.close(4429, "Too many initialisation requests");
+        }
+        self.init_received = true;
+        match (self.on_connect)(payload.as_ref()) {
+          Ok(context) => {
+            self.context = Some(context);
+            self.send(ServerMessage::ConnectionAck);
+          }
+          Err(_) => self.close(4403, "Forbidden"),
+        }
+      }
+      ClientMessage::Ping {} => self.send(ServerMessage::Pong),
+      ClientMessage::Pong {} => {}
+      ClientMessage::Subscribe { id, payload } => {
+        if self.context.is_none() {
+          return self.close(4401, "Unauthorized");
+        }
+        if self.subscriptions.contains_key(&id) {
+          return self.close(4409, &format!("Subscriber for {} already exists", id));
+        }
+        self.start(id, payload);
+      }
+      ClientMessage::Complete { id } => {
+        self.subscriptions.swap_remove(&id);
+      }
+      #[cfg(feature = "subscriptions-transport-ws")]
+      _ => self.close(4400, "Invalid message received"),
+    }
+  }
+
+  // The legacy protocol has no close codes of its own.
+  #[cfg(feature = "subscriptions-transport-ws")]
+  fn receive_subscriptions_transport_ws(&mut self, message: ClientMessage) {
+    match message {
+      ClientMessage::ConnectionInit { payload } => {
+        self.init_received = true;
+        match (self.on_connect)(payload.as_ref()) {
+          Ok(context) => {
+            self.context = Some(context);
+            self.send(ServerMessage::ConnectionAck);
+            self.send(ServerMessage::Ka);
+          }
+          Err(e) => {
+            self.send(ServerMessage::ConnectionError { payload: &e });
+            self.close(4403, "Forbidden");
+          }
+        }
+      }
+      ClientMessage::Start { id, payload } => {
+        if self.context.is_none() {
+          let e = GraphQLError::new("Unauthorized");
+          return self.send(ServerMessage::Error {
+            id: &id,
+            payload: &[e],
+          });
+        }
+        // A client may restart an operation under the same id.
+        self.subscriptions.swap_remove(&id);
+        self.start(id, payload);
+      }
+      ClientMessage::Stop { id } => {
+        if self.subscriptions.swap_remove(&id).is_some() {
+          self.send(ServerMessage::Complete { id: &id });
+        }
+      }
+      ClientMessage::ConnectionTerminate => self.close(1000, "Normal Closure"),
+      _ => self.close(4400, "Invalid message received"),
+    }
+  }
+
+  // Subscriptions keep running until their source stream ends, queries and
+  // mutations complete after their single response.
+  fn start(&mut self, id: String, mut request: GraphQLRequest) {
+    if let Err(e) = self.config.resolve_query(&mut request) {
+      return self.send(ServerMessage::Error {
+        id: &id,
+        payload: &[e],
+      });
+    }
+    let context = self.context.clone().unwrap();
+    match ResponseStream::new(&self.config, request, context) {
+      Ok(stream) => {
+        self.subscriptions.insert(id, stream);
+      }
+      Err(response) => {
+        self.send(ServerMessage::Error {
+          id: &id,
+          payload: &response.errors,
+        })
+      }
+    }
+  }
+
+  fn next_message<'a>(&self, id: &'a str, result: &'a ExecutionResult) -> ServerMessage<'a> {
+    match self.protocol {
+      Protocol::GraphQLTransportWs => ServerMessage::Next { id, payload: result },
+      #[cfg(feature = "subscriptions-transport-ws")]
+      Protocol::SubscriptionsTransportWs => ServerMessage::Data { id, payload: result },
+    }
+  }
+
+  fn send(&mut self, message: ServerMessage) {
+    self.outgoing.push_back(WsMessage::Text(to_json(&message).to_string()));
+  }
+
+  fn close(&mut self, code: u16, reason: &str) {
+    let mut end = reason.len().min(MAX_CLOSE_REASON);
+    while !reason.is_char_boundary(end) {
+      end -= 1;
+    }
+    self.subscriptions.clear();
+    self.outgoing.push_back(WsMessage::Close(code, reason[..end].to_owned()));
+    self.closed = true;
+  }
+}
+
+/// Yields the frames to send, ending after a `WsMessage::Close`.
+impl Stream for GraphQLWsConnection {
+  type Item = WsMessage;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<WsMessage>> {
+    let this = self.get_mut();
+    // Subscriptions take turns, so that one always ready does not starve the
+    // others.
+    let mut pending = 0;
+    while this.outgoing.is_empty() && pending < this.subscriptions.len() {
+      let index = this.next_subscription % this.subscriptions.len();
+      this.next_subscription = index + 1;
+      match Pin::new(&mut this.subscriptions[index]).poll_next(cx) {
+        Poll::Ready(Some(result)) => {
+          let id = this.subscriptions.get_index(index).unwrap().0.clone();
+          let message = this.next_message(&id, &result);
+          this.send(message);
+        }
+        Poll::Ready(None) => {
+          let (id, _) = this.subscriptions.shift_remove_index(index).unwrap();
+          this.next_subscription = index;
+          this.send(ServerMessage::Complete { id: &id });
+        }
+        Poll::Pending => pending += 1,
+      }
+    }
+
+    match this.outgoing.pop_front() {
+      Some(message) => Poll::Ready(Some(message)),
+      None if this.closed => Poll::Ready(None),
+      None => Poll::Pending,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+  ConnectionInit {
+    #[serde(default)]
+    payload: Option<Value>,
+  },
+  Ping {},
+  Pong {},
+  Subscribe {
+    id: String,
+    payload: GraphQLRequest,
+  },
+  Complete {
+    id: String,
+  },
+  #[cfg(feature = "subscriptions-transport-ws")]
+  Start {
+    id: String,
+    payload: GraphQLRequest,
+  },
+  #[cfg(feature = "subscriptions-transport-ws")]
+  Stop {
+    id: String,
+  },
+  #[cfg(feature = "subscriptions-transport-ws")]
+  ConnectionTerminate,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage<'a> {
+  ConnectionAck,
+  Pong,
+  Next {
+    id: &'a str,
+    payload: &'a ExecutionResult,
+  },
+  Error {
+    id: &'a str,
+    payload: &'a [GraphQLError],
+  },
+  Complete {
+    id: &'a str,
+  },
+  #[cfg(feature = "subscriptions-transport-ws")]
+  Data {
+    id: &'a str,
+    payload: &'a ExecutionResult,
+  },
+  #[cfg(feature = "subscriptions-transport-ws")]
+  ConnectionError {
+    payload: &'a GraphQLError,
+  },
+  #[cfg(feature = "subscriptions-transport-ws")]
+  Ka,
+}